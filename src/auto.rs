@@ -3,7 +3,9 @@
 use crate::errors::Error;
 use crate::vba::VbaProject;
 use crate::{
-    open_workbook, open_workbook_from_rs, DataType, Metadata, Ods, Range, Reader, Xls, Xlsb, Xlsx,
+    open_workbook, open_workbook_from_rs, Chart, Comment, ConditionalFormat, DataType,
+    DataValidation, Dimensions, Expr, Hyperlink, Metadata, Ods, Range, Reader, SheetLayout,
+    StyleId, Styles, Table, TableReader, TextRun, Xls, Xlsb, Xlsx,
 };
 use std::borrow::Cow;
 use std::fs::File;
@@ -61,16 +63,16 @@ where
     RS: std::io::Read + std::io::Seek + Clone,
 {
     if let Ok(ret) = open_workbook_from_rs::<Xls<RS>, RS>(data.clone()) {
-        return Ok(Sheets::Xls(ret));
+        Ok(Sheets::Xls(ret))
     } else if let Ok(ret) = open_workbook_from_rs::<Xlsx<RS>, RS>(data.clone()) {
-        return Ok(Sheets::Xlsx(ret));
+        Ok(Sheets::Xlsx(ret))
     } else if let Ok(ret) = open_workbook_from_rs::<Xlsb<RS>, RS>(data.clone()) {
-        return Ok(Sheets::Xlsb(ret));
+        Ok(Sheets::Xlsb(ret))
     } else if let Ok(ret) = open_workbook_from_rs::<Ods<RS>, RS>(data) {
-        return Ok(Sheets::Ods(ret));
+        Ok(Sheets::Ods(ret))
     } else {
-        return Err(Error::Msg("Cannot detect file format"));
-    };
+        Err(Error::Msg("Cannot detect file format"))
+    }
 }
 
 impl<RS> Reader<RS> for Sheets<RS>
@@ -124,6 +126,116 @@ where
         }
     }
 
+    /// Read worksheet formulas as syntax trees
+    fn worksheet_formula_ast(&mut self, name: &str) -> Result<Range<Expr>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_formula_ast(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_formula_ast(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_formula_ast(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_formula_ast(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read merged cell regions in corresponding worksheet
+    fn merged_regions(&mut self, name: &str) -> Result<Vec<Dimensions>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.merged_regions(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.merged_regions(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.merged_regions(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.merged_regions(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read the style table of the workbook
+    fn styles(&mut self) -> Result<Styles, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.styles().map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.styles().map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.styles().map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.styles().map_err(Error::Ods),
+        }
+    }
+
+    /// Read the style of each cell in corresponding worksheet
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_styles(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_styles(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_styles(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_styles(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read the comments of the corresponding worksheet
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_comments(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_comments(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_comments(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_comments(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<Vec<TextRun>>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_layout(&mut self, name: &str) -> Result<SheetLayout, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_layout(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_layout(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_layout(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_layout(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_data_validations(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<DataValidation>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_conditional_formats(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<ConditionalFormat>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_conditional_formats(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_conditional_formats(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_conditional_formats(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_conditional_formats(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_charts(&mut self, name: &str) -> Result<Vec<Chart>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_charts(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_charts(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_charts(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_charts(name).map_err(Error::Ods),
+        }
+    }
+
     fn worksheets(&mut self) -> Vec<(String, Range<DataType>)> {
         match *self {
             Sheets::Xls(ref mut e) => e.worksheets(),
@@ -143,3 +255,53 @@ where
         }
     }
 }
+
+impl<RS> TableReader<RS> for Sheets<RS>
+where
+    RS: std::io::Read + std::io::Seek,
+{
+    fn load_tables(&mut self) -> Result<(), Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.load_tables().map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.load_tables().map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.load_tables().map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.load_tables().map_err(Error::Ods),
+        }
+    }
+
+    fn table_names(&self) -> Vec<&String> {
+        match *self {
+            Sheets::Xls(ref e) => e.table_names(),
+            Sheets::Xlsx(ref e) => e.table_names(),
+            Sheets::Xlsb(ref e) => e.table_names(),
+            Sheets::Ods(ref e) => e.table_names(),
+        }
+    }
+
+    fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        match *self {
+            Sheets::Xls(ref e) => e.table_names_in_sheet(sheet_name),
+            Sheets::Xlsx(ref e) => e.table_names_in_sheet(sheet_name),
+            Sheets::Xlsb(ref e) => e.table_names_in_sheet(sheet_name),
+            Sheets::Ods(ref e) => e.table_names_in_sheet(sheet_name),
+        }
+    }
+
+    fn table_by_name(&mut self, table_name: &str) -> Result<Table<DataType>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.table_by_name(table_name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.table_by_name(table_name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.table_by_name(table_name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.table_by_name(table_name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_formula_resolved(&mut self, name: &str) -> Result<Range<String>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_formula_resolved(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_formula_resolved(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_formula_resolved(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_formula_resolved(name).map_err(Error::Ods),
+        }
+    }
+}
@@ -3,7 +3,8 @@
 use std::borrow::Cow;
 use std::cmp::min;
 use std::convert::TryInto;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
 
 use log::debug;
 
@@ -130,12 +131,20 @@ impl Cfb {
 
     /// Checks if directory exists
     pub fn has_directory(&self, name: &str) -> bool {
-        self.directories.iter().any(|d| &*d.name == name)
+        self.directories
+            .iter()
+            .any(|d| d.name.eq_ignore_ascii_case(name))
     }
 
     /// Gets a stream by name out of directories
+    ///
+    /// Names are compared case insensitively, e.g. some writers use `BOOK` or `WORKBOOK`
     pub fn get_stream<R: Read>(&mut self, name: &str, r: &mut R) -> Result<Vec<u8>, CfbError> {
-        match self.directories.iter().find(|d| &*d.name == name) {
+        match self
+            .directories
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+        {
             None => Err(CfbError::StreamNotFound(name.to_string())),
             Some(d) => {
                 if d.len < 4096 {
@@ -148,6 +157,110 @@ impl Cfb {
             }
         }
     }
+
+    /// Gets the location of a stream, with its length, to read it in parts
+    ///
+    /// Small streams live in the mini stream and are returned in memory.
+    pub fn get_stream_location<R: Read>(
+        &mut self,
+        name: &str,
+        r: &mut R,
+    ) -> Result<(StreamLocation, usize), CfbError> {
+        let (start, len) = match self
+            .directories
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+        {
+            None => return Err(CfbError::StreamNotFound(name.to_string())),
+            Some(d) => (d.start, d.len),
+        };
+        if len < 4096 {
+            let stream = self.get_stream(name, r)?;
+            let len = stream.len();
+            return Ok((StreamLocation::Memory(stream), len));
+        }
+        // sectors are stored after the header, which takes a whole sector
+        let size = self.sectors.size;
+        let mut offsets = Vec::with_capacity(len / size + 1);
+        let mut sector_id = start;
+        while sector_id != ENDOFCHAIN && offsets.len() * size < len {
+            offsets.push((sector_id as u64 + 1) * size as u64);
+            match self.fats.get(sector_id as usize) {
+                Some(&next) => sector_id = next,
+                None => break,
+            }
+        }
+        Ok((StreamLocation::Sectors { size, offsets }, len))
+    }
+}
+
+/// Location of a stream in a file
+#[derive(Debug, Clone)]
+pub enum StreamLocation {
+    /// Stream read in memory
+    Memory(Vec<u8>),
+    /// File offsets of the sectors of the stream
+    Sectors { size: usize, offsets: Vec<u64> },
+    /// Whole file, e.g. for BIFF2 to BIFF4 workbooks which are not stored in a compound file
+    File,
+}
+
+impl StreamLocation {
+    /// Reads `range` of the stream into `buf`, which is cleared first
+    ///
+    /// Fewer bytes are read if the stream is shorter.
+    pub fn read<R: Read + Seek>(
+        &self,
+        r: &mut R,
+        range: Range<usize>,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), CfbError> {
+        buf.clear();
+        match self {
+            StreamLocation::Memory(stream) => {
+                let end = min(range.end, stream.len());
+                buf.extend_from_slice(stream.get(range.start..end).unwrap_or_default());
+            }
+            StreamLocation::File => {
+                r.seek(SeekFrom::Start(range.start as u64))
+                    .map_err(CfbError::Io)?;
+                r.by_ref()
+                    .take(range.len() as u64)
+                    .read_to_end(buf)
+                    .map_err(CfbError::Io)?;
+            }
+            StreamLocation::Sectors { size, offsets } => {
+                let size = *size;
+                let mut pos = range.start;
+                while pos < range.end {
+                    let first = pos / size;
+                    let offset = match offsets.get(first) {
+                        Some(offset) => offset + (pos % size) as u64,
+                        None => break,
+                    };
+                    // contiguous sectors are read at once
+                    let mut last = first;
+                    while (last + 1) * size < range.end
+                        && offsets.get(last + 1) == Some(&(offsets[last] + size as u64))
+                    {
+                        last += 1;
+                    }
+                    let len = min((last + 1) * size, range.end) - pos;
+                    r.seek(SeekFrom::Start(offset)).map_err(CfbError::Io)?;
+                    let read = r
+                        .by_ref()
+                        .take(len as u64)
+                        .read_to_end(buf)
+                        .map_err(CfbError::Io)?;
+                    if read < len {
+                        break;
+                    }
+                    pos += len;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// A hidden struct which defines cfb files structure
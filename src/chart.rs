@@ -0,0 +1,76 @@
+//! Charts, read by `Reader::worksheet_charts`
+
+use crate::Dimensions;
+
+/// Kind of a chart, or of a series of a combination chart
+///
+/// 3D variants have the same kind as their 2D counterpart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ChartType {
+    /// Vertical bars
+    #[default]
+    Column,
+    /// Horizontal bars
+    Bar,
+    /// Lines
+    Line,
+    /// Filled areas
+    Area,
+    /// Pie, including pie of pie and bar of pie
+    Pie,
+    /// Doughnut
+    Doughnut,
+    /// Scatter (XY) plot
+    Scatter,
+    /// Bubbles
+    Bubble,
+    /// Radar
+    Radar,
+    /// Stock (high-low-close)
+    Stock,
+    /// Surface
+    Surface,
+    /// Another chart, by its element name in the file
+    Other(String),
+}
+
+/// A reference to the cells of a chart series
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartReference {
+    /// Formula, e.g. `Sheet1!$B$2:$B$5`
+    pub formula: String,
+    /// Referenced sheet, if the formula is a single cell or area
+    pub sheet: Option<String>,
+    /// Referenced cells, if the formula is a single cell or area
+    pub range: Option<Dimensions>,
+}
+
+/// A series of a chart
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartSeries {
+    /// Kind of chart the series is plotted as
+    pub chart_type: ChartType,
+    /// Name, as last displayed
+    pub name: Option<String>,
+    /// Cell holding the name
+    pub name_reference: Option<ChartReference>,
+    /// Categories, or x values of scatter and bubble charts
+    pub categories: Option<ChartReference>,
+    /// Values, or y values of scatter and bubble charts
+    pub values: Option<ChartReference>,
+}
+
+/// A chart of a worksheet or chart sheet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chart {
+    /// Name of the drawing object
+    pub name: Option<String>,
+    /// Cells the chart is drawn over, `None` for chart sheets
+    pub anchor: Option<Dimensions>,
+    /// Kind of chart, that of the first series for combination charts
+    pub chart_type: ChartType,
+    /// Title, the name of the single series for automatic titles
+    pub title: Option<String>,
+    /// Series, in plot order
+    pub series: Vec<ChartSeries>,
+}
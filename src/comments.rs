@@ -0,0 +1,31 @@
+//! Cell comments (notes), read by `Reader::worksheet_comments`
+
+use crate::style::TextRun;
+
+/// A cell comment (note)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comment {
+    /// Commented cell, as (row, column)
+    pub position: (u32, u32),
+    /// Author name, empty if unknown
+    pub author: String,
+    /// Text, without formatting
+    pub text: String,
+    /// Formatted parts of the text, whose concatenation is `text`
+    pub runs: Vec<TextRun>,
+    /// Replies, for threaded comments
+    pub replies: Vec<Comment>,
+}
+
+impl Comment {
+    /// Creates a comment, `text` being the concatenation of `runs`
+    pub(crate) fn new(position: (u32, u32), author: String, runs: Vec<TextRun>) -> Comment {
+        Comment {
+            position,
+            author,
+            text: runs.iter().map(|r| r.text.as_str()).collect(),
+            runs,
+            replies: Vec::new(),
+        }
+    }
+}
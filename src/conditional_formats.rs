@@ -0,0 +1,41 @@
+//! Conditional formatting rules, read by `Reader::worksheet_conditional_formats`
+
+use crate::validation::ValidationOperator;
+use crate::Dimensions;
+
+/// Kind of a conditional formatting rule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ConditionalFormatType {
+    /// Compares the cell value with the formulas
+    #[default]
+    CellIs,
+    /// Applies when the formula is true
+    Expression,
+    /// Colors the cells on a gradient
+    ColorScale,
+    /// Draws a bar proportional to the cell value
+    DataBar,
+    /// Displays an icon depending on the cell value
+    IconSet,
+    /// Another rule, e.g. `top10` or `containsText`, by its name in the file
+    Other(String),
+}
+
+/// A conditional formatting rule
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConditionalFormat {
+    /// Formatted cells
+    pub ranges: Vec<Dimensions>,
+    /// Kind of rule
+    pub rule_type: ConditionalFormatType,
+    /// Priority, the lowest value being evaluated first
+    pub priority: u32,
+    /// Comparison of the cell value with the formulas, for `CellIs` rules
+    pub operator: Option<ValidationOperator>,
+    /// Formulas, without the leading `=`
+    pub formulas: Vec<String>,
+    /// Index of the differential format applied, in the styles of the workbook
+    pub dxf_id: Option<u32>,
+    /// Name of the style applied, for ods files
+    pub style_name: Option<String>,
+}
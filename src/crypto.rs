@@ -0,0 +1,726 @@
+//! Decryption of password protected workbooks [MS-OFFCRYPTO]
+//!
+//! Office Open XML packages (xlsx, xlsb) are encrypted into a compound file, with
+//! either the Standard or the Agile encryption. Legacy xls workbook streams are
+//! obfuscated with a XOR, or encrypted with RC4 or RC4 CryptoAPI, record by record.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, KeyInit};
+use aes::{Aes128, Aes192, Aes256};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader as XmlReader;
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::cfb::{Cfb, CfbError};
+use crate::utils::{read_u16, read_u32, read_usize};
+
+/// An error while decrypting a workbook
+#[derive(Debug)]
+pub enum CryptoError {
+    /// Error reading the encrypted streams
+    Cfb(CfbError),
+    /// Io error
+    Io(std::io::Error),
+    /// Error reading the agile encryption descriptor
+    Xml(quick_xml::Error),
+    /// The password does not match the one used to encrypt the workbook
+    InvalidPassword,
+    /// The decrypted data does not match its integrity check
+    Integrity,
+    /// Unsupported encryption, e.g. an unknown algorithm
+    Unsupported(String),
+    /// Malformed encryption information
+    Invalid(&'static str),
+}
+
+from_err!(CfbError, CryptoError, Cfb);
+from_err!(std::io::Error, CryptoError, Io);
+from_err!(quick_xml::Error, CryptoError, Xml);
+
+impl std::fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoError::Cfb(e) => write!(f, "Cfb error: {e}"),
+            CryptoError::Io(e) => write!(f, "I/O error: {e}"),
+            CryptoError::Xml(e) => write!(f, "Xml error: {e}"),
+            CryptoError::InvalidPassword => write!(f, "Invalid password"),
+            CryptoError::Integrity => write!(f, "Decrypted data failed its integrity check"),
+            CryptoError::Unsupported(s) => write!(f, "Unsupported encryption: {s}"),
+            CryptoError::Invalid(s) => write!(f, "Invalid encryption information: {s}"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CryptoError::Cfb(e) => Some(e),
+            CryptoError::Io(e) => Some(e),
+            CryptoError::Xml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Checks if the reader is a compound file holding an encrypted package
+pub(crate) fn is_encrypted<RS: Read + Seek>(reader: &mut RS) -> std::io::Result<bool> {
+    let len = reader.seek(SeekFrom::End(0))? as usize;
+    reader.seek(SeekFrom::Start(0))?;
+    let encrypted =
+        Cfb::new(reader, len).map_or(false, |cfb| cfb.has_directory("EncryptedPackage"));
+    reader.seek(SeekFrom::Start(0))?;
+    Ok(encrypted)
+}
+
+/// Reads the whole package, decrypting it if it is encrypted
+pub(crate) fn read_package<RS: Read + Seek>(
+    mut reader: RS,
+    password: &str,
+) -> Result<Vec<u8>, CryptoError> {
+    let len = reader.seek(SeekFrom::End(0))? as usize;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut cfb = match Cfb::new(&mut reader, len) {
+        Ok(cfb) if cfb.has_directory("EncryptedPackage") => cfb,
+        _ => {
+            reader.seek(SeekFrom::Start(0))?;
+            let mut package = Vec::with_capacity(len);
+            reader.read_to_end(&mut package)?;
+            return Ok(package);
+        }
+    };
+    let info = cfb.get_stream("EncryptionInfo", &mut reader)?;
+    let package = cfb.get_stream("EncryptedPackage", &mut reader)?;
+    if info.len() < 8 || package.len() < 8 {
+        return Err(CryptoError::Invalid("stream too short"));
+    }
+    match (read_u16(&info), read_u16(&info[2..])) {
+        (4, 4) => decrypt_agile(&info[8..], &package, password),
+        (2..=4, 2) => decrypt_standard(&info[8..], &package, password),
+        (major, minor) => Err(CryptoError::Unsupported(format!(
+            "EncryptionInfo version {major}.{minor}"
+        ))),
+    }
+}
+
+/// Hash algorithms of the agile encryption
+#[derive(Debug, Clone, Copy)]
+enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn from_name(name: &str) -> Result<HashAlgorithm, CryptoError> {
+        match name {
+            "SHA1" | "SHA-1" => Ok(HashAlgorithm::Sha1),
+            "SHA256" => Ok(HashAlgorithm::Sha256),
+            "SHA384" => Ok(HashAlgorithm::Sha384),
+            "SHA512" => Ok(HashAlgorithm::Sha512),
+            name => Err(CryptoError::Unsupported(format!("hash algorithm {name}"))),
+        }
+    }
+
+    /// Hashes the concatenation of the parts
+    fn hash(self, parts: &[&[u8]]) -> Vec<u8> {
+        fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+        match self {
+            HashAlgorithm::Sha1 => digest::<Sha1>(parts),
+            HashAlgorithm::Sha256 => digest::<Sha256>(parts),
+            HashAlgorithm::Sha384 => digest::<Sha384>(parts),
+            HashAlgorithm::Sha512 => digest::<Sha512>(parts),
+        }
+    }
+
+    fn hmac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        fn mac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut mac = <M as KeyInit>::new_from_slice(key).expect("HMAC accepts any key length");
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        match self {
+            HashAlgorithm::Sha1 => mac::<Hmac<Sha1>>(key, data),
+            HashAlgorithm::Sha256 => mac::<Hmac<Sha256>>(key, data),
+            HashAlgorithm::Sha384 => mac::<Hmac<Sha384>>(key, data),
+            HashAlgorithm::Sha512 => mac::<Hmac<Sha512>>(key, data),
+        }
+    }
+}
+
+/// Iterated hash of the salt and password, common to the Standard and Agile encryptions
+fn password_hash(hash: HashAlgorithm, salt: &[u8], password: &str, spin_count: u32) -> Vec<u8> {
+    let password = password
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+    let mut h = hash.hash(&[salt, &password]);
+    for i in 0..spin_count {
+        h = hash.hash(&[&i.to_le_bytes(), &h]);
+    }
+    h
+}
+
+/// Truncates or pads, with 0x36 bytes, a hash to the expected length
+fn fit(mut data: Vec<u8>, len: usize) -> Vec<u8> {
+    data.resize(len, 0x36);
+    data
+}
+
+/// Decrypts AES blocks in place, in ECB mode if there is no initialization vector,
+/// else in CBC mode
+fn aes_decrypt(key: &[u8], iv: Option<&[u8]>, data: &mut [u8]) -> Result<(), CryptoError> {
+    fn decrypt<C: BlockDecrypt + KeyInit>(
+        key: &[u8],
+        iv: Option<&[u8]>,
+        data: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        let cipher = C::new_from_slice(key).map_err(|_| CryptoError::Invalid("key length"))?;
+        let mut previous = [0; 16];
+        if let Some(iv) = iv {
+            if iv.len() != 16 {
+                return Err(CryptoError::Invalid("initialization vector length"));
+            }
+            previous.copy_from_slice(iv);
+        }
+        for block in data.chunks_exact_mut(16) {
+            let encrypted: [u8; 16] = block.try_into().unwrap();
+            cipher.decrypt_block(GenericArray::from_mut_slice(block));
+            if iv.is_some() {
+                block.iter_mut().zip(previous).for_each(|(b, p)| *b ^= p);
+                previous = encrypted;
+            }
+        }
+        Ok(())
+    }
+    if data.len() % 16 != 0 {
+        return Err(CryptoError::Invalid("encrypted data length"));
+    }
+    match key.len() {
+        16 => decrypt::<Aes128>(key, iv, data),
+        24 => decrypt::<Aes192>(key, iv, data),
+        32 => decrypt::<Aes256>(key, iv, data),
+        _ => Err(CryptoError::Invalid("key length")),
+    }
+}
+
+/// Decrypts a package with the Standard encryption [MS-OFFCRYPTO 2.3.4.5]
+fn decrypt_standard(info: &[u8], package: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    // EncryptionHeader, after its size, then EncryptionVerifier
+    let header_len = read_usize(info);
+    let header = info
+        .get(4..4 + header_len)
+        .filter(|h| h.len() >= 32)
+        .ok_or(CryptoError::Invalid("EncryptionHeader"))?;
+    let alg_id = read_u32(&header[8..]);
+    let key_len = match (alg_id, read_u32(&header[16..])) {
+        (0x660E..=0x6610, bits) if matches!(bits, 128 | 192 | 256) => bits as usize / 8,
+        (alg_id, _) => {
+            return Err(CryptoError::Unsupported(format!(
+                "algorithm 0x{alg_id:X}, only AES is supported"
+            )))
+        }
+    };
+    let verifier = info
+        .get(4 + header_len..)
+        .filter(|v| v.len() >= 72)
+        .ok_or(CryptoError::Invalid("EncryptionVerifier"))?;
+    let salt = &verifier[4..20];
+
+    // 2.3.4.7 key derivation
+    let h = password_hash(HashAlgorithm::Sha1, salt, password, 50_000);
+    let h = HashAlgorithm::Sha1.hash(&[&h, &0u32.to_le_bytes()]);
+    let derive = |pad: u8| {
+        let mut buf = [pad; 64];
+        buf.iter_mut().zip(&h).for_each(|(b, h)| *b ^= h);
+        HashAlgorithm::Sha1.hash(&[&buf])
+    };
+    let mut key = derive(0x36);
+    key.extend(derive(0x5C));
+    key.truncate(key_len);
+
+    // 2.3.4.9 password verification
+    let mut verifier_hash = verifier[20..36].to_vec();
+    let mut expected = verifier[40..72].to_vec();
+    aes_decrypt(&key, None, &mut verifier_hash)?;
+    aes_decrypt(&key, None, &mut expected)?;
+    if HashAlgorithm::Sha1.hash(&[&verifier_hash]) != expected[..20] {
+        return Err(CryptoError::InvalidPassword);
+    }
+
+    let len = read_usize(package);
+    let mut data = package[8..].to_vec();
+    data.truncate(data.len() / 16 * 16);
+    aes_decrypt(&key, None, &mut data)?;
+    if data.len() < len {
+        return Err(CryptoError::Invalid("EncryptedPackage length"));
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+/// Encryption parameters of the agile encryption descriptor
+#[derive(Debug, Default)]
+struct AgileParams {
+    salt: Vec<u8>,
+    block_size: usize,
+    key_bits: usize,
+    hash_size: usize,
+    hash: String,
+    spin_count: u32,
+    encrypted_verifier_input: Vec<u8>,
+    encrypted_verifier_hash: Vec<u8>,
+    encrypted_key: Vec<u8>,
+    encrypted_hmac_key: Vec<u8>,
+    encrypted_hmac_value: Vec<u8>,
+}
+
+impl AgileParams {
+    fn read(&mut self, e: &BytesStart<'_>) -> Result<(), CryptoError> {
+        for a in e.attributes() {
+            let a = a.map_err(quick_xml::Error::from)?;
+            let value = std::str::from_utf8(&a.value)
+                .map_err(|_| CryptoError::Invalid("attribute encoding"))?;
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| CryptoError::Invalid("number"))
+            };
+            match a.key.as_ref() {
+                b"saltValue" => self.salt = base64(value)?,
+                b"blockSize" => self.block_size = number()?,
+                b"keyBits" => self.key_bits = number()?,
+                b"hashSize" => self.hash_size = number()?,
+                b"hashAlgorithm" => self.hash = value.to_string(),
+                b"spinCount" => self.spin_count = number()? as u32,
+                b"cipherAlgorithm" if value != "AES" => {
+                    return Err(CryptoError::Unsupported(format!("cipher {value}")))
+                }
+                b"cipherChaining" if value != "ChainingModeCBC" => {
+                    return Err(CryptoError::Unsupported(format!("chaining {value}")))
+                }
+                b"encryptedVerifierHashInput" => self.encrypted_verifier_input = base64(value)?,
+                b"encryptedVerifierHashValue" => self.encrypted_verifier_hash = base64(value)?,
+                b"encryptedKeyValue" => self.encrypted_key = base64(value)?,
+                b"encryptedHmacKey" => self.encrypted_hmac_key = base64(value)?,
+                b"encryptedHmacValue" => self.encrypted_hmac_value = base64(value)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypts a value with the key derived from the password and a block key
+    fn decrypt_with_password(
+        &self,
+        hash: HashAlgorithm,
+        password_hash: &[u8],
+        block_key: u64,
+        data: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let key = hash.hash(&[password_hash, &block_key.to_be_bytes()]);
+        let key = fit(key, self.key_bits / 8);
+        let mut data = data.to_vec();
+        aes_decrypt(&key, Some(&self.salt), &mut data)?;
+        Ok(data)
+    }
+}
+
+/// Decrypts a package with the Agile encryption [MS-OFFCRYPTO 2.3.4.10]
+fn decrypt_agile(
+    descriptor: &[u8],
+    package: &[u8],
+    password: &str,
+) -> Result<Vec<u8>, CryptoError> {
+    let mut key_data = AgileParams::default();
+    let mut encryptor = AgileParams::default();
+    let mut xml = XmlReader::from_reader(descriptor);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e) | Event::Empty(ref e) => match e.local_name().as_ref() {
+                b"keyData" | b"dataIntegrity" => key_data.read(e)?,
+                // certificate key encryptors have no spin count
+                b"encryptedKey" if e.try_get_attribute("spinCount")?.is_some() => {
+                    encryptor.read(e)?
+                }
+                _ => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    if encryptor.encrypted_key.is_empty() || key_data.block_size == 0 {
+        return Err(CryptoError::Invalid("missing password key encryptor"));
+    }
+
+    // 2.3.4.13 password verification, then 2.3.4.11 secret key
+    let hash = HashAlgorithm::from_name(&encryptor.hash)?;
+    let h = password_hash(hash, &encryptor.salt, password, encryptor.spin_count);
+    let input = encryptor.decrypt_with_password(
+        hash,
+        &h,
+        0xFEA7_D276_3B4B_9E79,
+        &encryptor.encrypted_verifier_input,
+    )?;
+    let expected = encryptor.decrypt_with_password(
+        hash,
+        &h,
+        0xD7AA_0F6D_3061_344E,
+        &encryptor.encrypted_verifier_hash,
+    )?;
+    let input = &input[..encryptor.salt.len().min(input.len())];
+    let actual = hash.hash(&[input]);
+    if expected.get(..actual.len()) != Some(&actual[..]) {
+        return Err(CryptoError::InvalidPassword);
+    }
+    let mut key = encryptor.decrypt_with_password(
+        hash,
+        &h,
+        0x146E_0BE7_ABAC_D0D6,
+        &encryptor.encrypted_key,
+    )?;
+    key.truncate(key_data.key_bits / 8);
+
+    // 2.3.4.14 data integrity, on the whole EncryptedPackage stream
+    let hash = HashAlgorithm::from_name(&key_data.hash)?;
+    if key_data.encrypted_hmac_key.is_empty() || key_data.encrypted_hmac_value.is_empty() {
+        return Err(CryptoError::Invalid("missing dataIntegrity"));
+    }
+    let iv = |block_key: u64| {
+        let iv = hash.hash(&[&key_data.salt, &block_key.to_be_bytes()]);
+        fit(iv, key_data.block_size)
+    };
+    let mut hmac_key = key_data.encrypted_hmac_key.clone();
+    aes_decrypt(&key, Some(&iv(0x5FB2_AD01_0CB9_E1F6)), &mut hmac_key)?;
+    let mut hmac_value = key_data.encrypted_hmac_value.clone();
+    aes_decrypt(&key, Some(&iv(0xA067_7F02_B22C_8433)), &mut hmac_value)?;
+    let hash_size = key_data.hash_size.min(hmac_key.len()).min(hmac_value.len());
+    if hash_size == 0 || hash.hmac(&hmac_key[..hash_size], package) != hmac_value[..hash_size] {
+        return Err(CryptoError::Integrity);
+    }
+
+    // 2.3.4.15 segments of 4096 bytes, with their own initialization vector
+    let len = read_usize(package);
+    let mut data = package[8..].to_vec();
+    for (i, segment) in data.chunks_mut(4096).enumerate() {
+        let iv = hash.hash(&[&key_data.salt, &(i as u32).to_le_bytes()]);
+        let iv = fit(iv, key_data.block_size);
+        let end = segment.len() / 16 * 16;
+        aes_decrypt(&key, Some(&iv), &mut segment[..end])?;
+    }
+    if data.len() < len {
+        return Err(CryptoError::Invalid("EncryptedPackage length"));
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+/// Decodes a base64 string
+fn base64(s: &str) -> Result<Vec<u8>, CryptoError> {
+    let mut data = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' | b' ' | b'\r' | b'\n' | b'\t' => continue,
+            _ => return Err(CryptoError::Invalid("base64 value")),
+        };
+        acc = (acc << 6 | v as u32) & 0xFFFF;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((acc >> bits) as u8);
+        }
+    }
+    Ok(data)
+}
+
+/// RC4 stream cipher
+struct Rc4 {
+    s: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    fn new(key: &[u8]) -> Rc4 {
+        let mut s = [0u8; 256];
+        s.iter_mut().enumerate().for_each(|(i, s)| *s = i as u8);
+        let mut j = 0u8;
+        for i in 0..256 {
+            j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+            s.swap(i, j as usize);
+        }
+        Rc4 { s, i: 0, j: 0 }
+    }
+
+    /// Next byte of the key stream
+    fn next_byte(&mut self) -> u8 {
+        self.i = self.i.wrapping_add(1);
+        self.j = self.j.wrapping_add(self.s[self.i as usize]);
+        self.s.swap(self.i as usize, self.j as usize);
+        self.s[self.s[self.i as usize].wrapping_add(self.s[self.j as usize]) as usize]
+    }
+
+    fn apply(&mut self, data: &mut [u8]) {
+        data.iter_mut().for_each(|b| *b ^= self.next_byte());
+    }
+}
+
+/// Size of the blocks after which the RC4 ciphers of xls files are rekeyed
+const XLS_BLOCK_SIZE: usize = 1024;
+
+/// Obfuscation or encryption of a xls workbook stream, as described by its FilePass record
+enum XlsCipher {
+    /// XOR obfuscation array [MS-OFFCRYPTO 2.3.7.2]
+    Xor([u8; 16]),
+    /// RC4 encryption, with the MD5 hash of the password and salt [MS-OFFCRYPTO 2.3.6.2]
+    Rc4([u8; 16]),
+    /// RC4 CryptoAPI encryption, with the SHA1 hash of the salt and password
+    /// [MS-OFFCRYPTO 2.3.5.2]
+    CryptoApi { hash: Vec<u8>, key_len: usize },
+}
+
+impl XlsCipher {
+    /// RC4 key of a block
+    fn block_key(&self, block: usize) -> Vec<u8> {
+        let block = (block as u32).to_le_bytes();
+        match self {
+            XlsCipher::Xor(_) => Vec::new(),
+            XlsCipher::Rc4(hash) => Md5::new()
+                .chain_update(&hash[..5])
+                .chain_update(block)
+                .finalize()
+                .to_vec(),
+            XlsCipher::CryptoApi { hash, key_len } => {
+                let mut key = HashAlgorithm::Sha1.hash(&[hash, &block]);
+                key.truncate(*key_len);
+                // 40 bits keys are padded to 128 bits
+                if key.len() == 5 {
+                    key.resize(16, 0);
+                }
+                key
+            }
+        }
+    }
+}
+
+/// Decryptor of the records of a xls workbook stream [MS-XLS 2.2.10]
+pub(crate) struct XlsDecryptor {
+    cipher: XlsCipher,
+    /// RC4 cipher of the current block, with the block and the stream position of its next byte
+    rc4: Option<(Rc4, usize, usize)>,
+}
+
+impl XlsDecryptor {
+    /// Reads the FilePass record and checks the password
+    pub(crate) fn new(file_pass: &[u8], password: &str) -> Result<XlsDecryptor, CryptoError> {
+        let cipher = match (file_pass.len(), read_u16(file_pass)) {
+            // BIFF5 XOR obfuscation has no encryption type
+            (4, _) => xor_cipher(&file_pass[..4], password)?,
+            (6.., 0) => xor_cipher(&file_pass[2..6], password)?,
+            (6.., 1) => match (read_u16(&file_pass[2..]), read_u16(&file_pass[4..])) {
+                (1, 1) => rc4_cipher(&file_pass[6..], password)?,
+                (2..=4, 2) => crypto_api_cipher(&file_pass[6..], password)?,
+                (major, minor) => {
+                    return Err(CryptoError::Unsupported(format!(
+                        "RC4 encryption version {major}.{minor}"
+                    )))
+                }
+            },
+            (_, typ) => {
+                return Err(CryptoError::Unsupported(format!(
+                    "FilePass encryption type {typ}"
+                )))
+            }
+        };
+        Ok(XlsDecryptor { cipher, rc4: None })
+    }
+
+    /// Decrypts in place the data of a record, starting at `offset` in the stream,
+    /// except for its first `skip` bytes which are not encrypted
+    pub(crate) fn decrypt(&mut self, data: &mut [u8], offset: usize, skip: usize) {
+        if let XlsCipher::Xor(array) = &self.cipher {
+            // the obfuscation array index depends on the end of the record
+            let start = offset + data.len();
+            for (i, b) in data.iter_mut().enumerate().skip(skip) {
+                *b = (*b ^ array[(start + i) % 16]).rotate_left(3);
+            }
+            return;
+        }
+        for (pos, b) in (offset..).zip(data.iter_mut()) {
+            let block = pos / XLS_BLOCK_SIZE;
+            if self
+                .rc4
+                .as_ref()
+                .map_or(false, |(_, b, next)| *b != block || *next > pos)
+            {
+                self.rc4 = None;
+            }
+            let cipher = &self.cipher;
+            let (rc4, _, next) = self.rc4.get_or_insert_with(|| {
+                let rc4 = Rc4::new(&cipher.block_key(block));
+                (rc4, block, block * XLS_BLOCK_SIZE)
+            });
+            let mut k = 0;
+            while *next <= pos {
+                k = rc4.next_byte();
+                *next += 1;
+            }
+            if pos - offset >= skip {
+                *b ^= k;
+            }
+        }
+    }
+}
+
+/// Password bytes of the XOR obfuscation, at most 15 [MS-OFFCRYPTO 2.3.7.4]
+fn xor_password(password: &str) -> Vec<u8> {
+    password
+        .encode_utf16()
+        .take(15)
+        .map(|c| match c.to_le_bytes() {
+            [0, high] => high,
+            [low, _] => low,
+        })
+        .collect()
+}
+
+/// XOR obfuscation array of a password, after checking its key and verifier
+/// [MS-OFFCRYPTO 2.3.7.1 and 2.3.7.2]
+fn xor_cipher(file_pass: &[u8], password: &str) -> Result<XlsCipher, CryptoError> {
+    const INITIAL_CODE: [u16; 15] = [
+        0xE1F0, 0x1D0F, 0xCC9C, 0x84C0, 0x110C, 0x0E10, 0xF1CE, 0x313E, 0x1872, 0xE139, 0xD40F,
+        0x84F9, 0x280C, 0xA96A, 0x4EC3,
+    ];
+    const XOR_MATRIX: [u16; 105] = [
+        0xAEFC, 0x4DD9, 0x9BB2, 0x2745, 0x4E8A, 0x9D14, 0x2A09, 0x7B61, 0xF6C2, 0xFDA5, 0xEB6B,
+        0xC6F7, 0x9DCF, 0x2BBF, 0x4563, 0x8AC6, 0x05AD, 0x0B5A, 0x16B4, 0x2D68, 0x5AD0, 0x0375,
+        0x06EA, 0x0DD4, 0x1BA8, 0x3750, 0x6EA0, 0xDD40, 0xD849, 0xA0B3, 0x5147, 0xA28E, 0x553D,
+        0xAA7A, 0x44D5, 0x6F45, 0xDE8A, 0xAD35, 0x4A4B, 0x9496, 0x390D, 0x721A, 0xEB23, 0xC667,
+        0x9CEF, 0x29FF, 0x53FE, 0xA7FC, 0x5FD9, 0x47D3, 0x8FA6, 0x0F6D, 0x1EDA, 0x3DB4, 0x7B68,
+        0xF6D0, 0xB861, 0x60E3, 0xC1C6, 0x93AD, 0x377B, 0x6EF6, 0xDDEC, 0x45A0, 0x8B40, 0x06A1,
+        0x0D42, 0x1A84, 0x3508, 0x6A10, 0xAA51, 0x4483, 0x8906, 0x022D, 0x045A, 0x08B4, 0x1168,
+        0x76B4, 0xED68, 0xCAF1, 0x85C3, 0x1BA7, 0x374E, 0x6E9C, 0x3730, 0x6E60, 0xDCC0, 0xA9A1,
+        0x4363, 0x86C6, 0x1DAD, 0x3331, 0x6662, 0xCCC4, 0x89A9, 0x0373, 0x06E6, 0x0DCC, 0x1021,
+        0x2042, 0x4084, 0x8108, 0x1231, 0x2462, 0x48C4,
+    ];
+    const PAD: [u8; 15] = [
+        0xBB, 0xFF, 0xFF, 0xBA, 0xFF, 0xFF, 0xB9, 0x80, 0x00, 0xBE, 0x0F, 0x00, 0xBF, 0x0F, 0x00,
+    ];
+
+    let password = xor_password(password);
+    if password.is_empty() {
+        return Err(CryptoError::InvalidPassword);
+    }
+    let mut key = INITIAL_CODE[password.len() - 1];
+    let mut element = XOR_MATRIX.len();
+    for &c in password.iter().rev() {
+        let mut c = c;
+        for _ in 0..7 {
+            element -= 1;
+            if c & 0x40 != 0 {
+                key ^= XOR_MATRIX[element];
+            }
+            c <<= 1;
+        }
+    }
+    let mut verifier = 0u16;
+    for &c in password.iter().rev().chain(&[password.len() as u8]) {
+        verifier = ((verifier >> 14) & 1 | (verifier << 1) & 0x7FFF) ^ c as u16;
+    }
+    verifier ^= 0xCE4B;
+    if key != read_u16(file_pass) || verifier != read_u16(&file_pass[2..]) {
+        return Err(CryptoError::InvalidPassword);
+    }
+
+    // the password, padded, xored alternatively with the low and high bytes of the key
+    let mut array = [0u8; 16];
+    let bytes = key.to_le_bytes();
+    password
+        .iter()
+        .chain(&PAD)
+        .zip(array.iter_mut())
+        .enumerate()
+        .for_each(|(i, (&c, a))| *a = (c ^ bytes[i % 2]).rotate_right(1));
+    Ok(XlsCipher::Xor(array))
+}
+
+/// RC4 encryption key, after checking the password verifier [MS-OFFCRYPTO 2.3.6]
+fn rc4_cipher(data: &[u8], password: &str) -> Result<XlsCipher, CryptoError> {
+    let data = data
+        .get(..48)
+        .ok_or(CryptoError::Invalid("RC4 encryption header"))?;
+    let password = password
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+    let h = Md5::digest(password);
+    let mut truncated = Md5::new();
+    for _ in 0..16 {
+        truncated.update(&h[..5]);
+        truncated.update(&data[..16]);
+    }
+    let cipher = XlsCipher::Rc4(truncated.finalize().into());
+
+    let mut verifier = data[16..48].to_vec();
+    Rc4::new(&cipher.block_key(0)).apply(&mut verifier);
+    if Md5::digest(&verifier[..16])[..] != verifier[16..] {
+        return Err(CryptoError::InvalidPassword);
+    }
+    Ok(cipher)
+}
+
+/// RC4 CryptoAPI encryption key, after checking the password verifier [MS-OFFCRYPTO 2.3.5]
+fn crypto_api_cipher(data: &[u8], password: &str) -> Result<XlsCipher, CryptoError> {
+    // Flags, then EncryptionHeader, after its size, then EncryptionVerifier
+    let header_len = data.get(4..8).map_or(0, read_usize);
+    let header = data
+        .get(8..8 + header_len)
+        .filter(|h| h.len() >= 32)
+        .ok_or(CryptoError::Invalid("EncryptionHeader"))?;
+    let key_len = match (read_u32(&header[8..]), read_u32(&header[16..])) {
+        (0x6801, 0) => 5,
+        (0x6801, bits) if bits % 8 == 0 && (40..=128).contains(&bits) => bits as usize / 8,
+        (alg_id, bits) => {
+            return Err(CryptoError::Unsupported(format!(
+                "algorithm 0x{alg_id:X} with {bits} bits keys"
+            )))
+        }
+    };
+    let verifier = data
+        .get(8 + header_len..)
+        .filter(|v| v.len() >= 60)
+        .ok_or(CryptoError::Invalid("EncryptionVerifier"))?;
+    let salt = &verifier[4..20];
+    let cipher = XlsCipher::CryptoApi {
+        hash: password_hash(HashAlgorithm::Sha1, salt, password, 0),
+        key_len,
+    };
+
+    let mut encrypted = verifier[20..36].to_vec();
+    encrypted.extend(&verifier[40..60]);
+    Rc4::new(&cipher.block_key(0)).apply(&mut encrypted);
+    if HashAlgorithm::Sha1.hash(&[&encrypted[..16]]) != encrypted[16..] {
+        return Err(CryptoError::InvalidPassword);
+    }
+    Ok(cipher)
+}
@@ -104,6 +104,18 @@ impl DataType {
             _ => None,
         }
     }
+    /// Format the value as displayed by Excel with the `format` number format code
+    ///
+    /// ```
+    /// use calamine::DataType;
+    ///
+    /// assert_eq!(DataType::Float(0.125).format_with("0.00%"), "12.50%");
+    /// assert_eq!(DataType::Float(1234.).format_with("#,##0.00"), "1,234.00");
+    /// assert_eq!(DataType::DateTime(45294.).format_with("dd-mmm-yy"), "03-Jan-24");
+    /// ```
+    pub fn format_with(&self, format: &str) -> String {
+        crate::number_format::format_value(self, format)
+    }
     /// Try converting data type into an int
     pub fn as_i64(&self) -> Option<i64> {
         match self {
@@ -179,7 +191,7 @@ impl DataType {
             DataType::Int(x) => {
                 let days = x - 25569;
                 let secs = days * 86400;
-                chrono::NaiveDateTime::from_timestamp_opt(secs, 0)
+                chrono::DateTime::from_timestamp(secs, 0).map(|dt| dt.naive_utc())
             }
             DataType::Float(f) | DataType::DateTime(f) => {
                 let excel_epoch = EXCEL_EPOCH.get_or_init(|| {
@@ -201,10 +213,7 @@ impl DataType {
 
 impl PartialEq<&str> for DataType {
     fn eq(&self, other: &&str) -> bool {
-        match *self {
-            DataType::String(ref s) if s == other => true,
-            _ => false,
-        }
+        matches!(*self, DataType::String(ref s) if s == other)
     }
 }
 
@@ -412,6 +421,7 @@ mod date_tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::excessive_precision)]
     fn test_dates() {
         use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 
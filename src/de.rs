@@ -1,6 +1,7 @@
 use serde::de::value::BorrowedStrDeserializer;
 use serde::de::{self, DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
 use serde::{self, forward_to_deserialize_any, Deserialize};
+use std::borrow::Cow;
 use std::marker::PhantomData;
 use std::{fmt, slice, str};
 
@@ -8,6 +9,7 @@ use super::{CellErrorType, CellType, DataType, Range, Rows};
 
 /// A cell deserialization specific error enum
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DeError {
     /// Cell out of range
     CellOutOfRange {
@@ -32,6 +34,17 @@ pub enum DeError {
     HeaderNotFound(String),
     /// Serde specific error
     Custom(String),
+    /// Cannot deserialize a cell
+    Cell {
+        /// Absolute cell position
+        pos: (u32, u32),
+        /// Header of the cell column, if any
+        header: Option<String>,
+        /// Cell value
+        value: DataType,
+        /// Deserialization error
+        error: Box<DeError>,
+    },
 }
 
 impl fmt::Display for DeError {
@@ -55,13 +68,49 @@ impl fmt::Display for DeError {
                 write!(f, "Cannot find header named '{}'", header)
             }
             DeError::Custom(ref s) => write!(f, "{}", s),
+            DeError::Cell {
+                ref pos,
+                ref header,
+                ref value,
+                ref error,
+            } => {
+                write!(f, "Cannot deserialize cell at position '{:?}'", pos)?;
+                if let Some(header) = header {
+                    write!(f, " (column '{}')", header)?;
+                }
+                write!(f, " with value {:?}: {}", value, error)
+            }
         }
     }
 }
 
 impl std::error::Error for DeError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        match *self {
+            DeError::Cell { ref error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+/// Error of a row which could not be deserialized
+#[derive(Debug)]
+pub struct RowError {
+    /// Absolute row index
+    pub row: u32,
+    /// Deserialization error
+    pub error: DeError,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "Row {}: {}", self.row, self.error)
+    }
+}
+
+impl std::error::Error for RowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
     }
 }
 
@@ -78,6 +127,71 @@ pub enum Headers<'h, H> {
     Custom(&'h [H]),
 }
 
+/// How header cells are compared to field names and custom headers
+#[derive(Clone, Debug, Default)]
+struct HeaderMatching {
+    case_insensitive: bool,
+    trim: bool,
+    snake_case: bool,
+    /// Alternative headers, by field name
+    aliases: Vec<(String, Vec<String>)>,
+}
+
+impl HeaderMatching {
+    fn is_exact(&self) -> bool {
+        !self.case_insensitive && !self.trim && !self.snake_case && self.aliases.is_empty()
+    }
+
+    fn normalize<'a>(&self, header: &'a str) -> Cow<'a, str> {
+        let header = if self.trim { header.trim() } else { header };
+        if self.snake_case {
+            Cow::Owned(snake_case(header))
+        } else if self.case_insensitive {
+            Cow::Owned(header.to_lowercase())
+        } else {
+            Cow::Borrowed(header)
+        }
+    }
+
+    /// Whether a normalized header matches a name or one of its aliases
+    fn matches(&self, header: &str, name: &str) -> bool {
+        self.normalize(name) == header
+            || self
+                .aliases
+                .iter()
+                .filter(|(n, _)| n == name)
+                .flat_map(|(_, aliases)| aliases)
+                .any(|alias| self.normalize(alias) == header)
+    }
+}
+
+/// Converts a header to snake_case, e.g. `Order ID`, `ORDER-ID` and `OrderId` to `order_id`
+fn snake_case(header: &str) -> String {
+    let mut snake = String::with_capacity(header.len());
+    let mut chars = header.chars().peekable();
+    let mut prev: Option<char> = None;
+    while let Some(c) = chars.next() {
+        if !c.is_alphanumeric() {
+            prev = None;
+            continue;
+        }
+        let boundary = match prev {
+            None => !snake.is_empty(),
+            // `orderId`, or the last capital of an acronym in `HTTPServer`
+            Some(p) if c.is_uppercase() => {
+                !p.is_uppercase() || chars.peek().map_or(false, |n| n.is_lowercase())
+            }
+            Some(_) => false,
+        };
+        if boundary {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+        prev = Some(c);
+    }
+    snake
+}
+
 /// Builds a `Range` deserializer with some configuration options.
 ///
 /// This can be used to optionally parse the first row as a header. Once built,
@@ -85,12 +199,18 @@ pub enum Headers<'h, H> {
 #[derive(Clone)]
 pub struct RangeDeserializerBuilder<'h, H> {
     headers: Headers<'h, H>,
+    header_row: usize,
+    header_rows: usize,
+    matching: HeaderMatching,
 }
 
 impl Default for RangeDeserializerBuilder<'static, &'static str> {
     fn default() -> Self {
         RangeDeserializerBuilder {
             headers: Headers::All,
+            header_row: 0,
+            header_rows: 1,
+            matching: HeaderMatching::default(),
         }
     }
 }
@@ -170,7 +290,109 @@ impl<'h, H: AsRef<str> + Clone + 'h> RangeDeserializerBuilder<'h, H> {
     pub fn with_headers(headers: &'h [H]) -> Self {
         RangeDeserializerBuilder {
             headers: Headers::Custom(headers),
+            header_row: 0,
+            header_rows: 1,
+            matching: HeaderMatching::default(),
+        }
+    }
+
+    /// Match headers regardless of their case.
+    ///
+    /// Applies to custom headers and to the fields of deserialized structs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use calamine::{open_workbook, Error, Xlsx, Reader, RangeDeserializerBuilder};
+    /// fn main() -> Result<(), Error> {
+    ///     let path = format!("{}/tests/temperature.xlsx", env!("CARGO_MANIFEST_DIR"));
+    ///     let mut workbook: Xlsx<_> = open_workbook(path)?;
+    ///     let range = workbook.worksheet_range("Sheet1")?;
+    ///     let mut iter = RangeDeserializerBuilder::with_headers(&["VALUE", "Label"])
+    ///         .case_insensitive(true)
+    ///         .from_range(&range)?;
+    ///
+    ///     let (value, label): (f64, String) = iter.next().unwrap()?;
+    ///     assert_eq!(label, "celsius");
+    ///     assert_eq!(value, 22.2222);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.matching.case_insensitive = yes;
+        self
+    }
+
+    /// Ignore leading and trailing whitespaces of the header cells.
+    pub fn trim_headers(&mut self, yes: bool) -> &mut Self {
+        self.matching.trim = yes;
+        self
+    }
+
+    /// Normalize headers to snake_case before matching them.
+    ///
+    /// `Order ID`, `order_id ` and `ORDER-ID` all match an `order_id` field.
+    pub fn snake_case_headers(&mut self, yes: bool) -> &mut Self {
+        self.matching.snake_case = yes;
+        self
+    }
+
+    /// Accept other headers for a field, or for a custom header.
+    ///
+    /// Aliases are normalized like the header cells.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use calamine::{open_workbook, Error, Xlsx, Reader, RangeDeserializerBuilder};
+    /// # use serde_derive::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Temperature {
+    ///     unit: String,
+    ///     value: f64,
+    /// }
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let path = format!("{}/tests/temperature.xlsx", env!("CARGO_MANIFEST_DIR"));
+    ///     let mut workbook: Xlsx<_> = open_workbook(path)?;
+    ///     let range = workbook.worksheet_range("Sheet1")?;
+    ///     let mut iter = RangeDeserializerBuilder::new()
+    ///         .header_aliases("unit", &["label"])
+    ///         .from_range(&range)?;
+    ///
+    ///     let temperature: Temperature = iter.next().unwrap()?;
+    ///     assert_eq!(temperature.unit, "celsius");
+    ///     assert_eq!(temperature.value, 22.2222);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn header_aliases(&mut self, name: &str, aliases: &[&str]) -> &mut Self {
+        let aliases = aliases.iter().map(|alias| alias.to_string());
+        match self.matching.aliases.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => existing.extend(aliases),
+            None => self
+                .matching
+                .aliases
+                .push((name.to_string(), aliases.collect())),
         }
+        self
+    }
+
+    /// Read the header at this row of the range, skipping the rows above it.
+    ///
+    /// Defaults to 0, the first row. Has no effect without headers.
+    pub fn header_row(&mut self, row: usize) -> &mut Self {
+        self.header_row = row;
+        self
+    }
+
+    /// Read the header over several rows, starting at the header row.
+    ///
+    /// The non-empty cells of each column are joined with a space, e.g. a
+    /// `Order` cell above an `ID` cell gives a `Order ID` header.
+    pub fn header_rows(&mut self, count: usize) -> &mut Self {
+        self.header_rows = count.max(1);
+        self
     }
 
     /// Build a `RangeDeserializer` from this configuration.
@@ -238,9 +460,12 @@ where
 {
     column_indexes: Vec<usize>,
     headers: Option<Vec<String>>,
+    /// Headers as compared to struct fields, when they are not matched exactly
+    normalized_headers: Option<Vec<String>>,
+    matching: HeaderMatching,
     rows: Rows<'cell, T>,
+    /// Position of the next row
     current_pos: (u32, u32),
-    end_pos: (u32, u32),
     _priv: PhantomData<D>,
 }
 
@@ -256,57 +481,118 @@ where
         let mut rows = range.rows();
 
         let mut current_pos = range.start().unwrap_or((0, 0));
-        let end_pos = range.end().unwrap_or((0, 0));
 
-        let (column_indexes, headers) = match builder.headers {
-            Headers::None => ((0..range.width()).collect(), None),
-            Headers::All => {
-                if let Some(row) = rows.next() {
-                    let all_indexes = (0..row.len()).collect::<Vec<_>>();
-                    let all_headers = {
-                        let de = RowDeserializer::new(&all_indexes, None, row, current_pos);
-                        current_pos.0 += 1;
-                        Deserialize::deserialize(de)?
-                    };
-                    (all_indexes, Some(all_headers))
-                } else {
-                    (Vec::new(), None)
-                }
-            }
-            Headers::Custom(headers) => {
-                if let Some(row) = rows.next() {
+        let headers = match builder.headers {
+            Headers::None => None,
+            Headers::All | Headers::Custom(_) => {
+                current_pos.0 += rows.by_ref().take(builder.header_row).count() as u32;
+                let mut headers: Option<Vec<String>> = None;
+                for row in rows.by_ref().take(builder.header_rows) {
                     let all_indexes = (0..row.len()).collect::<Vec<_>>();
-                    let de = RowDeserializer::new(&all_indexes, None, row, current_pos);
+                    let de = RowDeserializer::new(&all_indexes, None, None, row, current_pos);
                     current_pos.0 += 1;
-                    let all_headers: Vec<String> = Deserialize::deserialize(de)?;
-                    let custom_indexes = headers
-                        .iter()
-                        .map(|h| h.as_ref().trim())
-                        .map(|h| {
-                            all_headers
-                                .iter()
-                                .position(|header| header.trim() == h)
-                                .ok_or_else(|| DeError::HeaderNotFound(h.to_owned()))
-                        })
-                        .collect::<Result<Vec<_>, DeError>>()?;
-                    (custom_indexes, Some(all_headers))
-                } else {
-                    (Vec::new(), None)
+                    let row: Vec<String> = Deserialize::deserialize(de)?;
+                    headers = Some(match headers {
+                        Some(above) => join_headers(above, row),
+                        None => row,
+                    });
                 }
+                headers
             }
         };
+        let matching = builder.matching.clone();
+
+        let column_indexes = match (&builder.headers, &headers) {
+            (Headers::None, _) => (0..range.width()).collect(),
+            (Headers::Custom(custom), Some(all_headers)) => {
+                let all_headers = all_headers
+                    .iter()
+                    .map(|header| matching.normalize(header.trim()))
+                    .collect::<Vec<_>>();
+                custom
+                    .iter()
+                    .map(|h| h.as_ref().trim())
+                    .map(|h| {
+                        all_headers
+                            .iter()
+                            .position(|header| matching.matches(header, h))
+                            .ok_or_else(|| DeError::HeaderNotFound(h.to_owned()))
+                    })
+                    .collect::<Result<Vec<_>, DeError>>()?
+            }
+            (_, Some(all_headers)) => (0..all_headers.len()).collect(),
+            (_, None) => Vec::new(),
+        };
+        // struct fields are only known once deserializing
+        let normalized_headers = match &headers {
+            Some(headers) if !matching.is_exact() => Some(
+                headers
+                    .iter()
+                    .map(|header| matching.normalize(header).into_owned())
+                    .collect(),
+            ),
+            _ => None,
+        };
 
         Ok(RangeDeserializer {
             column_indexes,
             headers,
+            normalized_headers,
+            matching,
             rows,
             current_pos,
-            end_pos,
             _priv: PhantomData,
         })
     }
 }
 
+impl<'cell, T, D> RangeDeserializer<'cell, T, D>
+where
+    T: ToCellDeserializer<'cell>,
+    D: DeserializeOwned,
+{
+    /// Deserializes all the remaining rows, without stopping at the failing ones.
+    ///
+    /// Returns the deserialized rows, and the errors of all the rows which
+    /// could not be deserialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use calamine::{open_workbook, Error, Xlsx, Reader, RangeDeserializerBuilder};
+    /// fn main() -> Result<(), Error> {
+    ///     let path = format!("{}/tests/temperature.xlsx", env!("CARGO_MANIFEST_DIR"));
+    ///     let mut workbook: Xlsx<_> = open_workbook(path)?;
+    ///     let range = workbook.worksheet_range("Sheet1")?;
+    ///
+    ///     // labels are not numbers
+    ///     let (rows, errors) = RangeDeserializerBuilder::new()
+    ///         .has_headers(false)
+    ///         .from_range::<_, (f64, f64)>(&range)?
+    ///         .collect_lenient();
+    ///     assert!(rows.is_empty());
+    ///     assert_eq!(errors.len(), 3);
+    ///     assert_eq!(errors[1].row, 1);
+    ///     for error in errors {
+    ///         println!("{}", error);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn collect_lenient(mut self) -> (Vec<D>, Vec<RowError>) {
+        let mut rows = Vec::new();
+        let mut errors = Vec::new();
+        loop {
+            let row = self.current_pos.0;
+            match self.next() {
+                Some(Ok(d)) => rows.push(d),
+                Some(Err(error)) => errors.push(RowError { row, error }),
+                None => return (rows, errors),
+            }
+        }
+    }
+}
+
 impl<'cell, T, D> Iterator for RangeDeserializer<'cell, T, D>
 where
     T: ToCellDeserializer<'cell>,
@@ -318,31 +604,51 @@ where
         let RangeDeserializer {
             ref column_indexes,
             ref headers,
+            ref normalized_headers,
+            ref matching,
             ref mut rows,
-            mut current_pos,
+            ref mut current_pos,
             ..
         } = *self;
 
-        if let Some(row) = rows.next() {
-            current_pos.0 += 1;
-            let headers = headers.as_ref().map(|h| &**h);
-            let de = RowDeserializer::new(column_indexes, headers, row, current_pos);
-            Some(Deserialize::deserialize(de))
-        } else {
-            None
-        }
+        let row = rows.next()?;
+        let headers = headers.as_ref().map(|h| &**h);
+        let matching = normalized_headers.as_ref().map(|h| (matching, &**h));
+        let de = RowDeserializer::new(column_indexes, headers, matching, row, *current_pos);
+        current_pos.0 += 1;
+        Some(Deserialize::deserialize(de))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = (self.end_pos.0 - self.current_pos.0) as usize;
-
-        (remaining, Some(remaining))
+        self.rows.size_hint()
     }
 }
 
+/// Joins the cells of two header rows, column by column
+fn join_headers(above: Vec<String>, below: Vec<String>) -> Vec<String> {
+    let len = above.len().max(below.len());
+    let mut above = above.into_iter();
+    let mut below = below.into_iter();
+    (0..len)
+        .map(|_| {
+            let above = above.next().unwrap_or_default();
+            let below = below.next().unwrap_or_default();
+            match (above.trim(), below.trim()) {
+                ("", _) => below,
+                (_, "") => above,
+                (a, b) => format!("{} {}", a, b),
+            }
+        })
+        .collect()
+}
+
 struct RowDeserializer<'header, 'cell, T> {
     cells: &'cell [T],
     headers: Option<&'header [String]>,
+    /// Header matching options and normalized headers, when not matched exactly
+    matching: Option<(&'header HeaderMatching, &'header [String])>,
+    /// Fields of the deserialized struct
+    fields: &'static [&'static str],
     iter: slice::Iter<'header, usize>, // iterator over column indexes
     peek: Option<usize>,
     pos: (u32, u32),
@@ -355,12 +661,15 @@ where
     fn new(
         column_indexes: &'header [usize],
         headers: Option<&'header [String]>,
+        matching: Option<(&'header HeaderMatching, &'header [String])>,
         cells: &'cell [T],
         pos: (u32, u32),
     ) -> Self {
         RowDeserializer {
             iter: column_indexes.iter(),
             headers,
+            matching,
+            fields: &[],
             cells,
             pos,
             peek: None,
@@ -370,6 +679,21 @@ where
     fn has_headers(&self) -> bool {
         self.headers.is_some()
     }
+
+    /// Absolute position of the cell at this index of the row
+    fn cell_pos(&self, i: usize) -> (u32, u32) {
+        (self.pos.0, self.pos.1 + i as u32)
+    }
+
+    /// Attaches the position, header and value of a cell to its deserialization error
+    fn cell_error(&self, i: usize, error: DeError) -> DeError {
+        DeError::Cell {
+            pos: self.cell_pos(i),
+            header: self.headers.and_then(|h| h.get(i)).cloned(),
+            value: self.cells[i].to_data_type(),
+            error: Box::new(error),
+        }
+    }
 }
 
 impl<'de, 'header, 'cell, T> serde::Deserializer<'de> for RowDeserializer<'header, 'cell, T>
@@ -396,11 +720,12 @@ where
     }
 
     fn deserialize_struct<V: Visitor<'de>>(
-        self,
+        mut self,
         _name: &'static str,
-        _cells: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
+        self.fields = fields;
         if self.has_headers() {
             visitor.visit_map(self)
         } else {
@@ -427,10 +752,12 @@ where
     where
         D: DeserializeSeed<'de>,
     {
-        match self.iter.next().map(|i| &self.cells[*i]) {
-            Some(value) => {
-                let de = value.to_cell_deserializer(self.pos);
-                seed.deserialize(de).map(Some)
+        match self.iter.next() {
+            Some(&i) => {
+                let de = self.cells[i].to_cell_deserializer(self.cell_pos(i));
+                seed.deserialize(de)
+                    .map(Some)
+                    .map_err(|e| self.cell_error(i, e))
             }
             None => Ok(None),
         }
@@ -463,7 +790,17 @@ where
         for i in self.iter.by_ref() {
             if !self.cells[*i].is_empty() {
                 self.peek = Some(*i);
-                let de = BorrowedStrDeserializer::<Self::Error>::new(&headers[*i]);
+                // keys are the matching field names, if any
+                let key = match self.matching {
+                    Some((matching, normalized)) => self
+                        .fields
+                        .iter()
+                        .find(|field| matching.matches(&normalized[*i], field))
+                        .copied()
+                        .unwrap_or(&headers[*i]),
+                    None => &headers[*i],
+                };
+                let de = BorrowedStrDeserializer::<Self::Error>::new(key);
                 return seed.deserialize(de).map(Some);
             }
         }
@@ -474,13 +811,12 @@ where
         &mut self,
         seed: K,
     ) -> Result<K::Value, Self::Error> {
-        let cell = self
+        let i = self
             .peek
             .take()
-            .map(|i| &self.cells[i])
             .ok_or(DeError::UnexpectedEndOfRow { pos: self.pos })?;
-        let de = cell.to_cell_deserializer(self.pos);
-        seed.deserialize(de)
+        let de = self.cells[i].to_cell_deserializer(self.cell_pos(i));
+        seed.deserialize(de).map_err(|e| self.cell_error(i, e))
     }
 }
 
@@ -494,6 +830,13 @@ pub trait ToCellDeserializer<'a>: CellType {
 
     /// Assess if the cell is empty.
     fn is_empty(&self) -> bool;
+
+    /// The cell value, reported in deserialization errors.
+    ///
+    /// Defaults to `DataType::Empty`.
+    fn to_data_type(&self) -> DataType {
+        DataType::Empty
+    }
 }
 
 impl<'a> ToCellDeserializer<'a> for DataType {
@@ -510,6 +853,10 @@ impl<'a> ToCellDeserializer<'a> for DataType {
     fn is_empty(&self) -> bool {
         matches!(self, DataType::Empty)
     }
+
+    fn to_data_type(&self) -> DataType {
+        self.clone()
+    }
 }
 
 macro_rules! deserialize_num {
@@ -754,4 +1101,144 @@ mod tests {
             Content::Foo
         );
     }
+
+    #[test]
+    fn test_snake_case() {
+        for (header, snake) in [
+            ("Order ID", "order_id"),
+            ("order_id ", "order_id"),
+            ("ORDER-ID", "order_id"),
+            ("OrderId", "order_id"),
+            ("HTTPServer 2", "http_server_2"),
+            ("  (Unit price) ", "unit_price"),
+        ] {
+            assert_eq!(super::snake_case(header), snake, "{header}");
+        }
+    }
+
+    #[test]
+    fn test_header_matching() {
+        use super::{DataType, DeError, RangeDeserializerBuilder};
+        use crate::Range;
+
+        #[derive(Debug, serde_derive::Deserialize, PartialEq)]
+        struct Order {
+            order_id: u32,
+            customer_name: String,
+            amount: f64,
+        }
+
+        let mut range = Range::new((0, 0), (3, 2));
+        range.set_value((0, 0), DataType::String("Orders".to_string()));
+        range.set_value((1, 0), DataType::String("Order".to_string()));
+        range.set_value((1, 1), DataType::String("Customer".to_string()));
+        range.set_value((2, 0), DataType::String("ID ".to_string()));
+        range.set_value((2, 1), DataType::String("Name".to_string()));
+        range.set_value((2, 2), DataType::String("AMOUNT".to_string()));
+        range.set_value((3, 0), DataType::Float(1.));
+        range.set_value((3, 1), DataType::String("Ann".to_string()));
+        range.set_value((3, 2), DataType::Float(2.5));
+
+        let orders = RangeDeserializerBuilder::new()
+            .header_row(1)
+            .header_rows(2)
+            .snake_case_headers(true)
+            .from_range(&range)
+            .unwrap()
+            .collect::<Result<Vec<Order>, _>>()
+            .unwrap();
+        let expected = Order {
+            order_id: 1,
+            customer_name: "Ann".to_string(),
+            amount: 2.5,
+        };
+        assert_eq!(orders, [expected]);
+
+        // custom headers, with aliases
+        let mut range = Range::new((0, 0), (1, 1));
+        range.set_value((0, 0), DataType::String(" ORDER_ID ".to_string()));
+        range.set_value((0, 1), DataType::String("amount".to_string()));
+        range.set_value((1, 0), DataType::Float(1.));
+        range.set_value((1, 1), DataType::Float(2.5));
+        let mut builder = RangeDeserializerBuilder::with_headers(&["total", "order_id"]);
+        builder
+            .case_insensitive(true)
+            .trim_headers(true)
+            .header_aliases("total", &["Amount"]);
+        let rows = builder
+            .from_range(&range)
+            .unwrap()
+            .collect::<Result<Vec<(f64, u32)>, _>>()
+            .unwrap();
+        assert_eq!(rows, [(2.5, 1)]);
+
+        // exact matching by default
+        let builder = RangeDeserializerBuilder::with_headers(&["Amount"]);
+        assert!(matches!(
+            builder.from_range::<_, (f64,)>(&range),
+            Err(DeError::HeaderNotFound(h)) if h == "Amount"
+        ));
+    }
+
+    #[test]
+    fn test_cell_errors() {
+        use super::{DataType, DeError, RangeDeserializerBuilder};
+        use crate::{CellErrorType, Range};
+
+        #[derive(Debug, serde_derive::Deserialize, PartialEq)]
+        struct Row {
+            name: String,
+            qty: u32,
+        }
+
+        let mut range = Range::new((2, 1), (5, 2));
+        range.set_value((2, 1), DataType::String("name".to_string()));
+        range.set_value((2, 2), DataType::String("qty".to_string()));
+        range.set_value((3, 1), DataType::String("a".to_string()));
+        range.set_value((3, 2), DataType::String("many".to_string()));
+        range.set_value((4, 1), DataType::String("b".to_string()));
+        range.set_value((4, 2), DataType::Float(2.));
+        range.set_value((5, 1), DataType::String("c".to_string()));
+        range.set_value((5, 2), DataType::Error(CellErrorType::NA));
+
+        let mut rows = RangeDeserializerBuilder::new()
+            .from_range::<_, Row>(&range)
+            .unwrap();
+        match rows.next() {
+            Some(Err(DeError::Cell {
+                pos,
+                header,
+                value,
+                error,
+            })) => {
+                assert_eq!(pos, (3, 2));
+                assert_eq!(header.as_deref(), Some("qty"));
+                assert_eq!(value, DataType::String("many".to_string()));
+                assert!(matches!(*error, DeError::Custom(_)));
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+        assert_eq!(rows.size_hint(), (2, Some(2)));
+
+        let (rows, errors) = RangeDeserializerBuilder::new()
+            .from_range::<_, Row>(&range)
+            .unwrap()
+            .collect_lenient();
+        let expected = Row {
+            name: "b".to_string(),
+            qty: 2,
+        };
+        assert_eq!(rows, [expected]);
+        assert_eq!(errors.iter().map(|e| e.row).collect::<Vec<_>>(), [3, 5]);
+        assert!(matches!(
+            &errors[1].error,
+            DeError::Cell { pos: (5, 2), error, .. }
+                if matches!(**error, DeError::CellError { pos: (5, 2), .. })
+        ));
+        assert_eq!(
+            errors[1].to_string(),
+            "Row 5: Cannot deserialize cell at position '(5, 2)' (column 'qty') \
+             with value Error(NA): Cell error at position '(5, 2)': #N/A"
+        );
+    }
 }
@@ -0,0 +1,1863 @@
+//! Formula evaluation
+//!
+//! Some writers (e.g. openpyxl, or xlsxwriter without recalculation) store
+//! formulas without their computed value, which calamine then reads as
+//! `DataType::Empty`. An `Evaluator` computes these values from the data of
+//! the workbook itself.
+//!
+//! Cells with a cached value keep it, only formulas without a cached value are
+//! evaluated. Errors propagate as `DataType::Error`, and circular references
+//! evaluate to `#VALUE!`.
+//!
+//! Supported functions:
+//! - logical: `IF`, `AND`, `OR`, `NOT`, `IFERROR`, `IFNA`, `ISBLANK`, `ISERR`,
+//!   `ISERROR`, `ISNA`, `ISNUMBER`, `ISTEXT`, `ISLOGICAL`, `TRUE`, `FALSE`, `NA`
+//! - math: `SUM`, `PRODUCT`, `AVERAGE`, `MIN`, `MAX`, `COUNT`, `COUNTA`,
+//!   `COUNTBLANK`, `SUMIF`, `SUMIFS`, `COUNTIF`, `COUNTIFS`, `AVERAGEIF`,
+//!   `SUMPRODUCT`, `ABS`, `INT`, `TRUNC`, `ROUND`, `ROUNDUP`, `ROUNDDOWN`,
+//!   `MOD`, `POWER`, `SQRT`, `SIGN`, `EXP`, `LN`, `LOG10`, `PI`
+//! - lookup: `VLOOKUP`, `HLOOKUP`, `INDEX`, `MATCH`, `CHOOSE`, `ROW`, `COLUMN`,
+//!   `ROWS`, `COLUMNS`
+//! - text: `CONCATENATE`, `CONCAT`, `TEXTJOIN`, `LEN`, `LEFT`, `RIGHT`, `MID`,
+//!   `UPPER`, `LOWER`, `TRIM`, `TEXT`, `VALUE`, `FIND`, `SEARCH`, `SUBSTITUTE`,
+//!   `REPT`, `EXACT`
+//! - date: `DATE`, `TIME`, `YEAR`, `MONTH`, `DAY`, `HOUR`, `MINUTE`, `SECOND`,
+//!   `WEEKDAY`, `EDATE`, `EOMONTH`, `DAYS`, `TODAY`, `NOW`
+//!
+//! This module is only available with the `eval` feature.
+//!
+//! ```
+//! use calamine::eval::Evaluator;
+//! use calamine::{open_workbook, Xlsx};
+//!
+//! # let path = format!("{}/tests/shared_formula.xlsx", env!("CARGO_MANIFEST_DIR"));
+//! let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+//! let mut evaluator = Evaluator::new(&mut workbook);
+//! let range = evaluator.worksheet_range("Sheet1").unwrap();
+//! println!("B1 = {:?}", range.get_value((0, 1)));
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::formula::{self, BinaryOp, CellRef, Expr, SheetRef, UnaryOp};
+use crate::number_format::{date_to_serial, format_value, serial_to_date};
+use crate::{Cell, CellErrorType, DataType, Dimensions, Range, Reader, SheetType};
+
+/// Areas larger than this are clipped to the used part of the worksheet
+/// when read as arrays, e.g. whole columns
+const MAX_ARRAY_LEN: u64 = 100_000;
+
+/// Maximum nesting of formula evaluations, deeper formulas evaluate to `#VALUE!`
+const MAX_DEPTH: usize = 1_000;
+
+type EvalResult<T> = Result<T, CellErrorType>;
+
+/// An evaluated expression
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(DataType),
+    /// An area, on one or several (3D reference) consecutive sheets
+    Ref {
+        sheets: (usize, usize),
+        area: Dimensions,
+    },
+    Array(Vec<Vec<DataType>>),
+}
+
+impl From<DataType> for Value {
+    fn from(v: DataType) -> Value {
+        Value::Scalar(v)
+    }
+}
+
+impl From<CellErrorType> for Value {
+    fn from(e: CellErrorType) -> Value {
+        Value::Scalar(DataType::Error(e))
+    }
+}
+
+/// Position of the formula being evaluated
+#[derive(Debug, Clone, Copy)]
+struct Ctx {
+    sheet: usize,
+    pos: (u32, u32),
+}
+
+/// Worksheet data, read when first referenced
+struct Sheet {
+    name: String,
+    /// `None` until read, `Some(false)` if the worksheet could not be read
+    loaded: Option<bool>,
+    values: Range<DataType>,
+    formulas: Range<Expr>,
+}
+
+/// Reads the values and the formulas of a worksheet
+type Loader<'a> = Box<dyn FnMut(&str) -> Option<(Range<DataType>, Range<Expr>)> + 'a>;
+
+/// Computes the value of formulas from the workbook data
+pub struct Evaluator<'a> {
+    loader: Loader<'a>,
+    sheets: Vec<Sheet>,
+    /// Defined names, upper case
+    names: HashMap<String, Expr>,
+    /// Values of the evaluated formulas
+    cache: HashMap<(usize, u32, u32), DataType>,
+    /// Formulas being evaluated, to detect circular references
+    pending: HashSet<(usize, u32, u32)>,
+    /// Nesting of formula and name evaluations
+    depth: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    /// Creates a new evaluator
+    ///
+    /// Worksheets are read from the workbook when first referenced, references to a
+    /// worksheet which cannot be read are `#REF!` errors.
+    pub fn new<RS, R>(workbook: &'a mut R) -> Evaluator<'a>
+    where
+        RS: Read + Seek,
+        R: Reader<RS>,
+    {
+        let sheets = workbook
+            .sheets_metadata()
+            .iter()
+            .filter(|s| s.typ == SheetType::WorkSheet)
+            .map(|s| Sheet {
+                name: s.name.clone(),
+                loaded: None,
+                values: Range::default(),
+                formulas: Range::default(),
+            })
+            .collect();
+        let names = workbook
+            .defined_names()
+            .iter()
+            .filter_map(|(name, f)| Some((name.to_uppercase(), parse_name(f)?)))
+            .collect();
+        Evaluator {
+            loader: Box::new(move |name| {
+                let values = workbook.worksheet_range(name).ok()?;
+                let formulas = workbook.worksheet_formula_ast(name).ok()?;
+                Some((values, formulas))
+            }),
+            sheets,
+            names,
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            depth: 0,
+        }
+    }
+
+    /// Value of a cell, its formula is evaluated if it has no cached value
+    ///
+    /// Returns `None` if the worksheet does not exist.
+    pub fn cell_value(&mut self, sheet: &str, pos: (u32, u32)) -> Option<DataType> {
+        let sheet = self.sheet_index(sheet)?;
+        Some(self.value_at(sheet, pos))
+    }
+
+    /// Evaluates a formula as if it were in the `pos` cell of `sheet`
+    ///
+    /// Returns `None` if the worksheet does not exist.
+    pub fn evaluate(&mut self, sheet: &str, pos: (u32, u32), formula: &Expr) -> Option<DataType> {
+        let ctx = Ctx {
+            sheet: self.sheet_index(sheet)?,
+            pos,
+        };
+        let value = self.eval(formula, ctx, false);
+        Some(result(self.deref(value, ctx)))
+    }
+
+    /// Worksheet values, the formulas without cached value being evaluated
+    ///
+    /// Returns `None` if the worksheet does not exist or cannot be read.
+    pub fn worksheet_range(&mut self, sheet: &str) -> Option<Range<DataType>> {
+        let idx = self.sheet_index(sheet)?;
+        if !self.load(idx) {
+            return None;
+        }
+        let sheet = &self.sheets[idx];
+        let mut cells = absolute_cells(&sheet.values)
+            .map(|(pos, v)| Cell::new(pos, v.clone()))
+            .collect::<Vec<_>>();
+        let uncached = absolute_cells(&sheet.formulas)
+            .filter(|(pos, f)| {
+                **f != Expr::Missing
+                    && sheet
+                        .values
+                        .get_value(*pos)
+                        .map_or(true, DataType::is_empty)
+            })
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        // formulas are evaluated row by row, formulas usually refer to previous rows
+        for pos in uncached {
+            let value = self.value_at(idx, pos);
+            cells.push(Cell::new(pos, value));
+        }
+        cells.sort_by_key(|c| c.pos);
+        Some(Range::from_sparse(cells))
+    }
+
+    fn sheet_index(&self, name: &str) -> Option<usize> {
+        let name = name.to_lowercase();
+        self.sheets
+            .iter()
+            .position(|s| s.name.to_lowercase() == name)
+    }
+
+    /// Reads the worksheet if not done yet, returns false if it cannot be read
+    fn load(&mut self, idx: usize) -> bool {
+        let sheet = &mut self.sheets[idx];
+        if sheet.loaded.is_none() {
+            let data = (self.loader)(&sheet.name);
+            sheet.loaded = Some(data.is_some());
+            if let Some((values, formulas)) = data {
+                sheet.values = values;
+                sheet.formulas = formulas;
+            }
+        }
+        sheet.loaded == Some(true)
+    }
+
+    /// Value of a cell, evaluating its formula if needed
+    fn value_at(&mut self, sheet: usize, (row, col): (u32, u32)) -> DataType {
+        let key = (sheet, row, col);
+        if let Some(v) = self.cache.get(&key) {
+            return v.clone();
+        }
+        if !self.load(sheet) {
+            return DataType::Error(CellErrorType::Ref);
+        }
+        let s = &self.sheets[sheet];
+        match s.values.get_value((row, col)) {
+            Some(v) if !v.is_empty() => return v.clone(),
+            _ => (),
+        }
+        let formula = match s.formulas.get_value((row, col)) {
+            Some(f) if *f != Expr::Missing => f.clone(),
+            _ => return DataType::Empty,
+        };
+        if self.depth >= MAX_DEPTH || !self.pending.insert(key) {
+            return DataType::Error(CellErrorType::Value);
+        }
+        self.depth += 1;
+        let ctx = Ctx {
+            sheet,
+            pos: (row, col),
+        };
+        let value = self.eval(&formula, ctx, false);
+        let value = result(self.deref(value, ctx));
+        self.pending.remove(&key);
+        self.depth -= 1;
+        self.cache.insert(key, value.clone());
+        value
+    }
+
+    /// Evaluates an expression
+    ///
+    /// In array mode, operators apply to each value of areas and arrays, otherwise
+    /// areas are reduced to the cell in the same row or column as the formula.
+    fn eval(&mut self, e: &Expr, ctx: Ctx, array: bool) -> Value {
+        match e {
+            Expr::Missing => DataType::Empty.into(),
+            Expr::Number(n) => DataType::Float(*n).into(),
+            Expr::String(s) => DataType::String(s.clone()).into(),
+            Expr::Bool(b) => DataType::Bool(*b).into(),
+            Expr::Error(e) => e.clone().into(),
+            Expr::Array(rows) => Value::Array(
+                rows.iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|e| {
+                                let v = self.eval(e, ctx, false);
+                                self.deref(v, ctx)
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            Expr::Cell { sheet, cell } => self.reference(sheet.as_ref(), *cell, *cell, ctx),
+            Expr::Area { sheet, start, end } => self.reference(sheet.as_ref(), *start, *end, ctx),
+            Expr::Name(name) => {
+                let upper = name.to_uppercase();
+                let scoped = upper.rsplit_once('!').map(|(_, n)| n);
+                let expr = self
+                    .names
+                    .get(&upper)
+                    .or_else(|| scoped.and_then(|n| self.names.get(n)))
+                    .cloned();
+                match expr {
+                    Some(expr) if self.depth < MAX_DEPTH => {
+                        self.depth += 1;
+                        let v = self.eval(&expr, ctx, array);
+                        self.depth -= 1;
+                        v
+                    }
+                    Some(_) => CellErrorType::Value.into(),
+                    None => CellErrorType::Name.into(),
+                }
+            }
+            // tables are not known to the evaluator
+            Expr::Table { .. } => CellErrorType::Ref.into(),
+            Expr::Function { name, args } => {
+                self.function(name, args, ctx).unwrap_or_else(Value::from)
+            }
+            Expr::Paren(e) => self.eval(e, ctx, array),
+            Expr::Unary(op, e) => {
+                let v = self.eval(e, ctx, array);
+                self.map(v, ctx, array, |v| unary(*op, v))
+            }
+            Expr::Binary(BinaryOp::Range, lhs, rhs) => {
+                let (lhs, rhs) = (self.eval(lhs, ctx, false), self.eval(rhs, ctx, false));
+                match (lhs, rhs) {
+                    (
+                        Value::Ref {
+                            sheets: s1,
+                            area: a1,
+                        },
+                        Value::Ref {
+                            sheets: s2,
+                            area: a2,
+                        },
+                    ) if s1 == s2 => Value::Ref {
+                        sheets: s1,
+                        area: Dimensions::new(
+                            (a1.start.0.min(a2.start.0), a1.start.1.min(a2.start.1)),
+                            (a1.end.0.max(a2.end.0), a1.end.1.max(a2.end.1)),
+                        ),
+                    },
+                    (Value::Scalar(DataType::Error(e)), _)
+                    | (_, Value::Scalar(DataType::Error(e))) => e.into(),
+                    _ => CellErrorType::Value.into(),
+                }
+            }
+            Expr::Binary(BinaryOp::Intersect, lhs, rhs) => {
+                let (lhs, rhs) = (self.eval(lhs, ctx, false), self.eval(rhs, ctx, false));
+                match (lhs, rhs) {
+                    (
+                        Value::Ref {
+                            sheets: s1,
+                            area: a1,
+                        },
+                        Value::Ref {
+                            sheets: s2,
+                            area: a2,
+                        },
+                    ) if s1 == s2 => {
+                        let start = (a1.start.0.max(a2.start.0), a1.start.1.max(a2.start.1));
+                        let end = (a1.end.0.min(a2.end.0), a1.end.1.min(a2.end.1));
+                        if start.0 > end.0 || start.1 > end.1 {
+                            CellErrorType::Null.into()
+                        } else {
+                            Value::Ref {
+                                sheets: s1,
+                                area: Dimensions::new(start, end),
+                            }
+                        }
+                    }
+                    _ => CellErrorType::Value.into(),
+                }
+            }
+            Expr::Binary(BinaryOp::Union, _, _) => CellErrorType::Value.into(),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs, ctx, array);
+                let rhs = self.eval(rhs, ctx, array);
+                if array && (!self.is_scalar(&lhs) || !self.is_scalar(&rhs)) {
+                    let (lhs, rhs) = (self.array_values(lhs), self.array_values(rhs));
+                    let rows = lhs.len().max(rhs.len());
+                    let cols = lhs[0].len().max(rhs[0].len());
+                    Value::Array(
+                        (0..rows)
+                            .map(|r| {
+                                (0..cols)
+                                    .map(|c| {
+                                        binary(*op, broadcast(&lhs, r, c), broadcast(&rhs, r, c))
+                                    })
+                                    .collect()
+                            })
+                            .collect(),
+                    )
+                } else {
+                    let lhs = self.deref(lhs, ctx);
+                    let rhs = self.deref(rhs, ctx);
+                    binary(*op, lhs, rhs).into()
+                }
+            }
+        }
+    }
+
+    /// Reference to an area, `#REF!` if the sheet does not exist
+    fn reference(&self, sheet: Option<&SheetRef>, start: CellRef, end: CellRef, ctx: Ctx) -> Value {
+        let sheets = match sheet {
+            None => (ctx.sheet, ctx.sheet),
+            Some(sheet) => {
+                let first = self.sheet_index(&sheet.first);
+                let last = match &sheet.last {
+                    Some(last) => self.sheet_index(last),
+                    None => first,
+                };
+                match (first, last) {
+                    (Some(f), Some(l)) => (f.min(l), f.max(l)),
+                    _ => return CellErrorType::Ref.into(),
+                }
+            }
+        };
+        Value::Ref {
+            sheets,
+            area: Dimensions::new(
+                (start.row.min(end.row), start.col.min(end.col)),
+                (start.row.max(end.row), start.col.max(end.col)),
+            ),
+        }
+    }
+
+    fn is_scalar(&self, v: &Value) -> bool {
+        match v {
+            Value::Scalar(_) => true,
+            Value::Ref { area, .. } => area.start == area.end,
+            Value::Array(a) => a.len() == 1 && a[0].len() == 1,
+        }
+    }
+
+    /// Applies `f` to a value, or to each of its values in array mode
+    fn map<F: Fn(DataType) -> DataType>(&mut self, v: Value, ctx: Ctx, array: bool, f: F) -> Value {
+        if array && !self.is_scalar(&v) {
+            let a = self.array_values(v);
+            Value::Array(
+                a.into_iter()
+                    .map(|row| row.into_iter().map(&f).collect())
+                    .collect(),
+            )
+        } else {
+            f(self.deref(v, ctx)).into()
+        }
+    }
+
+    /// Reduces a value to a single one
+    ///
+    /// Areas are intersected with the row or the column of the formula (implicit intersection).
+    fn deref(&mut self, v: Value, ctx: Ctx) -> DataType {
+        match v {
+            Value::Scalar(v) => v,
+            Value::Array(a) => a
+                .into_iter()
+                .next()
+                .and_then(|row| row.into_iter().next())
+                .unwrap_or_default(),
+            Value::Ref { sheets, area } => {
+                if sheets.0 != sheets.1 {
+                    return DataType::Error(CellErrorType::Value);
+                }
+                let pos = if area.start == area.end {
+                    area.start
+                } else if area.start.1 == area.end.1
+                    && (area.start.0..=area.end.0).contains(&ctx.pos.0)
+                {
+                    (ctx.pos.0, area.start.1)
+                } else if area.start.0 == area.end.0
+                    && (area.start.1..=area.end.1).contains(&ctx.pos.1)
+                {
+                    (area.start.0, ctx.pos.1)
+                } else {
+                    return DataType::Error(CellErrorType::Value);
+                };
+                self.value_at(sheets.0, pos)
+            }
+        }
+    }
+
+    /// Used part of an area, `None` if the area is empty
+    fn clip(&mut self, sheet: usize, area: Dimensions) -> Option<Dimensions> {
+        self.load(sheet);
+        let s = &self.sheets[sheet];
+        let end = match (s.values.end(), s.formulas.end()) {
+            (Some(v), Some(f)) => (v.0.max(f.0), v.1.max(f.1)),
+            (Some(e), None) | (None, Some(e)) => e,
+            (None, None) => return None,
+        };
+        if area.start.0 > end.0 || area.start.1 > end.1 {
+            return None;
+        }
+        Some(Dimensions::new(
+            area.start,
+            (area.end.0.min(end.0), area.end.1.min(end.1)),
+        ))
+    }
+
+    /// Values of the used cells of an area, row by row
+    fn area_values(&mut self, sheets: (usize, usize), area: Dimensions) -> Vec<DataType> {
+        let mut values = Vec::new();
+        for sheet in sheets.0..=sheets.1 {
+            if !self.load(sheet) {
+                values.push(DataType::Error(CellErrorType::Ref));
+            } else if let Some(area) = self.clip(sheet, area) {
+                for row in area.start.0..=area.end.0 {
+                    for col in area.start.1..=area.end.1 {
+                        values.push(self.value_at(sheet, (row, col)));
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    /// Converts a value into a 2D array, large areas are clipped to their used part
+    fn array_values(&mut self, v: Value) -> Vec<Vec<DataType>> {
+        match v {
+            Value::Scalar(v) => vec![vec![v]],
+            Value::Array(a) if a.is_empty() || a[0].is_empty() => vec![vec![DataType::Empty]],
+            Value::Array(a) => a,
+            Value::Ref { sheets, .. } if sheets.0 != sheets.1 => {
+                vec![vec![DataType::Error(CellErrorType::Value)]]
+            }
+            Value::Ref { sheets, .. } if !self.load(sheets.0) => {
+                vec![vec![DataType::Error(CellErrorType::Ref)]]
+            }
+            Value::Ref { sheets, area } => {
+                let area = if area.len() > MAX_ARRAY_LEN {
+                    match self.clip(sheets.0, area) {
+                        Some(area) => area,
+                        None => return vec![vec![DataType::Empty]],
+                    }
+                } else {
+                    area
+                };
+                (area.start.0..=area.end.0)
+                    .map(|row| {
+                        (area.start.1..=area.end.1)
+                            .map(|col| self.value_at(sheets.0, (row, col)))
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn scalar(&mut self, e: &Expr, ctx: Ctx) -> DataType {
+        let v = self.eval(e, ctx, false);
+        self.deref(v, ctx)
+    }
+
+    fn number(&mut self, e: &Expr, ctx: Ctx) -> EvalResult<f64> {
+        to_number(&self.scalar(e, ctx))
+    }
+
+    fn text(&mut self, e: &Expr, ctx: Ctx) -> EvalResult<String> {
+        to_text(&self.scalar(e, ctx))
+    }
+
+    fn boolean(&mut self, e: &Expr, ctx: Ctx) -> EvalResult<bool> {
+        to_bool(&self.scalar(e, ctx))
+    }
+
+    /// Values of aggregate function arguments
+    ///
+    /// Values given directly are returned as `(value, true)`, values of areas
+    /// and arrays as `(value, false)`.
+    fn collect(&mut self, args: &[Expr], ctx: Ctx) -> Vec<(DataType, bool)> {
+        let mut values = Vec::new();
+        for arg in args {
+            match self.eval(arg, ctx, true) {
+                Value::Scalar(v) => values.push((v, true)),
+                Value::Ref { sheets, area } => values.extend(
+                    self.area_values(sheets, area)
+                        .into_iter()
+                        .map(|v| (v, false)),
+                ),
+                Value::Array(a) => values.extend(a.into_iter().flatten().map(|v| (v, false))),
+            }
+        }
+        values
+    }
+
+    /// Numbers of aggregate function arguments
+    ///
+    /// Text and booleans are only taken into account when given directly.
+    fn numbers(&mut self, args: &[Expr], ctx: Ctx) -> EvalResult<Vec<f64>> {
+        let mut numbers = Vec::new();
+        for (v, direct) in self.collect(args, ctx) {
+            match v {
+                DataType::Error(e) => return Err(e),
+                DataType::Int(_)
+                | DataType::Float(_)
+                | DataType::DateTime(_)
+                | DataType::Duration(_) => numbers.push(to_number(&v)?),
+                DataType::Empty => (),
+                _ if direct => numbers.push(to_number(&v)?),
+                _ => (),
+            }
+        }
+        Ok(numbers)
+    }
+
+    /// A table argument, as a 2D array
+    fn table(&mut self, e: &Expr, ctx: Ctx) -> EvalResult<Vec<Vec<DataType>>> {
+        match self.eval(e, ctx, true) {
+            Value::Scalar(DataType::Error(e)) => Err(e),
+            v => Ok(self.array_values(v)),
+        }
+    }
+
+    /// Evaluates a function call
+    fn function(&mut self, name: &str, args: &[Expr], ctx: Ctx) -> EvalResult<Value> {
+        let upper = name.to_uppercase();
+        let name = upper
+            .strip_prefix("_XLFN.")
+            .or_else(|| upper.strip_prefix("COM.MICROSOFT."))
+            .unwrap_or(&upper);
+        let arg = |i: usize| args.get(i).unwrap_or(&Expr::Missing);
+        let v = match name {
+            // logical
+            "IF" => {
+                let value = if self.boolean(arg(0), ctx)? {
+                    self.eval(arg(1), ctx, false)
+                } else if args.len() > 2 {
+                    self.eval(arg(2), ctx, false)
+                } else {
+                    DataType::Bool(false).into()
+                };
+                return Ok(value);
+            }
+            "AND" | "OR" => {
+                let mut bools = Vec::new();
+                for (v, direct) in self.collect(args, ctx) {
+                    match v {
+                        DataType::Error(e) => return Err(e),
+                        DataType::Empty => (),
+                        DataType::String(_) if !direct => (),
+                        v => bools.push(to_bool(&v)?),
+                    }
+                }
+                if bools.is_empty() {
+                    return Err(CellErrorType::Value);
+                }
+                if name == "AND" {
+                    DataType::Bool(bools.iter().all(|b| *b))
+                } else {
+                    DataType::Bool(bools.iter().any(|b| *b))
+                }
+            }
+            "NOT" => DataType::Bool(!self.boolean(arg(0), ctx)?),
+            "TRUE" => DataType::Bool(true),
+            "FALSE" => DataType::Bool(false),
+            "NA" => return Err(CellErrorType::NA),
+            "IFERROR" | "IFNA" => {
+                let v = self.scalar(arg(0), ctx);
+                match v {
+                    DataType::Error(CellErrorType::NA) => return Ok(self.eval(arg(1), ctx, false)),
+                    DataType::Error(_) if name == "IFERROR" => {
+                        return Ok(self.eval(arg(1), ctx, false))
+                    }
+                    v => v,
+                }
+            }
+            "ISBLANK" | "ISERR" | "ISERROR" | "ISNA" | "ISNUMBER" | "ISTEXT" | "ISLOGICAL" => {
+                let v = self.scalar(arg(0), ctx);
+                DataType::Bool(match name {
+                    "ISBLANK" => v.is_empty(),
+                    "ISERR" => matches!(v, DataType::Error(ref e) if *e != CellErrorType::NA),
+                    "ISERROR" => matches!(v, DataType::Error(_)),
+                    "ISNA" => v == DataType::Error(CellErrorType::NA),
+                    "ISNUMBER" => is_number(&v),
+                    "ISTEXT" => matches!(v, DataType::String(_)),
+                    _ => matches!(v, DataType::Bool(_)),
+                })
+            }
+
+            // math
+            "SUM" => DataType::Float(sum(&self.numbers(args, ctx)?)),
+            "PRODUCT" => {
+                let numbers = self.numbers(args, ctx)?;
+                if numbers.is_empty() {
+                    DataType::Float(0.)
+                } else {
+                    DataType::Float(numbers.iter().product())
+                }
+            }
+            "AVERAGE" => {
+                let numbers = self.numbers(args, ctx)?;
+                if numbers.is_empty() {
+                    return Err(CellErrorType::Div0);
+                }
+                DataType::Float(sum(&numbers) / numbers.len() as f64)
+            }
+            "MIN" | "MAX" => {
+                let numbers = self.numbers(args, ctx)?;
+                let v = if name == "MIN" {
+                    numbers.iter().copied().reduce(f64::min)
+                } else {
+                    numbers.iter().copied().reduce(f64::max)
+                };
+                DataType::Float(v.unwrap_or(0.))
+            }
+            "COUNT" => {
+                let count = self
+                    .collect(args, ctx)
+                    .into_iter()
+                    .filter(|(v, direct)| {
+                        is_number(v) || (*direct && !v.is_empty() && to_number(v).is_ok())
+                    })
+                    .count();
+                DataType::Float(count as f64)
+            }
+            "COUNTA" => {
+                let count = self
+                    .collect(args, ctx)
+                    .into_iter()
+                    .filter(|(v, _)| !v.is_empty())
+                    .count();
+                DataType::Float(count as f64)
+            }
+            "COUNTBLANK" => match self.eval(arg(0), ctx, false) {
+                Value::Ref { sheets, area } => {
+                    let total = area.len() * (sheets.1 - sheets.0 + 1) as u64;
+                    let filled = self
+                        .area_values(sheets, area)
+                        .iter()
+                        .filter(|v| !v.is_empty() && **v != DataType::String(String::new()))
+                        .count() as u64;
+                    DataType::Float((total - filled) as f64)
+                }
+                Value::Scalar(DataType::Error(e)) => return Err(e),
+                _ => return Err(CellErrorType::Value),
+            },
+            "SUMIF" | "COUNTIF" | "AVERAGEIF" => {
+                let range = self.table(arg(0), ctx)?;
+                let criteria = Criteria::new(self.scalar(arg(1), ctx))?;
+                let values = if name != "COUNTIF" && args.len() > 2 {
+                    // the sum range has the size of the criteria range
+                    match self.eval(arg(2), ctx, false) {
+                        Value::Ref { sheets, area } => {
+                            let end = (
+                                area.start.0 + range.len() as u32 - 1,
+                                area.start.1 + range[0].len() as u32 - 1,
+                            );
+                            let area = Dimensions::new(area.start, end);
+                            self.array_values(Value::Ref { sheets, area })
+                        }
+                        v => self.array_values(v),
+                    }
+                } else {
+                    range.clone()
+                };
+                let matches = matching(&range, &values, &criteria);
+                aggregate_if(name, matches)?
+            }
+            "SUMIFS" | "COUNTIFS" => {
+                let (values, pairs) = if name == "SUMIFS" {
+                    (Some(self.table(arg(0), ctx)?), &args[args.len().min(1)..])
+                } else {
+                    (None, args)
+                };
+                let mut selected: Option<Vec<Vec<bool>>> = None;
+                for pair in pairs.chunks(2) {
+                    let range = self.table(&pair[0], ctx)?;
+                    let criteria =
+                        Criteria::new(self.scalar(pair.get(1).unwrap_or(&Expr::Missing), ctx))?;
+                    let m = range
+                        .iter()
+                        .map(|row| row.iter().map(|v| criteria.matches(v)).collect::<Vec<_>>())
+                        .collect::<Vec<_>>();
+                    selected = Some(match selected {
+                        None => m,
+                        Some(s) => s
+                            .iter()
+                            .enumerate()
+                            .map(|(r, row)| {
+                                row.iter()
+                                    .enumerate()
+                                    .map(|(c, b)| {
+                                        *b && m
+                                            .get(r)
+                                            .and_then(|row| row.get(c))
+                                            .copied()
+                                            .unwrap_or(false)
+                                    })
+                                    .collect()
+                            })
+                            .collect(),
+                    });
+                }
+                let selected = selected.unwrap_or_default();
+                let values = values.unwrap_or_else(|| {
+                    selected
+                        .iter()
+                        .map(|row| vec![DataType::Empty; row.len()])
+                        .collect()
+                });
+                let matches = selected
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(r, row)| {
+                        row.iter()
+                            .enumerate()
+                            .filter(|(_, b)| **b)
+                            .map(move |(c, _)| (r, c))
+                    })
+                    .map(|(r, c)| {
+                        values
+                            .get(r)
+                            .and_then(|row| row.get(c))
+                            .cloned()
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>();
+                aggregate_if(if name == "SUMIFS" { "SUMIF" } else { "COUNTIF" }, matches)?
+            }
+            "SUMPRODUCT" => {
+                let mut arrays = Vec::with_capacity(args.len());
+                for a in args {
+                    arrays.push(self.table(a, ctx)?);
+                }
+                let (rows, cols) = match arrays.first() {
+                    Some(a) => (a.len(), a[0].len()),
+                    None => return Err(CellErrorType::Value),
+                };
+                if arrays.iter().any(|a| a.len() != rows || a[0].len() != cols) {
+                    return Err(CellErrorType::Value);
+                }
+                let mut sum = 0.;
+                for r in 0..rows {
+                    for c in 0..cols {
+                        let mut product = 1.;
+                        for a in &arrays {
+                            match &a[r][c] {
+                                DataType::Error(e) => return Err(e.clone()),
+                                v if is_number(v) => product *= to_number(v)?,
+                                _ => product = 0.,
+                            }
+                        }
+                        sum += product;
+                    }
+                }
+                DataType::Float(sum)
+            }
+            "ABS" => DataType::Float(self.number(arg(0), ctx)?.abs()),
+            "INT" => DataType::Float(self.number(arg(0), ctx)?.floor()),
+            "SIGN" => DataType::Float(match self.number(arg(0), ctx)? {
+                n if n > 0. => 1.,
+                n if n < 0. => -1.,
+                _ => 0.,
+            }),
+            "TRUNC" | "ROUND" | "ROUNDUP" | "ROUNDDOWN" => {
+                let n = self.number(arg(0), ctx)?;
+                let digits = if args.len() > 1 {
+                    self.number(arg(1), ctx)?.trunc() as i32
+                } else {
+                    0
+                };
+                DataType::Float(round(name, n, digits))
+            }
+            "MOD" => {
+                let n = self.number(arg(0), ctx)?;
+                let d = self.number(arg(1), ctx)?;
+                if d == 0. {
+                    return Err(CellErrorType::Div0);
+                }
+                DataType::Float(n - d * (n / d).floor())
+            }
+            "POWER" => {
+                let n = self.number(arg(0), ctx)?;
+                let p = self.number(arg(1), ctx)?;
+                return Ok(binary(BinaryOp::Pow, DataType::Float(n), DataType::Float(p)).into());
+            }
+            "SQRT" | "LN" | "LOG10" | "EXP" => {
+                let n = self.number(arg(0), ctx)?;
+                let v = match name {
+                    "SQRT" => n.sqrt(),
+                    "LN" => n.ln(),
+                    "LOG10" => n.log10(),
+                    _ => n.exp(),
+                };
+                finite(v)?
+            }
+            "PI" => DataType::Float(std::f64::consts::PI),
+
+            // lookup
+            "VLOOKUP" | "HLOOKUP" => {
+                let x = self.scalar(arg(0), ctx);
+                if let DataType::Error(e) = x {
+                    return Err(e);
+                }
+                let mut table = self.table(arg(1), ctx)?;
+                if name == "HLOOKUP" {
+                    table = transpose(table);
+                }
+                let idx = self.number(arg(2), ctx)?.trunc();
+                let approx = args.len() < 4 || self.boolean(arg(3), ctx)?;
+                if idx < 1. {
+                    return Err(CellErrorType::Value);
+                }
+                if idx as usize > table[0].len() {
+                    return Err(CellErrorType::Ref);
+                }
+                let keys = table.iter().map(|row| row[0].clone()).collect::<Vec<_>>();
+                let row = lookup(&keys, &x, if approx { 1 } else { 0 })?;
+                table[row][idx as usize - 1].clone()
+            }
+            "MATCH" => {
+                let x = self.scalar(arg(0), ctx);
+                if let DataType::Error(e) = x {
+                    return Err(e);
+                }
+                let table = self.table(arg(1), ctx)?;
+                let typ = if args.len() > 2 {
+                    self.number(arg(2), ctx)? as i32
+                } else {
+                    1
+                };
+                let keys = if table.len() == 1 {
+                    table.into_iter().next().unwrap_or_default()
+                } else if table[0].len() == 1 {
+                    table
+                        .into_iter()
+                        .map(|mut row| row.swap_remove(0))
+                        .collect()
+                } else {
+                    return Err(CellErrorType::NA);
+                };
+                DataType::Float((lookup(&keys, &x, typ.signum())? + 1) as f64)
+            }
+            "INDEX" => {
+                let row = if args.len() > 1 {
+                    self.number(arg(1), ctx)?.trunc()
+                } else {
+                    0.
+                };
+                let col = if args.len() > 2 {
+                    self.number(arg(2), ctx)?.trunc()
+                } else {
+                    0.
+                };
+                if row < 0. || col < 0. {
+                    return Err(CellErrorType::Value);
+                }
+                let (row, col) = (row as u32, col as u32);
+                match self.eval(arg(0), ctx, false) {
+                    Value::Ref { sheets, area } => {
+                        let (rows, cols) =
+                            (area.end.0 - area.start.0 + 1, area.end.1 - area.start.1 + 1);
+                        // a single row or column is indexed by its only dimension
+                        let (row, col) = if rows == 1 && args.len() == 2 {
+                            (1, row)
+                        } else {
+                            (row, col)
+                        };
+                        if row > rows || col > cols {
+                            return Err(CellErrorType::Ref);
+                        }
+                        let (r0, r1) = if row == 0 {
+                            (area.start.0, area.end.0)
+                        } else {
+                            (area.start.0 + row - 1, area.start.0 + row - 1)
+                        };
+                        let (c0, c1) = if col == 0 {
+                            (area.start.1, area.end.1)
+                        } else {
+                            (area.start.1 + col - 1, area.start.1 + col - 1)
+                        };
+                        return Ok(Value::Ref {
+                            sheets,
+                            area: Dimensions::new((r0, c0), (r1, c1)),
+                        });
+                    }
+                    Value::Scalar(DataType::Error(e)) => return Err(e),
+                    v => {
+                        let a = self.array_values(v);
+                        let (row, col) = if a.len() == 1 && args.len() == 2 {
+                            (1, row)
+                        } else {
+                            (row, col)
+                        };
+                        let (row, col) = (row.max(1) as usize - 1, col.max(1) as usize - 1);
+                        a.get(row)
+                            .and_then(|r| r.get(col))
+                            .cloned()
+                            .ok_or(CellErrorType::Ref)?
+                    }
+                }
+            }
+            "CHOOSE" => {
+                let i = self.number(arg(0), ctx)?.trunc();
+                if i < 1. || i as usize >= args.len() {
+                    return Err(CellErrorType::Value);
+                }
+                return Ok(self.eval(&args[i as usize], ctx, false));
+            }
+            "ROW" | "COLUMN" | "ROWS" | "COLUMNS" => {
+                let area = if args.is_empty() {
+                    Dimensions::new(ctx.pos, ctx.pos)
+                } else {
+                    match self.eval(arg(0), ctx, false) {
+                        Value::Ref { area, .. } => area,
+                        Value::Array(a) if name == "ROWS" || name == "COLUMNS" => {
+                            Dimensions::new((0, 0), (a.len() as u32 - 1, a[0].len() as u32 - 1))
+                        }
+                        Value::Scalar(DataType::Error(e)) => return Err(e),
+                        _ => return Err(CellErrorType::Value),
+                    }
+                };
+                DataType::Float(match name {
+                    "ROW" => area.start.0 + 1,
+                    "COLUMN" => area.start.1 + 1,
+                    "ROWS" => area.end.0 - area.start.0 + 1,
+                    _ => area.end.1 - area.start.1 + 1,
+                } as f64)
+            }
+
+            // text
+            "CONCATENATE" => {
+                let mut s = String::new();
+                for a in args {
+                    s.push_str(&self.text(a, ctx)?);
+                }
+                DataType::String(s)
+            }
+            "CONCAT" | "TEXTJOIN" => {
+                let (sep, ignore_empty, args) = if name == "TEXTJOIN" {
+                    let sep = self.text(arg(0), ctx)?;
+                    let ignore = self.boolean(arg(1), ctx)?;
+                    (sep, ignore, args.get(2..).unwrap_or_default())
+                } else {
+                    (String::new(), true, args)
+                };
+                let mut parts = Vec::new();
+                for (v, _) in self.collect(args, ctx) {
+                    let s = to_text(&v)?;
+                    if !(ignore_empty && s.is_empty()) {
+                        parts.push(s);
+                    }
+                }
+                DataType::String(parts.join(&sep))
+            }
+            "LEN" => DataType::Float(self.text(arg(0), ctx)?.chars().count() as f64),
+            "LEFT" | "RIGHT" => {
+                let s = self.text(arg(0), ctx)?;
+                let n = if args.len() > 1 {
+                    self.number(arg(1), ctx)?.trunc()
+                } else {
+                    1.
+                };
+                if n < 0. {
+                    return Err(CellErrorType::Value);
+                }
+                let len = s.chars().count();
+                let n = (n as usize).min(len);
+                DataType::String(if name == "LEFT" {
+                    s.chars().take(n).collect()
+                } else {
+                    s.chars().skip(len - n).collect()
+                })
+            }
+            "MID" => {
+                let s = self.text(arg(0), ctx)?;
+                let start = self.number(arg(1), ctx)?.trunc();
+                let n = self.number(arg(2), ctx)?.trunc();
+                if start < 1. || n < 0. {
+                    return Err(CellErrorType::Value);
+                }
+                DataType::String(
+                    s.chars()
+                        .skip(start as usize - 1)
+                        .take(n as usize)
+                        .collect(),
+                )
+            }
+            "UPPER" => DataType::String(self.text(arg(0), ctx)?.to_uppercase()),
+            "LOWER" => DataType::String(self.text(arg(0), ctx)?.to_lowercase()),
+            "TRIM" => DataType::String(
+                self.text(arg(0), ctx)?
+                    .split(' ')
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            "TEXT" => {
+                let v = self.scalar(arg(0), ctx);
+                if let DataType::Error(e) = v {
+                    return Err(e);
+                }
+                let format = self.text(arg(1), ctx)?;
+                DataType::String(format_value(&v, &format))
+            }
+            "VALUE" => match self.scalar(arg(0), ctx) {
+                DataType::String(s) => {
+                    DataType::Float(parse_number(&s).ok_or(CellErrorType::Value)?)
+                }
+                v => DataType::Float(to_number(&v)?),
+            },
+            "FIND" | "SEARCH" => {
+                let find = self.text(arg(0), ctx)?;
+                let within = self.text(arg(1), ctx)?;
+                let start = if args.len() > 2 {
+                    self.number(arg(2), ctx)?.trunc()
+                } else {
+                    1.
+                };
+                let chars = within.chars().collect::<Vec<_>>();
+                if start < 1. || start as usize > chars.len() + 1 {
+                    return Err(CellErrorType::Value);
+                }
+                let start = start as usize - 1;
+                let found = if name == "FIND" {
+                    let find = find.chars().collect::<Vec<_>>();
+                    (start..=chars.len()).find(|&i| chars[i..].starts_with(&find))
+                } else {
+                    let pattern = format!("{find}*");
+                    (start..=chars.len())
+                        .find(|&i| wildcard(&pattern, &chars[i..].iter().collect::<String>()))
+                };
+                DataType::Float(found.ok_or(CellErrorType::Value)? as f64 + 1.)
+            }
+            "SUBSTITUTE" => {
+                let s = self.text(arg(0), ctx)?;
+                let old = self.text(arg(1), ctx)?;
+                let new = self.text(arg(2), ctx)?;
+                if old.is_empty() {
+                    DataType::String(s)
+                } else if args.len() > 3 {
+                    let n = self.number(arg(3), ctx)?.trunc();
+                    if n < 1. {
+                        return Err(CellErrorType::Value);
+                    }
+                    match s.match_indices(&old).nth(n as usize - 1) {
+                        Some((i, _)) => {
+                            DataType::String(format!("{}{}{}", &s[..i], new, &s[i + old.len()..]))
+                        }
+                        None => DataType::String(s),
+                    }
+                } else {
+                    DataType::String(s.replace(&old, &new))
+                }
+            }
+            "REPT" => {
+                let s = self.text(arg(0), ctx)?;
+                let n = self.number(arg(1), ctx)?.trunc();
+                if n < 0. || s.len() as f64 * n > 32_767. {
+                    return Err(CellErrorType::Value);
+                }
+                DataType::String(s.repeat(n as usize))
+            }
+            "EXACT" => DataType::Bool(self.text(arg(0), ctx)? == self.text(arg(1), ctx)?),
+
+            // date
+            "DATE" => {
+                let mut y = self.number(arg(0), ctx)?.trunc() as i64;
+                let m = self.number(arg(1), ctx)?.trunc() as i64;
+                let d = self.number(arg(2), ctx)?.trunc() as i64;
+                if (0..1900).contains(&y) {
+                    y += 1900;
+                }
+                if !(1900..10_000).contains(&y) {
+                    return Err(CellErrorType::Num);
+                }
+                let serial = date_to_serial(y, m, d);
+                if serial < 0 {
+                    return Err(CellErrorType::Num);
+                }
+                DataType::DateTime(serial as f64)
+            }
+            "TIME" => {
+                let h = self.number(arg(0), ctx)?.trunc();
+                let m = self.number(arg(1), ctx)?.trunc();
+                let s = self.number(arg(2), ctx)?.trunc();
+                let t = (h * 3600. + m * 60. + s) / 86_400.;
+                if t < 0. {
+                    return Err(CellErrorType::Num);
+                }
+                DataType::DateTime(t.fract())
+            }
+            "YEAR" | "MONTH" | "DAY" => {
+                let serial = self.number(arg(0), ctx)?;
+                if serial < 0. {
+                    return Err(CellErrorType::Num);
+                }
+                let (y, m, d) = serial_to_date(serial.trunc() as i64);
+                DataType::Float(match name {
+                    "YEAR" => y as f64,
+                    "MONTH" => m as f64,
+                    _ => d as f64,
+                })
+            }
+            "HOUR" | "MINUTE" | "SECOND" => {
+                let serial = self.number(arg(0), ctx)?;
+                if serial < 0. {
+                    return Err(CellErrorType::Num);
+                }
+                let seconds = (serial.fract() * 86_400.).round() as i64 % 86_400;
+                DataType::Float(match name {
+                    "HOUR" => seconds / 3600,
+                    "MINUTE" => seconds % 3600 / 60,
+                    _ => seconds % 60,
+                } as f64)
+            }
+            "WEEKDAY" => {
+                let serial = self.number(arg(0), ctx)?.trunc() as i64;
+                let typ = if args.len() > 1 {
+                    self.number(arg(1), ctx)? as i64
+                } else {
+                    1
+                };
+                // 0 for sunday
+                let day = (serial - 1).rem_euclid(7);
+                DataType::Float(match typ {
+                    1 => day + 1,
+                    2 => (day + 6) % 7 + 1,
+                    3 => (day + 6) % 7,
+                    _ => return Err(CellErrorType::Num),
+                } as f64)
+            }
+            "EDATE" | "EOMONTH" => {
+                let serial = self.number(arg(0), ctx)?.trunc() as i64;
+                let months = self.number(arg(1), ctx)?.trunc() as i64;
+                let (y, m, d) = serial_to_date(serial);
+                let month = m as i64 + months;
+                let serial = if name == "EOMONTH" {
+                    date_to_serial(y, month + 1, 0)
+                } else {
+                    let last = date_to_serial(y, month + 1, 0) - date_to_serial(y, month, 0);
+                    date_to_serial(y, month, (d as i64).min(last))
+                };
+                if serial < 0 {
+                    return Err(CellErrorType::Num);
+                }
+                DataType::DateTime(serial as f64)
+            }
+            "DAYS" => DataType::Float(
+                self.number(arg(0), ctx)?.trunc() - self.number(arg(1), ctx)?.trunc(),
+            ),
+            "TODAY" | "NOW" => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0., |d| d.as_secs_f64());
+                // 1970-01-01 is 25569
+                let serial = now / 86_400. + 25_569.;
+                DataType::DateTime(if name == "TODAY" {
+                    serial.trunc()
+                } else {
+                    serial
+                })
+            }
+            _ => return Err(CellErrorType::Name),
+        };
+        Ok(v.into())
+    }
+}
+
+/// Non empty cells of a range, with their absolute position
+fn absolute_cells<T: crate::CellType>(range: &Range<T>) -> impl Iterator<Item = ((u32, u32), &T)> {
+    let start = range.start().unwrap_or_default();
+    range
+        .used_cells()
+        .map(move |(r, c, v)| ((start.0 + r as u32, start.1 + c as u32), v))
+}
+
+/// Parses a defined name formula, from any format
+fn parse_name(f: &str) -> Option<Expr> {
+    match formula::parse(f) {
+        // ods cell range addresses, e.g. `$Sheet1.$A$1:.$B$2`, are no Excel names
+        Ok(Expr::Name(n)) if n.contains('.') => None,
+        Ok(e) => Some(e),
+        Err(_) => None,
+    }
+    .or_else(|| formula::parse_open_formula(f).ok())
+    .or_else(|| formula::parse_open_formula(&format!("[{f}]")).ok())
+}
+
+/// Final value of a formula
+fn result(v: DataType) -> DataType {
+    match v {
+        DataType::Empty => DataType::Float(0.),
+        v => v,
+    }
+}
+
+fn is_number(v: &DataType) -> bool {
+    matches!(
+        v,
+        DataType::Int(_) | DataType::Float(_) | DataType::DateTime(_) | DataType::Duration(_)
+    )
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    match s.strip_suffix('%') {
+        Some(p) => p.trim().parse::<f64>().ok().map(|p| p / 100.),
+        None => s.parse().ok(),
+    }
+}
+
+fn to_number(v: &DataType) -> EvalResult<f64> {
+    match v {
+        DataType::Int(i) => Ok(*i as f64),
+        DataType::Float(f) | DataType::DateTime(f) | DataType::Duration(f) => Ok(*f),
+        DataType::Bool(b) => Ok(if *b { 1. } else { 0. }),
+        DataType::Empty => Ok(0.),
+        DataType::String(s) => parse_number(s).ok_or(CellErrorType::Value),
+        DataType::Error(e) => Err(e.clone()),
+        DataType::DateTimeIso(_) | DataType::DurationIso(_) => Err(CellErrorType::Value),
+    }
+}
+
+fn to_text(v: &DataType) -> EvalResult<String> {
+    match v {
+        DataType::String(s) | DataType::DateTimeIso(s) | DataType::DurationIso(s) => Ok(s.clone()),
+        DataType::Bool(b) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        DataType::Empty => Ok(String::new()),
+        DataType::Error(e) => Err(e.clone()),
+        v => Ok(format_value(&DataType::Float(to_number(v)?), "General")),
+    }
+}
+
+fn to_bool(v: &DataType) -> EvalResult<bool> {
+    match v {
+        DataType::Bool(b) => Ok(*b),
+        DataType::String(s) if s.eq_ignore_ascii_case("TRUE") => Ok(true),
+        DataType::String(s) if s.eq_ignore_ascii_case("FALSE") => Ok(false),
+        DataType::String(_) => Err(CellErrorType::Value),
+        v => to_number(v).map(|n| n != 0.),
+    }
+}
+
+fn finite(v: f64) -> EvalResult<DataType> {
+    if v.is_finite() {
+        Ok(DataType::Float(v))
+    } else {
+        Err(CellErrorType::Num)
+    }
+}
+
+fn unary(op: UnaryOp, v: DataType) -> DataType {
+    let n = match to_number(&v) {
+        Ok(n) => n,
+        Err(e) => return DataType::Error(e),
+    };
+    DataType::Float(match op {
+        UnaryOp::Plus => return v,
+        UnaryOp::Minus => -n,
+        UnaryOp::Percent => n / 100.,
+    })
+}
+
+fn binary(op: BinaryOp, lhs: DataType, rhs: DataType) -> DataType {
+    let v = match op {
+        BinaryOp::Concat => to_text(&lhs).and_then(|l| Ok(DataType::String(l + &to_text(&rhs)?))),
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            compare(&lhs, &rhs).map(|o| {
+                DataType::Bool(match op {
+                    BinaryOp::Eq => o == Ordering::Equal,
+                    BinaryOp::Ne => o != Ordering::Equal,
+                    BinaryOp::Lt => o == Ordering::Less,
+                    BinaryOp::Le => o != Ordering::Greater,
+                    BinaryOp::Gt => o == Ordering::Greater,
+                    _ => o != Ordering::Less,
+                })
+            })
+        }
+        _ => to_number(&lhs).and_then(|l| {
+            let r = to_number(&rhs)?;
+            match op {
+                BinaryOp::Add => finite(l + r),
+                BinaryOp::Sub => finite(l - r),
+                BinaryOp::Mul => finite(l * r),
+                BinaryOp::Div if r == 0. => Err(CellErrorType::Div0),
+                BinaryOp::Div => finite(l / r),
+                BinaryOp::Pow if l == 0. && r == 0. => Err(CellErrorType::Num),
+                BinaryOp::Pow if l == 0. && r < 0. => Err(CellErrorType::Div0),
+                BinaryOp::Pow => finite(l.powf(r)),
+                _ => Err(CellErrorType::Value),
+            }
+        }),
+    };
+    v.unwrap_or_else(DataType::Error)
+}
+
+/// Value of an array at (row, col), single rows and columns being repeated
+fn broadcast(a: &[Vec<DataType>], row: usize, col: usize) -> DataType {
+    let row = if a.len() == 1 { 0 } else { row };
+    let col = if a[0].len() == 1 { 0 } else { col };
+    a.get(row)
+        .and_then(|r| r.get(col))
+        .cloned()
+        .unwrap_or(DataType::Error(CellErrorType::NA))
+}
+
+fn transpose(table: Vec<Vec<DataType>>) -> Vec<Vec<DataType>> {
+    let cols = table[0].len();
+    (0..cols)
+        .map(|c| table.iter().map(|row| row[c].clone()).collect())
+        .collect()
+}
+
+/// Rank of a value type in comparisons: numbers < text < booleans
+fn rank(v: &DataType) -> u8 {
+    match v {
+        DataType::String(_) | DataType::DateTimeIso(_) | DataType::DurationIso(_) => 1,
+        DataType::Bool(_) => 2,
+        _ => 0,
+    }
+}
+
+/// Compares two values like Excel, text being case insensitive
+fn compare(lhs: &DataType, rhs: &DataType) -> EvalResult<Ordering> {
+    match (lhs, rhs) {
+        (DataType::Error(e), _) | (_, DataType::Error(e)) => return Err(e.clone()),
+        (DataType::Empty, DataType::Empty) => return Ok(Ordering::Equal),
+        _ => (),
+    }
+    // empty cells are equivalent to 0, "" or FALSE
+    let empty = |other: &DataType| match rank(other) {
+        0 => DataType::Float(0.),
+        1 => DataType::String(String::new()),
+        _ => DataType::Bool(false),
+    };
+    let (lhs, rhs) = match (lhs, rhs) {
+        (DataType::Empty, r) => (empty(r), r.clone()),
+        (l, DataType::Empty) => (l.clone(), empty(l)),
+        (l, r) => (l.clone(), r.clone()),
+    };
+    Ok(match (rank(&lhs), rank(&rhs)) {
+        (0, 0) => significant(to_number(&lhs)?)
+            .partial_cmp(&significant(to_number(&rhs)?))
+            .unwrap_or(Ordering::Equal),
+        (1, 1) => to_text(&lhs)?
+            .to_lowercase()
+            .cmp(&to_text(&rhs)?.to_lowercase()),
+        (2, 2) => to_bool(&lhs)?.cmp(&to_bool(&rhs)?),
+        (l, r) => l.cmp(&r),
+    })
+}
+
+/// Rounds `n` to 15 significant digits, the precision of Excel comparisons,
+/// e.g. `0.1+0.2=0.3` is true
+fn significant(n: f64) -> f64 {
+    if n == 0. || !n.is_finite() {
+        return n;
+    }
+    let digits = 14 - n.abs().log10().floor() as i32;
+    let scale = 10f64.powi(digits.abs());
+    if !scale.is_finite() {
+        n
+    } else if digits >= 0 {
+        (n * scale).round() / scale
+    } else {
+        (n / scale).round() * scale
+    }
+}
+
+/// Position of `x` in `keys`
+///
+/// `typ` is 0 for an exact match (with wildcards for text), 1 for the largest
+/// value lower or equal to `x` in ascending keys and -1 for the smallest value
+/// greater or equal to `x` in descending keys.
+fn lookup(keys: &[DataType], x: &DataType, typ: i32) -> EvalResult<usize> {
+    if typ == 0 {
+        return keys
+            .iter()
+            .position(|k| match (k, x) {
+                (DataType::String(k), DataType::String(x)) => wildcard(x, k),
+                (k, x) if !k.is_empty() && rank(k) == rank(x) => {
+                    compare(k, x) == Ok(Ordering::Equal)
+                }
+                _ => false,
+            })
+            .ok_or(CellErrorType::NA);
+    }
+    let mut found = None;
+    for (i, k) in keys.iter().enumerate() {
+        if k.is_empty() || rank(k) != rank(x) {
+            continue;
+        }
+        match compare(k, x) {
+            Ok(Ordering::Equal) => found = Some(i),
+            Ok(Ordering::Less) if typ > 0 => found = Some(i),
+            Ok(Ordering::Greater) if typ < 0 => found = Some(i),
+            _ => break,
+        }
+    }
+    found.ok_or(CellErrorType::NA)
+}
+
+/// Matches text against a pattern with `*`, `?` and `~` escapes, case insensitive
+fn wildcard(pattern: &str, text: &str) -> bool {
+    fn matches(p: &[char], t: &[char]) -> bool {
+        match p.first() {
+            None => t.is_empty(),
+            Some('*') => (0..=t.len()).any(|i| matches(&p[1..], &t[i..])),
+            Some('?') => !t.is_empty() && matches(&p[1..], &t[1..]),
+            Some('~') if p.len() > 1 => t.first() == Some(&p[1]) && matches(&p[2..], &t[1..]),
+            Some(c) => t.first() == Some(c) && matches(&p[1..], &t[1..]),
+        }
+    }
+    let p = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let t = text.to_lowercase().chars().collect::<Vec<_>>();
+    matches(&p, &t)
+}
+
+/// A criteria of `COUNTIF` like functions, e.g. `">=10"` or `"a*"`
+struct Criteria {
+    op: BinaryOp,
+    value: DataType,
+}
+
+impl Criteria {
+    fn new(v: DataType) -> EvalResult<Criteria> {
+        let s = match v {
+            DataType::Error(e) => return Err(e),
+            DataType::String(s) => s,
+            value => {
+                return Ok(Criteria {
+                    op: BinaryOp::Eq,
+                    value,
+                })
+            }
+        };
+        let (op, rest) = [
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<>", BinaryOp::Ne),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+            ("=", BinaryOp::Eq),
+        ]
+        .iter()
+        .find_map(|(p, op)| s.strip_prefix(p).map(|rest| (*op, rest)))
+        .unwrap_or((BinaryOp::Eq, &s));
+        let value = if rest.is_empty() {
+            DataType::Empty
+        } else if let Some(n) = parse_number(rest) {
+            DataType::Float(n)
+        } else if rest.eq_ignore_ascii_case("TRUE") || rest.eq_ignore_ascii_case("FALSE") {
+            DataType::Bool(rest.eq_ignore_ascii_case("TRUE"))
+        } else {
+            DataType::String(rest.to_string())
+        };
+        Ok(Criteria { op, value })
+    }
+
+    fn matches(&self, v: &DataType) -> bool {
+        match (&self.op, &self.value) {
+            (BinaryOp::Eq, DataType::Empty) => {
+                v.is_empty() || *v == DataType::String(String::new())
+            }
+            (BinaryOp::Ne, DataType::Empty) => !v.is_empty(),
+            (BinaryOp::Eq, DataType::String(p)) => {
+                matches!(v, DataType::String(s) if wildcard(p, s))
+            }
+            (BinaryOp::Ne, DataType::String(p)) => {
+                !matches!(v, DataType::String(s) if wildcard(p, s))
+            }
+            (BinaryOp::Ne, x) => compare(v, x) != Ok(Ordering::Equal) || v.is_empty(),
+            (op, x) => {
+                // numbers only match numbers, text only matches text
+                let v = match v {
+                    DataType::String(s) if rank(x) == 0 => match parse_number(s) {
+                        Some(n) if *op == BinaryOp::Eq => DataType::Float(n),
+                        _ => return false,
+                    },
+                    v if v.is_empty() || rank(v) != rank(x) => return false,
+                    v => v.clone(),
+                };
+                binary(*op, v, x.clone()) == DataType::Bool(true)
+            }
+        }
+    }
+}
+
+/// Values of `values` whose `range` counterpart matches `criteria`
+fn matching(
+    range: &[Vec<DataType>],
+    values: &[Vec<DataType>],
+    criteria: &Criteria,
+) -> Vec<DataType> {
+    let mut matches = Vec::new();
+    for (r, row) in range.iter().enumerate() {
+        for (c, v) in row.iter().enumerate() {
+            if criteria.matches(v) {
+                matches.push(
+                    values
+                        .get(r)
+                        .and_then(|row| row.get(c))
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            }
+        }
+    }
+    matches
+}
+
+/// Result of `SUMIF`, `COUNTIF` or `AVERAGEIF` on the matching values
+fn aggregate_if(name: &str, matches: Vec<DataType>) -> EvalResult<DataType> {
+    if name == "COUNTIF" {
+        return Ok(DataType::Float(matches.len() as f64));
+    }
+    let mut numbers = Vec::new();
+    for v in matches {
+        match v {
+            DataType::Error(e) => return Err(e),
+            v if is_number(&v) => numbers.push(to_number(&v)?),
+            _ => (),
+        }
+    }
+    let sum = sum(&numbers);
+    if name == "AVERAGEIF" {
+        if numbers.is_empty() {
+            return Err(CellErrorType::Div0);
+        }
+        return Ok(DataType::Float(sum / numbers.len() as f64));
+    }
+    Ok(DataType::Float(sum))
+}
+
+/// Sum of `numbers`, `0` and not `-0` if there are none
+fn sum(numbers: &[f64]) -> f64 {
+    numbers.iter().fold(0., |sum, n| sum + n)
+}
+
+/// Rounds `n` to `digits` decimals, as `ROUND`, `ROUNDUP`, `ROUNDDOWN` or `TRUNC`
+fn round(name: &str, n: f64, digits: i32) -> f64 {
+    let scale = 10f64.powi(digits);
+    // avoid representation errors, e.g. 2.675 being 2.67499999...
+    let scaled = (n * scale * 1e9).round() / 1e9;
+    let r = match name {
+        "ROUND" => scaled.abs().round().copysign(n),
+        "ROUNDUP" => scaled.abs().ceil().copysign(n),
+        _ => scaled.trunc(),
+    };
+    r / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluator(
+        cells: Vec<((u32, u32), DataType)>,
+        formulas: &[((u32, u32), &str)],
+    ) -> Evaluator<'static> {
+        let values = Range::from_sparse(cells.into_iter().map(|(p, v)| Cell::new(p, v)).collect());
+        let formulas = Range::from_sparse(
+            formulas
+                .iter()
+                .map(|(p, f)| Cell::new(*p, formula::parse(f).unwrap()))
+                .collect(),
+        );
+        Evaluator {
+            loader: Box::new(|_| None),
+            sheets: vec![Sheet {
+                name: "Sheet1".to_string(),
+                loaded: Some(true),
+                values,
+                formulas,
+            }],
+            names: HashMap::new(),
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            depth: 0,
+        }
+    }
+
+    fn eval(e: &mut Evaluator<'_>, f: &str) -> DataType {
+        e.evaluate("Sheet1", (20, 20), &formula::parse(f).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn operators() {
+        let mut e = evaluator(vec![((0, 0), DataType::Float(2.))], &[]);
+        assert_eq!(eval(&mut e, "1+2*3"), DataType::Float(7.));
+        assert_eq!(eval(&mut e, "-A1^2"), DataType::Float(4.));
+        assert_eq!(eval(&mut e, "A1/0"), DataType::Error(CellErrorType::Div0));
+        assert_eq!(
+            eval(&mut e, "\"a\"&A1&TRUE"),
+            DataType::String("a2TRUE".to_string())
+        );
+        assert_eq!(eval(&mut e, "\"abc\"=\"ABC\""), DataType::Bool(true));
+        assert_eq!(eval(&mut e, "\"a\">1"), DataType::Bool(true));
+        assert_eq!(eval(&mut e, "B1=0"), DataType::Bool(true));
+        assert_eq!(
+            eval(&mut e, "\"x\"+1"),
+            DataType::Error(CellErrorType::Value)
+        );
+        assert_eq!(eval(&mut e, "50%"), DataType::Float(0.5));
+        assert_eq!(eval(&mut e, "0.1+0.2=0.3"), DataType::Bool(true));
+        assert_eq!(eval(&mut e, "0.1+0.2>0.3"), DataType::Bool(false));
+        assert_eq!(eval(&mut e, "1=1.0000000001"), DataType::Bool(false));
+        assert_eq!(eval(&mut e, "1E-300*3=3E-300"), DataType::Bool(true));
+        assert_eq!(
+            eval(&mut e, "123456789012345678=123456789012346000"),
+            DataType::Bool(true)
+        );
+        assert_eq!(eval(&mut e, "1E-320<2E-320"), DataType::Bool(true));
+    }
+
+    #[test]
+    fn functions() {
+        let cells = vec![
+            ((0, 0), DataType::String("apple".to_string())),
+            ((0, 1), DataType::Float(3.)),
+            ((1, 0), DataType::String("banana".to_string())),
+            ((1, 1), DataType::Float(5.)),
+            ((2, 0), DataType::String("cherry".to_string())),
+            ((2, 1), DataType::Float(7.)),
+        ];
+        let mut e = evaluator(cells, &[]);
+        assert_eq!(eval(&mut e, "SUM(B1:B3,1)"), DataType::Float(16.));
+        assert!(matches!(
+            eval(&mut e, "SUM(A1:A3)"),
+            DataType::Float(n) if n == 0. && n.is_sign_positive()
+        ));
+        assert_eq!(eval(&mut e, "PRODUCT(A1:A3)"), DataType::Float(0.));
+        assert_eq!(eval(&mut e, "AVERAGE(B:B)"), DataType::Float(5.));
+        assert_eq!(eval(&mut e, "COUNTA(A1:B3)"), DataType::Float(6.));
+        assert_eq!(
+            eval(&mut e, "VLOOKUP(\"banana\",A1:B3,2,FALSE)"),
+            DataType::Float(5.)
+        );
+        assert_eq!(
+            eval(&mut e, "VLOOKUP(\"b*\",A1:B3,2,FALSE)"),
+            DataType::Float(5.)
+        );
+        assert_eq!(
+            eval(&mut e, "VLOOKUP(\"kiwi\",A1:B3,2,FALSE)"),
+            DataType::Error(CellErrorType::NA)
+        );
+        assert_eq!(
+            eval(&mut e, "INDEX(A1:A3,MATCH(7,B1:B3,0))"),
+            DataType::String("cherry".to_string())
+        );
+        assert_eq!(eval(&mut e, "MATCH(6,B1:B3)"), DataType::Float(2.));
+        assert_eq!(eval(&mut e, "SUMIF(B1:B3,\">4\")"), DataType::Float(12.));
+        assert_eq!(eval(&mut e, "COUNTIF(A1:A3,\"*an*\")"), DataType::Float(1.));
+        assert_eq!(
+            eval(&mut e, "SUMPRODUCT(B1:B3,B1:B3)"),
+            DataType::Float(83.)
+        );
+        assert_eq!(eval(&mut e, "SUM(B1:B3*2)"), DataType::Float(30.));
+        assert_eq!(
+            eval(&mut e, "IF(B1>3,\"big\",\"small\")"),
+            DataType::String("small".to_string())
+        );
+        assert_eq!(
+            eval(&mut e, "IFERROR(1/0,\"oops\")"),
+            DataType::String("oops".to_string())
+        );
+        assert_eq!(eval(&mut e, "ROUND(2.675,2)"), DataType::Float(2.68));
+        assert_eq!(
+            eval(&mut e, "MID(UPPER(A2),2,3)&LEN(A3)"),
+            DataType::String("ANA6".to_string())
+        );
+        assert_eq!(
+            eval(&mut e, "SUBSTITUTE(\"a-b-c\",\"-\",\"+\",2)"),
+            DataType::String("a-b+c".to_string())
+        );
+        assert_eq!(
+            eval(&mut e, "TEXT(B2,\"0.00\")"),
+            DataType::String("5.00".to_string())
+        );
+        assert_eq!(
+            eval(&mut e, "NOPE(1)"),
+            DataType::Error(CellErrorType::Name)
+        );
+    }
+
+    #[test]
+    fn dates() {
+        let mut e = evaluator(vec![], &[]);
+        assert_eq!(eval(&mut e, "DATE(2024,2,29)"), DataType::DateTime(45351.));
+        assert_eq!(eval(&mut e, "DATE(2023,14,1)"), DataType::DateTime(45323.));
+        assert_eq!(
+            eval(&mut e, "YEAR(45351)*100+MONTH(45351)"),
+            DataType::Float(202402.)
+        );
+        assert_eq!(
+            eval(&mut e, "EOMONTH(DATE(2024,1,31),1)"),
+            DataType::DateTime(45351.)
+        );
+        assert_eq!(
+            eval(&mut e, "EDATE(DATE(2024,1,31),1)"),
+            DataType::DateTime(45351.)
+        );
+        assert_eq!(
+            eval(&mut e, "WEEKDAY(DATE(2024,2,29))"),
+            DataType::Float(5.)
+        );
+        assert_eq!(eval(&mut e, "HOUR(TIME(13,30,0))"), DataType::Float(13.));
+    }
+
+    #[test]
+    fn references() {
+        let mut e = evaluator(
+            vec![((0, 0), DataType::Float(1.))],
+            &[
+                ((0, 1), "A1:A3*2"),
+                ((1, 0), "A1+1"),
+                ((2, 0), "A2*10"),
+                ((3, 0), "A5"),
+                ((4, 0), "A4"),
+            ],
+        );
+        assert_eq!(e.cell_value("Sheet1", (2, 0)), Some(DataType::Float(20.)));
+        assert_eq!(
+            e.cell_value("Sheet1", (3, 0)),
+            Some(DataType::Error(CellErrorType::Value))
+        );
+        let range = e.worksheet_range("Sheet1").unwrap();
+        assert_eq!(range.get_value((1, 0)), Some(&DataType::Float(2.)));
+        // implicit intersection with the formula row
+        assert_eq!(range.get_value((0, 1)), Some(&DataType::Float(2.)));
+        assert_eq!(
+            eval(&mut e, "Sheet2!A1"),
+            DataType::Error(CellErrorType::Ref)
+        );
+        assert_eq!(e.cell_value("Sheet2", (0, 0)), None);
+    }
+
+    #[test]
+    fn lazy_sheets() {
+        let loaded = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let log = loaded.clone();
+        let sheet = |name: &str| Sheet {
+            name: name.to_string(),
+            loaded: None,
+            values: Range::default(),
+            formulas: Range::default(),
+        };
+        let mut e = Evaluator {
+            loader: Box::new(move |name| {
+                log.borrow_mut().push(name.to_string());
+                let values = Range::from_sparse(vec![Cell::new((0, 0), DataType::Float(1.))]);
+                Some((values, Range::default())).filter(|_| name != "Broken")
+            }),
+            sheets: vec![sheet("Sheet1"), sheet("Sheet2"), sheet("Broken")],
+            names: HashMap::new(),
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            depth: 0,
+        };
+        assert_eq!(eval(&mut e, "1+1"), DataType::Float(2.));
+        assert!(loaded.borrow().is_empty());
+        assert_eq!(eval(&mut e, "Sheet2!A1+Sheet2!A1"), DataType::Float(2.));
+        assert_eq!(*loaded.borrow(), ["Sheet2"]);
+        assert_eq!(
+            eval(&mut e, "Broken!A1"),
+            DataType::Error(CellErrorType::Ref)
+        );
+        assert_eq!(
+            eval(&mut e, "SUM(Broken!A1:B2)"),
+            DataType::Error(CellErrorType::Ref)
+        );
+        assert!(e.worksheet_range("Broken").is_none());
+        assert_eq!(*loaded.borrow(), ["Sheet2", "Broken"]);
+        assert_eq!(
+            eval(&mut e, "SUM(Sheet1:Broken!A1)"),
+            DataType::Error(CellErrorType::Ref)
+        );
+    }
+}
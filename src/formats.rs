@@ -69,7 +69,7 @@ pub fn builtin_format_by_id(id: &[u8]) -> CellFormat {
         b"22" |
         // mm:ss
         b"45" |
-        // mmss.0
+        // mm:ss.0
         b"47" => CellFormat::DateTime,
         // [h]:mm:ss
         b"46" => CellFormat::TimeDelta,
@@ -88,6 +88,51 @@ pub fn builtin_format_by_code(code: u16) -> CellFormat {
     }
 }
 
+/// Format code of builtin number formats
+///
+/// Locale dependent formats (5 to 8 and 27 to 36) are given for en-US
+pub fn builtin_format_code(id: u16) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        5 => "$#,##0_);($#,##0)",
+        6 => "$#,##0_);[Red]($#,##0)",
+        7 => "$#,##0.00_);($#,##0.00)",
+        8 => "$#,##0.00_);[Red]($#,##0.00)",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "mm-dd-yy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yy h:mm",
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        41 => r#"_(* #,##0_);_(* \(#,##0\);_(* "-"_);_(@_)"#,
+        42 => r#"_("$"* #,##0_);_("$"* \(#,##0\);_("$"* "-"_);_(@_)"#,
+        43 => r#"_(* #,##0.00_);_(* \(#,##0.00\);_(* "-"??_);_(@_)"#,
+        44 => r#"_("$"* #,##0.00_);_("$"* \(#,##0.00\);_("$"* "-"??_);_(@_)"#,
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mm:ss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
 // convert i64 to date, if format == Date
 pub fn format_excel_i64(value: i64, format: Option<&CellFormat>, is_1904: bool) -> DataType {
     match format {
@@ -105,7 +150,7 @@ pub fn format_excel_i64(value: i64, format: Option<&CellFormat>, is_1904: bool)
 
 // convert f64 to date, if format == Date
 #[inline]
-pub fn format_excel_f64_ref<'a>(
+pub fn format_excel_f64_ref(
     value: f64,
     format: Option<&CellFormat>,
     is_1904: bool,
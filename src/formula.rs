@@ -0,0 +1,2285 @@
+//! Formula abstract syntax tree
+//!
+//! Formulas of all formats can be read as an `Expr` tree with
+//! `Reader::worksheet_formula_ast`. xls and xlsb formulas are decoded from
+//! their binary token stream (rgce), xlsx and ods formula text is parsed.
+//!
+//! `Expr` implements `Display`, which renders the formula with the Excel
+//! syntax (without the leading `=`) whatever the source format.
+//!
+//! ```
+//! use calamine::formula::{parse, BinaryOp, CellRef, Expr};
+//!
+//! let expr = parse("=SUM(A1:B2)*2").unwrap();
+//! match &expr {
+//!     Expr::Binary(BinaryOp::Mul, lhs, _) => match &**lhs {
+//!         Expr::Function { name, args } => {
+//!             assert_eq!(name, "SUM");
+//!             assert_eq!(
+//!                 args[0],
+//!                 Expr::Area {
+//!                     sheet: None,
+//!                     start: CellRef::new(0, 0),
+//!                     end: CellRef::new(1, 1),
+//!                 }
+//!             );
+//!         }
+//!         e => panic!("unexpected {:?}", e),
+//!     },
+//!     e => panic!("unexpected {:?}", e),
+//! }
+//! assert_eq!(expr.to_string(), "SUM(A1:B2)*2");
+//! ```
+
+use std::fmt;
+
+use encoding_rs::UTF_16LE;
+use log::debug;
+
+use crate::utils::{read_f64, read_u16, read_u32, FTAB, FTAB_ARGC};
+use crate::{CellErrorType, CellType, Range, TableMetadata};
+
+/// Last row of a xlsx/xlsb worksheet (0 based)
+const MAX_ROW: u32 = 1_048_575;
+/// Last column of a xlsx/xlsb worksheet (0 based)
+const MAX_COL: u32 = 16_383;
+/// Last row of a xls worksheet (0 based)
+const MAX_ROW_BIFF8: u32 = 65_535;
+/// Last column of a xls worksheet (0 based)
+const MAX_COL_BIFF8: u32 = 255;
+
+/// A formula error
+#[derive(Debug)]
+pub enum FormulaError {
+    /// Unexpected character in formula text
+    Unexpected {
+        /// Position of the character, in chars
+        position: usize,
+        /// Character found
+        found: char,
+    },
+    /// Formula text ended unexpectedly
+    UnexpectedEnd,
+    /// Invalid number literal
+    Number(String),
+    /// Invalid cell reference
+    Reference(String),
+    /// Unsupported Ptg
+    Ptg(u8),
+    /// Unsupported etpg
+    Etpg(u8),
+    /// Unsupported iftab
+    IfTab(usize),
+    /// Unsupported BErr
+    BErr(u8),
+    /// Token stream shorter than expected
+    Truncated,
+    /// Invalid formula, stack length too short
+    StackLen,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormulaError::Unexpected { position, found } => {
+                write!(f, "Unexpected '{found}' at position {position}")
+            }
+            FormulaError::UnexpectedEnd => write!(f, "Unexpected end of formula"),
+            FormulaError::Number(n) => write!(f, "Invalid number '{n}'"),
+            FormulaError::Reference(r) => write!(f, "Invalid reference '{r}'"),
+            FormulaError::Ptg(t) => write!(f, "Unsupported ptg {t:X}"),
+            FormulaError::Etpg(t) => write!(f, "Unsupported etpg {t:X}"),
+            FormulaError::IfTab(t) => write!(f, "Unsupported iftab {t:X}"),
+            FormulaError::BErr(t) => write!(f, "Unsupported BErr {t:X}"),
+            FormulaError::Truncated => write!(f, "Formula token stream is truncated"),
+            FormulaError::StackLen => write!(f, "Invalid stack length"),
+        }
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+/// A cell reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CellRef {
+    /// Row, 0 based
+    pub row: u32,
+    /// Column, 0 based
+    pub col: u32,
+    /// Absolute row (`$` before the row)
+    pub abs_row: bool,
+    /// Absolute column (`$` before the column)
+    pub abs_col: bool,
+}
+
+impl CellRef {
+    /// Creates a new relative reference
+    pub fn new(row: u32, col: u32) -> CellRef {
+        CellRef {
+            row,
+            col,
+            abs_row: false,
+            abs_col: false,
+        }
+    }
+
+    /// Creates a new absolute reference
+    pub fn absolute(row: u32, col: u32) -> CellRef {
+        CellRef {
+            row,
+            col,
+            abs_row: true,
+            abs_col: true,
+        }
+    }
+
+    fn fmt_col(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.abs_col {
+            f.write_str("$")?;
+        }
+        let mut letters = Vec::new();
+        let mut col = self.col + 1;
+        while col > 0 {
+            letters.push((b'A' + ((col - 1) % 26) as u8) as char);
+            col = (col - 1) / 26;
+        }
+        letters.iter().rev().try_for_each(|c| write!(f, "{c}"))
+    }
+
+    fn fmt_row(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.abs_row {
+            f.write_str("$")?;
+        }
+        write!(f, "{}", self.row + 1)
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_col(f)?;
+        self.fmt_row(f)
+    }
+}
+
+/// The sheet a reference points to
+///
+/// 3D references, e.g. `Sheet1:Sheet3!A1`, span several sheets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SheetRef {
+    /// First (or only) sheet
+    pub first: String,
+    /// Last sheet of a 3D reference
+    pub last: Option<String>,
+}
+
+impl SheetRef {
+    /// Creates a reference to a single sheet
+    pub fn new<S: Into<String>>(name: S) -> SheetRef {
+        SheetRef {
+            first: name.into(),
+            last: None,
+        }
+    }
+}
+
+impl fmt::Display for SheetRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quote = needs_quotes(&self.first) || self.last.as_deref().map_or(false, needs_quotes);
+        let mut name = self.first.clone();
+        if let Some(last) = &self.last {
+            name.push(':');
+            name.push_str(last);
+        }
+        if quote {
+            write!(f, "'{}'", name.replace('\'', "''"))
+        } else {
+            f.write_str(&name)
+        }
+    }
+}
+
+/// Whether a sheet name must be quoted in a formula
+fn needs_quotes(name: &str) -> bool {
+    // external workbook index, e.g. [1]Sheet1
+    let name = match name.strip_prefix('[').and_then(|n| n.split_once(']')) {
+        Some((book, name)) if book.bytes().all(|c| c.is_ascii_digit()) => name,
+        _ => name,
+    };
+    name.is_empty()
+        || name.starts_with(|c: char| c.is_ascii_digit())
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        || matches!(endpoint(name), Some(Endpoint::Cell(_)))
+}
+
+/// A unary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    /// `+x`
+    Plus,
+    /// `-x`
+    Minus,
+    /// `x%`
+    Percent,
+}
+
+/// A binary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `^`
+    Pow,
+    /// `&`
+    Concat,
+    /// `=`
+    Eq,
+    /// `<>`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `:`, the range between two references
+    Range,
+    /// `,`, the union of two references
+    Union,
+    /// ` `, the intersection of two references
+    Intersect,
+}
+
+impl BinaryOp {
+    /// Operator as written in Excel formulas
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+            BinaryOp::Concat => "&",
+            BinaryOp::Eq => "=",
+            BinaryOp::Ne => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Range => ":",
+            BinaryOp::Union => ",",
+            BinaryOp::Intersect => " ",
+        }
+    }
+
+    /// Left and right binding powers, operators are left associative
+    fn binding_power(&self) -> (u8, u8) {
+        match self {
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => (1, 2),
+            BinaryOp::Concat => (3, 4),
+            BinaryOp::Add | BinaryOp::Sub => (5, 6),
+            BinaryOp::Mul | BinaryOp::Div => (7, 8),
+            BinaryOp::Pow => (9, 10),
+            BinaryOp::Union => (13, 14),
+            BinaryOp::Intersect => (15, 16),
+            BinaryOp::Range => (17, 18),
+        }
+    }
+
+    /// Ptg 0x03 to 0x11 [MS-XLS 2.5.198.25]
+    fn from_ptg(ptg: u8) -> BinaryOp {
+        match ptg {
+            0x03 => BinaryOp::Add,
+            0x04 => BinaryOp::Sub,
+            0x05 => BinaryOp::Mul,
+            0x06 => BinaryOp::Div,
+            0x07 => BinaryOp::Pow,
+            0x08 => BinaryOp::Concat,
+            0x09 => BinaryOp::Lt,
+            0x0A => BinaryOp::Le,
+            0x0B => BinaryOp::Eq,
+            0x0C => BinaryOp::Ge,
+            0x0D => BinaryOp::Gt,
+            0x0E => BinaryOp::Ne,
+            0x0F => BinaryOp::Intersect,
+            0x10 => BinaryOp::Union,
+            _ => BinaryOp::Range,
+        }
+    }
+}
+
+/// Binding power of prefix `+` and `-`
+const PREFIX_BP: u8 = 11;
+/// Binding power of postfix `%`
+const PERCENT_BP: u8 = 12;
+
+/// A formula expression
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Expr {
+    /// Missing function argument, also used for empty cells
+    #[default]
+    Missing,
+    /// Number literal
+    Number(f64),
+    /// String literal
+    String(String),
+    /// Boolean literal
+    Bool(bool),
+    /// Error literal, or invalid reference
+    Error(CellErrorType),
+    /// Array literal, as rows of values
+    Array(Vec<Vec<Expr>>),
+    /// Single cell reference
+    Cell {
+        /// Sheet, `None` for the formula sheet
+        sheet: Option<SheetRef>,
+        /// Cell
+        cell: CellRef,
+    },
+    /// Area reference
+    ///
+    /// Whole columns and whole rows span all the rows, respectively all the
+    /// columns, of the worksheet.
+    Area {
+        /// Sheet, `None` for the formula sheet
+        sheet: Option<SheetRef>,
+        /// Top left cell
+        start: CellRef,
+        /// Bottom right cell
+        end: CellRef,
+    },
+    /// Defined name
+    Name(String),
+    /// Table structured reference, e.g. `Sales[[#Headers],[Qty]]` or `[@Qty]`
+    Table {
+        /// Table name, `None` for the table of the formula cell
+        table: Option<String>,
+        /// Brackets content, e.g. `[#Headers],[Qty]`
+        spec: String,
+    },
+    /// Function call
+    Function {
+        /// Function name, as written in formulas
+        name: String,
+        /// Arguments
+        args: Vec<Expr>,
+    },
+    /// Unary operation
+    Unary(UnaryOp, Box<Expr>),
+    /// Binary operation
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// Parenthesized expression
+    Paren(Box<Expr>),
+}
+
+impl CellType for Expr {}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Missing => Ok(()),
+            Expr::Number(n) => write!(f, "{n}"),
+            Expr::String(s) => write!(f, "\"{}\"", s.replace('"', "\"\"")),
+            Expr::Bool(b) => f.write_str(if *b { "TRUE" } else { "FALSE" }),
+            Expr::Error(e) => write!(f, "{e}"),
+            Expr::Array(rows) => {
+                f.write_str("{")?;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(";")?;
+                    }
+                    for (j, v) in row.iter().enumerate() {
+                        if j > 0 {
+                            f.write_str(",")?;
+                        }
+                        write!(f, "{v}")?;
+                    }
+                }
+                f.write_str("}")
+            }
+            Expr::Cell { sheet, cell } => {
+                if let Some(sheet) = sheet {
+                    write!(f, "{sheet}!")?;
+                }
+                write!(f, "{cell}")
+            }
+            Expr::Area { sheet, start, end } => {
+                if let Some(sheet) = sheet {
+                    write!(f, "{sheet}!")?;
+                }
+                if start.row == 0 && (end.row == MAX_ROW || end.row == MAX_ROW_BIFF8) {
+                    start.fmt_col(f)?;
+                    f.write_str(":")?;
+                    end.fmt_col(f)
+                } else if start.col == 0 && (end.col == MAX_COL || end.col == MAX_COL_BIFF8) {
+                    start.fmt_row(f)?;
+                    f.write_str(":")?;
+                    end.fmt_row(f)
+                } else {
+                    write!(f, "{start}:{end}")
+                }
+            }
+            Expr::Name(name) => f.write_str(name),
+            Expr::Table { table, spec } => {
+                if let Some(table) = table {
+                    f.write_str(table)?;
+                }
+                write!(f, "[{spec}]")
+            }
+            Expr::Function { name, args } => {
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                f.write_str(")")
+            }
+            Expr::Unary(UnaryOp::Plus, e) => write!(f, "+{e}"),
+            Expr::Unary(UnaryOp::Minus, e) => write!(f, "-{e}"),
+            Expr::Unary(UnaryOp::Percent, e) => write!(f, "{e}%"),
+            Expr::Binary(op, lhs, rhs) => write!(f, "{lhs}{}{rhs}", op.symbol()),
+            Expr::Paren(e) => write!(f, "({e})"),
+        }
+    }
+}
+
+/// Parses an Excel formula, with or without its leading `=`
+///
+/// This is the syntax of xlsx formulas, e.g. `SUM(Sheet1!A1:B2,3)`.
+pub fn parse(formula: &str) -> Result<Expr, FormulaError> {
+    let formula = formula.trim_start();
+    let formula = formula.strip_prefix('=').unwrap_or(formula);
+    Parser::new(formula, Dialect::Excel).parse()
+}
+
+/// Parses an OpenDocument formula
+///
+/// This is the syntax of ods formulas, e.g. `of:=SUM([$Sheet1.A1:.B2];3)`.
+/// The namespace prefix and the leading `=` are optional.
+pub fn parse_open_formula(formula: &str) -> Result<Expr, FormulaError> {
+    let formula = formula.trim_start();
+    let formula = match formula.split_once(':') {
+        Some((ns, f)) if f.starts_with('=') && ns.chars().all(|c| c.is_ascii_alphanumeric()) => f,
+        _ => formula,
+    };
+    let formula = formula.strip_prefix('=').unwrap_or(formula);
+    Parser::new(formula, Dialect::OpenFormula).parse()
+}
+
+/// Formula text syntax
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dialect {
+    /// Excel, references like `Sheet1!A1:B2`, `,` separates arguments
+    Excel,
+    /// OpenFormula, references like `[$Sheet1.A1:.B2]`, `;` separates arguments
+    OpenFormula,
+}
+
+/// Reference endpoint, e.g. `A1`, `$B` or `3`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Endpoint {
+    Cell(CellRef),
+    Col(u32, bool),
+    Row(u32, bool),
+}
+
+/// Parses a reference endpoint: a cell, a column or a row
+fn endpoint(s: &str) -> Option<Endpoint> {
+    let (abs_col, s) = match s.strip_prefix('$') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let letters = s.bytes().take_while(|c| c.is_ascii_alphabetic()).count();
+    let (col, rest) = s.split_at(letters);
+    let (abs_row, digits) = match rest.strip_prefix('$') {
+        Some(d) => (true, d),
+        None => (false, rest),
+    };
+    if !digits.bytes().all(|c| c.is_ascii_digit()) || (col.is_empty() && abs_row) {
+        return None;
+    }
+    let col = if col.is_empty() {
+        None
+    } else if col.len() > 3 {
+        return None;
+    } else {
+        let col = col.bytes().fold(0, |col, c| {
+            col * 26 + (c.to_ascii_uppercase() - b'A') as u32 + 1
+        });
+        if col > MAX_COL + 1 {
+            return None;
+        }
+        Some(col - 1)
+    };
+    let row = if digits.is_empty() {
+        None
+    } else {
+        match digits.parse::<u32>() {
+            Ok(r) if (1..=MAX_ROW + 1).contains(&r) => Some(r - 1),
+            _ => return None,
+        }
+    };
+    match (col, row) {
+        (Some(col), Some(row)) => Some(Endpoint::Cell(CellRef {
+            row,
+            col,
+            abs_row,
+            abs_col,
+        })),
+        (Some(col), None) if !abs_row => Some(Endpoint::Col(col, abs_col)),
+        (None, Some(row)) => Some(Endpoint::Row(row, abs_col)),
+        _ => None,
+    }
+}
+
+/// Builds a reference from its endpoints, `None` if they are not compatible
+fn reference(sheet: Option<SheetRef>, start: Endpoint, end: Option<Endpoint>) -> Option<Expr> {
+    let (start, end) = match (start, end) {
+        (Endpoint::Cell(cell), None) => return Some(Expr::Cell { sheet, cell }),
+        (Endpoint::Cell(start), Some(Endpoint::Cell(end))) => (start, end),
+        (Endpoint::Col(c1, abs1), Some(Endpoint::Col(c2, abs2))) => (
+            CellRef {
+                row: 0,
+                col: c1,
+                abs_row: true,
+                abs_col: abs1,
+            },
+            CellRef {
+                row: MAX_ROW,
+                col: c2,
+                abs_row: true,
+                abs_col: abs2,
+            },
+        ),
+        (Endpoint::Row(r1, abs1), Some(Endpoint::Row(r2, abs2))) => (
+            CellRef {
+                row: r1,
+                col: 0,
+                abs_row: abs1,
+                abs_col: true,
+            },
+            CellRef {
+                row: r2,
+                col: MAX_COL,
+                abs_row: abs2,
+                abs_col: true,
+            },
+        ),
+        _ => return None,
+    };
+    Some(Expr::Area { sheet, start, end })
+}
+
+/// Error literals, longest first when one is the prefix of another
+const ERRORS: [(&str, CellErrorType); 9] = [
+    ("#NULL!", CellErrorType::Null),
+    ("#DIV/0!", CellErrorType::Div0),
+    ("#VALUE!", CellErrorType::Value),
+    ("#REF!", CellErrorType::Ref),
+    ("#NAME?", CellErrorType::Name),
+    ("#NUM!", CellErrorType::Num),
+    ("#N/A", CellErrorType::NA),
+    ("#GETTING_DATA", CellErrorType::GettingData),
+    ("#DATA!", CellErrorType::GettingData),
+];
+
+/// A recursive descent (Pratt) formula text parser
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    dialect: Dialect,
+}
+
+impl Parser {
+    fn new(formula: &str, dialect: Dialect) -> Parser {
+        Parser {
+            chars: formula.chars().collect(),
+            pos: 0,
+            dialect,
+        }
+    }
+
+    fn parse(mut self) -> Result<Expr, FormulaError> {
+        let expr = self.expr(0, false)?;
+        self.skip_ws();
+        match self.peek() {
+            None => Ok(expr),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    /// Skips whitespaces, returns true if any
+    fn skip_ws(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn unexpected(&self) -> FormulaError {
+        match self.peek() {
+            Some(found) => FormulaError::Unexpected {
+                position: self.pos,
+                found,
+            },
+            None => FormulaError::UnexpectedEnd,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), FormulaError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    /// Parses an expression whose operators bind at least as tight as `min_bp`
+    ///
+    /// `union` is set directly within parentheses, where `,` is the Excel union operator.
+    fn expr(&mut self, min_bp: u8, union: bool) -> Result<Expr, FormulaError> {
+        let mut lhs = self.prefix(union)?;
+        loop {
+            let pos = self.pos;
+            let ws = self.skip_ws();
+            if self.peek() == Some('%') {
+                if PERCENT_BP < min_bp {
+                    self.pos = pos;
+                    break;
+                }
+                self.pos += 1;
+                lhs = Expr::Unary(UnaryOp::Percent, Box::new(lhs));
+                continue;
+            }
+            let (op, len) = match self.binary_op(ws, union) {
+                Some(op) => op,
+                None => {
+                    self.pos = pos;
+                    break;
+                }
+            };
+            let (l_bp, r_bp) = op.binding_power();
+            if l_bp < min_bp {
+                self.pos = pos;
+                break;
+            }
+            self.pos += len;
+            let rhs = self.expr(r_bp, union)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// Binary operator at the current position, and its length
+    fn binary_op(&self, ws: bool, union: bool) -> Option<(BinaryOp, usize)> {
+        let op = match (self.peek()?, self.peek_at(1)) {
+            ('+', _) => (BinaryOp::Add, 1),
+            ('-', _) => (BinaryOp::Sub, 1),
+            ('*', _) => (BinaryOp::Mul, 1),
+            ('/', _) => (BinaryOp::Div, 1),
+            ('^', _) => (BinaryOp::Pow, 1),
+            ('&', _) => (BinaryOp::Concat, 1),
+            ('=', _) => (BinaryOp::Eq, 1),
+            ('<', Some('>')) => (BinaryOp::Ne, 2),
+            ('<', Some('=')) => (BinaryOp::Le, 2),
+            ('<', _) => (BinaryOp::Lt, 1),
+            ('>', Some('=')) => (BinaryOp::Ge, 2),
+            ('>', _) => (BinaryOp::Gt, 1),
+            (':', _) => (BinaryOp::Range, 1),
+            (',', _) if union && self.dialect == Dialect::Excel => (BinaryOp::Union, 1),
+            ('~', _) if self.dialect == Dialect::OpenFormula => (BinaryOp::Union, 1),
+            ('!', _) if self.dialect == Dialect::OpenFormula => (BinaryOp::Intersect, 1),
+            (c, _)
+                if ws
+                    && self.dialect == Dialect::Excel
+                    && (c.is_alphanumeric() || matches!(c, '$' | '\'' | '(' | '_')) =>
+            {
+                (BinaryOp::Intersect, 0)
+            }
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Parses an operand, with its prefix operators
+    fn prefix(&mut self, union: bool) -> Result<Expr, FormulaError> {
+        self.skip_ws();
+        let c = self.peek().ok_or(FormulaError::UnexpectedEnd)?;
+        match c {
+            '+' | '-' => {
+                self.pos += 1;
+                let op = if c == '+' {
+                    UnaryOp::Plus
+                } else {
+                    UnaryOp::Minus
+                };
+                let e = self.expr(PREFIX_BP, union)?;
+                Ok(Expr::Unary(op, Box::new(e)))
+            }
+            '(' => {
+                self.pos += 1;
+                let e = self.expr(0, true)?;
+                self.skip_ws();
+                self.expect(')')?;
+                Ok(Expr::Paren(Box::new(e)))
+            }
+            '"' => self.string().map(Expr::String),
+            '{' => self.array(),
+            '#' => self.error().map(Expr::Error),
+            '[' if self.dialect == Dialect::OpenFormula => self.odf_reference(),
+            '\'' if self.dialect == Dialect::Excel => {
+                let sheet = self.quoted_sheet()?;
+                self.expect('!')?;
+                self.sheet_reference(sheet)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                if self.dialect == Dialect::Excel {
+                    let pos = self.pos;
+                    let word = self.word();
+                    if let Some(e) = self.local_reference(&word, None) {
+                        return Ok(e);
+                    }
+                    self.pos = pos;
+                }
+                self.number()
+            }
+            '[' if self.dialect == Dialect::Excel => {
+                // structured reference without table name, e.g. `[@Qty]`, unless
+                // it is the external workbook prefix of a reference, e.g. `[1]Sheet1!A1`
+                let pos = self.pos;
+                let spec = self.bracketed()?;
+                if self.peek().map_or(true, |c| !is_word_char(c) && c != '\'') {
+                    return Ok(Expr::Table { table: None, spec });
+                }
+                self.pos = pos;
+                let word = self.word();
+                self.word_operand(word)
+            }
+            c if is_word_char(c) => {
+                let word = self.word();
+                self.word_operand(word)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Reads a name-like word, e.g. `SUM`, `$A$1`, `Sheet1` or `[1]Sheet1`
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        if self.dialect == Dialect::Excel && self.peek() == Some('[') {
+            while let Some(c) = self.bump() {
+                word.push(c);
+                if c == ']' {
+                    break;
+                }
+            }
+        }
+        while let Some(c) = self.peek().filter(|&c| is_word_char(c)) {
+            word.push(c);
+            self.pos += 1;
+        }
+        word
+    }
+
+    /// Operand starting with a word: function call, reference, boolean or name
+    fn word_operand(&mut self, word: String) -> Result<Expr, FormulaError> {
+        if word.is_empty() {
+            return Err(self.unexpected());
+        }
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let args = self.arguments()?;
+                return Ok(Expr::Function { name: word, args });
+            }
+            Some('!') if self.dialect == Dialect::Excel => {
+                self.pos += 1;
+                return self.sheet_reference(SheetRef::new(word));
+            }
+            Some('[') if self.dialect == Dialect::Excel => {
+                let spec = self.bracketed()?;
+                return Ok(Expr::Table {
+                    table: Some(word),
+                    spec,
+                });
+            }
+            Some(':') if self.dialect == Dialect::Excel => {
+                // 3D reference, e.g. Sheet1:Sheet3!A1
+                let pos = self.pos;
+                self.pos += 1;
+                let last = self.word();
+                if !last.is_empty() && self.peek() == Some('!') {
+                    self.pos += 1;
+                    let sheet = SheetRef {
+                        first: word,
+                        last: Some(last),
+                    };
+                    return self.sheet_reference(sheet);
+                }
+                self.pos = pos;
+            }
+            _ => (),
+        }
+        if self.dialect == Dialect::Excel {
+            if let Some(e) = self.local_reference(&word, None) {
+                return Ok(e);
+            }
+        }
+        if self.dialect == Dialect::OpenFormula {
+            // named expressions may be prefixed, e.g. `$$Name`
+            if let Some(name) = word.strip_prefix("$$") {
+                return Ok(Expr::Name(name.to_string()));
+            }
+        }
+        if word.eq_ignore_ascii_case("TRUE") {
+            Ok(Expr::Bool(true))
+        } else if word.eq_ignore_ascii_case("FALSE") {
+            Ok(Expr::Bool(false))
+        } else {
+            Ok(Expr::Name(word))
+        }
+    }
+
+    /// Reference after its sheet prefix `Sheet1!`
+    fn sheet_reference(&mut self, sheet: SheetRef) -> Result<Expr, FormulaError> {
+        if self.peek() == Some('#') {
+            return self.error().map(Expr::Error);
+        }
+        let word = self.word();
+        if let Some(e) = self.local_reference(&word, Some(sheet.clone())) {
+            return Ok(e);
+        }
+        if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(FormulaError::Reference(format!("{sheet}!{word}")));
+        }
+        // sheet scoped name
+        Ok(Expr::Name(format!("{sheet}!{word}")))
+    }
+
+    /// Interprets `word` as a cell, or as the start of an area
+    ///
+    /// Returns `None`, without moving, if `word` is not a reference.
+    fn local_reference(&mut self, word: &str, sheet: Option<SheetRef>) -> Option<Expr> {
+        let start = endpoint(word)?;
+        if self.peek() == Some(':') {
+            let pos = self.pos;
+            self.pos += 1;
+            let end = self.word();
+            if !matches!(self.peek(), Some('(' | '!')) {
+                if let Some(e) =
+                    endpoint(&end).and_then(|end| reference(sheet.clone(), start, Some(end)))
+                {
+                    return Some(e);
+                }
+            }
+            self.pos = pos;
+        }
+        reference(sheet, start, None)
+    }
+
+    /// Content of the brackets of a structured reference, with nested brackets
+    ///
+    /// `'` escapes the next character, e.g. `[Col'[1']]`.
+    fn bracketed(&mut self) -> Result<String, FormulaError> {
+        self.expect('[')?;
+        let mut s = String::new();
+        let mut depth = 0;
+        loop {
+            match self.bump() {
+                Some('\'') => {
+                    s.push('\'');
+                    s.push(self.bump().ok_or(FormulaError::UnexpectedEnd)?);
+                }
+                Some(']') if depth == 0 => return Ok(s),
+                Some(c) => {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => (),
+                    }
+                    s.push(c);
+                }
+                None => return Err(FormulaError::UnexpectedEnd),
+            }
+        }
+    }
+
+    /// `'Sheet name'`, or `'Sheet1:Sheet3'`
+    fn quoted_sheet(&mut self) -> Result<SheetRef, FormulaError> {
+        let name = self.quoted('\'')?;
+        Ok(match name.split_once(':') {
+            Some((first, last)) => SheetRef {
+                first: first.to_string(),
+                last: Some(last.to_string()),
+            },
+            None => SheetRef::new(name),
+        })
+    }
+
+    /// Text between `quote`s, with doubled quotes as escapes
+    fn quoted(&mut self, quote: char) -> Result<String, FormulaError> {
+        self.expect(quote)?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => {
+                    if self.peek() == Some(quote) {
+                        self.pos += 1;
+                        s.push(quote);
+                    } else {
+                        return Ok(s);
+                    }
+                }
+                Some(c) => s.push(c),
+                None => return Err(FormulaError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, FormulaError> {
+        self.quoted('"')
+    }
+
+    fn error(&mut self) -> Result<CellErrorType, FormulaError> {
+        for (s, e) in ERRORS.iter() {
+            if self.starts_with(s) {
+                self.pos += s.chars().count();
+                return Ok(e.clone());
+            }
+        }
+        Err(self.unexpected())
+    }
+
+    fn number(&mut self) -> Result<Expr, FormulaError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .map_or(false, |c| c.is_ascii_digit() || c == '.')
+        {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('e' | 'E'))
+            && self
+                .peek_at(1)
+                .map_or(false, |c| c.is_ascii_digit() || c == '+' || c == '-')
+        {
+            self.pos += 2;
+            while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        let n: String = self.chars[start..self.pos].iter().collect();
+        n.parse()
+            .map(Expr::Number)
+            .map_err(|_| FormulaError::Number(n))
+    }
+
+    /// Function arguments, after the opening parenthesis
+    fn arguments(&mut self) -> Result<Vec<Expr>, FormulaError> {
+        let sep = match self.dialect {
+            Dialect::Excel => ',',
+            Dialect::OpenFormula => ';',
+        };
+        let mut args = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(')') {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            self.skip_ws();
+            if matches!(self.peek(), Some(c) if c == sep || c == ')') {
+                args.push(Expr::Missing);
+            } else {
+                args.push(self.expr(0, false)?);
+                self.skip_ws();
+            }
+            match self.bump() {
+                Some(')') => return Ok(args),
+                Some(c) if c == sep => (),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected());
+                }
+            }
+        }
+    }
+
+    /// Array literal, e.g. `{1,2;3,4}`
+    fn array(&mut self) -> Result<Expr, FormulaError> {
+        let (col_sep, row_sep) = match self.dialect {
+            Dialect::Excel => (',', ';'),
+            Dialect::OpenFormula => (';', '|'),
+        };
+        self.expect('{')?;
+        let mut rows = vec![Vec::new()];
+        loop {
+            let value = self.expr(0, false)?;
+            rows.last_mut().unwrap().push(value);
+            self.skip_ws();
+            match self.bump() {
+                Some('}') => return Ok(Expr::Array(rows)),
+                Some(c) if c == col_sep => (),
+                Some(c) if c == row_sep => rows.push(Vec::new()),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected());
+                }
+            }
+        }
+    }
+
+    /// OpenFormula reference, e.g. `[.A1]`, `[$Sheet1.A1:.B2]` or `['My sheet'.A:.C]`
+    fn odf_reference(&mut self) -> Result<Expr, FormulaError> {
+        self.expect('[')?;
+        if self.peek() == Some('#') {
+            let e = self.error()?;
+            self.expect(']')?;
+            return Ok(Expr::Error(e));
+        }
+        let (sheet, start) = self.odf_endpoint()?;
+        let end = if self.peek() == Some(':') {
+            self.pos += 1;
+            Some(self.odf_endpoint()?)
+        } else {
+            None
+        };
+        self.expect(']')?;
+        let sheet = match (sheet, end.as_ref().and_then(|e| e.0.clone())) {
+            (Some(first), Some(last)) if first != last => Some(SheetRef {
+                first,
+                last: Some(last),
+            }),
+            (first, _) => first.map(SheetRef::new),
+        };
+        reference(sheet, start, end.map(|e| e.1))
+            .ok_or_else(|| FormulaError::Reference(format!("{start:?}")))
+    }
+
+    /// `$Sheet1.A1`, `'My sheet'.A1` or `.A1`
+    fn odf_endpoint(&mut self) -> Result<(Option<String>, Endpoint), FormulaError> {
+        if self.peek() == Some('$') && self.peek_at(1) != Some('.') {
+            self.pos += 1;
+        }
+        let sheet = match self.peek() {
+            Some('.') => None,
+            Some('\'') => Some(self.quoted('\'')?),
+            _ => {
+                let mut name = String::new();
+                while let Some(c) = self.peek().filter(|&c| !matches!(c, '.' | ']' | ':')) {
+                    name.push(c);
+                    self.pos += 1;
+                }
+                Some(name)
+            }
+        };
+        self.expect('.')?;
+        let mut cell = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '$')
+        {
+            cell.push(c);
+            self.pos += 1;
+        }
+        let end = endpoint(&cell).ok_or(FormulaError::Reference(cell))?;
+        Ok((sheet, end))
+    }
+}
+
+/// Syntax tree of a formula, `#NAME?` as in Excel if it cannot be parsed or decoded
+pub(crate) fn or_unrecognized(expr: Result<Expr, FormulaError>, pos: (u32, u32)) -> Expr {
+    expr.unwrap_or_else(|e| {
+        debug!("Unrecognised formula for cell {:?}: {}", pos, e);
+        Expr::Error(CellErrorType::Name)
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '\\' | '$' | '?')
+}
+
+/// Binary formula flavour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RgceKind {
+    /// xls, Excel 2.x worksheets
+    Biff2,
+    /// xls, Excel 3.0 worksheets
+    Biff3,
+    /// xls, Excel 4.0 worksheets
+    Biff4,
+    /// xls, Excel 5.0 and 95 workbooks
+    Biff5,
+    /// xls [MS-XLS 2.5.198]
+    Biff8,
+    /// xlsb [MS-XLSB 2.5.97]
+    Biff12,
+}
+
+/// Workbook data needed to decode a formula token stream
+pub(crate) struct RgceContext<'a> {
+    pub kind: RgceKind,
+    /// Sheets referenced by each XTI (ixti), `None` if invalid
+    pub sheets: &'a [Option<SheetRef>],
+    /// Defined names, referenced by their one-based index
+    pub names: &'a [(String, String)],
+}
+
+/// A cursor over a formula token stream, reporting truncated data
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], FormulaError> {
+        if self.0.len() < n {
+            return Err(FormulaError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, FormulaError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, FormulaError> {
+        self.take(2).map(read_u16)
+    }
+
+    fn u32(&mut self) -> Result<u32, FormulaError> {
+        self.take(4).map(read_u32)
+    }
+
+    fn f64(&mut self) -> Result<f64, FormulaError> {
+        self.take(8).map(read_f64)
+    }
+}
+
+/// BErr [MS-XLS 2.5.11]
+fn berr(err: u8) -> Result<CellErrorType, FormulaError> {
+    match err {
+        0x00 => Ok(CellErrorType::Null),
+        0x07 => Ok(CellErrorType::Div0),
+        0x0F => Ok(CellErrorType::Value),
+        0x17 => Ok(CellErrorType::Ref),
+        0x1D => Ok(CellErrorType::Name),
+        0x24 => Ok(CellErrorType::Num),
+        0x2A => Ok(CellErrorType::NA),
+        0x2B => Ok(CellErrorType::GettingData),
+        e => Err(FormulaError::BErr(e)),
+    }
+}
+
+impl RgceKind {
+    /// Whether tokens predate BIFF8, with 8 bits columns and relative flags in the rows
+    fn is_legacy(self) -> bool {
+        matches!(
+            self,
+            RgceKind::Biff2 | RgceKind::Biff3 | RgceKind::Biff4 | RgceKind::Biff5
+        )
+    }
+}
+
+impl<'a> RgceContext<'a> {
+    /// Decodes a formula token stream
+    ///
+    /// `extra` is the additional data (rgcb) following the tokens, where
+    /// array constants are stored.
+    pub(crate) fn parse(&self, rgce: &[u8], extra: &[u8]) -> Result<Expr, FormulaError> {
+        let mut rgce = Bytes(rgce);
+        let mut extra = Bytes(extra);
+        let mut stack = Vec::new();
+        while !rgce.0.is_empty() {
+            let ptg = rgce.u8()?;
+            match ptg {
+                0x01 | 0x02 => {
+                    // PtgExp, PtgTbl: shared formula or data table, ignore
+                    debug!("ignoring PtgExp array/shared formula");
+                    rgce.take(if self.kind == RgceKind::Biff2 { 3 } else { 4 })?;
+                    stack.push(Expr::Missing);
+                }
+                0x03..=0x11 => {
+                    let rhs = stack.pop().ok_or(FormulaError::StackLen)?;
+                    let lhs = stack.pop().ok_or(FormulaError::StackLen)?;
+                    let op = BinaryOp::from_ptg(ptg);
+                    stack.push(Expr::Binary(op, Box::new(lhs), Box::new(rhs)));
+                }
+                0x12..=0x15 => {
+                    let e = Box::new(stack.pop().ok_or(FormulaError::StackLen)?);
+                    stack.push(match ptg {
+                        0x12 => Expr::Unary(UnaryOp::Plus, e),
+                        0x13 => Expr::Unary(UnaryOp::Minus, e),
+                        0x14 => Expr::Unary(UnaryOp::Percent, e),
+                        _ => Expr::Paren(e),
+                    });
+                }
+                0x16 => stack.push(Expr::Missing),
+                0x17 => stack.push(Expr::String(self.short_string(&mut rgce)?)),
+                0x18 => match self.kind {
+                    RgceKind::Biff8 => match rgce.u8()? {
+                        // PtgList
+                        0x19 => {
+                            rgce.take(12)?;
+                            stack.push(Expr::Missing);
+                        }
+                        _ => {
+                            rgce.take(4)?;
+                        }
+                    },
+                    RgceKind::Biff12 => match rgce.u8()? {
+                        // PtgList
+                        0x19 => {
+                            rgce.take(12)?;
+                            stack.push(Expr::Missing);
+                        }
+                        // PtgSxName
+                        0x1D => {
+                            rgce.take(4)?;
+                            stack.push(Expr::Missing);
+                        }
+                        e => return Err(FormulaError::Etpg(e)),
+                    },
+                    _ => return Err(FormulaError::Ptg(ptg)),
+                },
+                0x19 => {
+                    // BIFF2 attributes have 8 bits data
+                    let size = if self.kind == RgceKind::Biff2 { 1 } else { 2 };
+                    match rgce.u8()? {
+                        0x04 => {
+                            // PtgAttrChoose
+                            let n = match size {
+                                1 => rgce.u8()? as usize,
+                                _ => rgce.u16()? as usize,
+                            };
+                            rgce.take(size * (n + 1))?;
+                        }
+                        0x10 => {
+                            // PtgAttrSum
+                            rgce.take(size)?;
+                            let e = stack.pop().ok_or(FormulaError::StackLen)?;
+                            stack.push(Expr::Function {
+                                name: "SUM".to_string(),
+                                args: vec![e],
+                            });
+                        }
+                        0x01 | 0x02 | 0x08 | 0x20 | 0x21 | 0x40 | 0x41 | 0x80 => {
+                            rgce.take(size)?;
+                        }
+                        e => return Err(FormulaError::Etpg(e)),
+                    }
+                }
+                0x1C => stack.push(Expr::Error(berr(rgce.u8()?)?)),
+                0x1D => stack.push(Expr::Bool(rgce.u8()? != 0)),
+                0x1E => stack.push(Expr::Number(rgce.u16()? as f64)),
+                0x1F => stack.push(Expr::Number(rgce.f64()?)),
+                0x20 | 0x40 | 0x60 => {
+                    // PtgArray, values are in PtgExtraArray
+                    rgce.take(match self.kind {
+                        RgceKind::Biff2 => 6,
+                        RgceKind::Biff12 => 14,
+                        _ => 7,
+                    })?;
+                    stack.push(self.array(&mut extra)?);
+                }
+                0x21 | 0x22 | 0x41 | 0x42 | 0x61 | 0x62 => {
+                    // BIFF2 and BIFF3 function indexes have 8 bits
+                    let short = matches!(self.kind, RgceKind::Biff2 | RgceKind::Biff3);
+                    let (iftab, argc) = if ptg & 0x1F == 0x02 {
+                        // PtgFuncVar
+                        let argc = (rgce.u8()? & 0x7F) as usize;
+                        let iftab = match short {
+                            true => rgce.u8()? as usize,
+                            false => (rgce.u16()? & 0x7FFF) as usize,
+                        };
+                        (iftab, argc)
+                    } else {
+                        // PtgFunc
+                        let iftab = match short {
+                            true => rgce.u8()? as usize,
+                            false => rgce.u16()? as usize,
+                        };
+                        let argc = *FTAB_ARGC.get(iftab).ok_or(FormulaError::IfTab(iftab))?;
+                        (iftab, argc as usize)
+                    };
+                    if stack.len() < argc {
+                        return Err(FormulaError::StackLen);
+                    }
+                    let mut args = stack.split_off(stack.len() - argc);
+                    let name = if iftab == 0xFF {
+                        // user defined function, its name is the first argument
+                        match args.first() {
+                            Some(Expr::Name(_)) => match args.remove(0) {
+                                Expr::Name(name) => name,
+                                _ => unreachable!(),
+                            },
+                            _ => CellErrorType::Name.to_string(),
+                        }
+                    } else {
+                        FTAB.get(iftab)
+                            .ok_or(FormulaError::IfTab(iftab))?
+                            .to_string()
+                    };
+                    stack.push(Expr::Function { name, args });
+                }
+                0x23 | 0x43 | 0x63 => {
+                    // PtgName, one-based, followed by unused bytes before BIFF8
+                    let iname = match self.kind {
+                        RgceKind::Biff8 | RgceKind::Biff12 => rgce.u32()? as usize,
+                        kind => {
+                            let iname = rgce.u16()? as usize;
+                            rgce.take(match kind {
+                                RgceKind::Biff2 => 5,
+                                RgceKind::Biff5 => 12,
+                                _ => 8,
+                            })?;
+                            iname
+                        }
+                    };
+                    stack.push(
+                        iname
+                            .checked_sub(1)
+                            .and_then(|i| self.names.get(i))
+                            .map_or(Expr::Error(CellErrorType::Ref), |n| Expr::Name(n.0.clone())),
+                    );
+                }
+                0x24 | 0x44 | 0x64 => {
+                    let cell = self.loc(&mut rgce)?;
+                    stack.push(Expr::Cell { sheet: None, cell });
+                }
+                0x25 | 0x45 | 0x65 => {
+                    let (start, end) = self.area(&mut rgce)?;
+                    stack.push(Expr::Area {
+                        sheet: None,
+                        start,
+                        end,
+                    });
+                }
+                0x26 | 0x46 | 0x66 => {
+                    // PtgMemArea, the sub expression follows
+                    rgce.take(self.mem_size() + 4)?;
+                    match self.kind {
+                        RgceKind::Biff12 => {
+                            let n = extra.u32()? as usize;
+                            extra.take(16 * n)?;
+                        }
+                        kind => {
+                            let n = extra.u16()? as usize;
+                            extra.take(if kind.is_legacy() { 6 * n } else { 8 * n })?;
+                        }
+                    }
+                }
+                0x27 | 0x47 | 0x67 | 0x28 | 0x48 | 0x68 => {
+                    // PtgMemErr, PtgMemNoMem, the sub expression follows
+                    rgce.take(self.mem_size() + 4)?;
+                }
+                0x29 | 0x49 | 0x69 | 0x2E | 0x4E | 0x6E | 0x2F | 0x4F | 0x6F => {
+                    // PtgMemFunc, PtgMemAreaN, PtgMemNoMemN, the sub expression follows
+                    rgce.take(self.mem_size())?;
+                }
+                0x2A | 0x4A | 0x6A => {
+                    // PtgRefErr
+                    self.loc(&mut rgce)?;
+                    stack.push(Expr::Error(CellErrorType::Ref));
+                }
+                0x2B | 0x4B | 0x6B => {
+                    // PtgAreaErr
+                    self.area(&mut rgce)?;
+                    stack.push(Expr::Error(CellErrorType::Ref));
+                }
+                0x39 | 0x59 | 0x79 => {
+                    // PtgNameX, external names are not resolved
+                    rgce.take(if self.kind == RgceKind::Biff5 { 24 } else { 6 })?;
+                    stack.push(Expr::Error(CellErrorType::Name));
+                }
+                0x3A | 0x5A | 0x7A => {
+                    // PtgRef3d
+                    let sheet = self.sheet(&mut rgce)?;
+                    let cell = self.loc(&mut rgce)?;
+                    stack.push(match sheet {
+                        Some(sheet) => Expr::Cell {
+                            sheet: Some(sheet),
+                            cell,
+                        },
+                        None => Expr::Error(CellErrorType::Ref),
+                    });
+                }
+                0x3B | 0x5B | 0x7B => {
+                    // PtgArea3d
+                    let sheet = self.sheet(&mut rgce)?;
+                    let (start, end) = self.area(&mut rgce)?;
+                    stack.push(match sheet {
+                        Some(sheet) => Expr::Area {
+                            sheet: Some(sheet),
+                            start,
+                            end,
+                        },
+                        None => Expr::Error(CellErrorType::Ref),
+                    });
+                }
+                0x3C | 0x5C | 0x7C => {
+                    // PtgRefErr3d
+                    self.sheet(&mut rgce)?;
+                    self.loc(&mut rgce)?;
+                    stack.push(Expr::Error(CellErrorType::Ref));
+                }
+                0x3D | 0x5D | 0x7D => {
+                    // PtgAreaErr3d
+                    self.sheet(&mut rgce)?;
+                    self.area(&mut rgce)?;
+                    stack.push(Expr::Error(CellErrorType::Ref));
+                }
+                _ => return Err(FormulaError::Ptg(ptg)),
+            }
+        }
+        match (stack.pop(), stack.is_empty()) {
+            (Some(e), true) => Ok(e),
+            (None, _) => Ok(Expr::Missing),
+            _ => Err(FormulaError::StackLen),
+        }
+    }
+
+    /// Size of the sub expression length of PtgMem tokens
+    fn mem_size(&self) -> usize {
+        if self.kind == RgceKind::Biff2 {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Sheet of a 3D reference, from its ixti
+    ///
+    /// BIFF5 references hold the external sheet index, negative for the sheets of the
+    /// workbook, followed by the indexes of the first and last sheets.
+    fn sheet(&self, rgce: &mut Bytes<'_>) -> Result<Option<SheetRef>, FormulaError> {
+        if self.kind == RgceKind::Biff5 {
+            let ixals = rgce.u16()? as i16;
+            rgce.take(8)?;
+            let first = rgce.u16()? as usize;
+            let last = rgce.u16()? as usize;
+            if ixals >= 0 {
+                return Ok(None);
+            }
+            let name = |i: usize| self.sheets.get(i).cloned().flatten().map(|s| s.first);
+            return Ok(name(first).map(|first| SheetRef {
+                last: name(last).filter(|last| *last != first),
+                first,
+            }));
+        }
+        let ixti = rgce.u16()? as usize;
+        Ok(self.sheets.get(ixti).cloned().flatten())
+    }
+
+    /// RgceLoc [MS-XLS 2.5.198.109], RgceLoc [MS-XLSB 2.5.97.91]
+    fn loc(&self, rgce: &mut Bytes<'_>) -> Result<CellRef, FormulaError> {
+        let row = match self.kind {
+            RgceKind::Biff8 => rgce.u16()? as u32,
+            RgceKind::Biff12 => rgce.u32()?,
+            _ => {
+                let row = rgce.u16()?;
+                return Ok(row_ref(row, rgce.u8()?));
+            }
+        };
+        Ok(col_ref(row, rgce.u16()?))
+    }
+
+    /// RgceArea [MS-XLS 2.5.198.105], RgceArea [MS-XLSB 2.5.97.89]
+    fn area(&self, rgce: &mut Bytes<'_>) -> Result<(CellRef, CellRef), FormulaError> {
+        let (first, last) = match self.kind {
+            RgceKind::Biff8 => (rgce.u16()? as u32, rgce.u16()? as u32),
+            RgceKind::Biff12 => (rgce.u32()?, rgce.u32()?),
+            _ => {
+                let (first, last) = (rgce.u16()?, rgce.u16()?);
+                let (first_col, last_col) = (rgce.u8()?, rgce.u8()?);
+                return Ok((row_ref(first, first_col), row_ref(last, last_col)));
+            }
+        };
+        Ok((col_ref(first, rgce.u16()?), col_ref(last, rgce.u16()?)))
+    }
+
+    /// String of PtgStr
+    fn short_string(&self, rgce: &mut Bytes<'_>) -> Result<String, FormulaError> {
+        match self.kind {
+            RgceKind::Biff8 => {
+                // ShortXLUnicodeString
+                let cch = rgce.u8()? as usize;
+                biff8_string(rgce, cch)
+            }
+            RgceKind::Biff12 => {
+                let cch = rgce.u16()? as usize;
+                Ok(UTF_16LE.decode(rgce.take(2 * cch)?).0.into_owned())
+            }
+            _ => {
+                let cch = rgce.u8()? as usize;
+                Ok(rgce.take(cch)?.iter().map(|&b| b as char).collect())
+            }
+        }
+    }
+
+    /// PtgExtraArray [MS-XLS 2.5.198.8], [MS-XLSB 2.5.97.18]
+    fn array(&self, extra: &mut Bytes<'_>) -> Result<Expr, FormulaError> {
+        let (rows, cols) = match self.kind {
+            RgceKind::Biff12 => {
+                let rows = extra.u32()? as usize;
+                let cols = extra.u32()? as usize;
+                (rows, cols)
+            }
+            _ => {
+                let cols = extra.u8()? as usize + 1;
+                let rows = extra.u16()? as usize + 1;
+                (rows, cols)
+            }
+        };
+        if rows.saturating_mul(cols) > extra.0.len() {
+            return Err(FormulaError::Truncated);
+        }
+        let mut values = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let mut row = Vec::with_capacity(cols);
+            for _ in 0..cols {
+                row.push(self.ser_ar(extra)?);
+            }
+            values.push(row);
+        }
+        Ok(Expr::Array(values))
+    }
+
+    /// SerAr [MS-XLS 2.5.196], [MS-XLSB 2.5.97.12]
+    fn ser_ar(&self, extra: &mut Bytes<'_>) -> Result<Expr, FormulaError> {
+        let typ = extra.u8()?;
+        let value = match (self.kind, typ) {
+            (RgceKind::Biff12, 0x00) => Expr::Number(extra.f64()?),
+            (RgceKind::Biff12, 0x01) => {
+                // XLWideString
+                let cch = extra.u32()? as usize;
+                Expr::String(UTF_16LE.decode(extra.take(2 * cch)?).0.into_owned())
+            }
+            (RgceKind::Biff12, 0x02) => Expr::Bool(extra.u8()? != 0),
+            (RgceKind::Biff12, 0x04) => {
+                let e = berr(extra.u8()?)?;
+                extra.take(3)?;
+                Expr::Error(e)
+            }
+            (RgceKind::Biff12, typ) => return Err(FormulaError::Etpg(typ)),
+            (_, 0x00) => {
+                extra.take(8)?;
+                Expr::Missing
+            }
+            (_, 0x01) => Expr::Number(extra.f64()?),
+            (RgceKind::Biff8, 0x02) => {
+                // XLUnicodeString
+                let cch = extra.u16()? as usize;
+                Expr::String(biff8_string(extra, cch)?)
+            }
+            (_, 0x02) => {
+                // byte string before BIFF8
+                let cch = extra.u8()? as usize;
+                Expr::String(extra.take(cch)?.iter().map(|&b| b as char).collect())
+            }
+            (_, 0x04) => {
+                let b = extra.u8()?;
+                extra.take(7)?;
+                Expr::Bool(b != 0)
+            }
+            (_, 0x10) => {
+                let e = berr(extra.u8()?)?;
+                extra.take(7)?;
+                Expr::Error(e)
+            }
+            (_, typ) => return Err(FormulaError::Etpg(typ)),
+        };
+        Ok(value)
+    }
+}
+
+/// Column with its relative flags [MS-XLS 2.5.198.20]
+fn col_ref(row: u32, col: u16) -> CellRef {
+    CellRef {
+        row,
+        col: (col & 0x3FFF) as u32,
+        abs_row: col & 0x4000 == 0,
+        abs_col: col & 0x8000 == 0,
+    }
+}
+
+/// Cell reference before BIFF8, with the relative flags in the row
+fn row_ref(row: u16, col: u8) -> CellRef {
+    CellRef {
+        row: (row & 0x3FFF) as u32,
+        col: col as u32,
+        abs_row: row & 0x8000 == 0,
+        abs_col: row & 0x4000 == 0,
+    }
+}
+
+/// Characters of a BIFF8 string, after its character count
+fn biff8_string(rgce: &mut Bytes<'_>, cch: usize) -> Result<String, FormulaError> {
+    let high_byte = rgce.u8()? & 0x1 != 0;
+    if high_byte {
+        Ok(UTF_16LE.decode(rgce.take(2 * cch)?).0.into_owned())
+    } else {
+        Ok(rgce.take(cch)?.iter().map(|&b| b as char).collect())
+    }
+}
+
+/// Renders the table structured reference of a PtgList token, e.g. `Sales[[#This Row],[Qty]]`
+///
+/// `data` follows the eptg: ixti, flags, list index, first and last columns
+/// [MS-XLSB 2.5.97.52] [MS-XLS 2.5.198.51]. Unknown tables or columns are `#REF!`.
+pub(crate) fn ptg_list(data: &[u8], tables: &[TableMetadata]) -> String {
+    let flags = read_u16(&data[2..]);
+    let list = read_u32(&data[4..]);
+    let table = match tables.iter().find(|t| t.id == list) {
+        // fInvalid
+        Some(table) if flags & 0x1000 == 0 => table,
+        _ => return "#REF!".to_string(),
+    };
+    let column = |offset: usize| {
+        let col = read_u16(&data[offset..]) as usize;
+        table
+            .columns
+            .get(col)
+            .map(|name| format!("[{}]", escape_column(name)))
+    };
+    let mut items = match (flags >> 2) & 0x1F {
+        0x01 => vec!["[#All]"],
+        0x02 => vec!["[#Headers]"],
+        0x04 => vec!["[#Data]"],
+        0x06 => vec!["[#Headers]", "[#Data]"],
+        0x08 => vec!["[#Totals]"],
+        0x0C => vec!["[#Data]", "[#Totals]"],
+        0x10 => vec!["[#This Row]"],
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+    let columns = match flags & 0x3 {
+        0x1 => column(8),
+        0x2 => column(8)
+            .zip(column(10))
+            .map(|(c1, c2)| format!("{c1}:{c2}")),
+        _ => Some(String::new()),
+    };
+    match columns {
+        Some(c) if c.is_empty() => (),
+        Some(c) => items.push(c),
+        None => return "#REF!".to_string(),
+    }
+    let name = &table.name;
+    match items.as_slice() {
+        [] => format!("{name}[]"),
+        [item] if !item.contains(':') => format!("{name}{item}"),
+        _ => {
+            let separator = if flags & 0x100 != 0 { ", " } else { "," };
+            let items = items.join(separator);
+            if flags & 0x80 != 0 {
+                format!("{name}[ {items} ]")
+            } else {
+                format!("{name}[{items}]")
+            }
+        }
+    }
+}
+
+/// Escapes the special characters of a column name with a `'`
+fn escape_column(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '[' | ']' | '#' | '\'') {
+            escaped.push('\'');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Removes the `'` escaping special characters of a column name
+fn unescape_column(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Resolves the table structured references of a formula to absolute A1 references,
+/// e.g. `SUM(Sales[Amount])` to `SUM($C$2:$C$10)`
+///
+/// `position` and `sheet` locate the formula cell, for `[#This Row]` and for
+/// references without table name. Other references are kept as is.
+pub(crate) fn resolve_structured_references(
+    formula: &str,
+    position: (u32, u32),
+    sheet: &str,
+    tables: &[TableMetadata],
+) -> String {
+    let chars = formula.chars().collect::<Vec<_>>();
+    let mut resolved = String::with_capacity(formula.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            quote @ ('"' | '\'') => {
+                // string literal or quoted sheet name, with doubled quotes
+                let start = i;
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == quote && chars.get(i + 1) != Some(&quote) {
+                        break;
+                    }
+                    i += if chars[i] == quote { 2 } else { 1 };
+                }
+                i = chars.len().min(i + 1);
+                resolved.extend(&chars[start..i]);
+            }
+            c if is_word_char(c) => {
+                let start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let name = chars[start..i].iter().collect::<String>();
+                let Some(end) = bracket_end(&chars, i) else {
+                    resolved.push_str(&name);
+                    continue;
+                };
+                let spec = chars[i + 1..end - 1].iter().collect::<String>();
+                let reference = tables
+                    .iter()
+                    .find(|t| t.name.eq_ignore_ascii_case(&name))
+                    .and_then(|t| table_reference(t, &spec, position, sheet));
+                match reference {
+                    Some(r) => resolved.push_str(&r),
+                    None => resolved.extend(&chars[start..end]),
+                }
+                i = end;
+            }
+            '[' => {
+                let Some(end) = bracket_end(&chars, i) else {
+                    resolved.extend(&chars[i..]);
+                    break;
+                };
+                let spec = chars[i + 1..end - 1].iter().collect::<String>();
+                // external workbook index, e.g. [1]Sheet1!A1
+                let reference = if spec.chars().all(|c| c.is_ascii_digit()) {
+                    None
+                } else {
+                    tables
+                        .iter()
+                        .find(|t| {
+                            t.sheet_name == sheet && t.dimensions.contains(position.0, position.1)
+                        })
+                        .and_then(|t| table_reference(t, &spec, position, sheet))
+                };
+                match reference {
+                    Some(r) => resolved.push_str(&r),
+                    None => resolved.extend(&chars[i..end]),
+                }
+                i = end;
+            }
+            c => {
+                resolved.push(c);
+                i += 1;
+            }
+        }
+    }
+    resolved
+}
+
+/// Position after the bracket closing the one at `start`
+fn bracket_end(chars: &[char], start: usize) -> Option<usize> {
+    if chars.get(start) != Some(&'[') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' => i += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Splits the items of a structured reference, outside of brackets
+fn split_items(spec: &str, separator: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\'' => escaped = true,
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                items.push(&spec[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    items.push(&spec[start..]);
+    items
+}
+
+/// Resolves the brackets content of a structured reference of `table`
+fn table_reference(
+    table: &TableMetadata,
+    spec: &str,
+    position: (u32, u32),
+    sheet: &str,
+) -> Option<String> {
+    let column = |name: &str| {
+        let name = unescape_column(name).to_lowercase();
+        let col = table
+            .columns
+            .iter()
+            .position(|c| c.to_lowercase() == name)?;
+        Some(table.dimensions.start.1 + col as u32)
+    };
+    let mut specifiers = Vec::new();
+    let mut columns = None;
+    let spec = spec.trim();
+    let items = match spec.strip_prefix('@') {
+        Some(items) => {
+            specifiers.push("this row".to_string());
+            items.trim()
+        }
+        None => spec,
+    };
+    if items.starts_with('[') {
+        for item in split_items(items, ',') {
+            let item = item.trim();
+            if let Some(specifier) = item.strip_prefix("[#").and_then(|s| s.strip_suffix(']')) {
+                specifiers.push(specifier.to_lowercase());
+                continue;
+            }
+            let mut range = split_items(item, ':').into_iter().map(|c| {
+                let c = c.trim();
+                column(c.strip_prefix('[')?.strip_suffix(']')?)
+            });
+            let first = range.next()??;
+            let last = range.next().unwrap_or(Some(first))?;
+            columns = Some((first.min(last), first.max(last)));
+        }
+    } else if let Some(specifier) = items.strip_prefix('#') {
+        specifiers.push(specifier.to_lowercase());
+    } else if !items.is_empty() {
+        let col = column(items)?;
+        columns = Some((col, col));
+    }
+
+    let all = table.dimensions;
+    let data = table.data();
+    let mut rows: Option<(u32, u32)> = None;
+    for specifier in specifiers {
+        let span = match specifier.as_str() {
+            "all" => (all.start.0, all.end.0),
+            "data" => (data.start.0, data.end.0),
+            "headers" if table.header_rows > 0 => {
+                (all.start.0, all.start.0 + table.header_rows - 1)
+            }
+            "totals" if table.totals_rows > 0 => (all.end.0 + 1 - table.totals_rows, all.end.0),
+            "this row" => (position.0, position.0),
+            _ => return None,
+        };
+        rows = Some(rows.map_or(span, |(first, last)| (first.min(span.0), last.max(span.1))));
+    }
+    let (first_row, last_row) = rows.unwrap_or((data.start.0, data.end.0));
+    let (first_col, last_col) = columns.unwrap_or((all.start.1, all.end.1));
+
+    let mut reference = String::new();
+    if table.sheet_name != sheet {
+        reference = format!("{}!", SheetRef::new(table.sheet_name.as_str()));
+    }
+    let start = CellRef::absolute(first_row, first_col);
+    let end = CellRef::absolute(last_row, last_col);
+    if start == end {
+        reference.push_str(&start.to_string());
+    } else {
+        reference.push_str(&format!("{start}:{end}"));
+    }
+    Some(reference)
+}
+
+/// Resolves the table structured references of each formula of a worksheet
+pub(crate) fn resolve_table_references(
+    mut formulas: Range<String>,
+    sheet: &str,
+    tables: &[TableMetadata],
+) -> Range<String> {
+    let Some(start) = formulas.start() else {
+        return formulas;
+    };
+    let cells = formulas
+        .used_cells()
+        .map(|(row, col, _)| (row, col))
+        .collect::<Vec<_>>();
+    for (row, col) in cells {
+        let position = (start.0 + row as u32, start.1 + col as u32);
+        let formula = &mut formulas[(row, col)];
+        *formula = resolve_structured_references(formula, position, sheet, tables);
+    }
+    formulas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dimensions;
+
+    fn cell(sheet: Option<&str>, row: u32, col: u32) -> Expr {
+        Expr::Cell {
+            sheet: sheet.map(SheetRef::new),
+            cell: CellRef::new(row, col),
+        }
+    }
+
+    #[test]
+    fn parse_excel() {
+        let e = parse("=1+2*3").unwrap();
+        assert_eq!(
+            e,
+            Expr::Binary(
+                BinaryOp::Add,
+                Box::new(Expr::Number(1.)),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    Box::new(Expr::Number(2.)),
+                    Box::new(Expr::Number(3.))
+                ))
+            )
+        );
+        // negation binds tighter than power
+        let e = parse("-2^2").unwrap();
+        assert!(matches!(e, Expr::Binary(BinaryOp::Pow, _, _)));
+        assert_eq!(parse("Sheet2!B3").unwrap(), cell(Some("Sheet2"), 2, 1));
+        assert_eq!(
+            parse("'My Sheet'!$A$1").unwrap(),
+            Expr::Cell {
+                sheet: Some(SheetRef::new("My Sheet")),
+                cell: CellRef::absolute(0, 0),
+            }
+        );
+        assert_eq!(
+            parse("Sheet1:Sheet3!A1").unwrap(),
+            Expr::Cell {
+                sheet: Some(SheetRef {
+                    first: "Sheet1".to_string(),
+                    last: Some("Sheet3".to_string()),
+                }),
+                cell: CellRef::new(0, 0),
+            }
+        );
+        assert_eq!(parse("MyName").unwrap(), Expr::Name("MyName".to_string()));
+        assert_eq!(parse("true").unwrap(), Expr::Bool(true));
+        assert_eq!(parse("#N/A").unwrap(), Expr::Error(CellErrorType::NA));
+        assert_eq!(
+            parse("{1,\"a\";TRUE,#REF!}").unwrap(),
+            Expr::Array(vec![
+                vec![Expr::Number(1.), Expr::String("a".to_string())],
+                vec![Expr::Bool(true), Expr::Error(CellErrorType::Ref)],
+            ])
+        );
+        assert_eq!(
+            parse("IF(A1,,\"x\"\"y\")").unwrap(),
+            Expr::Function {
+                name: "IF".to_string(),
+                args: vec![
+                    cell(None, 0, 0),
+                    Expr::Missing,
+                    Expr::String("x\"y".to_string())
+                ],
+            }
+        );
+        assert!(matches!(
+            parse("(A1:B2 B1:C3,D4)").unwrap(),
+            Expr::Paren(e) if matches!(*e, Expr::Binary(BinaryOp::Union, _, _))
+        ));
+        assert!(parse("SUM(1,").is_err());
+        assert!(parse("1+)").is_err());
+    }
+
+    #[test]
+    fn parse_open_formula_refs() {
+        assert_eq!(
+            parse_open_formula("of:=SUM([.A1:.B2];[$Sheet1.C3])").unwrap(),
+            Expr::Function {
+                name: "SUM".to_string(),
+                args: vec![
+                    Expr::Area {
+                        sheet: None,
+                        start: CellRef::new(0, 0),
+                        end: CellRef::new(1, 1),
+                    },
+                    cell(Some("Sheet1"), 2, 2),
+                ],
+            }
+        );
+        assert_eq!(
+            parse_open_formula("of:=['It''s'.$B$2]").unwrap(),
+            Expr::Cell {
+                sheet: Some(SheetRef::new("It's")),
+                cell: CellRef::absolute(1, 1),
+            }
+        );
+        assert_eq!(
+            parse_open_formula("of:={1;2|3;4}").unwrap().to_string(),
+            "{1,2;3,4}"
+        );
+    }
+
+    #[test]
+    fn parse_structured_references() {
+        assert_eq!(
+            parse("SUM(Sales[Amount])").unwrap(),
+            Expr::Function {
+                name: "SUM".to_string(),
+                args: vec![Expr::Table {
+                    table: Some("Sales".to_string()),
+                    spec: "Amount".to_string(),
+                }],
+            }
+        );
+        assert_eq!(
+            parse("[@Qty]*[@Price]").unwrap(),
+            Expr::Binary(
+                BinaryOp::Mul,
+                Box::new(Expr::Table {
+                    table: None,
+                    spec: "@Qty".to_string(),
+                }),
+                Box::new(Expr::Table {
+                    table: None,
+                    spec: "@Price".to_string(),
+                }),
+            )
+        );
+        assert_eq!(
+            parse("Sales[[#Headers],[Col'[1']]]").unwrap(),
+            Expr::Table {
+                table: Some("Sales".to_string()),
+                spec: "[#Headers],[Col'[1']]".to_string(),
+            }
+        );
+        assert!(parse("Sales[[Qty]").is_err());
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for f in [
+            "SUM(A1:B2)*2",
+            "IF(Sheet1!$A$1>=10%,\"yes\",-B2)",
+            "'My Sheet'!A:C",
+            "SUM($3:$5)",
+            "Sheet1:Sheet3!AA10",
+            "{1,2;3,4}",
+            "A1:B2 B1:C3",
+            "SUM((A1,C1))",
+            "[1]Sheet1!A1&\"x\"",
+            "SUM(Sales[[Qty]:[Price]])+[@Qty]",
+            "_xlfn.XLOOKUP(A1,B:B,C:C)",
+            "1.5E+3",
+        ] {
+            let e = parse(f).unwrap();
+            let expected = if f == "1.5E+3" { "1500" } else { f };
+            assert_eq!(e.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn rgce_biff8() {
+        let sheets = [Some(SheetRef::new("Sheet 2"))];
+        let names = [("Rate".to_string(), "Sheet1!$A$1".to_string())];
+        let ctx = RgceContext {
+            kind: RgceKind::Biff8,
+            sheets: &sheets,
+            names: &names,
+        };
+        // SUM(A1:B2, Sheet 2!$C$3) * Rate
+        let mut rgce = vec![0x25, 0, 0, 1, 0, 0x00, 0xC0, 0x01, 0xC0];
+        rgce.extend([0x3A, 0, 0, 2, 0, 2, 0]);
+        rgce.extend([0x22, 2, 4, 0]);
+        rgce.extend([0x23, 1, 0, 0, 0]);
+        rgce.push(0x05);
+        let e = ctx.parse(&rgce, &[]).unwrap();
+        assert_eq!(e.to_string(), "SUM(A1:B2,'Sheet 2'!$C$3)*Rate");
+
+        // {1,"a"}
+        let rgce = [0x60, 0, 0, 0, 0, 0, 0, 0];
+        let mut extra = vec![1, 0, 0, 0x01];
+        extra.extend(1f64.to_le_bytes());
+        extra.extend([0x02, 1, 0, 0, b'a']);
+        assert_eq!(ctx.parse(&rgce, &extra).unwrap().to_string(), "{1,\"a\"}");
+
+        assert!(matches!(
+            ctx.parse(&[0x24, 0], &[]),
+            Err(FormulaError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn rgce_legacy() {
+        let sheets = [
+            Some(SheetRef::new("Sheet1")),
+            Some(SheetRef::new("Sheet 2")),
+        ];
+        let names = [("Rate".to_string(), "Sheet1!$A$1".to_string())];
+        let ctx = RgceContext {
+            kind: RgceKind::Biff5,
+            sheets: &sheets,
+            names: &names,
+        };
+        // SUM(A1:B2, Sheet 2!$C$3) * Rate, rows hold the relative flags
+        let mut rgce = vec![0x25, 0, 0xC0, 1, 0xC0, 0, 1];
+        rgce.extend([
+            0x3A, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 2, 0, 2,
+        ]);
+        rgce.extend([0x22, 2, 4, 0]);
+        rgce.extend([0x23, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        rgce.push(0x05);
+        let e = ctx.parse(&rgce, &[]).unwrap();
+        assert_eq!(e.to_string(), "SUM(A1:B2,'Sheet 2'!$C$3)*Rate");
+
+        // {1,"a"}&"b" with 1 byte function indexes and string lengths
+        let ctx = RgceContext {
+            kind: RgceKind::Biff2,
+            ..ctx
+        };
+        let rgce = [0x60, 0, 0, 0, 0, 0, 0, 0x17, 1, b'b', 0x08];
+        let mut extra = vec![1, 0, 0, 0x01];
+        extra.extend(1f64.to_le_bytes());
+        extra.extend([0x02, 1, b'a']);
+        let e = ctx.parse(&rgce, &extra).unwrap();
+        assert_eq!(e.to_string(), "{1,\"a\"}&\"b\"");
+        let e = ctx.parse(&[0x44, 2, 0xC0, 1, 0x1E, 0, 0, 0x41, 27], &[]);
+        assert_eq!(e.unwrap().to_string(), "ROUND(B3,0)");
+    }
+
+    #[test]
+    fn rgce_biff12() {
+        let ctx = RgceContext {
+            kind: RgceKind::Biff12,
+            sheets: &[],
+            names: &[],
+        };
+        // "x"&B3%
+        let mut rgce = vec![0x17, 1, 0, b'x', 0];
+        rgce.extend([0x24, 2, 0, 0, 0, 1, 0xC0, 0x14, 0x08]);
+        assert_eq!(ctx.parse(&rgce, &[]).unwrap().to_string(), "\"x\"&B3%");
+    }
+
+    fn sales() -> TableMetadata {
+        TableMetadata {
+            id: 3,
+            name: "Sales".to_string(),
+            sheet_name: "Data".to_string(),
+            columns: vec!["Qty".to_string(), "Unit [EUR]".to_string()],
+            dimensions: Dimensions::new((1, 1), (5, 2)),
+            header_rows: 1,
+            totals_rows: 1,
+            insert_row: false,
+        }
+    }
+
+    #[test]
+    fn table_list() {
+        let tables = [sales()];
+        let list = |flags: u16, list: u32, first: u16, last: u16| {
+            let mut data = vec![0, 0];
+            data.extend(flags.to_le_bytes());
+            data.extend(list.to_le_bytes());
+            data.extend(first.to_le_bytes());
+            data.extend(last.to_le_bytes());
+            ptg_list(&data, &tables)
+        };
+        assert_eq!(list(0, 3, 0, 0), "Sales[]");
+        assert_eq!(list(0x01 << 2, 3, 0, 0), "Sales[#All]");
+        assert_eq!(list(0x01, 3, 1, 1), "Sales[Unit '[EUR']]");
+        assert_eq!(
+            list(0x02 | 0x0C << 2, 3, 0, 1),
+            "Sales[[#Data],[#Totals],[Qty]:[Unit '[EUR']]]"
+        );
+        assert_eq!(
+            list(0x01 | 0x10 << 2 | 0x180, 3, 0, 0),
+            "Sales[ [#This Row], [Qty] ]"
+        );
+        assert_eq!(list(0x01, 3, 5, 5), "#REF!");
+        assert_eq!(list(0, 4, 0, 0), "#REF!");
+    }
+
+    #[test]
+    fn structured_references() {
+        let tables = [sales()];
+        let resolve = |formula: &str, position: (u32, u32), sheet: &str| {
+            resolve_structured_references(formula, position, sheet, &tables)
+        };
+        assert_eq!(resolve("SUM(Sales[Qty])", (0, 0), "Data"), "SUM($B$3:$B$5)");
+        assert_eq!(resolve("sales[#Totals]", (0, 0), "Data"), "$B$6:$C$6");
+        assert_eq!(
+            resolve("[@Qty]*[@[Unit '[EUR']]]", (3, 2), "Data"),
+            "$B$4*$C$4"
+        );
+        assert_eq!(
+            resolve("Sales[[#Headers],[Qty]:[Unit '[EUR']]]", (0, 0), "Other"),
+            "Data!$B$2:$C$2"
+        );
+        // outside of the table, in another book, in a string
+        assert_eq!(resolve("[@Qty]", (3, 2), "Other"), "[@Qty]");
+        assert_eq!(resolve("[1]Data!A1", (3, 1), "Data"), "[1]Data!A1");
+        assert_eq!(resolve("\"Sales[Qty]\"", (0, 0), "Data"), "\"Sales[Qty]\"");
+        assert_eq!(resolve("Sales[Price]", (0, 0), "Data"), "Sales[Price]");
+    }
+}
@@ -0,0 +1,16 @@
+//! Cell hyperlinks, read by `Reader::worksheet_hyperlinks`
+
+use crate::Dimensions;
+
+/// A hyperlink of a cell range
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hyperlink {
+    /// Linked cells
+    pub range: Dimensions,
+    /// External target, e.g. an url, a mail address or a file path
+    pub url: Option<String>,
+    /// Location in the workbook (e.g. `Sheet2!A1`) or in the external target
+    pub location: Option<String>,
+    /// Text displayed when hovering the link
+    pub tooltip: Option<String>,
+}
@@ -0,0 +1,85 @@
+//! Row and column layout, read by `Reader::worksheet_layout`
+
+/// Layout of consecutive rows or columns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineLayout {
+    /// First row or column
+    pub first: u32,
+    /// Last row or column, included
+    pub last: u32,
+    /// Hidden by the author, or by a filter
+    pub hidden: bool,
+    /// Outline (grouping) level, 0 if not grouped
+    pub outline_level: u8,
+    /// Row height in points, or column width in characters
+    pub size: Option<f64>,
+}
+
+impl LineLayout {
+    /// Checks whether `index` is one of the rows or columns
+    pub fn contains(&self, index: u32) -> bool {
+        self.first <= index && index <= self.last
+    }
+}
+
+/// Row and column layout of a worksheet
+///
+/// Only rows and columns which differ from the defaults are listed, ordered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SheetLayout {
+    /// Default row height, in points
+    pub default_row_height: Option<f64>,
+    /// Default column width, in characters
+    pub default_column_width: Option<f64>,
+    /// Rows layout
+    pub rows: Vec<LineLayout>,
+    /// Columns layout
+    pub columns: Vec<LineLayout>,
+}
+
+impl SheetLayout {
+    /// Gets the layout of a row, if it is not the default one
+    pub fn row(&self, row: u32) -> Option<&LineLayout> {
+        self.rows.iter().find(|l| l.contains(row))
+    }
+
+    /// Gets the layout of a column, if it is not the default one
+    pub fn column(&self, col: u32) -> Option<&LineLayout> {
+        self.columns.iter().find(|l| l.contains(col))
+    }
+
+    /// Checks whether a row is hidden
+    pub fn is_row_hidden(&self, row: u32) -> bool {
+        self.row(row).map_or(false, |l| l.hidden)
+    }
+
+    /// Checks whether a column is hidden
+    pub fn is_column_hidden(&self, col: u32) -> bool {
+        self.column(col).map_or(false, |l| l.hidden)
+    }
+
+    /// Appends the layout of some rows
+    pub(crate) fn push_rows(&mut self, rows: LineLayout) {
+        push_line_layout(&mut self.rows, rows);
+    }
+
+    /// Appends the layout of some columns
+    pub(crate) fn push_columns(&mut self, columns: LineLayout) {
+        push_line_layout(&mut self.columns, columns);
+    }
+}
+
+/// Appends a layout, merged with the previous one if it follows it and matches
+fn push_line_layout(lines: &mut Vec<LineLayout>, line: LineLayout) {
+    if let Some(prev) = lines.last_mut() {
+        if prev.last + 1 == line.first
+            && prev.hidden == line.hidden
+            && prev.outline_level == line.outline_level
+            && prev.size == line.size
+        {
+            prev.last = line.last;
+            return;
+        }
+    }
+    lines.push(line);
+}
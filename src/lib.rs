@@ -62,18 +62,29 @@ mod utils;
 
 mod auto;
 mod cfb;
+mod chart;
+mod comments;
+mod conditional_formats;
+mod crypto;
 mod datatype;
+#[cfg(feature = "eval")]
+pub mod eval;
 mod formats;
+pub mod formula;
+mod hyperlinks;
+mod layout;
+mod number_format;
 mod ods;
+mod validation;
 mod xls;
 mod xlsb;
 mod xlsx;
 
 mod de;
 mod errors;
+pub mod style;
 pub mod vba;
 
-use datatype::DataTypeRef;
 use serde::de::DeserializeOwned;
 use std::borrow::Cow;
 use std::cmp::{max, min};
@@ -84,13 +95,24 @@ use std::ops::{Index, IndexMut};
 use std::path::Path;
 
 pub use crate::auto::{open_workbook_auto, open_workbook_auto_from_rs, Sheets};
-pub use crate::datatype::DataType;
-pub use crate::de::{DeError, RangeDeserializer, RangeDeserializerBuilder, ToCellDeserializer};
+pub use crate::chart::{Chart, ChartReference, ChartSeries, ChartType};
+pub use crate::comments::Comment;
+pub use crate::conditional_formats::{ConditionalFormat, ConditionalFormatType};
+pub use crate::crypto::CryptoError;
+pub use crate::datatype::{DataType, DataTypeRef};
+pub use crate::de::{
+    DeError, RangeDeserializer, RangeDeserializerBuilder, RowError, ToCellDeserializer,
+};
 pub use crate::errors::Error;
-pub use crate::ods::{Ods, OdsError};
-pub use crate::xls::{Xls, XlsError, XlsOptions};
+pub use crate::formula::{Expr, FormulaError};
+pub use crate::hyperlinks::Hyperlink;
+pub use crate::layout::{LineLayout, SheetLayout};
+pub use crate::ods::{Ods, OdsCellReader, OdsError};
+pub use crate::style::{Style, StyleId, Styles, TextRun};
+pub use crate::validation::{DataValidation, ValidationOperator, ValidationType};
+pub use crate::xls::{Xls, XlsCellReader, XlsError, XlsOptions};
 pub use crate::xlsb::{Xlsb, XlsbError};
-pub use crate::xlsx::{Xlsx, XlsxError};
+pub use crate::xlsx::{Xlsx, XlsxError, XlsxRowReader, XlsxWriter};
 
 use crate::vba::VbaProject;
 
@@ -132,13 +154,30 @@ impl fmt::Display for CellErrorType {
     }
 }
 
-#[derive(Debug, PartialEq, Default, Clone, Copy)]
-pub(crate) struct Dimensions {
+/// A rectangular area of a worksheet, bounds included
+///
+/// Used for instance to describe merged cell regions.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+pub struct Dimensions {
+    /// Top left cell position, as (row, column)
     pub start: (u32, u32),
+    /// Bottom right cell position, as (row, column)
     pub end: (u32, u32),
 }
 
+#[allow(clippy::len_without_is_empty)]
 impl Dimensions {
+    /// Creates a new `Dimensions` from its start and end positions
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> Dimensions {
+        Dimensions { start, end }
+    }
+
+    /// Checks whether the (row, column) position is within the area
+    pub fn contains(&self, row: u32, col: u32) -> bool {
+        row >= self.start.0 && row <= self.end.0 && col >= self.start.1 && col <= self.end.1
+    }
+
+    /// Number of cells in the area
     pub fn len(&self) -> u64 {
         (self.end.0 - self.start.0 + 1) as u64 * (self.end.1 - self.start.1 + 1) as u64
     }
@@ -153,6 +192,47 @@ pub struct Metadata {
     sheets: Vec<Sheet>,
     /// Map of sheet names/sheet path within zip archive
     names: Vec<(String, String)>,
+    properties: DocumentProperties,
+}
+
+impl Metadata {
+    /// Get the document properties (author, dates, custom properties ...)
+    pub fn properties(&self) -> &DocumentProperties {
+        &self.properties
+    }
+}
+
+/// Document properties
+///
+/// Dates are kept as ISO 8601 strings, e.g. `2023-01-31T08:30:00Z`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentProperties {
+    /// Title
+    pub title: Option<String>,
+    /// Subject
+    pub subject: Option<String>,
+    /// Author, who created the document
+    pub author: Option<String>,
+    /// Keywords
+    pub keywords: Option<String>,
+    /// Description or comments
+    pub description: Option<String>,
+    /// Category
+    pub category: Option<String>,
+    /// Last person who modified the document
+    pub last_modified_by: Option<String>,
+    /// Creation date
+    pub created: Option<String>,
+    /// Last modification date
+    pub modified: Option<String>,
+    /// Application which wrote the document
+    pub application: Option<String>,
+    /// Company
+    pub company: Option<String>,
+    /// Manager
+    pub manager: Option<String>,
+    /// User defined properties, as (name, value)
+    pub custom: Vec<(String, DataType)>,
 }
 
 /// Type of sheet
@@ -206,6 +286,9 @@ pub struct Sheet {
 // FIXME `Reader` must only be seek `Seek` for `Xls::xls`. Because of the present API this limits
 // the kinds of readers (other) data in formats can be read from.
 /// A trait to share spreadsheets reader functions across different `FileType`s
+///
+/// The methods reading formulas as syntax trees, styles, merged regions and the other
+/// worksheet annotations default to empty results, for formats without them.
 pub trait Reader<RS>: Sized
 where
     RS: Read + Seek,
@@ -231,6 +314,260 @@ where
     /// Read worksheet formula in corresponding worksheet path
     fn worksheet_formula(&mut self, _: &str) -> Result<Range<String>, Self::Error>;
 
+    /// Read worksheet formulas as syntax trees
+    ///
+    /// xls and xlsb formulas are decoded from their binary form, xlsx and ods
+    /// formulas are parsed. Formulas that cannot be parsed or decoded are
+    /// `Expr::Error(CellErrorType::Name)`, the other cells are still read.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader, Expr};
+    ///
+    /// # let path = format!("{}/tests/shared_formula.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// let formulas = workbook.worksheet_formula_ast("Sheet1").unwrap();
+    /// for (row, col, expr) in formulas.used_cells() {
+    ///     if let Expr::Function { name, .. } = expr {
+    ///         println!("({}, {}) calls {}", row, col, name);
+    ///     }
+    /// }
+    /// ```
+    fn worksheet_formula_ast(&mut self, _name: &str) -> Result<Range<Expr>, Self::Error> {
+        Ok(Range::default())
+    }
+
+    /// Read the merged cell regions of the corresponding worksheet
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/issue_261.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for region in workbook.merged_regions("Some Sheet").unwrap_or_default() {
+    ///     println!("merged cells from {:?} to {:?}", region.start, region.end);
+    /// }
+    /// ```
+    fn merged_regions(&mut self, _name: &str) -> Result<Vec<Dimensions>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the style table of the workbook
+    ///
+    /// Styles are only read on demand, use `worksheet_styles` to know which style
+    /// applies to each cell.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/issue3.xlsm", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// let styles = workbook.styles().unwrap();
+    /// let cell_styles = workbook.worksheet_styles("Sheet1").unwrap();
+    /// for (row, col, id) in cell_styles.used_cells() {
+    ///     if styles[*id].fill.is_visible() {
+    ///         println!("cell ({}, {}) is highlighted", row, col);
+    ///     }
+    /// }
+    /// ```
+    fn styles(&mut self) -> Result<Styles, Self::Error> {
+        Ok(Styles::default())
+    }
+
+    /// Read the style of each cell of the corresponding worksheet
+    ///
+    /// Only styles set on cells are reported, row and column default styles are ignored.
+    /// Cells with the default style (`StyleId(0)`) are not stored, in xlsx and xlsb.
+    fn worksheet_styles(&mut self, _name: &str) -> Result<Range<StyleId>, Self::Error> {
+        Ok(Range::default())
+    }
+
+    /// Read the comments (notes) of the corresponding worksheet
+    ///
+    /// For threaded comments, the replies are in `Comment::replies`.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/comments.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for comment in workbook.worksheet_comments("Sheet1").unwrap() {
+    ///     println!("{:?} {}: {}", comment.position, comment.author, comment.text);
+    /// }
+    /// ```
+    fn worksheet_comments(&mut self, _name: &str) -> Result<Vec<Comment>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the hyperlinks of the corresponding worksheet
+    ///
+    /// Cell values only hold the displayed text of the links.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/hyperlinks.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for link in workbook.worksheet_hyperlinks("Sheet1").unwrap() {
+    ///     println!("{:?}: {:?} {:?}", link.range.start, link.url, link.location);
+    /// }
+    /// ```
+    fn worksheet_hyperlinks(&mut self, _name: &str) -> Result<Vec<Hyperlink>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the text cells of the corresponding worksheet as formatted runs
+    ///
+    /// A run without font uses the font of the cell style. Cells holding other values,
+    /// formula results included, have no runs.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/rich_text.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// let range = workbook.worksheet_rich_text("Sheet1").unwrap();
+    /// for (row, col, runs) in range.used_cells() {
+    ///     for run in runs {
+    ///         let bold = run.font.as_ref().map_or(false, |f| f.bold);
+    ///         println!("({}, {}) {:?} bold: {}", row, col, run.text, bold);
+    ///     }
+    /// }
+    /// ```
+    fn worksheet_rich_text(&mut self, _name: &str) -> Result<Range<Vec<TextRun>>, Self::Error> {
+        Ok(Range::default())
+    }
+
+    /// Read the hidden flags, outline levels and sizes of the rows and columns of the
+    /// corresponding worksheet
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/layout.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// let layout = workbook.worksheet_layout("Sheet1").unwrap();
+    /// let range = workbook.worksheet_range("Sheet1").unwrap();
+    /// for (row, col, value) in range.cells() {
+    ///     let (row, col) = (row as u32, col as u32);
+    ///     if !layout.is_row_hidden(row) && !layout.is_column_hidden(col) {
+    ///         println!("({}, {}): {}", row, col, value);
+    ///     }
+    /// }
+    /// ```
+    fn worksheet_layout(&mut self, _name: &str) -> Result<SheetLayout, Self::Error> {
+        Ok(SheetLayout::default())
+    }
+
+    /// Read the data validation rules of the corresponding worksheet
+    ///
+    /// Ods formulas are kept in the OpenFormula syntax, as with `worksheet_formula`,
+    /// inline lists excepted.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/data_validations.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for validation in workbook.worksheet_data_validations("Sheet1").unwrap() {
+    ///     if let Some(values) = validation.list_values() {
+    ///         println!("{:?}: one of {:?}", validation.ranges, values);
+    ///     }
+    /// }
+    /// ```
+    fn worksheet_data_validations(
+        &mut self,
+        _name: &str,
+    ) -> Result<Vec<DataValidation>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the conditional formatting rules of the corresponding worksheet
+    ///
+    /// Xls files only expose `CellIs` and `Expression` rules, as in BIFF8 CF records.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/conditional_formats.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for format in workbook.worksheet_conditional_formats("Sheet1").unwrap() {
+    ///     println!("{:?} on {:?}: {:?}", format.rule_type, format.ranges, format.formulas);
+    /// }
+    /// ```
+    fn worksheet_conditional_formats(
+        &mut self,
+        _name: &str,
+    ) -> Result<Vec<ConditionalFormat>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the charts of the corresponding worksheet or chart sheet
+    ///
+    /// Only xlsx and xlsb charts are read, other formats having none.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, open_workbook, Reader};
+    ///
+    /// # let path = format!("{}/tests/charts.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for chart in workbook.worksheet_charts("Sheet1").unwrap() {
+    ///     println!("{:?} chart {:?}", chart.chart_type, chart.title);
+    ///     for series in chart.series {
+    ///         let values = series.values.map(|v| v.formula);
+    ///         println!("  {:?}: {:?}", series.name, values);
+    ///     }
+    /// }
+    /// ```
+    fn worksheet_charts(&mut self, _name: &str) -> Result<Vec<Chart>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read worksheet values as displayed, using the number format of each cell
+    ///
+    /// Cells without a number format are rendered with the "General" format.
+    fn worksheet_display_range(&mut self, name: &str) -> Result<Range<String>, Self::Error> {
+        let styles = self.styles()?;
+        let ids = self.worksheet_styles(name)?;
+        let range = self.worksheet_range(name)?;
+        let (row, col) = range.start().unwrap_or_default();
+        let cells = range
+            .used_cells()
+            .map(|(r, c, v)| {
+                let pos = (row + r as u32, col + c as u32);
+                let format = ids
+                    .get_value(pos)
+                    .and_then(|id| styles.get(*id))
+                    .and_then(|s| s.number_format.as_deref())
+                    .unwrap_or("General");
+                Cell::new(pos, v.format_with(format))
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    /// Read worksheet values, evaluating the formulas which have no cached value
+    ///
+    /// Only available with the `eval` feature, see the [`eval`] module.
+    #[cfg(feature = "eval")]
+    fn worksheet_range_evaluated(&mut self, name: &str) -> Result<Range<DataType>, Self::Error> {
+        let range = eval::Evaluator::new(self).worksheet_range(name);
+        match range {
+            Some(range) => Ok(range),
+            // let the reader report the missing or unreadable worksheet
+            None => self.worksheet_range(name),
+        }
+    }
+
     /// Get all sheet names of this workbook, in workbook order
     ///
     /// # Examples
@@ -271,6 +608,33 @@ where
     fn pictures(&self) -> Option<Vec<(String, Vec<u8>)>>;
 }
 
+/// A trait to read the tables of a workbook
+///
+/// Tables are the list objects of xlsx and xlsb files and the named database ranges
+/// of ods files. They must be loaded with `load_tables` before being referenced.
+pub trait TableReader<RS>: Reader<RS>
+where
+    RS: Read + Seek,
+{
+    /// Load the tables of the workbook
+    fn load_tables(&mut self) -> Result<(), Self::Error>;
+
+    /// Get the names of all the tables
+    fn table_names(&self) -> Vec<&String>;
+
+    /// Get the names of all the tables in a sheet
+    fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String>;
+
+    /// Get the table by name
+    fn table_by_name(&mut self, table_name: &str) -> Result<Table<DataType>, Self::Error>;
+
+    /// Get the formulas of a worksheet, with table structured references such as
+    /// `Sales[Amount]` or `[@Qty]` resolved to A1 references
+    ///
+    /// References which cannot be resolved are kept as is.
+    fn worksheet_formula_resolved(&mut self, name: &str) -> Result<Range<String>, Self::Error>;
+}
+
 /// Convenient function to open a file with a BufReader<File>
 pub fn open_workbook<R, P>(path: P) -> Result<R, R::Error>
 where
@@ -433,7 +797,7 @@ impl<T: CellType> Range<T> {
             // search bounds
             let row_start = cells.first().unwrap().pos.0;
             let row_end = cells.last().unwrap().pos.0;
-            let mut col_start = std::u32::MAX;
+            let mut col_start = u32::MAX;
             let mut col_end = 0;
             for c in cells.iter().map(|c| c.pos.1) {
                 if c < col_start {
@@ -452,7 +816,9 @@ impl<T: CellType> Range<T> {
                 let row = (c.pos.0 - row_start) as usize;
                 let col = (c.pos.1 - col_start) as usize;
                 let idx = row.saturating_mul(cols) + col;
-                v.get_mut(idx).map(|v| *v = c.val);
+                if let Some(v) = v.get_mut(idx) {
+                    *v = c.val;
+                }
             }
             Range {
                 start: (row_start, col_start),
@@ -866,6 +1232,49 @@ impl<'a, T: 'a + CellType> DoubleEndedIterator for Rows<'a, T> {
 
 impl<'a, T: 'a + CellType> ExactSizeIterator for Rows<'a, T> {}
 
+/// Metadata of a table, as read before its data
+#[derive(Debug, Clone)]
+pub(crate) struct TableMetadata {
+    /// Unique id, referenced by xls and xlsb formulas
+    pub id: u32,
+    pub name: String,
+    pub sheet_name: String,
+    pub columns: Vec<String>,
+    /// Cells of the whole table, including the header and totals rows
+    pub dimensions: Dimensions,
+    pub header_rows: u32,
+    pub totals_rows: u32,
+    /// The empty row used to add data is shown after the data rows
+    pub insert_row: bool,
+}
+
+impl TableMetadata {
+    /// Cells of the data rows
+    pub fn data(&self) -> Dimensions {
+        let mut data = self.dimensions;
+        data.start.0 += self.header_rows;
+        data.end.0 = data
+            .end
+            .0
+            .saturating_sub(self.totals_rows + self.insert_row as u32);
+        data
+    }
+
+    /// Builds the table out of its worksheet
+    pub fn table(&self, range: &Range<DataType>) -> Table<DataType> {
+        let data = self.data();
+        Table {
+            name: self.name.clone(),
+            sheet_name: self.sheet_name.clone(),
+            columns: self.columns.clone(),
+            data: range.range(data.start, data.end),
+        }
+    }
+}
+
+/// Tables metadata, `None` until loaded
+pub(crate) type Tables = Option<Vec<TableMetadata>>;
+
 /// Struct with the key elements of a table
 pub struct Table<T> {
     pub(crate) name: String,
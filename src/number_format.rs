@@ -0,0 +1,1014 @@
+//! Number format rendering
+//!
+//! Renders cell values the way Excel displays them, from a number format code
+//! (ECMA-376 Part 1, 18.8.31 numFmts).
+
+use crate::formats::{detect_custom_number_format, CellFormat};
+use crate::DataType;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const DAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    /// Digit placeholder, one of '0', '#' or '?'
+    Digit(char),
+    Point,
+    Comma,
+    Percent,
+    /// Scientific notation, with the 'E' as written and whether '+' is always shown
+    Exponent(char, bool),
+    Slash,
+    /// Fixed fraction denominator
+    Denominator(u32),
+    /// Text placeholder '@'
+    Text,
+    General,
+    Year(usize),
+    Month(usize),
+    Day(usize),
+    Hour(usize),
+    Minute(usize),
+    Second(usize),
+    /// Fractional seconds digits
+    SubSecond(usize),
+    /// AM/PM or A/P markers, as written
+    AmPm(String, String),
+    /// Elapsed time, [h], [mm] or [ss]
+    Elapsed(char, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Condition {
+    Lt(f64),
+    Le(f64),
+    Gt(f64),
+    Ge(f64),
+    Eq(f64),
+    Ne(f64),
+}
+
+impl Condition {
+    fn matches(self, v: f64) -> bool {
+        match self {
+            Condition::Lt(c) => v < c,
+            Condition::Le(c) => v <= c,
+            Condition::Gt(c) => v > c,
+            Condition::Ge(c) => v >= c,
+            Condition::Eq(c) => v == c,
+            Condition::Ne(c) => v != c,
+        }
+    }
+}
+
+/// A `;` separated part of a format code
+#[derive(Debug)]
+struct Section {
+    tokens: Vec<Token>,
+    condition: Option<Condition>,
+    is_date: bool,
+}
+
+/// Formats `value` as Excel would display it with the `code` number format
+pub(crate) fn format_value(value: &DataType, code: &str) -> String {
+    match value {
+        DataType::Int(v) => format_number(*v as f64, code),
+        DataType::Float(v) | DataType::DateTime(v) | DataType::Duration(v) => {
+            format_number(*v, code)
+        }
+        DataType::String(s) => format_text(s, code),
+        DataType::Bool(true) => "TRUE".to_string(),
+        DataType::Bool(false) => "FALSE".to_string(),
+        DataType::Error(e) => e.to_string(),
+        DataType::DateTimeIso(s) | DataType::DurationIso(s) => s.clone(),
+        DataType::Empty => String::new(),
+    }
+}
+
+fn format_number(v: f64, code: &str) -> String {
+    if code.trim().is_empty() {
+        return format_general(v);
+    }
+    let sections = parse_sections(code);
+    let numeric = &sections[..sections.len().min(3)];
+    let (section, v, minus) = if numeric[..numeric.len().min(2)]
+        .iter()
+        .any(|s| s.condition.is_some())
+    {
+        // explicit conditions, the value is displayed with its sign
+        let section = numeric
+            .iter()
+            .find(|s| s.condition.map_or(true, |c| c.matches(v)))
+            .unwrap_or(&numeric[0]);
+        (section, v.abs(), v < 0.)
+    } else if v < 0. && numeric.len() > 1 {
+        (&numeric[1], -v, false)
+    } else if v == 0. && numeric.len() > 2 {
+        (&numeric[2], v, false)
+    } else {
+        (&numeric[0], v.abs(), v < 0.)
+    };
+    format_section(section, v, minus)
+}
+
+fn format_text(s: &str, code: &str) -> String {
+    let sections = parse_sections(code);
+    let section = match sections.len() {
+        4 => &sections[3],
+        1 if sections[0].tokens.contains(&Token::Text) => &sections[0],
+        _ => return s.to_string(),
+    };
+    let mut out = String::new();
+    for token in &section.tokens {
+        match token {
+            Token::Text => out.push_str(s),
+            Token::Literal(l) => out.push_str(l),
+            _ => (),
+        }
+    }
+    out
+}
+
+/// Splits a format code into its sections, ignoring separators in quotes and brackets
+fn parse_sections(code: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let (mut start, mut quoted, mut escaped, mut bracket) = (0, false, false, false);
+    for (i, c) in code.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '"' => quoted = !quoted,
+            _ if quoted => (),
+            '\\' | '_' | '*' => escaped = true,
+            '[' => bracket = true,
+            ']' => bracket = false,
+            ';' if !bracket => {
+                sections.push(parse_section(&code[start..i]));
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    sections.push(parse_section(&code[start..]));
+    sections
+}
+
+fn parse_section(code: &str) -> Section {
+    let is_date = detect_custom_number_format(code) != CellFormat::Other;
+    let chars = code.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut condition = None;
+    let mut has_point = false;
+    let mut i = 0;
+    let repeats = |i: usize, c: char| {
+        chars[i..]
+            .iter()
+            .take_while(|n| n.eq_ignore_ascii_case(&c))
+            .count()
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '"' => {
+                let start = i;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                push_literal(&mut tokens, &chars[start..i].iter().collect::<String>());
+                i += 1;
+            }
+            '\\' => {
+                if let Some(n) = chars.get(i) {
+                    push_literal(&mut tokens, &n.to_string());
+                    i += 1;
+                }
+            }
+            '_' => {
+                push_literal(&mut tokens, " ");
+                i += 1;
+            }
+            // fill character, cells have no width here
+            '*' => i += 1,
+            '[' => {
+                let start = i;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                let content = chars[start..i].iter().collect::<String>();
+                i += 1;
+                parse_bracket(&content, &mut tokens, &mut condition);
+            }
+            '@' => tokens.push(Token::Text),
+            'G' | 'g'
+                if chars[i - 1..]
+                    .iter()
+                    .take(7)
+                    .collect::<String>()
+                    .eq_ignore_ascii_case("general") =>
+            {
+                tokens.push(Token::General);
+                i += 6;
+            }
+            'A' | 'a' if is_date => {
+                let rest = |n| chars[i - 1..].iter().take(n).collect::<String>();
+                let (am_pm, a_p) = (rest(5), rest(3));
+                if am_pm.eq_ignore_ascii_case("am/pm") {
+                    tokens.push(Token::AmPm(am_pm[..2].into(), am_pm[3..].into()));
+                    i += 4;
+                } else if a_p.eq_ignore_ascii_case("a/p") {
+                    tokens.push(Token::AmPm(a_p[..1].into(), a_p[2..].into()));
+                    i += 2;
+                } else {
+                    push_literal(&mut tokens, &c.to_string());
+                }
+            }
+            'y' | 'Y' | 'e' | 'E' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' if is_date => {
+                let n = repeats(i - 1, c);
+                i += n - 1;
+                tokens.push(match c.to_ascii_lowercase() {
+                    'y' => Token::Year(n),
+                    'e' => Token::Year(4),
+                    'm' => Token::Month(n),
+                    'd' => Token::Day(n),
+                    'h' => Token::Hour(n),
+                    _ => Token::Second(n),
+                });
+            }
+            '.' if is_date && chars.get(i) == Some(&'0') => {
+                let n = repeats(i, '0');
+                i += n;
+                tokens.push(Token::SubSecond(n.min(3)));
+            }
+            '0' | '#' | '?' if !is_date => tokens.push(Token::Digit(c)),
+            '.' if !is_date && !has_point => {
+                has_point = true;
+                tokens.push(Token::Point);
+            }
+            ',' if !is_date => tokens.push(Token::Comma),
+            '%' => tokens.push(Token::Percent),
+            'E' | 'e' if matches!(chars.get(i), Some('+' | '-')) => {
+                tokens.push(Token::Exponent(c, chars[i] == '+'));
+                i += 1;
+            }
+            '/' if !is_date => {
+                tokens.push(Token::Slash);
+                let n = chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+                if n > 0 && chars[i] != '0' {
+                    let den = chars[i..i + n].iter().collect::<String>();
+                    tokens.push(Token::Denominator(den.parse().unwrap_or(1)));
+                    i += n;
+                }
+            }
+            _ => push_literal(&mut tokens, &c.to_string()),
+        }
+    }
+
+    // 'm' is a minute when following hours or preceding seconds
+    for i in 0..tokens.len() {
+        if let Token::Month(n @ 1..=2) = tokens[i] {
+            let is_literal = |t: &&Token| matches!(t, Token::Literal(_));
+            let after_hours = tokens[..i]
+                .iter()
+                .rev()
+                .find(|t| !is_literal(t))
+                .map_or(false, |t| {
+                    matches!(t, Token::Hour(_) | Token::Elapsed('h', _))
+                });
+            let before_seconds = tokens[i + 1..]
+                .iter()
+                .find(|t| !is_literal(t))
+                .map_or(false, |t| {
+                    matches!(t, Token::Second(_) | Token::Elapsed('s', _))
+                });
+            if after_hours || before_seconds {
+                tokens[i] = Token::Minute(n);
+            }
+        }
+    }
+
+    Section {
+        tokens,
+        condition,
+        is_date,
+    }
+}
+
+fn push_literal(tokens: &mut Vec<Token>, s: &str) {
+    if let Some(Token::Literal(l)) = tokens.last_mut() {
+        l.push_str(s);
+    } else {
+        tokens.push(Token::Literal(s.to_string()));
+    }
+}
+
+/// Parses the content of a `[...]` block: condition, locale, elapsed time or color
+fn parse_bracket(content: &str, tokens: &mut Vec<Token>, condition: &mut Option<Condition>) {
+    if let Some(currency) = content.strip_prefix('$') {
+        // locale tag, e.g. [$€-407] or [$-409]
+        let symbol = currency.split('-').next().unwrap_or_default();
+        if !symbol.is_empty() {
+            push_literal(tokens, symbol);
+        }
+        return;
+    }
+    let parse = |s: &str| s.trim().parse::<f64>().ok();
+    let cond = if let Some(v) = content.strip_prefix("<=") {
+        parse(v).map(Condition::Le)
+    } else if let Some(v) = content.strip_prefix(">=") {
+        parse(v).map(Condition::Ge)
+    } else if let Some(v) = content.strip_prefix("<>") {
+        parse(v).map(Condition::Ne)
+    } else if let Some(v) = content.strip_prefix('<') {
+        parse(v).map(Condition::Lt)
+    } else if let Some(v) = content.strip_prefix('>') {
+        parse(v).map(Condition::Gt)
+    } else if let Some(v) = content.strip_prefix('=') {
+        parse(v).map(Condition::Eq)
+    } else {
+        None
+    };
+    if cond.is_some() {
+        *condition = cond;
+        return;
+    }
+    let mut chars = content.chars();
+    if let Some(c @ ('h' | 'H' | 'm' | 'M' | 's' | 'S')) = chars.next() {
+        if chars.all(|n| n.eq_ignore_ascii_case(&c)) {
+            tokens.push(Token::Elapsed(c.to_ascii_lowercase(), content.len()));
+        }
+    }
+    // colors and other tags are not rendered
+}
+
+fn format_section(section: &Section, v: f64, minus: bool) -> String {
+    let tokens = &section.tokens;
+    if tokens.contains(&Token::General) {
+        let mut out = String::new();
+        for token in tokens {
+            match token {
+                Token::General => out.push_str(&format_general(if minus { -v } else { v })),
+                Token::Literal(l) => out.push_str(l),
+                _ => (),
+            }
+        }
+        return out;
+    }
+    if section.is_date {
+        return if minus {
+            format_general(-v)
+        } else {
+            format_date(tokens, v)
+        };
+    }
+    if let Some(exp) = tokens.iter().position(|t| matches!(t, Token::Exponent(..))) {
+        return format_scientific(tokens, exp, v, minus);
+    }
+    if let Some(slash) = tokens.iter().position(|t| *t == Token::Slash) {
+        if slash > 0 && matches!(tokens[slash - 1], Token::Digit(_)) {
+            return format_fraction(tokens, slash, v, minus);
+        }
+    }
+    format_decimal(tokens, v, minus)
+}
+
+/// Excel "General" format, at most 11 characters wide
+fn format_general(v: f64) -> String {
+    let sign = if v < 0. { "-" } else { "" };
+    let v = v.abs();
+    if v == 0. {
+        return "0".to_string();
+    }
+    if !(1e-9..1e11).contains(&v) {
+        let s = format!("{:.5E}", v);
+        let (mantissa, exp) = s.split_once('E').unwrap_or((&s, "0"));
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        let exp = exp.parse::<i32>().unwrap_or(0);
+        let exp_sign = if exp < 0 { '-' } else { '+' };
+        return format!("{}{}E{}{:02}", sign, mantissa, exp_sign, exp.abs());
+    }
+    let int_len = (v.log10().floor() as i32 + 1).max(1) as usize;
+    let (int, frac) = decimal_digits(v, 10usize.saturating_sub(int_len));
+    let frac = frac.trim_end_matches('0');
+    let int = if int.is_empty() { "0" } else { &int };
+    if frac.is_empty() {
+        format!("{}{}", sign, int)
+    } else {
+        format!("{}{}.{}", sign, int, frac)
+    }
+}
+
+/// Rounds a positive value to `decimals` places, half away from zero, once reduced
+/// to the 15 significant digits Excel works with
+///
+/// Returns the integer digits, empty for 0, and exactly `decimals` fractional digits
+fn decimal_digits(v: f64, decimals: usize) -> (String, String) {
+    let s = format!("{:.14e}", v);
+    let (mantissa, exp) = s.split_once('e').unwrap_or((&s, "0"));
+    let mut point = exp.parse::<i32>().unwrap_or(0) + 1;
+    let mut digits = mantissa
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .map(|b| b - b'0')
+        .collect::<Vec<_>>();
+    let keep = point + decimals as i32;
+    if keep < 0 {
+        return (String::new(), "0".repeat(decimals));
+    }
+    let keep = keep as usize;
+    if keep < digits.len() {
+        let round_up = digits[keep] >= 5;
+        digits.truncate(keep);
+        if round_up {
+            let mut i = keep;
+            loop {
+                if i == 0 {
+                    digits.insert(0, 1);
+                    point += 1;
+                    break;
+                }
+                i -= 1;
+                if digits[i] == 9 {
+                    digits[i] = 0;
+                } else {
+                    digits[i] += 1;
+                    break;
+                }
+            }
+        }
+    }
+    let digit = |i: i32| {
+        if i < 0 {
+            '0'
+        } else {
+            digits.get(i as usize).map_or('0', |d| char::from(b'0' + d))
+        }
+    };
+    let int = (0..point)
+        .map(digit)
+        .skip_while(|c| *c == '0')
+        .collect::<String>();
+    let frac = (point..point + decimals as i32).map(digit).collect();
+    (int, frac)
+}
+
+fn is_digit(t: &Token) -> bool {
+    matches!(t, Token::Digit(_))
+}
+
+/// Renders the integer part placeholders, right to left, the leftmost placeholder
+/// taking all remaining digits
+fn format_integer(tokens: &[Token], int: &str, grouping: bool, out: &mut String) {
+    let first = tokens.iter().position(is_digit);
+    let mut digits = int.chars().rev();
+    let mut rev = Vec::new();
+    let mut count = 0;
+    let mut push_digit = |rev: &mut Vec<char>, d: char| {
+        if grouping && count > 0 && count % 3 == 0 {
+            rev.push(',');
+        }
+        rev.push(d);
+        count += 1;
+    };
+    for (i, token) in tokens.iter().enumerate().rev() {
+        match token {
+            Token::Digit(p) => {
+                match (digits.next(), p) {
+                    (Some(d), _) => push_digit(&mut rev, d),
+                    (None, '0') => push_digit(&mut rev, '0'),
+                    (None, '?') => rev.push(' '),
+                    _ => (),
+                }
+                if Some(i) == first {
+                    for d in digits.by_ref() {
+                        push_digit(&mut rev, d);
+                    }
+                }
+            }
+            Token::Literal(l) => rev.extend(l.chars().rev()),
+            Token::Percent => rev.push('%'),
+            _ => (),
+        }
+    }
+    out.extend(rev.into_iter().rev());
+}
+
+fn format_decimal(tokens: &[Token], v: f64, minus: bool) -> String {
+    let point = tokens
+        .iter()
+        .position(|t| *t == Token::Point)
+        .unwrap_or(tokens.len());
+    let first_digit = tokens.iter().position(is_digit);
+    let last_digit = tokens.iter().rposition(is_digit);
+    let decimals = tokens[point..].iter().filter(|t| is_digit(t)).count();
+
+    // commas between integer placeholders group thousands, trailing ones scale by 1000
+    let mut grouping = false;
+    let mut scale = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if *token != Token::Comma {
+            continue;
+        }
+        let in_number = first_digit.map_or(false, |f| f < i);
+        if in_number && i < point && tokens[i + 1..point].iter().any(is_digit) {
+            grouping = true;
+        } else if in_number
+            && last_digit.map_or(false, |l| l < i)
+            && tokens[last_digit.unwrap_or(0) + 1..i]
+                .iter()
+                .all(|t| *t == Token::Comma)
+        {
+            scale += 1;
+        }
+    }
+    let percent = tokens.iter().filter(|t| **t == Token::Percent).count();
+    let v = v * 100f64.powi(percent as i32) / 1000f64.powi(scale);
+    let (int, frac) = decimal_digits(v, decimals);
+
+    let mut out = String::new();
+    if minus && (!int.is_empty() || frac.bytes().any(|b| b != b'0')) {
+        out.push('-');
+    }
+    // commas before the first placeholder are plain text
+    let int_tokens = tokens[..point]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            Token::Comma if first_digit.map_or(true, |f| i < f) => Some(Token::Literal(",".into())),
+            Token::Comma => None,
+            t => Some(t.clone()),
+        })
+        .collect::<Vec<_>>();
+    format_integer(&int_tokens, &int, grouping, &mut out);
+
+    let last_non_zero = frac.rfind(|c| c != '0');
+    let mut frac_digits = frac.chars().enumerate();
+    for token in &tokens[point.min(tokens.len())..] {
+        match token {
+            Token::Point => out.push('.'),
+            Token::Digit(p) => {
+                let (i, d) = frac_digits.next().unwrap_or((0, '0'));
+                match p {
+                    _ if last_non_zero.map_or(false, |l| i <= l) => out.push(d),
+                    '0' => out.push('0'),
+                    '?' => out.push(' '),
+                    _ => (),
+                }
+            }
+            Token::Literal(l) => out.push_str(l),
+            Token::Percent => out.push('%'),
+            _ => (),
+        }
+    }
+    out
+}
+
+fn format_scientific(tokens: &[Token], exp_pos: usize, v: f64, minus: bool) -> String {
+    let mantissa_tokens = &tokens[..exp_pos];
+    let point = mantissa_tokens
+        .iter()
+        .position(|t| *t == Token::Point)
+        .unwrap_or(exp_pos);
+    let int_digits = mantissa_tokens[..point]
+        .iter()
+        .filter(|t| is_digit(t))
+        .count()
+        .max(1) as i32;
+    let decimals = mantissa_tokens[point..]
+        .iter()
+        .filter(|t| is_digit(t))
+        .count();
+
+    let mut exp = if v == 0. {
+        0
+    } else {
+        let e = v.log10().floor() as i32;
+        if int_digits > 1 {
+            e.div_euclid(int_digits) * int_digits
+        } else {
+            e
+        }
+    };
+    let mut mantissa = v / 10f64.powi(exp);
+    // rounding may overflow the integer placeholders, e.g. 9.999 with 0.00E+00
+    let (int, _) = decimal_digits(mantissa, decimals);
+    if int_digits == 1 && int.len() > 1 {
+        exp += 1;
+        mantissa = v / 10f64.powi(exp);
+    }
+
+    let mut out = format_decimal(mantissa_tokens, mantissa, minus);
+    let (e, plus) = match tokens[exp_pos] {
+        Token::Exponent(e, plus) => (e, plus),
+        _ => ('E', true),
+    };
+    out.push(e);
+    if exp < 0 {
+        out.push('-');
+    } else if plus {
+        out.push('+');
+    }
+    let exp_tokens = &tokens[exp_pos + 1..];
+    let width = exp_tokens
+        .iter()
+        .filter(|t| **t == Token::Digit('0'))
+        .count();
+    let exp_str = format!("{:0width$}", exp.abs(), width = width);
+    let mut exp_written = false;
+    for token in exp_tokens {
+        match token {
+            Token::Digit(_) if !exp_written => {
+                out.push_str(&exp_str);
+                exp_written = true;
+            }
+            Token::Literal(l) => out.push_str(l),
+            _ => (),
+        }
+    }
+    if !exp_written {
+        out.push_str(&exp_str);
+    }
+    out
+}
+
+fn format_fraction(tokens: &[Token], slash: usize, v: f64, minus: bool) -> String {
+    // numerator placeholders are right before the slash, the integer part before them
+    let num_start = tokens[..slash]
+        .iter()
+        .rposition(|t| !is_digit(t))
+        .map_or(0, |i| i + 1);
+    let int_end = tokens[..num_start]
+        .iter()
+        .rposition(is_digit)
+        .map(|i| i + 1);
+    let den_tokens = &tokens[slash + 1..];
+    let (max_den, fixed_den) = match den_tokens.first() {
+        Some(Token::Denominator(d)) => (*d, true),
+        _ => {
+            let n = den_tokens.iter().take_while(|t| is_digit(t)).count();
+            (10u32.pow(n.clamp(1, 7) as u32) - 1, false)
+        }
+    };
+
+    let (mut whole, frac) = match int_end {
+        Some(_) => (v.trunc(), v.fract()),
+        None => (0., v),
+    };
+    let (mut num, den) = if fixed_den {
+        ((frac * max_den as f64).round() as u64, max_den as u64)
+    } else {
+        best_fraction(frac, max_den as u64)
+    };
+    if int_end.is_some() && num == den {
+        whole += 1.;
+        num = 0;
+    }
+    if int_end.is_none() {
+        num += (whole as u64) * den;
+    }
+
+    let mut out = String::new();
+    if minus && (whole != 0. || num != 0) {
+        out.push('-');
+    }
+    let placeholders = |tokens: &[Token]| {
+        tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Digit(p) => Some(*p),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    if let Some(int_end) = int_end {
+        let int = if whole == 0. {
+            if num == 0 {
+                "0".to_string()
+            } else {
+                String::new()
+            }
+        } else {
+            format!("{}", whole)
+        };
+        format_integer(&tokens[..int_end], &int, false, &mut out);
+        if num == 0 {
+            // the fraction is blanked
+            let width = tokens[int_end..]
+                .iter()
+                .map(|t| match t {
+                    Token::Literal(l) => l.chars().count(),
+                    Token::Denominator(d) => d.to_string().len(),
+                    _ => 1,
+                })
+                .sum::<usize>();
+            out.extend(std::iter::repeat(' ').take(width));
+            return out;
+        }
+        for token in &tokens[int_end..num_start] {
+            if let Token::Literal(l) = token {
+                out.push_str(l);
+            }
+        }
+    } else {
+        for token in &tokens[..num_start] {
+            if let Token::Literal(l) = token {
+                out.push_str(l);
+            }
+        }
+    }
+
+    let num_str = num.to_string();
+    let num_places = placeholders(&tokens[num_start..slash]);
+    for p in num_places.iter().skip(num_str.len()) {
+        match p {
+            '0' => out.push('0'),
+            '?' => out.push(' '),
+            _ => (),
+        }
+    }
+    out.push_str(&num_str);
+    out.push('/');
+    let den_str = den.to_string();
+    out.push_str(&den_str);
+    if !fixed_den {
+        let den_places = placeholders(den_tokens);
+        for p in den_places.iter().skip(den_str.len()) {
+            if *p != '#' {
+                out.push(' ');
+            }
+        }
+    }
+    for token in den_tokens {
+        if let Token::Literal(l) = token {
+            out.push_str(l);
+        }
+    }
+    out
+}
+
+/// Closest fraction of `v` with a denominator up to `max_den`
+fn best_fraction(v: f64, max_den: u64) -> (u64, u64) {
+    let mut best = (v.round() as u64, 1);
+    let mut best_err = (v - best.0 as f64).abs();
+    for den in 2..=max_den {
+        let num = (v * den as f64).round();
+        let err = (v - num / den as f64).abs();
+        if err < best_err {
+            best = (num as u64, den);
+            best_err = err;
+        }
+        if best_err == 0. {
+            break;
+        }
+    }
+    best
+}
+
+/// Converts an Excel serial day into (year, month, day), with the 1900 leap year bug
+pub(crate) fn serial_to_date(days: i64) -> (i64, u32, u32) {
+    match days {
+        0 => return (1900, 1, 0),
+        60 => return (1900, 2, 29),
+        _ => (),
+    }
+    let days = if days < 60 { days } else { days - 1 };
+    // days since 1970-01-01, then http://howardhinnant.github.io/date_algorithms.html
+    let z = days - 25568 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+/// Converts a date into an Excel serial day, with the 1900 leap year bug
+///
+/// Months and days out of their range roll over, e.g. month 13 is January of the next year.
+#[cfg(feature = "eval")]
+pub(crate) fn date_to_serial(year: i64, month: i64, day: i64) -> i64 {
+    let y = year + (month - 1).div_euclid(12);
+    let m = (month - 1).rem_euclid(12) + 1;
+    // days since 1970-01-01 of the first day of the month
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let first = era * 146_097 + doe - 719_468 + 25_568;
+    let first = if first >= 60 { first + 1 } else { first };
+    first + day - 1
+}
+
+fn format_date(tokens: &[Token], v: f64) -> String {
+    let sub_digits = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::SubSecond(n) => Some(*n),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let sub_scale = 10i64.pow(sub_digits as u32);
+    let ticks = (v * 86_400. * sub_scale as f64).round() as i64;
+    let sub = ticks % sub_scale;
+    let total_seconds = ticks / sub_scale;
+    let days = total_seconds / 86_400;
+    let (hour, minute, second) = (
+        total_seconds % 86_400 / 3600,
+        total_seconds % 3600 / 60,
+        total_seconds % 60,
+    );
+    let (year, month, day) = serial_to_date(days);
+    let weekday = (days - 1).rem_euclid(7) as usize;
+    let am_pm = tokens.iter().any(|t| matches!(t, Token::AmPm(..)));
+
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Literal(l) => out.push_str(l),
+            Token::Year(n) if *n <= 2 => out.push_str(&format!("{:02}", year % 100)),
+            Token::Year(_) => out.push_str(&format!("{:04}", year)),
+            Token::Month(1) => out.push_str(&month.to_string()),
+            Token::Month(2) => out.push_str(&format!("{:02}", month)),
+            Token::Month(3) => out.push_str(&MONTHS[month as usize - 1][..3]),
+            Token::Month(4) => out.push_str(MONTHS[month as usize - 1]),
+            Token::Month(_) => out.push_str(&MONTHS[month as usize - 1][..1]),
+            Token::Day(1) => out.push_str(&day.to_string()),
+            Token::Day(2) => out.push_str(&format!("{:02}", day)),
+            Token::Day(3) => out.push_str(&DAYS[weekday][..3]),
+            Token::Day(_) => out.push_str(DAYS[weekday]),
+            Token::Hour(n) => {
+                let hour = match (am_pm, hour % 12) {
+                    (false, _) => hour,
+                    (true, 0) => 12,
+                    (true, h) => h,
+                };
+                push_padded(&mut out, hour, *n);
+            }
+            Token::Minute(n) => push_padded(&mut out, minute, *n),
+            Token::Second(n) => push_padded(&mut out, second, *n),
+            Token::SubSecond(n) => {
+                let s = format!("{:0width$}", sub, width = sub_digits);
+                out.push('.');
+                out.push_str(&s[..*n]);
+            }
+            Token::AmPm(am, pm) => out.push_str(if hour < 12 { am } else { pm }),
+            Token::Elapsed(unit, n) => {
+                let elapsed = match unit {
+                    'h' => total_seconds / 3600,
+                    'm' => total_seconds / 60,
+                    _ => total_seconds,
+                };
+                push_padded(&mut out, elapsed, *n);
+            }
+            Token::Percent => out.push('%'),
+            _ => (),
+        }
+    }
+    out
+}
+
+fn push_padded(out: &mut String, v: i64, width: usize) {
+    if width >= 2 {
+        out.push_str(&format!("{:02}", v));
+    } else {
+        out.push_str(&v.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(v: f64, code: &str) -> String {
+        format_value(&DataType::Float(v), code)
+    }
+
+    #[test]
+    fn general() {
+        assert_eq!(fmt(0., "General"), "0");
+        assert_eq!(fmt(42., "General"), "42");
+        assert_eq!(fmt(-1.5, "General"), "-1.5");
+        assert_eq!(fmt(1. / 3., "General"), "0.333333333");
+        assert_eq!(fmt(123.456789012345, "General"), "123.456789");
+        assert_eq!(fmt(1.23456789e15, "General"), "1.23457E+15");
+        assert_eq!(fmt(0.1 + 0.2, ""), "0.3");
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(fmt(1234.5, "0.00"), "1234.50");
+        assert_eq!(fmt(1234.5, "#,##0.00"), "1,234.50");
+        assert_eq!(fmt(-1234567., "#,##0"), "-1,234,567");
+        assert_eq!(fmt(2.675, "0.00"), "2.68");
+        assert_eq!(fmt(0.5, "#.00"), ".50");
+        assert_eq!(fmt(3.1, "0.0#"), "3.1");
+        assert_eq!(fmt(3.1, "0.0?"), "3.1 ");
+        assert_eq!(fmt(12., "000"), "012");
+        assert_eq!(fmt(1234567., "0.0,,"), "1.2");
+        assert_eq!(fmt(123456789., "000-00-0000"), "123-45-6789");
+        assert_eq!(fmt(0.125, "0.00%"), "12.50%");
+        assert_eq!(fmt(-0.001, "0.00"), "0.00");
+    }
+
+    #[test]
+    fn sections() {
+        let code = "#,##0.00;(#,##0.00);\"zero\";\"text: \"@";
+        assert_eq!(fmt(1., code), "1.00");
+        assert_eq!(fmt(-1., code), "(1.00)");
+        assert_eq!(fmt(0., code), "zero");
+        assert_eq!(
+            format_value(&DataType::String("abc".into()), code),
+            "text: abc"
+        );
+        assert_eq!(fmt(5., "0_);[Red]\\(0\\)"), "5 ");
+        assert_eq!(fmt(-5., "0_);[Red]\\(0\\)"), "(5)");
+        assert_eq!(fmt(-5., "0;;"), "");
+    }
+
+    #[test]
+    fn conditions() {
+        let code = "[>=1000000]0.0,,\"M\";[>=1000]0.0,\"K\";0";
+        assert_eq!(fmt(2500000., code), "2.5M");
+        assert_eq!(fmt(2500., code), "2.5K");
+        assert_eq!(fmt(25., code), "25");
+    }
+
+    #[test]
+    fn scientific() {
+        assert_eq!(fmt(12345., "0.00E+00"), "1.23E+04");
+        assert_eq!(fmt(0.00012, "0.00E+00"), "1.20E-04");
+        assert_eq!(fmt(12345., "##0.0E+0"), "12.3E+3");
+        assert_eq!(fmt(9.999, "0.00E+00"), "1.00E+01");
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(fmt(1.25, "# ?/?"), "1 1/4");
+        assert_eq!(fmt(0.3333, "?/?"), "1/3");
+        assert_eq!(fmt(2.14159, "# ??/??"), "2 14/99");
+        assert_eq!(fmt(3.5, "# ??/??"), "3  1/2 ");
+        assert_eq!(fmt(1.5, "# ?/4"), "1 2/4");
+        assert_eq!(fmt(2., "# ?/?"), "2    ");
+    }
+
+    #[test]
+    fn text_and_locale() {
+        assert_eq!(fmt(1234.5, "[$€-407] #,##0.00"), "€ 1,234.50");
+        assert_eq!(fmt(5., "0 \"days\""), "5 days");
+        assert_eq!(format_value(&DataType::String("x".into()), "0.00"), "x");
+        assert_eq!(format_value(&DataType::Bool(true), "0.00"), "TRUE");
+    }
+
+    #[test]
+    fn dates() {
+        // 2024-01-03 14:05:09
+        let v = 45294. + (14. * 3600. + 5. * 60. + 9.) / 86400.;
+        assert_eq!(fmt(v, "dd-mmm-yy"), "03-Jan-24");
+        assert_eq!(fmt(v, "yyyy-mm-dd hh:mm:ss"), "2024-01-03 14:05:09");
+        assert_eq!(fmt(v, "dddd, mmmm d"), "Wednesday, January 3");
+        assert_eq!(fmt(v, "h:mm AM/PM"), "2:05 PM");
+        assert_eq!(fmt(v, "[$-409]m/d/yy h:mm a/p"), "1/3/24 2:05 p");
+        assert_eq!(fmt(60., "yyyy-mm-dd"), "1900-02-29");
+        assert_eq!(fmt(61., "yyyy-mm-dd"), "1900-03-01");
+        assert_eq!(fmt(1.5 / 86400., "ss.00"), "01.50");
+        let code = crate::formats::builtin_format_code(47).unwrap();
+        assert_eq!(fmt(v, code), "05:09.0");
+    }
+
+    #[test]
+    fn elapsed() {
+        assert_eq!(fmt(1.5, "[h]:mm:ss"), "36:00:00");
+        assert_eq!(fmt(0.5 / 24., "[mm]:ss"), "30:00");
+        assert_eq!(fmt(1., "[ss]"), "86400");
+    }
+}
@@ -0,0 +1,213 @@
+use quick_xml::events::Event;
+use quick_xml::name::QName;
+
+use super::{get_datatype, OdsError, OdsReader};
+use crate::{Cell, DataType, Dimensions};
+
+/// A cells reader for a single ods table
+///
+/// Rows are parsed one at a time, repeated rows and columns are expanded on the fly.
+pub struct OdsCellReader<'a> {
+    reader: OdsReader<'a>,
+    buf: Vec<u8>,
+    row_buf: Vec<u8>,
+    cell_buf: Vec<u8>,
+    /// Non empty cells of the current row, as (column, value, formula)
+    row: Vec<(u32, DataType, String)>,
+    /// Absolute index of the current row
+    row_index: u32,
+    /// Number of times the current row is repeated
+    row_repeats: u32,
+    /// Current repetition of the current row
+    repeat: u32,
+    /// Next cell to yield in `row`
+    pos: usize,
+    /// Absolute index of the next row
+    next_row_index: u32,
+    merge_cells: Vec<Dimensions>,
+    done: bool,
+}
+
+impl<'a> OdsCellReader<'a> {
+    /// Creates a new reader, `reader` must be positioned just after a `table:table` start event
+    pub(crate) fn new(reader: OdsReader<'a>) -> Self {
+        OdsCellReader {
+            reader,
+            buf: Vec::with_capacity(1024),
+            row_buf: Vec::with_capacity(1024),
+            cell_buf: Vec::with_capacity(1024),
+            row: Vec::new(),
+            row_index: 0,
+            row_repeats: 0,
+            repeat: 0,
+            pos: 0,
+            next_row_index: 0,
+            merge_cells: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Reads the next non empty cell value
+    pub fn next_cell(&mut self) -> Result<Option<Cell<DataType>>, OdsError> {
+        while let Some(idx) = self.next_index()? {
+            let (col, value, _) = &self.row[idx];
+            if !value.is_empty() {
+                let pos = (self.row_index + self.repeat, *col);
+                return Ok(Some(Cell::new(pos, value.clone())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next non empty cell formula
+    pub fn next_formula(&mut self) -> Result<Option<Cell<String>>, OdsError> {
+        while let Some(idx) = self.next_index()? {
+            let (col, _, formula) = &self.row[idx];
+            if !formula.is_empty() {
+                let pos = (self.row_index + self.repeat, *col);
+                return Ok(Some(Cell::new(pos, formula.clone())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads until the end of the table, returning all its merged cells regions
+    pub(crate) fn read_merge_cells(mut self) -> Result<Vec<Dimensions>, OdsError> {
+        while self.read_row()? {}
+        Ok(self.merge_cells)
+    }
+
+    /// Index of the next cell in the current row, reading next rows as needed
+    fn next_index(&mut self) -> Result<Option<usize>, OdsError> {
+        loop {
+            if self.pos < self.row.len() {
+                self.pos += 1;
+                return Ok(Some(self.pos - 1));
+            }
+            if self.repeat + 1 < self.row_repeats && !self.row.is_empty() {
+                self.repeat += 1;
+                self.pos = 0;
+                continue;
+            }
+            if !self.read_row()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Reads the next `table:table-row`, returns false at the end of the table
+    fn read_row(&mut self) -> Result<bool, OdsError> {
+        if self.done {
+            return Ok(false);
+        }
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    let row_repeats = match e.try_get_attribute(b"table:number-rows-repeated")? {
+                        Some(c) => c
+                            .decode_and_unescape_value(&self.reader)
+                            .map_err(OdsError::Xml)?
+                            .parse()
+                            .map_err(OdsError::ParseInt)?,
+                        None => 1,
+                    };
+                    self.row_index = self.next_row_index;
+                    self.row_repeats = row_repeats;
+                    self.next_row_index += row_repeats;
+                    self.repeat = 0;
+                    self.pos = 0;
+                    self.row.clear();
+                    read_row(
+                        &mut self.reader,
+                        &mut self.row_buf,
+                        &mut self.cell_buf,
+                        &mut self.row,
+                        (self.row_index, row_repeats),
+                        &mut self.merge_cells,
+                    )?;
+                    return Ok(true);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => {
+                    self.done = true;
+                    self.row.clear();
+                    return Ok(false);
+                }
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                Ok(_) => (),
+            }
+        }
+    }
+}
+
+/// Reads all non empty cells of a `table:table-row`
+///
+/// `rows` is the absolute index of the row and its number of repeats
+fn read_row(
+    reader: &mut OdsReader<'_>,
+    row_buf: &mut Vec<u8>,
+    cell_buf: &mut Vec<u8>,
+    cells: &mut Vec<(u32, DataType, String)>,
+    (row, row_repeats): (u32, u32),
+    merge_cells: &mut Vec<Dimensions>,
+) -> Result<(), OdsError> {
+    let mut col = 0;
+    loop {
+        row_buf.clear();
+        match reader.read_event_into(row_buf) {
+            Ok(Event::Start(ref e))
+                if e.name() == QName(b"table:table-cell")
+                    || e.name() == QName(b"table:covered-table-cell") =>
+            {
+                let mut repeats = 1;
+                let mut spans = (1, 1);
+                for a in e.attributes() {
+                    let a = a.map_err(OdsError::XmlAttr)?;
+                    let value = || -> Result<u32, OdsError> {
+                        reader
+                            .decoder()
+                            .decode(&a.value)?
+                            .parse()
+                            .map_err(OdsError::ParseInt)
+                    };
+                    match a.key {
+                        QName(b"table:number-columns-repeated") => repeats = value()?,
+                        QName(b"table:number-rows-spanned") => spans.0 = value()?,
+                        QName(b"table:number-columns-spanned") => spans.1 = value()?,
+                        _ => (),
+                    }
+                }
+                if spans.0 > 1 || spans.1 > 1 {
+                    for r in row..row + row_repeats {
+                        merge_cells.push(Dimensions {
+                            start: (r, col),
+                            end: (r + spans.0 - 1, col + spans.1 - 1),
+                        });
+                    }
+                }
+
+                let (value, formula, is_closed) = get_datatype(reader, e.attributes(), cell_buf)?;
+                if !value.is_empty() || !formula.is_empty() {
+                    for c in col..col + repeats {
+                        cells.push((c, value.clone(), formula.clone()));
+                    }
+                }
+                col += repeats;
+
+                if !is_closed {
+                    reader.read_to_end_into(e.name(), cell_buf)?;
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:table-row") => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            Ok(e) => {
+                return Err(OdsError::Mismatch {
+                    expected: "table-cell",
+                    found: format!("{:?}", e),
+                });
+            }
+        }
+    }
+    Ok(())
+}
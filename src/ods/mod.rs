@@ -0,0 +1,1925 @@
+//! A module to parse Open Document Spreadsheets
+//!
+//! # Reference
+//! OASIS Open Document Format for Office Application 1.2 (ODF 1.2)
+//! http://docs.oasis-open.org/office/v1.2/OpenDocument-v1.2.pdf
+
+mod cells_reader;
+
+pub use cells_reader::OdsCellReader;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Seek};
+use std::marker::PhantomData;
+
+use log::warn;
+use quick_xml::events::attributes::Attributes;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use quick_xml::Reader as XmlReader;
+use zip::read::ZipArchive;
+use zip::result::ZipError;
+
+use crate::formula::{self, Expr};
+use crate::style::{BorderSide, Color, Font, Style, TextRun};
+use crate::vba::VbaProject;
+use crate::{
+    Cell, CellType, Chart, Comment, ConditionalFormat, ConditionalFormatType, DataType,
+    DataValidation, Dimensions, DocumentProperties, Hyperlink, LineLayout, Metadata, Range, Reader,
+    Sheet, SheetLayout, SheetType, SheetVisible, StyleId, Styles, Table, TableMetadata,
+    TableReader, Tables, ValidationOperator, ValidationType,
+};
+
+const MIMETYPE: &[u8] = b"application/vnd.oasis.opendocument.spreadsheet";
+
+type OdsReader<'a> = XmlReader<&'a [u8]>;
+
+/// An enum for ods specific errors
+#[derive(Debug)]
+pub enum OdsError {
+    /// Io error
+    Io(std::io::Error),
+    /// Zip error
+    Zip(zip::result::ZipError),
+    /// Xml error
+    Xml(quick_xml::Error),
+    /// Xml attribute error
+    XmlAttr(quick_xml::events::attributes::AttrError),
+    /// Error while parsing string
+    Parse(std::string::ParseError),
+    /// Error while parsing integer
+    ParseInt(std::num::ParseIntError),
+    /// Error while parsing float
+    ParseFloat(std::num::ParseFloatError),
+    /// Error while parsing bool
+    ParseBool(std::str::ParseBoolError),
+
+    /// Invalid MIME
+    InvalidMime(Vec<u8>),
+    /// File not found
+    FileNotFound(&'static str),
+    /// Unexpected end of file
+    Eof(&'static str),
+    /// Unexpected error
+    Mismatch {
+        /// Expected
+        expected: &'static str,
+        /// Found
+        found: String,
+    },
+    /// Formula error
+    Formula(crate::formula::FormulaError),
+    /// Worksheet not found
+    WorksheetNotFound(String),
+    /// Table not found
+    TableNotFound(String),
+}
+
+from_err!(std::io::Error, OdsError, Io);
+from_err!(zip::result::ZipError, OdsError, Zip);
+from_err!(quick_xml::Error, OdsError, Xml);
+from_err!(std::string::ParseError, OdsError, Parse);
+from_err!(std::num::ParseFloatError, OdsError, ParseFloat);
+from_err!(crate::formula::FormulaError, OdsError, Formula);
+
+impl std::fmt::Display for OdsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OdsError::Io(e) => write!(f, "I/O error: {e}"),
+            OdsError::Zip(e) => write!(f, "Zip error: {e:?}"),
+            OdsError::Xml(e) => write!(f, "Xml error: {e}"),
+            OdsError::XmlAttr(e) => write!(f, "Xml attribute error: {e}"),
+            OdsError::Parse(e) => write!(f, "Parse string error: {e}"),
+            OdsError::ParseInt(e) => write!(f, "Parse integer error: {e}"),
+            OdsError::ParseFloat(e) => write!(f, "Parse float error: {e}"),
+            OdsError::ParseBool(e) => write!(f, "Parse bool error: {e}"),
+            OdsError::InvalidMime(mime) => write!(f, "Invalid MIME type: {mime:?}"),
+            OdsError::FileNotFound(file) => write!(f, "'{file}' file not found in archive"),
+            OdsError::Eof(node) => write!(f, "Expecting '{node}' node, found end of xml file"),
+            OdsError::Mismatch { expected, found } => {
+                write!(f, "Expecting '{expected}', found '{found}'")
+            }
+            OdsError::Formula(e) => write!(f, "Formula error: {e}"),
+            OdsError::WorksheetNotFound(name) => write!(f, "Worksheet '{name}' not found"),
+            OdsError::TableNotFound(name) => write!(f, "Table '{name}' not found"),
+        }
+    }
+}
+
+impl std::error::Error for OdsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OdsError::Io(e) => Some(e),
+            OdsError::Zip(e) => Some(e),
+            OdsError::Xml(e) => Some(e),
+            OdsError::Parse(e) => Some(e),
+            OdsError::ParseInt(e) => Some(e),
+            OdsError::ParseFloat(e) => Some(e),
+            OdsError::Formula(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An OpenDocument Spreadsheet document parser
+///
+/// # Reference
+/// OASIS Open Document Format for Office Application 1.2 (ODF 1.2)
+/// http://docs.oasis-open.org/office/v1.2/OpenDocument-v1.2.pdf
+pub struct Ods<RS> {
+    /// content.xml, inflated once as deflated data cannot be seeked
+    content: Vec<u8>,
+    /// Offsets of each table content within content.xml
+    sheets: BTreeMap<String, usize>,
+    metadata: Metadata,
+    /// Named database ranges, their columns are only read with the tables
+    database_ranges: Vec<TableMetadata>,
+    /// Tables: Name, Sheet, Columns, Data dimensions
+    tables: Tables,
+    marker: PhantomData<RS>,
+    #[cfg(feature = "picture")]
+    pictures: Option<Vec<(String, Vec<u8>)>>,
+}
+
+impl<RS> Reader<RS> for Ods<RS>
+where
+    RS: Read + Seek,
+{
+    type Error = OdsError;
+
+    fn new(reader: RS) -> Result<Self, OdsError> {
+        let mut zip = ZipArchive::new(reader)?;
+
+        // check mimetype
+        match zip.by_name("mimetype") {
+            Ok(mut f) => {
+                let mut buf = [0u8; 46];
+                f.read_exact(&mut buf)?;
+                if &buf[..] != MIMETYPE {
+                    return Err(OdsError::InvalidMime(buf.to_vec()));
+                }
+            }
+            Err(ZipError::FileNotFound) => return Err(OdsError::FileNotFound("mimetype")),
+            Err(e) => return Err(OdsError::Zip(e)),
+        }
+
+        #[cfg(feature = "picture")]
+        let pictures = read_pictures(&mut zip)?;
+
+        let content = match zip.by_name("content.xml") {
+            Ok(mut f) => {
+                let mut content = Vec::with_capacity(f.size() as usize);
+                f.read_to_end(&mut content)?;
+                content
+            }
+            Err(ZipError::FileNotFound) => return Err(OdsError::FileNotFound("content.xml")),
+            Err(e) => return Err(OdsError::Zip(e)),
+        };
+        let Content {
+            sheets,
+            sheets_metadata,
+            defined_names,
+            database_ranges,
+        } = parse_content(&content)?;
+        let metadata = Metadata {
+            sheets: sheets_metadata,
+            names: defined_names,
+            properties: parse_meta(&mut zip)?,
+        };
+
+        Ok(Ods {
+            content,
+            metadata,
+            sheets,
+            database_ranges,
+            tables: None,
+            marker: PhantomData,
+            #[cfg(feature = "picture")]
+            pictures,
+        })
+    }
+
+    /// Gets `VbaProject`
+    fn vba_project(&mut self) -> Option<Result<Cow<'_, VbaProject>, OdsError>> {
+        None
+    }
+
+    /// Read sheets from workbook.xml and get their corresponding path from relationships
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Read worksheet data in corresponding worksheet path
+    fn worksheet_range(&mut self, name: &str) -> Result<Range<DataType>, OdsError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cells_reader.next_cell()? {
+            cells.push(cell);
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheets(&mut self) -> Vec<(String, Range<DataType>)> {
+        let names = self.sheets.keys().cloned().collect::<Vec<_>>();
+        names
+            .into_iter()
+            .filter_map(|name| match self.worksheet_range(&name) {
+                Ok(ws) => Some((name, ws)),
+                Err(e) => {
+                    warn!("cannot read worksheet '{}': {}", name, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Read worksheet data in corresponding worksheet path
+    fn worksheet_formula(&mut self, name: &str) -> Result<Range<String>, OdsError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cells_reader.next_formula()? {
+            cells.push(cell);
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_ast(&mut self, name: &str) -> Result<Range<Expr>, OdsError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cells_reader.next_formula()? {
+            let expr = formula::parse_open_formula(&cell.val);
+            cells.push(Cell::new(
+                cell.pos,
+                formula::or_unrecognized(expr, cell.pos),
+            ));
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    /// Read merged cell regions in corresponding worksheet path
+    fn merged_regions(&mut self, name: &str) -> Result<Vec<Dimensions>, OdsError> {
+        self.worksheet_cells_reader(name)?.read_merge_cells()
+    }
+
+    /// Read automatic cell styles, the default style being `StyleId(0)`
+    fn styles(&mut self) -> Result<Styles, OdsError> {
+        let (styles, _) = parse_cell_styles(&self.content)?;
+        Ok(Styles::new(styles))
+    }
+
+    /// Read cell style names in corresponding worksheet
+    ///
+    /// Cells with the style of their column are skipped, repeated cells are only expanded
+    /// within the cells with a value.
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, OdsError> {
+        let (_, ids) = parse_cell_styles(&self.content)?;
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut cells = RepeatedCells::default();
+        let mut columns = Vec::new();
+        let (mut row, mut row_repeats, mut col) = (0u32, 0, 0u32);
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-column") => {
+                    let col_repeats = repeats(&reader, e, b"table:number-columns-repeated")?;
+                    let style = match e.try_get_attribute(b"table:default-cell-style-name")? {
+                        Some(a) => Some(a.decode_and_unescape_value(&reader)?.into_owned()),
+                        None => None,
+                    };
+                    columns.push((col_repeats, style));
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    row = row.saturating_add(row_repeats);
+                    row_repeats = repeats(&reader, e, b"table:number-rows-repeated")?;
+                    col = 0;
+                }
+                Ok(Event::Start(ref e))
+                    if e.name() == QName(b"table:table-cell")
+                        || e.name() == QName(b"table:covered-table-cell") =>
+                {
+                    let col_repeats = repeats(&reader, e, b"table:number-columns-repeated")?;
+                    cells.read_value(e, (row, col), (row_repeats, col_repeats))?;
+                    if let Some(a) = e.try_get_attribute(b"table:style-name")? {
+                        let style = a.decode_and_unescape_value(&reader)?;
+                        if column_style(&columns, col) != Some(&*style) {
+                            if let Some(&id) = ids.get(&*style) {
+                                cells.push((row, col), (row_repeats, col_repeats), id);
+                            }
+                        }
+                    }
+                    col = col.saturating_add(col_repeats);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(cells.into_range())
+    }
+
+    /// Read the paragraphs of string cells in corresponding worksheet
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<Vec<TextRun>>, OdsError> {
+        let fonts = parse_text_styles(&self.content)?;
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut cell_buf = Vec::with_capacity(1024);
+        let mut cells = RepeatedCells::default();
+        let (mut row, mut row_repeats, mut col) = (0u32, 0, 0u32);
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    row = row.saturating_add(row_repeats);
+                    row_repeats = repeats(&reader, e, b"table:number-rows-repeated")?;
+                    col = 0;
+                }
+                Ok(Event::Start(ref e))
+                    if e.name() == QName(b"table:table-cell")
+                        || e.name() == QName(b"table:covered-table-cell") =>
+                {
+                    let col_repeats = repeats(&reader, e, b"table:number-columns-repeated")?;
+                    cells.read_value(e, (row, col), (row_repeats, col_repeats))?;
+                    let is_string = e
+                        .try_get_attribute(b"office:value-type")?
+                        .map_or(false, |a| &*a.value == b"string");
+                    let mut runs = TextRuns::new(&fonts);
+                    loop {
+                        cell_buf.clear();
+                        match reader.read_event_into(&mut cell_buf) {
+                            Ok(Event::Start(ref e)) if e.name() == QName(b"office:annotation") => {
+                                reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                            }
+                            Ok(Event::End(ref end)) if end.name() == e.name() => break,
+                            Ok(Event::Eof) => return Err(OdsError::Eof("table:table-cell")),
+                            Ok(ref event) => runs.read_event(&reader, event)?,
+                            Err(e) => return Err(OdsError::Xml(e)),
+                        }
+                    }
+                    if is_string && !runs.runs.is_empty() {
+                        cells.push((row, col), (row_repeats, col_repeats), runs.runs);
+                    }
+                    col = col.saturating_add(col_repeats);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(cells.into_range())
+    }
+
+    /// Read the visibility, grouping and size of rows and columns in corresponding worksheet
+    fn worksheet_layout(&mut self, name: &str) -> Result<SheetLayout, OdsError> {
+        let sizes = parse_line_sizes(&self.content)?;
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut layout = SheetLayout::default();
+        let (mut row, mut col) = (0, 0);
+        let (mut row_groups, mut col_groups) = (0u8, 0u8);
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row-group") => {
+                    row_groups += 1
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table-row-group") => {
+                    row_groups = row_groups.saturating_sub(1)
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-column-group") => {
+                    col_groups += 1
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table-column-group") => {
+                    col_groups = col_groups.saturating_sub(1)
+                }
+                Ok(Event::Start(ref e))
+                    if e.name() == QName(b"table:table-row")
+                        || e.name() == QName(b"table:table-column") =>
+                {
+                    let is_row = e.name() == QName(b"table:table-row");
+                    let count = if is_row {
+                        repeats(&reader, e, b"table:number-rows-repeated")?
+                    } else {
+                        repeats(&reader, e, b"table:number-columns-repeated")?
+                    };
+                    let first = if is_row { &mut row } else { &mut col };
+                    let mut lines = LineLayout {
+                        first: *first,
+                        last: *first + count.max(1) - 1,
+                        outline_level: if is_row { row_groups } else { col_groups },
+                        ..Default::default()
+                    };
+                    *first += count;
+                    if let Some(a) = e.try_get_attribute(b"table:visibility")? {
+                        lines.hidden = &*a.value == b"collapse" || &*a.value == b"filter";
+                    }
+                    if let Some(a) = e.try_get_attribute(b"table:style-name")? {
+                        lines.size = sizes.get(&*a.decode_and_unescape_value(&reader)?).copied();
+                    }
+                    if lines.hidden || lines.outline_level > 0 || lines.size.is_some() {
+                        if is_row {
+                            layout.push_rows(lines);
+                        } else {
+                            layout.push_columns(lines);
+                        }
+                    }
+                    if is_row {
+                        reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                    }
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Read `office:annotation` elements of the cells of the corresponding worksheet
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, OdsError> {
+        let fonts = parse_text_styles(&self.content)?;
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut comments = Vec::new();
+        let (mut row, mut row_repeats, mut col, mut cell_col) = (0, 0, 0, 0);
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    row += row_repeats;
+                    row_repeats = repeats(&reader, e, b"table:number-rows-repeated")?;
+                    col = 0;
+                }
+                Ok(Event::Start(ref e))
+                    if e.name() == QName(b"table:table-cell")
+                        || e.name() == QName(b"table:covered-table-cell") =>
+                {
+                    cell_col = col;
+                    col += repeats(&reader, e, b"table:number-columns-repeated")?;
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"office:annotation") => {
+                    comments.push(read_annotation(&mut reader, (row, cell_col), &fonts)?);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(comments)
+    }
+
+    /// Read the `text:a` elements of the cells of the corresponding worksheet
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, OdsError> {
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut links = Vec::new();
+        let (mut row, mut row_repeats, mut col, mut cell_col) = (0, 0, 0, 0);
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    row += row_repeats;
+                    row_repeats = repeats(&reader, e, b"table:number-rows-repeated")?;
+                    col = 0;
+                }
+                Ok(Event::Start(ref e))
+                    if e.name() == QName(b"table:table-cell")
+                        || e.name() == QName(b"table:covered-table-cell") =>
+                {
+                    cell_col = col;
+                    col += repeats(&reader, e, b"table:number-columns-repeated")?;
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"office:annotation") => {
+                    reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"text:a") => {
+                    let mut link = Hyperlink {
+                        range: Dimensions::new(
+                            (row, cell_col),
+                            (row + row_repeats.max(1) - 1, col.max(cell_col + 1) - 1),
+                        ),
+                        ..Default::default()
+                    };
+                    if let Some(a) = e.try_get_attribute(b"xlink:href")? {
+                        let href = a.decode_and_unescape_value(&reader)?;
+                        match href.strip_prefix('#') {
+                            Some(location) => link.location = Some(location.to_string()),
+                            None => link.url = Some(href.into_owned()),
+                        }
+                    }
+                    if let Some(a) = e.try_get_attribute(b"office:title")? {
+                        link.tooltip = Some(a.decode_and_unescape_value(&reader)?.into_owned());
+                    }
+                    links.push(link);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(links)
+    }
+
+    /// Read the `table:content-validation` of the cells of the corresponding worksheet
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, OdsError> {
+        let mut validations = parse_content_validations(&self.content)?;
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let (mut row, mut row_repeats, mut col) = (0, 0, 0);
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    row += row_repeats;
+                    row_repeats = repeats(&reader, e, b"table:number-rows-repeated")?;
+                    col = 0;
+                }
+                Ok(Event::Start(ref e))
+                    if e.name() == QName(b"table:table-cell")
+                        || e.name() == QName(b"table:covered-table-cell") =>
+                {
+                    let cell_col = col;
+                    col += repeats(&reader, e, b"table:number-columns-repeated")?;
+                    if let Some(a) = e.try_get_attribute(b"table:content-validation-name")? {
+                        let name = a.decode_and_unescape_value(&reader)?;
+                        if let Some((_, validation)) =
+                            validations.iter_mut().find(|(n, _)| *n == name)
+                        {
+                            push_cells(
+                                &mut validation.ranges,
+                                Dimensions::new(
+                                    (row, cell_col),
+                                    (row + row_repeats.max(1) - 1, col.max(cell_col + 1) - 1),
+                                ),
+                            );
+                        }
+                    }
+                    reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(validations
+            .into_iter()
+            .map(|(_, v)| v)
+            .filter(|v| !v.ranges.is_empty())
+            .collect())
+    }
+
+    /// Read the `calcext:conditional-formats` of the corresponding worksheet
+    fn worksheet_conditional_formats(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<ConditionalFormat>, OdsError> {
+        let mut reader = self.table_reader(name)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut formats = Vec::new();
+        let mut ranges = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"calcext:conditional-format") => {
+                    ranges = match e.try_get_attribute(b"calcext:target-range-address")? {
+                        Some(a) => parse_range_address(&a.decode_and_unescape_value(&reader)?),
+                        None => Vec::new(),
+                    };
+                }
+                Ok(Event::Start(ref e))
+                    if matches!(
+                        e.name().as_ref(),
+                        b"calcext:condition"
+                            | b"calcext:date-is"
+                            | b"calcext:color-scale"
+                            | b"calcext:data-bar"
+                            | b"calcext:icon-set"
+                    ) =>
+                {
+                    let mut format = ConditionalFormat {
+                        ranges: ranges.clone(),
+                        priority: formats.len() as u32 + 1,
+                        ..Default::default()
+                    };
+                    match e.name().as_ref() {
+                        b"calcext:color-scale" => {
+                            format.rule_type = ConditionalFormatType::ColorScale
+                        }
+                        b"calcext:data-bar" => format.rule_type = ConditionalFormatType::DataBar,
+                        b"calcext:icon-set" => format.rule_type = ConditionalFormatType::IconSet,
+                        b"calcext:date-is" => {
+                            format.rule_type = ConditionalFormatType::Other("date-is".to_string())
+                        }
+                        _ => {
+                            if let Some(a) = e.try_get_attribute(b"calcext:value")? {
+                                read_format_condition(
+                                    &mut format,
+                                    &a.decode_and_unescape_value(&reader)?,
+                                );
+                            }
+                        }
+                    }
+                    let style = match e.try_get_attribute(b"calcext:apply-style-name")? {
+                        Some(a) => Some(a),
+                        None => e.try_get_attribute(b"calcext:style")?,
+                    };
+                    if let Some(a) = style {
+                        format.style_name =
+                            Some(a.decode_and_unescape_value(&reader)?.into_owned());
+                    }
+                    formats.push(format);
+                    reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => break,
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table")),
+                Err(e) => return Err(OdsError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(formats)
+    }
+
+    /// Charts are embedded objects, which are not read
+    fn worksheet_charts(&mut self, name: &str) -> Result<Vec<Chart>, OdsError> {
+        if !self.sheets.contains_key(name) {
+            return Err(OdsError::WorksheetNotFound(name.into()));
+        }
+        Ok(Vec::new())
+    }
+
+    #[cfg(feature = "picture")]
+    fn pictures(&self) -> Option<Vec<(String, Vec<u8>)>> {
+        self.pictures.to_owned()
+    }
+}
+
+impl<RS: Read + Seek> TableReader<RS> for Ods<RS> {
+    /// Load the named database ranges, reading their header row as column names
+    ///
+    /// Columns of ranges without header row are unnamed.
+    fn load_tables(&mut self) -> Result<(), OdsError> {
+        if self.tables.is_some() {
+            return Ok(());
+        }
+        let mut ranges: HashMap<String, Range<DataType>> = HashMap::new();
+        let mut tables = self.database_ranges.clone();
+        for table in tables.iter_mut().filter(|t| t.header_rows != 0) {
+            if !ranges.contains_key(&table.sheet_name) {
+                let range = self.worksheet_range(&table.sheet_name)?;
+                ranges.insert(table.sheet_name.clone(), range);
+            }
+            let range = &ranges[&table.sheet_name];
+            let dims = table.dimensions;
+            table.columns = (dims.start.1..=dims.end.1)
+                .map(|col| {
+                    range
+                        .get_value((dims.start.0, col))
+                        .map_or_else(String::new, |v| v.to_string())
+                })
+                .collect();
+        }
+        self.tables = Some(tables);
+        Ok(())
+    }
+
+    fn table_names(&self) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    fn table_by_name(&mut self, table_name: &str) -> Result<Table<DataType>, OdsError> {
+        let table = self
+            .tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .find(|t| t.name == table_name)
+            .cloned()
+            .ok_or_else(|| OdsError::TableNotFound(table_name.into()))?;
+        let range = self.worksheet_range(&table.sheet_name)?;
+        Ok(table.table(&range))
+    }
+
+    /// OpenFormula has no structured references, formulas are returned unchanged
+    fn worksheet_formula_resolved(&mut self, name: &str) -> Result<Range<String>, OdsError> {
+        self.worksheet_formula(name)
+    }
+}
+
+impl<RS: Read + Seek> Ods<RS> {
+    /// Get a cells reader for a given worksheet
+    ///
+    /// Only the requested table is parsed, one row at a time.
+    pub fn worksheet_cells_reader<'a>(
+        &'a mut self,
+        name: &str,
+    ) -> Result<OdsCellReader<'a>, OdsError> {
+        Ok(OdsCellReader::new(self.table_reader(name)?))
+    }
+
+    /// Get a xml reader positioned just after the start of a given table
+    fn table_reader(&self, name: &str) -> Result<OdsReader<'_>, OdsError> {
+        let offset = *self
+            .sheets
+            .get(name)
+            .ok_or_else(|| OdsError::WorksheetNotFound(name.into()))?;
+        Ok(xml_reader(&self.content[offset..]))
+    }
+}
+
+fn xml_reader(content: &[u8]) -> OdsReader<'_> {
+    let mut r = XmlReader::from_reader(content);
+    r.check_end_names(false)
+        .trim_text(false)
+        .check_comments(false)
+        .expand_empty_elements(true);
+    r
+}
+
+struct Content {
+    sheets: BTreeMap<String, usize>,
+    sheets_metadata: Vec<Sheet>,
+    defined_names: Vec<(String, String)>,
+    database_ranges: Vec<TableMetadata>,
+}
+
+/// Parses content.xml, only indexing the position of each table
+///
+/// Tables are skipped without being parsed, see `skip_table`.
+fn parse_content(content: &[u8]) -> Result<Content, OdsError> {
+    let mut reader = xml_reader(content);
+    let mut buf = Vec::with_capacity(1024);
+    // position of `reader` within content.xml
+    let mut base = 0;
+    let mut sheets = BTreeMap::new();
+    let mut defined_names = Vec::new();
+    let mut sheets_metadata = Vec::new();
+    let mut database_ranges = Vec::new();
+    let mut styles = HashMap::new();
+    let mut style_name: Option<String> = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:style") => {
+                style_name = e
+                    .try_get_attribute(b"style:name")?
+                    .map(|a| a.decode_and_unescape_value(&reader))
+                    .transpose()
+                    .map_err(OdsError::Xml)?
+                    .map(|x| x.to_string())
+            }
+            Ok(Event::Start(ref e))
+                if style_name.clone().is_some() && e.name() == QName(b"style:table-properties") =>
+            {
+                let visible = match e.try_get_attribute(b"table:display")? {
+                    Some(a) => match a
+                        .decode_and_unescape_value(&reader)
+                        .map_err(OdsError::Xml)?
+                        .parse()
+                        .map_err(OdsError::ParseBool)?
+                    {
+                        true => SheetVisible::Visible,
+                        false => SheetVisible::Hidden,
+                    },
+                    None => SheetVisible::Visible,
+                };
+                styles.insert(style_name.clone(), visible);
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table") => {
+                let visible = styles
+                    .get(
+                        &e.try_get_attribute(b"table:style-name")?
+                            .map(|a| a.decode_and_unescape_value(&reader))
+                            .transpose()
+                            .map_err(OdsError::Xml)?
+                            .map(|x| x.to_string()),
+                    )
+                    .map(|v| v.to_owned())
+                    .unwrap_or(SheetVisible::Visible);
+                if let Some(ref a) = e
+                    .attributes()
+                    .filter_map(|a| a.ok())
+                    .find(|a| a.key == QName(b"table:name"))
+                {
+                    let name = a
+                        .decode_and_unescape_value(&reader)
+                        .map_err(OdsError::Xml)?
+                        .to_string();
+                    let offset = base + reader.buffer_position();
+                    base = skip_table(content, offset).ok_or(OdsError::Eof("table:table"))?;
+                    reader = xml_reader(&content[base..]);
+                    sheets_metadata.push(Sheet {
+                        name: name.clone(),
+                        typ: SheetType::WorkSheet,
+                        visible,
+                    });
+                    sheets.insert(name, offset);
+                }
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:named-expressions") => {
+                defined_names = read_named_expressions(&mut reader)?;
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:database-range") => {
+                let mut name = String::new();
+                let mut address = String::new();
+                let mut contains_header = true;
+                for a in e.attributes() {
+                    let a = a.map_err(OdsError::XmlAttr)?;
+                    let v = a.decode_and_unescape_value(&reader)?;
+                    match a.key {
+                        QName(b"table:name") => name = v.into_owned(),
+                        QName(b"table:target-range-address") => address = v.into_owned(),
+                        QName(b"table:contains-header") => contains_header = v != "false",
+                        _ => (),
+                    }
+                }
+                let sheet = range_sheet_name(&address);
+                // unnamed ranges are created for sorting or filtering a sheet
+                if let (Some(sheet), Some(&dimensions), false) = (
+                    sheet,
+                    parse_range_address(&address).first(),
+                    name.starts_with("__Anonymous_Sheet_DB__"),
+                ) {
+                    database_ranges.push(TableMetadata {
+                        id: database_ranges.len() as u32,
+                        name,
+                        sheet_name: sheet,
+                        columns: Vec::new(),
+                        dimensions,
+                        header_rows: contains_header as u32,
+                        totals_rows: 0,
+                        insert_row: false,
+                    });
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(Content {
+        sheets,
+        sheets_metadata,
+        defined_names,
+        database_ranges,
+    })
+}
+
+/// Position just after the end of the table whose content starts at `start`
+///
+/// Only the markup delimiters are scanned, none of the rows and cells of the table is parsed.
+fn skip_table(content: &[u8], start: usize) -> Option<usize> {
+    const START: &[u8] = b"<table:table";
+    const END: &[u8] = b"</table:table";
+    let mut depth = 1;
+    let mut pos = start;
+    loop {
+        pos += content.get(pos..)?.iter().position(|&b| b == b'<')?;
+        let tag = &content[pos..];
+        if tag.starts_with(b"<!--") {
+            pos += find(tag, b"-->")? + 3;
+            continue;
+        }
+        if tag.starts_with(b"<![CDATA[") {
+            pos += find(tag, b"]]>")? + 3;
+            continue;
+        }
+        let end = tag_end(tag)?;
+        let is_name_end =
+            |len: usize| tag[len] == b'>' || tag[len] == b'/' || tag[len].is_ascii_whitespace();
+        if tag.starts_with(END) && is_name_end(END.len()) {
+            depth -= 1;
+        } else if tag.starts_with(START) && is_name_end(START.len()) && tag[end - 1] != b'/' {
+            depth += 1;
+        }
+        pos += end + 1;
+        if depth == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+/// Position of the `>` closing the tag starting `tag`, attribute values may contain `>`
+fn tag_end(tag: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    tag.iter().position(|&b| match quote {
+        Some(q) => {
+            if b == q {
+                quote = None;
+            }
+            false
+        }
+        None if b == b'"' || b == b'\'' => {
+            quote = Some(b);
+            false
+        }
+        None => b == b'>',
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Parses the `table:content-validations` of content.xml, by validation name
+///
+/// The validated cells are not known yet, they refer to the validations by name.
+fn parse_content_validations(content: &[u8]) -> Result<Vec<(String, DataValidation)>, OdsError> {
+    let mut reader = xml_reader(content);
+    let mut buf = Vec::with_capacity(1024);
+    let mut validations: Vec<(String, DataValidation)> = Vec::new();
+    let mut message = None;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:content-validation") => {
+                let mut name = String::new();
+                let mut validation = DataValidation::default();
+                for a in e.attributes() {
+                    let a = a.map_err(OdsError::XmlAttr)?;
+                    let v = a.decode_and_unescape_value(&reader)?;
+                    match a.key {
+                        QName(b"table:name") => name = v.into_owned(),
+                        QName(b"table:condition") => read_condition(&mut validation, &v),
+                        QName(b"table:allow-empty-cell") => validation.allow_blank = v == "true",
+                        _ => (),
+                    }
+                }
+                validations.push((name, validation));
+            }
+            Ok(Event::Start(ref e))
+                if e.name() == QName(b"table:help-message")
+                    || e.name() == QName(b"table:error-message") =>
+            {
+                let is_error = e.name() == QName(b"table:error-message");
+                if let Some((_, validation)) = validations.last_mut() {
+                    if let Some(a) = e.try_get_attribute(b"table:title")? {
+                        let title = Some(a.decode_and_unescape_value(&reader)?.into_owned());
+                        if is_error {
+                            validation.error_title = title;
+                        } else {
+                            validation.prompt_title = title;
+                        }
+                    }
+                }
+                message = Some((is_error, String::new()));
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"text:p") => {
+                if let Some((_, ref mut text)) = message {
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+                }
+            }
+            Ok(Event::Text(ref t)) => {
+                if let Some((_, ref mut text)) = message {
+                    text.push_str(&t.unescape()?);
+                }
+            }
+            Ok(Event::End(ref e))
+                if e.name() == QName(b"table:help-message")
+                    || e.name() == QName(b"table:error-message") =>
+            {
+                if let (Some((is_error, text)), Some((_, validation))) =
+                    (message.take(), validations.last_mut())
+                {
+                    let text = Some(text).filter(|t| !t.is_empty());
+                    if is_error {
+                        validation.error_message = text;
+                    } else {
+                        validation.prompt_message = text;
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:content-validations") => break,
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table") => break,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(validations)
+}
+
+/// Reads the type, operator and formulas of a validation from its `table:condition`,
+/// e.g. `of:cell-content-is-whole-number() and cell-content-is-between(1;10)`
+fn read_condition(validation: &mut DataValidation, condition: &str) {
+    let condition = condition
+        .split_once(':')
+        .filter(|(ns, _)| !ns.contains('('))
+        .map_or(condition, |(_, c)| c);
+    let (typ, condition) = match condition.split_once(" and ") {
+        Some((typ, condition)) => (typ.trim(), condition.trim()),
+        None => ("", condition.trim()),
+    };
+    validation.validation_type = match typ {
+        "cell-content-is-whole-number()" => ValidationType::Whole,
+        "cell-content-is-decimal-number()" => ValidationType::Decimal,
+        "cell-content-is-date()" => ValidationType::Date,
+        "cell-content-is-time()" => ValidationType::Time,
+        _ => ValidationType::Any,
+    };
+    let function = |name: &str| {
+        condition
+            .strip_prefix(name)
+            .and_then(|c| c.strip_prefix('('))
+            .and_then(|c| c.strip_suffix(')'))
+    };
+    if let Some(list) = function("cell-content-is-in-list") {
+        validation.validation_type = ValidationType::List;
+        let values = split_arguments(list, ';');
+        validation.formula1 = if values.iter().all(|v| v.starts_with('"')) {
+            let values: Vec<_> = values.iter().map(|v| v.trim_matches('"')).collect();
+            Some(format!("\"{}\"", values.join(",")))
+        } else {
+            Some(list.to_string())
+        };
+    } else if let Some(formula) = function("is-true-formula") {
+        validation.validation_type = ValidationType::Custom;
+        validation.formula1 = Some(formula.to_string());
+    } else {
+        let mut condition = condition;
+        if let Some(c) = condition.strip_prefix("cell-content-text-length") {
+            validation.validation_type = ValidationType::TextLength;
+            condition = c;
+        } else if let Some(c) = condition.strip_prefix("cell-content") {
+            condition = c;
+        }
+        let between = [
+            ("-is-between(", ValidationOperator::Between),
+            ("-is-not-between(", ValidationOperator::NotBetween),
+        ];
+        let comparisons = [
+            ("()<=", ValidationOperator::LessThanOrEqual),
+            ("()>=", ValidationOperator::GreaterThanOrEqual),
+            ("()!=", ValidationOperator::NotEqual),
+            ("()<", ValidationOperator::LessThan),
+            ("()>", ValidationOperator::GreaterThan),
+            ("()=", ValidationOperator::Equal),
+        ];
+        if let Some((args, operator)) = between.iter().find_map(|(prefix, operator)| {
+            let args = condition.strip_prefix(prefix)?.strip_suffix(')')?;
+            Some((args, *operator))
+        }) {
+            let mut args = split_arguments(args, ';').into_iter();
+            validation.operator = operator;
+            validation.formula1 = args.next().map(str::to_string);
+            validation.formula2 = args.next().map(str::to_string);
+        } else if let Some((value, operator)) = comparisons
+            .iter()
+            .find_map(|(prefix, operator)| Some((condition.strip_prefix(prefix)?, *operator)))
+        {
+            validation.operator = operator;
+            validation.formula1 = Some(value.trim().to_string());
+        }
+    }
+}
+
+/// Splits the arguments of a function, ignoring the separators in strings or nested calls
+fn split_arguments(args: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut in_string, mut start) = (0, false, 0);
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            c if c == separator && !in_string && depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(args[start..].trim());
+    parts
+}
+
+/// Adds validated cells, extending the range of the previous row if they are below it
+fn push_cells(ranges: &mut Vec<Dimensions>, cells: Dimensions) {
+    let above = ranges.iter_mut().rev().find(|r| {
+        r.start.1 == cells.start.1 && r.end.1 == cells.end.1 && r.end.0 + 1 == cells.start.0
+    });
+    match above {
+        Some(range) => range.end.0 = cells.end.0,
+        None => ranges.push(cells),
+    }
+}
+
+/// Reads the kind, operator and formulas of a rule from its `calcext:value`,
+/// e.g. `between(1,10)`, `>=5` or `formula-is(A1>B1)`
+fn read_format_condition(format: &mut ConditionalFormat, value: &str) {
+    let comparisons = [
+        ("<=", ValidationOperator::LessThanOrEqual),
+        (">=", ValidationOperator::GreaterThanOrEqual),
+        ("!=", ValidationOperator::NotEqual),
+        ("<", ValidationOperator::LessThan),
+        (">", ValidationOperator::GreaterThan),
+        ("=", ValidationOperator::Equal),
+    ];
+    if let Some((formula, operator)) = comparisons
+        .iter()
+        .find_map(|(prefix, operator)| Some((value.strip_prefix(prefix)?, *operator)))
+    {
+        format.rule_type = ConditionalFormatType::CellIs;
+        format.operator = Some(operator);
+        format.formulas.push(formula.trim().to_string());
+        return;
+    }
+    let (function, args) = match value.split_once('(') {
+        Some((function, args)) => (function, args.strip_suffix(')').unwrap_or(args)),
+        None => (value, ""),
+    };
+    let args = split_arguments(args, ',');
+    format.formulas = args
+        .into_iter()
+        .filter(|a| !a.is_empty())
+        .map(str::to_string)
+        .collect();
+    match function {
+        "between" | "not-between" => {
+            format.rule_type = ConditionalFormatType::CellIs;
+            format.operator = Some(match function {
+                "between" => ValidationOperator::Between,
+                _ => ValidationOperator::NotBetween,
+            });
+        }
+        "formula-is" => format.rule_type = ConditionalFormatType::Expression,
+        _ => format.rule_type = ConditionalFormatType::Other(function.to_string()),
+    }
+}
+
+/// Parses a list of space separated ranges, e.g. `Sheet1.A1:Sheet1.B2 Sheet1.D1`
+fn parse_range_address(address: &str) -> Vec<Dimensions> {
+    // ranges are separated by spaces, which quoted sheet names may contain
+    let mut quoted = false;
+    address
+        .split(|c: char| {
+            if c == '\'' {
+                quoted = !quoted;
+            }
+            c == ' ' && !quoted
+        })
+        .filter(|range| !range.is_empty())
+        .filter_map(|range| {
+            let (start, end) = range.split_once(':').unwrap_or((range, range));
+            Some(Dimensions::new(cell_position(start)?, cell_position(end)?))
+        })
+        .collect()
+}
+
+/// Gets the sheet name of a range address, e.g. `'My sheet'.A1:'My sheet'.C4`
+fn range_sheet_name(address: &str) -> Option<String> {
+    let start = address.split(':').next()?;
+    let (sheet, _) = start.rsplit_once('.')?;
+    let sheet = sheet.trim_start_matches('$');
+    match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        Some(quoted) => Some(quoted.replace("''", "'")),
+        None if sheet.is_empty() => None,
+        None => Some(sheet.to_string()),
+    }
+}
+
+/// Parses a cell address, e.g. `Sheet1.$A$1`, as (row, column)
+fn cell_position(address: &str) -> Option<(u32, u32)> {
+    let cell = address.rsplit('.').next()?.replace('$', "");
+    let digits = cell.find(|c: char| c.is_ascii_digit())?;
+    let (col, row) = cell.split_at(digits);
+    let col = col.chars().try_fold(0u32, |col, c| {
+        c.is_ascii_alphabetic()
+            .then(|| col * 26 + (c.to_ascii_uppercase() as u32 - 'A' as u32 + 1))
+    })?;
+    let row: u32 = row.parse().ok()?;
+    Some((row.checked_sub(1)?, col.checked_sub(1)?))
+}
+
+/// Parses the document properties of meta.xml
+fn parse_meta<RS: Read + Seek>(zip: &mut ZipArchive<RS>) -> Result<DocumentProperties, OdsError> {
+    let mut meta = Vec::new();
+    match zip.by_name("meta.xml") {
+        Ok(mut f) => f.read_to_end(&mut meta)?,
+        Err(ZipError::FileNotFound) => return Ok(DocumentProperties::default()),
+        Err(e) => return Err(OdsError::Zip(e)),
+    };
+    let mut reader = xml_reader(&meta);
+    let mut buf = Vec::with_capacity(1024);
+    let mut properties = DocumentProperties::default();
+    let mut keywords = Vec::new();
+    let mut value = String::new();
+    // name and value type of the current user defined property
+    let mut user_defined = None;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                value.clear();
+                if e.name() == QName(b"meta:user-defined") {
+                    let name = match e.try_get_attribute(b"meta:name")? {
+                        Some(a) => a.decode_and_unescape_value(&reader)?.into_owned(),
+                        None => String::new(),
+                    };
+                    let typ = match e.try_get_attribute(b"meta:value-type")? {
+                        Some(a) => a.decode_and_unescape_value(&reader)?.into_owned(),
+                        None => String::new(),
+                    };
+                    user_defined = Some((name, typ));
+                }
+            }
+            Ok(Event::Text(ref t)) => value.push_str(&t.unescape()?),
+            Ok(Event::End(ref e)) => {
+                let text = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+                match e.name().as_ref() {
+                    b"meta:generator" => properties.application = text,
+                    b"dc:title" => properties.title = text,
+                    b"dc:subject" => properties.subject = text,
+                    b"dc:description" => properties.description = text,
+                    b"meta:keyword" => keywords.extend(text),
+                    b"meta:initial-creator" => properties.author = text,
+                    b"dc:creator" => properties.last_modified_by = text,
+                    b"meta:creation-date" => properties.created = text,
+                    b"dc:date" => properties.modified = text,
+                    b"meta:user-defined" => {
+                        if let Some((name, typ)) = user_defined.take() {
+                            let value = value.trim().to_string();
+                            let value = match typ.as_str() {
+                                "float" => value
+                                    .parse()
+                                    .map_or_else(|_| DataType::String(value), DataType::Float),
+                                "boolean" => DataType::Bool(value == "true"),
+                                "date" => DataType::DateTimeIso(value),
+                                "time" => DataType::DurationIso(value),
+                                _ => DataType::String(value),
+                            };
+                            properties.custom.push((name, value));
+                        }
+                    }
+                    b"office:meta" => break,
+                    _ => (),
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+    if !keywords.is_empty() {
+        properties.keywords = Some(keywords.join(", "));
+    }
+    Ok(properties)
+}
+
+/// Reads a repeat count attribute, defaulting to 1
+fn repeats(reader: &OdsReader<'_>, e: &BytesStart<'_>, key: &[u8]) -> Result<u32, OdsError> {
+    match e.try_get_attribute(key)? {
+        Some(c) => c
+            .decode_and_unescape_value(reader)?
+            .parse()
+            .map_err(OdsError::ParseInt),
+        None => Ok(1),
+    }
+}
+
+/// Cells of a table, repeated over rows and columns
+///
+/// Repeats are kept as is while reading and only expanded up to the last row and column
+/// with a value: trailing formatted rows and columns are often repeated up to the maximum
+/// sheet size.
+struct RepeatedCells<T> {
+    runs: Vec<(Dimensions, T)>,
+    /// Last row and column with a value
+    end: Option<(u32, u32)>,
+}
+
+impl<T> Default for RepeatedCells<T> {
+    fn default() -> Self {
+        RepeatedCells {
+            runs: Vec::new(),
+            end: None,
+        }
+    }
+}
+
+impl<T: CellType> RepeatedCells<T> {
+    /// Extends the used cells if the `table:table-cell` `e` has a value
+    fn read_value(
+        &mut self,
+        e: &BytesStart<'_>,
+        start: (u32, u32),
+        repeats: (u32, u32),
+    ) -> Result<(), OdsError> {
+        if e.try_get_attribute(b"office:value-type")?.is_some() {
+            if let Some(dimensions) = repeated_dimensions(start, repeats) {
+                let (row, col) = self.end.unwrap_or_default();
+                self.end = Some((row.max(dimensions.end.0), col.max(dimensions.end.1)));
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, start: (u32, u32), repeats: (u32, u32), value: T) {
+        if let Some(dimensions) = repeated_dimensions(start, repeats) {
+            self.runs.push((dimensions, value));
+        }
+    }
+
+    fn into_range(self) -> Range<T> {
+        let (last_row, last_col) = match self.end {
+            Some(end) => end,
+            None => return Range::empty(),
+        };
+        let mut cells = Vec::new();
+        for (dimensions, value) in self.runs {
+            for row in dimensions.start.0..=dimensions.end.0.min(last_row) {
+                for col in dimensions.start.1..=dimensions.end.1.min(last_col) {
+                    cells.push(Cell::new((row, col), value.clone()));
+                }
+            }
+        }
+        Range::from_sparse(cells)
+    }
+}
+
+/// The cells `start` repeated `repeats` (rows, columns) times, if any
+fn repeated_dimensions(start: (u32, u32), (rows, cols): (u32, u32)) -> Option<Dimensions> {
+    let end = (
+        start.0.saturating_add(rows.checked_sub(1)?),
+        start.1.saturating_add(cols.checked_sub(1)?),
+    );
+    Some(Dimensions { start, end })
+}
+
+/// The default cell style of column `col`, out of the `table:table-column` repeats and styles
+fn column_style(columns: &[(u32, Option<String>)], col: u32) -> Option<&str> {
+    let mut end = 0u32;
+    for (repeats, style) in columns {
+        end = end.saturating_add(*repeats);
+        if col < end {
+            return style.as_deref();
+        }
+    }
+    None
+}
+
+/// Parses the `table-cell` automatic styles of content.xml
+///
+/// Returns the styles, prefixed by the default style, and the id of each style name
+fn parse_cell_styles(content: &[u8]) -> Result<(Vec<Style>, HashMap<String, StyleId>), OdsError> {
+    let mut reader = xml_reader(content);
+    let mut buf = Vec::with_capacity(1024);
+    let mut styles = vec![Style::default()];
+    let mut ids = HashMap::new();
+    let mut in_cell_style = false;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:style") => {
+                let family = e.try_get_attribute(b"style:family")?;
+                in_cell_style = family.map_or(false, |a| &*a.value == b"table-cell");
+                if in_cell_style {
+                    if let Some(a) = e.try_get_attribute(b"style:name")? {
+                        let name = a.decode_and_unescape_value(&reader)?.to_string();
+                        ids.insert(name, StyleId(styles.len() as u32));
+                    }
+                    styles.push(Style::default());
+                }
+            }
+            Ok(Event::Start(ref e)) if in_cell_style => {
+                let style = styles.last_mut().expect("default style");
+                for a in e.attributes() {
+                    let a = a.map_err(OdsError::XmlAttr)?;
+                    let v = a.decode_and_unescape_value(&reader)?;
+                    read_style_property(style, e.name(), a.key, &v);
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"style:style") => in_cell_style = false,
+            Ok(Event::End(ref e)) if e.name() == QName(b"office:automatic-styles") => break,
+            Ok(Event::Start(ref e)) if e.name() == QName(b"office:body") => break,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok((styles, ids))
+}
+
+/// Parses the sizes of the `table-row` and `table-column` automatic styles of content.xml,
+/// by style name
+///
+/// Row heights are in points, column widths in characters. Optimal row heights are ignored.
+fn parse_line_sizes(content: &[u8]) -> Result<HashMap<String, f64>, OdsError> {
+    let mut reader = xml_reader(content);
+    let mut buf = Vec::with_capacity(1024);
+    let mut sizes = HashMap::new();
+    let mut name = None;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:style") => {
+                name = match e.try_get_attribute(b"style:name")? {
+                    Some(a) => Some(a.decode_and_unescape_value(&reader)?.to_string()),
+                    None => None,
+                };
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:table-column-properties") => {
+                if let (Some(name), Some(a)) = (&name, e.try_get_attribute(b"style:column-width")?)
+                {
+                    if let Some(width) = length_to_points(&a.decode_and_unescape_value(&reader)?) {
+                        // Excel widths are a number of characters of 7 pixels, plus 5 pixels
+                        let width = ((width / 0.75 - 5.) / 7.).max(0.);
+                        sizes.insert(name.clone(), width);
+                    }
+                }
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:table-row-properties") => {
+                let optimal = e
+                    .try_get_attribute(b"style:use-optimal-row-height")?
+                    .map_or(false, |a| &*a.value == b"true");
+                if let (Some(name), Some(a), false) =
+                    (&name, e.try_get_attribute(b"style:row-height")?, optimal)
+                {
+                    if let Some(height) = length_to_points(&a.decode_and_unescape_value(&reader)?) {
+                        sizes.insert(name.clone(), height);
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"office:automatic-styles") => break,
+            Ok(Event::Start(ref e)) if e.name() == QName(b"office:body") => break,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(sizes)
+}
+
+/// Converts an ODF length (e.g. `2.5cm`) to points
+fn length_to_points(v: &str) -> Option<f64> {
+    let unit = v.find(|c: char| c.is_ascii_alphabetic())?;
+    let factor = match &v[unit..] {
+        "pt" => 1.,
+        "in" => 72.,
+        "cm" => 72. / 2.54,
+        "mm" => 72. / 25.4,
+        "pc" => 12.,
+        "px" => 0.75,
+        _ => return None,
+    };
+    v[..unit].trim().parse::<f64>().ok().map(|l| l * factor)
+}
+
+/// Parses the fonts of the `text` automatic styles of content.xml, by style name
+fn parse_text_styles(content: &[u8]) -> Result<HashMap<String, Font>, OdsError> {
+    let mut reader = xml_reader(content);
+    let mut buf = Vec::with_capacity(1024);
+    let mut fonts = HashMap::new();
+    let mut current: Option<(String, Style)> = None;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:style") => {
+                let family = e.try_get_attribute(b"style:family")?;
+                current = match e.try_get_attribute(b"style:name")? {
+                    Some(a) if family.map_or(false, |f| &*f.value == b"text") => Some((
+                        a.decode_and_unescape_value(&reader)?.to_string(),
+                        Style::default(),
+                    )),
+                    _ => None,
+                };
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:text-properties") => {
+                if let Some((_, style)) = current.as_mut() {
+                    for a in e.attributes() {
+                        let a = a.map_err(OdsError::XmlAttr)?;
+                        let v = a.decode_and_unescape_value(&reader)?;
+                        read_style_property(style, e.name(), a.key, &v);
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"style:style") => {
+                if let Some((name, style)) = current.take() {
+                    fonts.insert(name, style.font);
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"office:automatic-styles") => break,
+            Ok(Event::Start(ref e)) if e.name() == QName(b"office:body") => break,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(fonts)
+}
+
+/// Text runs of `text:p` paragraphs, spans with a text style become formatted runs
+///
+/// ODF 1.2-6.1
+struct TextRuns<'a> {
+    fonts: &'a HashMap<String, Font>,
+    runs: Vec<TextRun>,
+    spans: Vec<Option<Font>>,
+    paragraphs: usize,
+    in_paragraph: bool,
+}
+
+impl<'a> TextRuns<'a> {
+    fn new(fonts: &'a HashMap<String, Font>) -> Self {
+        TextRuns {
+            fonts,
+            runs: Vec::new(),
+            spans: Vec::new(),
+            paragraphs: 0,
+            in_paragraph: false,
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        let font = self.spans.last().and_then(|f| f.as_ref());
+        match self.runs.last_mut() {
+            Some(run) if run.font.as_ref() == font => run.text.push_str(text),
+            _ => self.runs.push(TextRun {
+                text: text.to_string(),
+                font: font.cloned(),
+            }),
+        }
+    }
+
+    /// Adds the content of a paragraph event, other events are ignored
+    fn read_event(&mut self, reader: &OdsReader<'_>, event: &Event<'_>) -> Result<(), OdsError> {
+        match event {
+            Event::Start(ref e) if e.name() == QName(b"text:p") => {
+                if self.paragraphs > 0 {
+                    self.push("\n");
+                }
+                self.paragraphs += 1;
+                self.in_paragraph = true;
+            }
+            Event::End(ref e) if e.name() == QName(b"text:p") => self.in_paragraph = false,
+            _ if !self.in_paragraph => (),
+            Event::Start(ref e) if e.name() == QName(b"text:span") => {
+                let font = match e.try_get_attribute(b"text:style-name")? {
+                    Some(a) => self
+                        .fonts
+                        .get(&*a.decode_and_unescape_value(reader)?)
+                        .cloned(),
+                    None => None,
+                };
+                self.spans.push(font);
+            }
+            Event::End(ref e) if e.name() == QName(b"text:span") => {
+                self.spans.pop();
+            }
+            Event::Start(ref e) if e.name() == QName(b"text:s") => {
+                let count = repeats(reader, e, b"text:c")?;
+                self.push(&" ".repeat(count as usize));
+            }
+            Event::Start(ref e) if e.name() == QName(b"text:line-break") => self.push("\n"),
+            Event::Start(ref e) if e.name() == QName(b"text:tab") => self.push("\t"),
+            Event::Text(ref t) => self.push(&t.unescape()?),
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// Reads an `office:annotation` element
+///
+/// ODF 1.2-14.1
+fn read_annotation(
+    reader: &mut OdsReader<'_>,
+    position: (u32, u32),
+    fonts: &HashMap<String, Font>,
+) -> Result<Comment, OdsError> {
+    let mut buf = Vec::with_capacity(256);
+    let mut author = String::new();
+    let mut runs = TextRuns::new(fonts);
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"dc:creator") => {
+                let mut text_buf = Vec::new();
+                loop {
+                    text_buf.clear();
+                    match reader.read_event_into(&mut text_buf)? {
+                        Event::Text(t) => author.push_str(&t.unescape()?),
+                        Event::End(end) if end.name() == e.name() => break,
+                        Event::Eof => return Err(OdsError::Eof("dc:creator")),
+                        _ => (),
+                    }
+                }
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"dc:date") => {
+                reader.read_to_end_into(e.name(), &mut Vec::new())?;
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"office:annotation") => {
+                return Ok(Comment::new(position, author, runs.runs));
+            }
+            Ok(Event::Eof) => return Err(OdsError::Eof("office:annotation")),
+            Ok(ref event) => runs.read_event(reader, event)?,
+            Err(e) => return Err(OdsError::Xml(e)),
+        }
+    }
+}
+
+/// Maps a formatting property of a table-cell style to `style`
+///
+/// ODF 1.2-17.18 (table-cell), 17.6 (paragraph) and 16.27.28 (text properties)
+fn read_style_property(style: &mut Style, element: QName<'_>, key: QName<'_>, v: &str) {
+    match (element.as_ref(), key.as_ref()) {
+        (b"style:table-cell-properties", b"fo:background-color") => {
+            if let Some(color) = Color::from_hex(v) {
+                style.fill.pattern = Some("solid".into());
+                style.fill.foreground = Some(color);
+            }
+        }
+        (b"style:table-cell-properties", b"fo:border") => {
+            let side = border_side(v);
+            style.border.left = side.clone();
+            style.border.right = side.clone();
+            style.border.top = side.clone();
+            style.border.bottom = side;
+        }
+        (b"style:table-cell-properties", b"fo:border-left") => style.border.left = border_side(v),
+        (b"style:table-cell-properties", b"fo:border-right") => style.border.right = border_side(v),
+        (b"style:table-cell-properties", b"fo:border-top") => style.border.top = border_side(v),
+        (b"style:table-cell-properties", b"fo:border-bottom") => {
+            style.border.bottom = border_side(v)
+        }
+        (b"style:table-cell-properties", b"style:vertical-align") => {
+            style.alignment.vertical = match v {
+                "top" => Some("top".into()),
+                "middle" => Some("center".into()),
+                "bottom" => Some("bottom".into()),
+                _ => None,
+            }
+        }
+        (b"style:table-cell-properties", b"fo:wrap-option") => {
+            style.alignment.wrap_text = v == "wrap"
+        }
+        (b"style:table-cell-properties", b"style:rotation-angle") => {
+            if let Ok(angle) = v.trim_end_matches("deg").parse::<f64>() {
+                let angle = angle.round() as i32 % 360;
+                style.alignment.rotation = if angle > 180 { angle - 360 } else { angle };
+            }
+        }
+        (b"style:paragraph-properties", b"fo:text-align") => {
+            style.alignment.horizontal = match v {
+                "start" | "left" => Some("left".into()),
+                "end" | "right" => Some("right".into()),
+                "center" => Some("center".into()),
+                "justify" => Some("justify".into()),
+                _ => None,
+            }
+        }
+        (b"style:text-properties", b"fo:font-weight") => style.font.bold = v == "bold",
+        (b"style:text-properties", b"fo:font-style") => style.font.italic = v == "italic",
+        (b"style:text-properties", b"fo:font-size") => {
+            style.font.size = v.trim_end_matches("pt").parse().ok()
+        }
+        (b"style:text-properties", b"style:font-name") => style.font.name = Some(v.into()),
+        (b"style:text-properties", b"fo:color") => style.font.color = Color::from_hex(v),
+        (b"style:text-properties", b"style:text-underline-style") => {
+            style.font.underline = v != "none"
+        }
+        (b"style:text-properties", b"style:text-line-through-style") => {
+            style.font.strike = v != "none"
+        }
+        _ => (),
+    }
+}
+
+/// Parses a `fo:border` value, e.g. "0.06pt solid #000000"
+fn border_side(v: &str) -> BorderSide {
+    let mut side = BorderSide::default();
+    for token in v.split_whitespace() {
+        match token {
+            "solid" => side.style = Some("thin".into()),
+            "double" | "dashed" | "dotted" => side.style = Some(token.into()),
+            _ if token.starts_with('#') => side.color = Color::from_hex(token),
+            _ => (),
+        }
+    }
+    if side.style.is_none() {
+        side.color = None;
+    }
+    side
+}
+
+/// Converts table-cell element into a `DataType`
+///
+/// ODF 1.2-19.385
+fn get_datatype(
+    reader: &mut OdsReader<'_>,
+    atts: Attributes<'_>,
+    buf: &mut Vec<u8>,
+) -> Result<(DataType, String, bool), OdsError> {
+    let mut is_string = false;
+    let mut is_value_set = false;
+    let mut val = DataType::Empty;
+    let mut formula = String::new();
+    for a in atts {
+        let a = a.map_err(OdsError::XmlAttr)?;
+        match a.key {
+            QName(b"office:value") if !is_value_set => {
+                let v = reader.decoder().decode(&a.value)?;
+                val = DataType::Float(v.parse().map_err(OdsError::ParseFloat)?);
+                is_value_set = true;
+            }
+            QName(b"office:string-value" | b"office:date-value" | b"office:time-value")
+                if !is_value_set =>
+            {
+                let attr = a
+                    .decode_and_unescape_value(reader)
+                    .map_err(OdsError::Xml)?
+                    .to_string();
+                val = match a.key {
+                    QName(b"office:date-value") => DataType::DateTimeIso(attr),
+                    QName(b"office:time-value") => DataType::DurationIso(attr),
+                    _ => DataType::String(attr),
+                };
+                is_value_set = true;
+            }
+            QName(b"office:boolean-value") if !is_value_set => {
+                let b = &*a.value == b"TRUE" || &*a.value == b"true";
+                val = DataType::Bool(b);
+                is_value_set = true;
+            }
+            QName(b"office:value-type") if !is_value_set => is_string = &*a.value == b"string",
+            QName(b"table:formula") => {
+                formula = a
+                    .decode_and_unescape_value(reader)
+                    .map_err(OdsError::Xml)?
+                    .to_string();
+            }
+            _ => (),
+        }
+    }
+    if !is_value_set && is_string {
+        // If the value type is string and the office:string-value attribute
+        // is not present, the element content defines the value.
+        let mut s = String::new();
+        let mut first_paragraph = true;
+        loop {
+            buf.clear();
+            match reader.read_event_into(buf) {
+                Ok(Event::Text(ref e)) => {
+                    s.push_str(&e.unescape()?);
+                }
+                Ok(Event::End(ref e))
+                    if e.name() == QName(b"table:table-cell")
+                        || e.name() == QName(b"table:covered-table-cell") =>
+                {
+                    return Ok((DataType::String(s), formula, true));
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"office:annotation") => {
+                    // comments are read by `worksheet_comments`
+                    reader.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"text:p") => {
+                    if first_paragraph {
+                        first_paragraph = false;
+                    } else {
+                        s.push('\n');
+                    }
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"text:s") => {
+                    let count = match e.try_get_attribute("text:c")? {
+                        Some(c) => c
+                            .decode_and_unescape_value(reader)
+                            .map_err(OdsError::Xml)?
+                            .parse()
+                            .map_err(OdsError::ParseInt)?,
+                        None => 1,
+                    };
+                    for _ in 0..count {
+                        s.push(' ');
+                    }
+                }
+                Err(e) => return Err(OdsError::Xml(e)),
+                Ok(Event::Eof) => return Err(OdsError::Eof("table:table-cell")),
+                _ => (),
+            }
+        }
+    } else {
+        Ok((val, formula, false))
+    }
+}
+
+fn read_named_expressions(reader: &mut OdsReader<'_>) -> Result<Vec<(String, String)>, OdsError> {
+    let mut defined_names = Vec::new();
+    let mut buf = Vec::with_capacity(512);
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e))
+                if e.name() == QName(b"table:named-range")
+                    || e.name() == QName(b"table:named-expression") =>
+            {
+                let mut name = String::new();
+                let mut formula = String::new();
+                for a in e.attributes() {
+                    let a = a.map_err(OdsError::XmlAttr)?;
+                    match a.key {
+                        QName(b"table:name") => {
+                            name = a
+                                .decode_and_unescape_value(reader)
+                                .map_err(OdsError::Xml)?
+                                .to_string();
+                        }
+                        QName(b"table:cell-range-address" | b"table:expression") => {
+                            formula = a
+                                .decode_and_unescape_value(reader)
+                                .map_err(OdsError::Xml)?
+                                .to_string();
+                        }
+                        _ => (),
+                    }
+                }
+                defined_names.push((name, formula));
+            }
+            Ok(Event::End(ref e))
+                if e.name() == QName(b"table:named-range")
+                    || e.name() == QName(b"table:named-expression") => {}
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:named-expressions") => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            Ok(e) => {
+                return Err(OdsError::Mismatch {
+                    expected: "table:named-expressions",
+                    found: format!("{:?}", e),
+                });
+            }
+        }
+    }
+    Ok(defined_names)
+}
+
+#[cfg(feature = "picture")]
+type Pictures = Option<Vec<(String, Vec<u8>)>>;
+
+/// Read pictures
+#[cfg(feature = "picture")]
+fn read_pictures<RS: Read + Seek>(zip: &mut ZipArchive<RS>) -> Result<Pictures, OdsError> {
+    let mut pics = Vec::new();
+    for i in 0..zip.len() {
+        let mut zfile = zip.by_index(i)?;
+        let zname = zfile.name().to_owned();
+        // no Thumbnails
+        if zname.starts_with("Pictures") {
+            let name_ext: Vec<&str> = zname.split(".").collect();
+            if let Some(ext) = name_ext.last() {
+                if [
+                    "emf", "wmf", "pict", "jpeg", "jpg", "png", "dib", "gif", "tiff", "eps", "bmp",
+                    "wpg",
+                ]
+                .contains(ext)
+                {
+                    let mut buf: Vec<u8> = Vec::new();
+                    zfile.read_to_end(&mut buf)?;
+                    pics.push((ext.to_string(), buf));
+                }
+            }
+        }
+    }
+    if pics.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(pics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_tables() {
+        let content = br#"<table:table table:name="a"><table:table-row><table:table-cell table:formula="of:=[.A1]>0"/><!-- </table:table> --></table:table-row><table:table table:name="sub"><table:table/></table:table ></table:table><table:table table:name="b">"#;
+        let start = content.iter().position(|&b| b == b'>').unwrap() + 1;
+        let end = skip_table(content, start).unwrap();
+        assert!(content[end..].starts_with(br#"<table:table table:name="b">"#));
+        assert_eq!(skip_table(content, end + 28), None);
+    }
+}
@@ -0,0 +1,295 @@
+//! Cell styles
+//!
+//! Styles are opt-in: they are only read when calling `Reader::styles` or
+//! `Reader::worksheet_styles`.
+//!
+//! Whatever the file format, enumerated values (fill patterns, border styles,
+//! alignments) use the names of the Office Open XML (ECMA-376) vocabulary,
+//! e.g. `"solid"`, `"thin"` or `"center"`.
+
+use std::ops::Index;
+
+use crate::CellType;
+
+/// Index of a `Style` in the workbook `Styles` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StyleId(pub u32);
+
+impl CellType for StyleId {}
+
+/// A color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// Alpha, red, green and blue components, as 0xAARRGGBB
+    Argb(u32),
+    /// Index in the workbook color palette
+    Indexed(u32),
+    /// Theme color, with a tint in [-1.0, 1.0]
+    Theme {
+        /// Index of the theme color
+        index: u32,
+        /// Tint to apply, negative values darken the color
+        tint: f64,
+    },
+    /// Automatic (system) color
+    Auto,
+}
+
+impl Color {
+    /// Parses a "RRGGBB" or "AARRGGBB" hexadecimal string, with an optional leading '#'
+    pub(crate) fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.trim_start_matches('#');
+        let v = u32::from_str_radix(hex, 16).ok()?;
+        match hex.len() {
+            6 => Some(Color::Argb(0xFF00_0000 | v)),
+            8 => Some(Color::Argb(v)),
+            _ => None,
+        }
+    }
+}
+
+/// Font properties
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Font {
+    /// Font name
+    pub name: Option<String>,
+    /// Font size, in points
+    pub size: Option<f64>,
+    /// Bold
+    pub bold: bool,
+    /// Italic
+    pub italic: bool,
+    /// Underlined (any kind of underline)
+    pub underline: bool,
+    /// Strike through
+    pub strike: bool,
+    /// Font color
+    pub color: Option<Color>,
+}
+
+/// A part of a rich text, with its own formatting
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextRun {
+    /// Text of the run
+    pub text: String,
+    /// Font of the run, `None` if the run has no specific formatting
+    pub font: Option<Font>,
+}
+
+/// Rich text cells, as read by `Reader::worksheet_rich_text`
+impl CellType for Vec<TextRun> {}
+
+/// Cell background
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fill {
+    /// Pattern type, e.g. "solid" or "gray125", `None` if there is no fill
+    pub pattern: Option<String>,
+    /// Pattern foreground color, the cell color for "solid" fills
+    pub foreground: Option<Color>,
+    /// Pattern background color
+    pub background: Option<Color>,
+}
+
+impl Fill {
+    /// Checks whether the cell background is painted
+    pub fn is_visible(&self) -> bool {
+        self.pattern.is_some()
+    }
+}
+
+/// One side of a cell border
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BorderSide {
+    /// Line style, e.g. "thin" or "double", `None` if there is no line
+    pub style: Option<String>,
+    /// Line color
+    pub color: Option<Color>,
+}
+
+/// Cell borders
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Border {
+    /// Left side
+    pub left: BorderSide,
+    /// Right side
+    pub right: BorderSide,
+    /// Top side
+    pub top: BorderSide,
+    /// Bottom side
+    pub bottom: BorderSide,
+}
+
+/// Cell content alignment
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Alignment {
+    /// Horizontal alignment, e.g. "left" or "center"
+    pub horizontal: Option<String>,
+    /// Vertical alignment, e.g. "top" or "center"
+    pub vertical: Option<String>,
+    /// Text is wrapped
+    pub wrap_text: bool,
+    /// Indentation level
+    pub indent: u32,
+    /// Text rotation, in degrees
+    pub rotation: i32,
+}
+
+/// The style of a cell
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    /// Number format code, e.g. "0.00%"
+    pub number_format: Option<String>,
+    /// Font
+    pub font: Font,
+    /// Fill
+    pub fill: Fill,
+    /// Borders
+    pub border: Border,
+    /// Alignment
+    pub alignment: Alignment,
+}
+
+/// The style table of a workbook, indexed by `StyleId`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Styles {
+    styles: Vec<Style>,
+}
+
+impl Styles {
+    pub(crate) fn new(styles: Vec<Style>) -> Styles {
+        Styles { styles }
+    }
+
+    /// Get the style corresponding to `id`
+    pub fn get(&self, id: StyleId) -> Option<&Style> {
+        self.styles.get(id.0 as usize)
+    }
+
+    /// Number of styles
+    pub fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    /// Checks whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty()
+    }
+
+    /// Iterates over all styles, along with their id
+    pub fn iter(&self) -> impl Iterator<Item = (StyleId, &Style)> {
+        self.styles
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (StyleId(i as u32), s))
+    }
+}
+
+impl Index<StyleId> for Styles {
+    type Output = Style;
+
+    fn index(&self, id: StyleId) -> &Style {
+        &self.styles[id.0 as usize]
+    }
+}
+
+/// Fill pattern name of a binary FillPattern value (MS-XLS 2.5.12, MS-XLSB 2.5.52)
+pub(crate) fn fill_pattern(fls: u8) -> Option<&'static str> {
+    Some(match fls {
+        0 => return None,
+        1 => "solid",
+        2 => "mediumGray",
+        3 => "darkGray",
+        4 => "lightGray",
+        5 => "darkHorizontal",
+        6 => "darkVertical",
+        7 => "darkDown",
+        8 => "darkUp",
+        9 => "darkGrid",
+        10 => "darkTrellis",
+        11 => "lightHorizontal",
+        12 => "lightVertical",
+        13 => "lightDown",
+        14 => "lightUp",
+        15 => "lightGrid",
+        16 => "lightTrellis",
+        17 => "gray125",
+        18 => "gray0625",
+        _ => return None,
+    })
+}
+
+/// Border style name of a binary BorderStyle value (MS-XLS 2.5.11, MS-XLSB 2.5.4)
+pub(crate) fn border_style(dg: u8) -> Option<&'static str> {
+    Some(match dg {
+        0 => return None,
+        1 => "thin",
+        2 => "medium",
+        3 => "dashed",
+        4 => "dotted",
+        5 => "thick",
+        6 => "double",
+        7 => "hair",
+        8 => "mediumDashed",
+        9 => "dashDot",
+        10 => "mediumDashDot",
+        11 => "dashDotDot",
+        12 => "mediumDashDotDot",
+        13 => "slantDashDot",
+        _ => return None,
+    })
+}
+
+/// Horizontal alignment name of a binary HorizAlign value (MS-XLS 2.5.20)
+pub(crate) fn horizontal_alignment(alc: u8) -> Option<&'static str> {
+    Some(match alc {
+        1 => "left",
+        2 => "center",
+        3 => "right",
+        4 => "fill",
+        5 => "justify",
+        6 => "centerContinuous",
+        7 => "distributed",
+        _ => return None,
+    })
+}
+
+/// Vertical alignment name of a binary VertAlign value (MS-XLS 2.5.20)
+pub(crate) fn vertical_alignment(alcv: u8) -> Option<&'static str> {
+    Some(match alcv {
+        0 => "top",
+        1 => "center",
+        2 => "bottom",
+        3 => "justify",
+        4 => "distributed",
+        _ => return None,
+    })
+}
+
+/// Rotation in degrees of a binary trot value, 255 being vertical text
+pub(crate) fn rotation(trot: u8) -> i32 {
+    match trot {
+        0..=90 => trot as i32,
+        91..=180 => 90 - trot as i32,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_from_hex() {
+        assert_eq!(Color::from_hex("FF0000"), Some(Color::Argb(0xFFFF0000)));
+        assert_eq!(Color::from_hex("#00ff00"), Some(Color::Argb(0xFF00FF00)));
+        assert_eq!(Color::from_hex("800000FF"), Some(Color::Argb(0x800000FF)));
+        assert_eq!(Color::from_hex("transparent"), None);
+    }
+
+    #[test]
+    fn binary_rotation() {
+        assert_eq!(rotation(45), 45);
+        assert_eq!(rotation(135), -45);
+        assert_eq!(rotation(255), 0);
+    }
+}
@@ -1,6 +1,18 @@
 //! Internal module providing handy function
 
 use std::convert::TryInto;
+use std::io::{BufReader, Read, Seek};
+
+use log::debug;
+use quick_xml::events::Event;
+use quick_xml::Reader as XmlReader;
+use zip::read::ZipArchive;
+use zip::result::ZipError;
+
+use crate::formula::{self, Expr};
+use crate::{
+    Chart, ChartReference, ChartSeries, ChartType, DataType, Dimensions, DocumentProperties,
+};
 
 macro_rules! from_err {
     ($from:ty, $to:tt, $var:tt) => {
@@ -70,6 +82,455 @@ pub fn push_column(mut col: u32, buf: &mut String) {
     }
 }
 
+/// A relationship of a part of an Office Open XML package
+pub(crate) struct Relationship {
+    pub id: String,
+    pub typ: String,
+    /// Package path of the target, or its uri if `external`
+    pub target: String,
+    pub external: bool,
+}
+
+/// Reads the relationships of a part of an Office Open XML package (xlsx, xlsb)
+pub(crate) fn read_relationships<RS, E>(
+    zip: &mut ZipArchive<RS>,
+    part: &str,
+) -> Result<Vec<Relationship>, E>
+where
+    RS: Read + Seek,
+    E: From<quick_xml::Error> + From<ZipError>,
+{
+    let (folder, file) = part.rsplit_once('/').unwrap_or(("", part));
+    let rels = if folder.is_empty() {
+        format!("_rels/{file}.rels")
+    } else {
+        format!("{folder}/_rels/{file}.rels")
+    };
+    let f = match zip.by_name(&rels) {
+        Ok(f) => f,
+        Err(ZipError::FileNotFound) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut xml = XmlReader::from_reader(BufReader::new(f));
+    xml.trim_text(false).expand_empty_elements(true);
+    let mut relationships = Vec::new();
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.local_name().as_ref() == b"Relationship" => {
+                let mut rel = Relationship {
+                    id: String::new(),
+                    typ: String::new(),
+                    target: String::new(),
+                    external: false,
+                };
+                for a in e.attributes() {
+                    let a = a.map_err(quick_xml::Error::from)?;
+                    match a.key.as_ref() {
+                        b"Id" => rel.id = a.decode_and_unescape_value(&xml)?.into_owned(),
+                        b"Type" => rel.typ = a.decode_and_unescape_value(&xml)?.into_owned(),
+                        b"Target" => rel.target = a.decode_and_unescape_value(&xml)?.into_owned(),
+                        b"TargetMode" => rel.external = &*a.value == b"External",
+                        _ => (),
+                    }
+                }
+                if !rel.external {
+                    rel.target = resolve_path(folder, &rel.target);
+                }
+                relationships.push(rel);
+            }
+            Event::Eof => return Ok(relationships),
+            _ => (),
+        }
+    }
+}
+
+/// Resolves a relationship target relative to the folder of its source part
+fn resolve_path(folder: &str, target: &str) -> String {
+    let mut path: Vec<&str> = match target.strip_prefix('/') {
+        Some(_) => Vec::new(),
+        None => folder.split('/').filter(|p| !p.is_empty()).collect(),
+    };
+    for p in target.split('/') {
+        match p {
+            "" | "." => (),
+            ".." => {
+                path.pop();
+            }
+            p => path.push(p),
+        }
+    }
+    path.join("/")
+}
+
+/// Reads the document properties of an Office Open XML package (xlsx, xlsb)
+///
+/// The core, extended and custom properties parts are found through the package
+/// relationships. Properties are informative: a malformed part is skipped.
+pub(crate) fn read_document_properties<RS, E>(zip: &mut ZipArchive<RS>) -> DocumentProperties
+where
+    RS: Read + Seek,
+    E: From<quick_xml::Error> + From<ZipError> + std::fmt::Display,
+{
+    let mut properties = DocumentProperties::default();
+    let relationships = match read_relationships::<_, E>(zip, "") {
+        Ok(relationships) => relationships,
+        Err(e) => {
+            debug!("cannot read package relationships: {}", e);
+            return properties;
+        }
+    };
+    for rel in relationships {
+        if rel.external
+            || !(rel.typ.ends_with("/core-properties")
+                || rel.typ.ends_with("/extended-properties")
+                || rel.typ.ends_with("/custom-properties"))
+        {
+            continue;
+        }
+        if let Err(e) = read_properties_part::<_, E>(zip, &rel.target, &mut properties) {
+            debug!("cannot read document properties '{}': {}", rel.target, e);
+        }
+    }
+    properties
+}
+
+/// Reads a core, extended or custom properties part
+fn read_properties_part<RS, E>(
+    zip: &mut ZipArchive<RS>,
+    part: &str,
+    properties: &mut DocumentProperties,
+) -> Result<(), E>
+where
+    RS: Read + Seek,
+    E: From<quick_xml::Error> + From<ZipError>,
+{
+    let f = match zip.by_name(part) {
+        Ok(f) => f,
+        Err(ZipError::FileNotFound) => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut xml = XmlReader::from_reader(BufReader::new(f));
+    xml.trim_text(false).expand_empty_elements(true);
+    let mut buf = Vec::with_capacity(64);
+    let mut value = String::new();
+    // name of the current custom property
+    let mut name = None;
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e) => {
+                value.clear();
+                if e.local_name().as_ref() == b"property" {
+                    name = match e.try_get_attribute("name")? {
+                        Some(a) => Some(a.decode_and_unescape_value(&xml)?.into_owned()),
+                        None => None,
+                    };
+                }
+            }
+            Event::Text(t) => value.push_str(&t.unescape()?),
+            Event::End(ref e) => {
+                let text = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+                match (e.local_name().as_ref(), &name) {
+                    (b"property", _) => name = None,
+                    (typ, Some(name)) => properties
+                        .custom
+                        .push((name.clone(), custom_property(typ, value.trim()))),
+                    (b"title", _) => properties.title = text,
+                    (b"subject", _) => properties.subject = text,
+                    (b"creator", _) => properties.author = text,
+                    (b"keywords", _) => properties.keywords = text,
+                    (b"description", _) => properties.description = text,
+                    (b"category", _) => properties.category = text,
+                    (b"lastModifiedBy", _) => properties.last_modified_by = text,
+                    (b"created", _) => properties.created = text,
+                    (b"modified", _) => properties.modified = text,
+                    (b"Application", _) => properties.application = text,
+                    (b"Company", _) => properties.company = text,
+                    (b"Manager", _) => properties.manager = text,
+                    _ => (),
+                }
+            }
+            Event::Eof => return Ok(()),
+            _ => (),
+        }
+    }
+}
+
+/// Reads the charts of a worksheet or chart sheet of an Office Open XML package (xlsx, xlsb)
+///
+/// Charts are DrawingML parts, linked from the drawing of the sheet.
+pub(crate) fn read_charts<RS, E>(zip: &mut ZipArchive<RS>, sheet: &str) -> Result<Vec<Chart>, E>
+where
+    RS: Read + Seek,
+    E: From<quick_xml::Error> + From<ZipError>,
+{
+    let mut charts = Vec::new();
+    for drawing in read_relationships::<_, E>(zip, sheet)? {
+        if drawing.external || !drawing.typ.ends_with("/drawing") {
+            continue;
+        }
+        let rels = read_relationships::<_, E>(zip, &drawing.target)?;
+        for (frame, id) in read_chart_anchors::<_, E>(zip, &drawing.target)? {
+            let Some(rel) = rels.iter().find(|r| r.id == id && !r.external) else {
+                continue;
+            };
+            if let Some(chart) = read_chart::<_, E>(zip, &rel.target)? {
+                charts.push(Chart {
+                    name: frame.name,
+                    anchor: frame.anchor,
+                    ..chart
+                });
+            }
+        }
+    }
+    Ok(charts)
+}
+
+/// Reads the charts of a drawing part, with only their name and anchor, and the
+/// relationship id of their chart part
+fn read_chart_anchors<RS, E>(
+    zip: &mut ZipArchive<RS>,
+    part: &str,
+) -> Result<Vec<(Chart, String)>, E>
+where
+    RS: Read + Seek,
+    E: From<quick_xml::Error> + From<ZipError>,
+{
+    let f = match zip.by_name(part) {
+        Ok(f) => f,
+        Err(ZipError::FileNotFound) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut xml = XmlReader::from_reader(BufReader::new(f));
+    xml.trim_text(false).expand_empty_elements(true);
+    let mut buf = Vec::with_capacity(64);
+    let mut anchors = Vec::new();
+    let mut name = None;
+    let mut cells: Vec<(u32, u32)> = Vec::new();
+    let mut id = None;
+    let mut value = String::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e) => {
+                value.clear();
+                match e.local_name().as_ref() {
+                    b"twoCellAnchor" | b"oneCellAnchor" | b"absoluteAnchor" => {
+                        name = None;
+                        cells.clear();
+                        id = None;
+                    }
+                    b"from" | b"to" => cells.push((0, 0)),
+                    b"cNvPr" => {
+                        if let Some(a) = e.try_get_attribute("name")? {
+                            name = Some(a.decode_and_unescape_value(&xml)?.into_owned());
+                        }
+                    }
+                    b"chart" => {
+                        for a in e.attributes() {
+                            let a = a.map_err(quick_xml::Error::from)?;
+                            if a.key.local_name().as_ref() == b"id" {
+                                id = Some(a.decode_and_unescape_value(&xml)?.into_owned());
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Event::Text(t) => value.push_str(&t.unescape()?),
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"row" => {
+                    if let Some(cell) = cells.last_mut() {
+                        cell.0 = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                b"col" => {
+                    if let Some(cell) = cells.last_mut() {
+                        cell.1 = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                b"twoCellAnchor" | b"oneCellAnchor" | b"absoluteAnchor" => {
+                    if let Some(id) = id.take() {
+                        let anchor = cells.first().map(|&start| Dimensions {
+                            start,
+                            end: cells.get(1).copied().unwrap_or(start),
+                        });
+                        let chart = Chart {
+                            name: name.take(),
+                            anchor,
+                            ..Chart::default()
+                        };
+                        anchors.push((chart, id));
+                    }
+                }
+                _ => (),
+            },
+            Event::Eof => return Ok(anchors),
+            _ => (),
+        }
+    }
+}
+
+/// Reads a DrawingML chart part
+fn read_chart<RS, E>(zip: &mut ZipArchive<RS>, part: &str) -> Result<Option<Chart>, E>
+where
+    RS: Read + Seek,
+    E: From<quick_xml::Error> + From<ZipError>,
+{
+    let f = match zip.by_name(part) {
+        Ok(f) => f,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut xml = XmlReader::from_reader(BufReader::new(f));
+    xml.trim_text(false).expand_empty_elements(true);
+    let mut buf = Vec::with_capacity(64);
+    let mut chart = Chart::default();
+    // local names of the open elements
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut plot = None;
+    let mut has_title = false;
+    let mut title = String::new();
+    let mut value = String::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e) => {
+                value.clear();
+                let local = e.local_name().as_ref().to_vec();
+                let parent = path.last().map(|p| p.as_slice());
+                match (parent, local.as_slice()) {
+                    (Some(b"plotArea"), name) if name.ends_with(b"Chart") => {
+                        plot = Some(chart_type(name))
+                    }
+                    (Some(b"barChart" | b"bar3DChart"), b"barDir") => {
+                        if let Some(a) = e.try_get_attribute("val")? {
+                            if a.value.as_ref() == b"bar" {
+                                plot = Some(ChartType::Bar);
+                            }
+                        }
+                    }
+                    (Some(name), b"ser") if name.ends_with(b"Chart") => {
+                        chart.series.push(ChartSeries {
+                            chart_type: plot.clone().unwrap_or_default(),
+                            ..ChartSeries::default()
+                        })
+                    }
+                    (Some(b"chart"), b"title") => has_title = true,
+                    _ => (),
+                }
+                path.push(local);
+            }
+            Event::Text(t) => value.push_str(&t.unescape()?),
+            Event::End(_) => {
+                let Some(local) = path.pop() else {
+                    continue;
+                };
+                let in_title = path.len() > 2 && path[1] == b"chart" && path[2] == b"title";
+                let in_series = path.iter().rposition(|p| p == b"ser");
+                match local.as_slice() {
+                    b"t" if in_title => title.push_str(&value),
+                    b"p" if in_title && !title.is_empty() => title.push('\n'),
+                    b"v" if in_title && path.iter().any(|p| p == b"strCache") => {
+                        title.push_str(&value)
+                    }
+                    b"f" | b"v" => {
+                        let (Some(ser), Some(series)) = (in_series, chart.series.last_mut()) else {
+                            continue;
+                        };
+                        let target = path.get(ser + 1).map(|p| p.as_slice());
+                        match (local.as_slice(), target) {
+                            (b"f", Some(b"tx")) => {
+                                series.name_reference = Some(chart_reference(&value))
+                            }
+                            (b"v", Some(b"tx")) if series.name.is_none() => {
+                                series.name = Some(value.clone())
+                            }
+                            (b"f", Some(b"cat" | b"xVal")) => {
+                                series.categories = Some(chart_reference(&value))
+                            }
+                            (b"f", Some(b"val" | b"yVal")) => {
+                                series.values = Some(chart_reference(&value))
+                            }
+                            _ => (),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    let title = title.trim_end_matches('\n');
+    chart.title = if !title.is_empty() {
+        Some(title.to_string())
+    } else if has_title && chart.series.len() == 1 {
+        chart.series[0].name.clone()
+    } else {
+        None
+    };
+    chart.chart_type = match chart.series.first() {
+        Some(series) => series.chart_type.clone(),
+        None => plot.unwrap_or_default(),
+    };
+    Ok(Some(chart))
+}
+
+/// Gets the kind of chart of a plot area element
+fn chart_type(name: &[u8]) -> ChartType {
+    match name {
+        b"barChart" | b"bar3DChart" => ChartType::Column,
+        b"lineChart" | b"line3DChart" => ChartType::Line,
+        b"areaChart" | b"area3DChart" => ChartType::Area,
+        b"pieChart" | b"pie3DChart" | b"ofPieChart" => ChartType::Pie,
+        b"doughnutChart" => ChartType::Doughnut,
+        b"scatterChart" => ChartType::Scatter,
+        b"bubbleChart" => ChartType::Bubble,
+        b"radarChart" => ChartType::Radar,
+        b"stockChart" => ChartType::Stock,
+        b"surfaceChart" | b"surface3DChart" => ChartType::Surface,
+        name => ChartType::Other(String::from_utf8_lossy(name).into_owned()),
+    }
+}
+
+/// Resolves the sheet and cells of a chart series formula
+fn chart_reference(formula: &str) -> ChartReference {
+    let (sheet, range) = match formula::parse(formula) {
+        Ok(Expr::Cell { sheet, cell }) => {
+            (sheet, Some(((cell.row, cell.col), (cell.row, cell.col))))
+        }
+        Ok(Expr::Area { sheet, start, end }) => {
+            (sheet, Some(((start.row, start.col), (end.row, end.col))))
+        }
+        _ => (None, None),
+    };
+    ChartReference {
+        formula: formula.to_string(),
+        sheet: range.and(sheet).map(|s| s.first),
+        range: range.map(|(start, end)| Dimensions { start, end }),
+    }
+}
+
+/// Converts the value of a custom property, by its variant type
+fn custom_property(typ: &[u8], value: &str) -> DataType {
+    match typ {
+        b"i1" | b"i2" | b"i4" | b"i8" | b"int" | b"ui1" | b"ui2" | b"ui4" | b"ui8" | b"uint" => {
+            value
+                .parse()
+                .map_or_else(|_| DataType::String(value.to_string()), DataType::Int)
+        }
+        b"r4" | b"r8" | b"decimal" => value
+            .parse()
+            .map_or_else(|_| DataType::String(value.to_string()), DataType::Float),
+        b"bool" => DataType::Bool(value == "true" || value == "1"),
+        b"filetime" | b"date" => DataType::DateTimeIso(value.to_string()),
+        _ => DataType::String(value.to_string()),
+    }
+}
+
 pub const FTAB_LEN: usize = 485;
 
 /* [MS-XLS] 2.5.198.17 */
@@ -1064,4 +1525,20 @@ mod tests {
             [u32::from_le_bytes(*b"ABCD"), u32::from_le_bytes(*b"EFGH")]
         );
     }
+
+    #[test]
+    fn relationship_paths() {
+        assert_eq!(
+            resolve_path("xl/worksheets", "../comments1.xml"),
+            "xl/comments1.xml"
+        );
+        assert_eq!(
+            resolve_path("xl", "worksheets/sheet1.xml"),
+            "xl/worksheets/sheet1.xml"
+        );
+        assert_eq!(
+            resolve_path("xl/worksheets", "/xl/persons/person.xml"),
+            "xl/persons/person.xml"
+        );
+    }
 }
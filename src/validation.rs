@@ -0,0 +1,130 @@
+//! Data validation rules, read by `Reader::worksheet_data_validations`
+
+use crate::Dimensions;
+
+/// Kind of values allowed by a data validation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationType {
+    /// Any value
+    #[default]
+    Any,
+    /// Whole numbers
+    Whole,
+    /// Decimal numbers
+    Decimal,
+    /// Values of a list, given by `formula1`
+    List,
+    /// Dates
+    Date,
+    /// Times
+    Time,
+    /// Texts, compared by length
+    TextLength,
+    /// Values for which the `formula1` formula is true
+    Custom,
+}
+
+/// Comparison of the cell values with the formulas of a data validation or a conditional format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationOperator {
+    /// Between `formula1` and `formula2`, both included
+    #[default]
+    Between,
+    /// Not between `formula1` and `formula2`
+    NotBetween,
+    /// Equal to `formula1`
+    Equal,
+    /// Not equal to `formula1`
+    NotEqual,
+    /// Greater than `formula1`
+    GreaterThan,
+    /// Less than `formula1`
+    LessThan,
+    /// Greater than or equal to `formula1`
+    GreaterThanOrEqual,
+    /// Less than or equal to `formula1`
+    LessThanOrEqual,
+}
+
+impl ValidationType {
+    /// Converts the type of a BIFF (xls or xlsb) data validation
+    pub(crate) fn from_biff(typ: u32) -> ValidationType {
+        match typ {
+            1 => ValidationType::Whole,
+            2 => ValidationType::Decimal,
+            3 => ValidationType::List,
+            4 => ValidationType::Date,
+            5 => ValidationType::Time,
+            6 => ValidationType::TextLength,
+            7 => ValidationType::Custom,
+            _ => ValidationType::Any,
+        }
+    }
+}
+
+impl ValidationOperator {
+    /// Converts the operator of a BIFF (xls or xlsb) data validation
+    pub(crate) fn from_biff(operator: u32) -> ValidationOperator {
+        match operator {
+            1 => ValidationOperator::NotBetween,
+            2 => ValidationOperator::Equal,
+            3 => ValidationOperator::NotEqual,
+            4 => ValidationOperator::GreaterThan,
+            5 => ValidationOperator::LessThan,
+            6 => ValidationOperator::GreaterThanOrEqual,
+            7 => ValidationOperator::LessThanOrEqual,
+            _ => ValidationOperator::Between,
+        }
+    }
+}
+
+/// A data validation rule, restricting the values of some cells
+///
+/// Formulas are written without the leading `=`, the values of an inline list
+/// being quoted and separated by commas (e.g. `"Yes,No"`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataValidation {
+    /// Validated cells
+    pub ranges: Vec<Dimensions>,
+    /// Kind of allowed values
+    pub validation_type: ValidationType,
+    /// Comparison with the formulas, ignored for `List` and `Custom` validations
+    pub operator: ValidationOperator,
+    /// First formula, or the source of a list
+    pub formula1: Option<String>,
+    /// Second formula, for `Between` and `NotBetween` operators
+    pub formula2: Option<String>,
+    /// Blank cells are valid
+    pub allow_blank: bool,
+    /// Title of the error message
+    pub error_title: Option<String>,
+    /// Error message displayed for an invalid value
+    pub error_message: Option<String>,
+    /// Title of the input message
+    pub prompt_title: Option<String>,
+    /// Input message displayed when a cell is selected
+    pub prompt_message: Option<String>,
+}
+
+impl DataValidation {
+    /// Checks whether a cell, as (row, column), is validated by this rule
+    pub fn contains(&self, pos: (u32, u32)) -> bool {
+        self.ranges.iter().any(|d| d.contains(pos.0, pos.1))
+    }
+
+    /// Gets the values of a `List` validation written inline
+    ///
+    /// Returns `None` if the list comes from cells, e.g. `$A$1:$A$3`.
+    pub fn list_values(&self) -> Option<Vec<String>> {
+        if self.validation_type != ValidationType::List {
+            return None;
+        }
+        let list = self.formula1.as_ref()?;
+        let list = list.strip_prefix('"')?.strip_suffix('"')?;
+        Some(
+            list.split(',')
+                .map(|v| v.trim_start().to_string())
+                .collect(),
+        )
+    }
+}
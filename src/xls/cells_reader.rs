@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+
+use log::debug;
+
+use super::{
+    parse_biff2_cell, parse_bool_err, parse_dimensions, parse_formula, parse_formula_record,
+    parse_formula_value, parse_label, parse_label_sst, parse_merge_cells, parse_mul_rk,
+    parse_number, parse_rk, parse_string, Biff, FormulaRecord, RecordIter, XlsError, Xti,
+};
+use crate::cfb::XlsEncoding;
+use crate::formats::CellFormat;
+use crate::formula::{RgceContext, SheetRef};
+use crate::utils::read_u16;
+use crate::{Cell, DataType, Dimensions, Range, TableMetadata};
+
+/// A cells reader for a single xls worksheet
+///
+/// Records of the worksheet substream are only decoded when requested
+pub struct XlsCellReader<'a> {
+    records: RecordIter<'a>,
+    strings: &'a [String],
+    formats: &'a [CellFormat],
+    is_1904: bool,
+    encoding: &'a XlsEncoding,
+    biff: Biff,
+    sheet_names: &'a [String],
+    defined_names: &'a [(String, String)],
+    xtis: &'a [Xti],
+    tables: &'a [TableMetadata],
+    /// Sheets referenced by index in formulas written before BIFF8
+    legacy_sheets: Vec<Option<SheetRef>>,
+    dimensions: Dimensions,
+    /// Position of the last Formula record, its string value comes in the next record
+    fmla_pos: (u32, u32),
+    /// XF index of the next BIFF2 cell, when it does not fit in its attributes
+    ixfe: Option<u16>,
+    cells: VecDeque<Cell<DataType>>,
+    formulas: VecDeque<Cell<String>>,
+    merge_cells: Vec<Dimensions>,
+    eof: bool,
+}
+
+impl<'a> XlsCellReader<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        records: RecordIter<'a>,
+        strings: &'a [String],
+        formats: &'a [CellFormat],
+        is_1904: bool,
+        encoding: &'a XlsEncoding,
+        biff: Biff,
+        sheet_names: &'a [String],
+        defined_names: &'a [(String, String)],
+        xtis: &'a [Xti],
+        tables: &'a [TableMetadata],
+    ) -> Result<Self, XlsError> {
+        // Dimensions record comes before any cell, look ahead without consuming the records
+        let mut dimensions = Dimensions::default();
+        for r in records.clone() {
+            let r = r?;
+            match r.typ {
+                // BIFF2 and later Dimensions
+                0x0000 | 0x0200 => {
+                    dimensions = parse_dimensions(r.data)?;
+                    break;
+                }
+                0x000A => break,
+                _ => (),
+            }
+        }
+        let legacy_sheets = match biff {
+            Biff::Biff8 => Vec::new(),
+            _ => sheet_names
+                .iter()
+                .map(|name| Some(SheetRef::new(name.clone())))
+                .collect(),
+        };
+        Ok(XlsCellReader {
+            records,
+            strings,
+            formats,
+            is_1904,
+            encoding,
+            biff,
+            sheet_names,
+            defined_names,
+            xtis,
+            tables,
+            legacy_sheets,
+            dimensions,
+            fmla_pos: (0, 0),
+            ixfe: None,
+            cells: VecDeque::new(),
+            formulas: VecDeque::new(),
+            merge_cells: Vec::new(),
+            eof: false,
+        })
+    }
+
+    pub(crate) fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Merged cells regions read so far
+    pub(crate) fn merge_cells(&self) -> &[Dimensions] {
+        &self.merge_cells
+    }
+
+    /// Reads the next cell value
+    pub fn next_cell(&mut self) -> Result<Option<Cell<DataType>>, XlsError> {
+        loop {
+            if let Some(cell) = self.cells.pop_front() {
+                return Ok(Some(cell));
+            }
+            if !self.read_record(true, false)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Reads the next cell formula
+    pub fn next_formula(&mut self) -> Result<Option<Cell<String>>, XlsError> {
+        loop {
+            if let Some(cell) = self.formulas.pop_front() {
+                return Ok(Some(cell));
+            }
+            if !self.read_record(false, true)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Reads both values and formulas of the whole worksheet at once
+    pub(crate) fn read_all(&mut self) -> Result<(Range<DataType>, Range<String>), XlsError> {
+        let mut cells = Vec::with_capacity(self.dimensions.len().min(1_000_000) as usize);
+        let mut formulas = Vec::new();
+        while self.read_record(true, true)? {
+            cells.extend(self.cells.drain(..));
+            formulas.extend(self.formulas.drain(..));
+        }
+        Ok((Range::from_sparse(cells), Range::from_sparse(formulas)))
+    }
+
+    /// Decodes the next record, queuing any cell value and/or formula it contains
+    ///
+    /// Returns false once the end of the worksheet substream has been reached
+    fn read_record(&mut self, values: bool, formulas: bool) -> Result<bool, XlsError> {
+        if self.eof {
+            return Ok(false);
+        }
+        let r = match self.records.next() {
+            Some(r) => r?,
+            None => {
+                self.eof = true;
+                return Ok(false);
+            }
+        };
+        match r.typ {
+            0x000A => {
+                // 10: EOF
+                self.eof = true;
+                return Ok(false);
+            }
+            // 229: MergeCells
+            0x00E5 => self.merge_cells.extend(parse_merge_cells(r.data)?),
+            _ if !values && !matches!(r.typ, 0x0006 | 0x0206 | 0x0406) => (),
+            // BIFF2 Integer, Number, Label and BoolErr
+            0x0002..=0x0005 if matches!(self.biff, Biff::Biff2) => {
+                let ixfe = self.ixfe.take();
+                self.cells.push_back(parse_biff2_cell(
+                    &r,
+                    ixfe,
+                    self.formats,
+                    self.is_1904,
+                    self.encoding,
+                )?)
+            }
+            // BIFF2 IXFE
+            0x0044 => self.ixfe = r.data.get(..2).map(read_u16),
+            //0x0201 => cells.push(parse_blank(r.data)?), // 513: Blank
+            0x0203 => self
+                .cells
+                .push_back(parse_number(r.data, self.formats, self.is_1904)?), // 515: Number
+            // 516: Label [MS-XLS 2.4.148], 214: RString before BIFF8
+            0x0204 | 0x00D6 => self
+                .cells
+                .extend(parse_label(r.data, self.encoding, self.biff)?),
+            0x0205 => self.cells.push_back(parse_bool_err(r.data)?), // 517: BoolErr
+            // 519 String (formula value), 7 in BIFF2
+            0x0007 | 0x0207 => {
+                let val = DataType::String(parse_string(r.data, self.encoding, self.biff)?);
+                self.cells.push_back(Cell::new(self.fmla_pos, val))
+            }
+            0x027E => self
+                .cells
+                .push_back(parse_rk(r.data, self.formats, self.is_1904)?), // 638: Rk
+            0x00FD => self.cells.extend(parse_label_sst(r.data, self.strings)?), // LabelSst
+            0x00BD => {
+                // 189: MulRk
+                let mut cells = Vec::new();
+                parse_mul_rk(r.data, &mut cells, self.formats, self.is_1904)?;
+                self.cells.extend(cells);
+            }
+            0x0006 | 0x0206 | 0x0406 => {
+                // 6: Formula, 518 and 1030 in BIFF3 and BIFF4
+                let fmla = parse_formula_record(&r, self.biff)?;
+                self.fmla_pos = (fmla.row, fmla.col);
+                if values {
+                    if let Some(val) = parse_formula_value(fmla.value)? {
+                        // If the value is a string
+                        // it will appear in 0x0207 record coming next
+                        self.cells.push_back(Cell::new(self.fmla_pos, val));
+                    }
+                }
+                if formulas {
+                    let fmla = match self.biff {
+                        Biff::Biff8 => parse_formula(
+                            &r.data[20..],
+                            self.sheet_names,
+                            self.defined_names,
+                            self.xtis,
+                            self.tables,
+                            self.encoding,
+                        ),
+                        _ => self.legacy_formula(&fmla),
+                    }
+                    .unwrap_or_else(|e| {
+                        debug!("{}", e);
+                        format!(
+                            "Unrecognised formula \
+                             for cell ({}, {}): {:?}",
+                            self.fmla_pos.0, self.fmla_pos.1, e
+                        )
+                    });
+                    self.formulas.push_back(Cell::new(self.fmla_pos, fmla));
+                }
+            }
+            _ => (),
+        }
+        Ok(true)
+    }
+
+    /// Formula text of a formula written before BIFF8, rendered from its expression tree
+    fn legacy_formula(&self, fmla: &FormulaRecord<'_>) -> Result<String, XlsError> {
+        let ctx = RgceContext {
+            kind: self.biff.rgce_kind(),
+            sheets: &self.legacy_sheets,
+            names: self.defined_names,
+        };
+        Ok(ctx.parse(fmla.rgce, fmla.extra)?.to_string())
+    }
+}
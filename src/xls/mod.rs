@@ -0,0 +1,3380 @@
+mod cells_reader;
+
+pub use cells_reader::XlsCellReader;
+
+use std::borrow::Cow;
+use std::cmp::min;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+use std::fmt::Write;
+use std::io::{Read, Seek, SeekFrom};
+
+use log::debug;
+
+use crate::cfb::{Cfb, StreamLocation, XlsEncoding};
+use crate::crypto::{CryptoError, XlsDecryptor};
+use crate::formats::{
+    builtin_format_by_code, builtin_format_code, detect_custom_number_format, format_excel_f64,
+    format_excel_i64, CellFormat,
+};
+use crate::formula::{
+    or_unrecognized, ptg_list, resolve_table_references, Expr, RgceContext, RgceKind, SheetRef,
+};
+use crate::style::{
+    border_style, fill_pattern, horizontal_alignment, rotation, vertical_alignment, Alignment,
+    Border, BorderSide, Color, Fill, Font, Style, TextRun,
+};
+use crate::utils::{
+    push_column, read_f64, read_i16, read_i32, read_u16, read_u32, read_u64, read_usize,
+};
+use crate::vba::VbaProject;
+use crate::{
+    Cell, CellErrorType, Chart, Comment, ConditionalFormat, ConditionalFormatType, DataType,
+    DataValidation, Dimensions, DocumentProperties, Hyperlink, LineLayout, Metadata, Range, Reader,
+    Sheet, SheetLayout, SheetType, SheetVisible, StyleId, Styles, Table, TableMetadata,
+    TableReader, ValidationOperator, ValidationType,
+};
+
+#[derive(Debug)]
+/// An enum to handle Xls specific errors
+pub enum XlsError {
+    /// Io error
+    Io(std::io::Error),
+    /// Cfb error
+    Cfb(crate::cfb::CfbError),
+    /// Vba error
+    Vba(crate::vba::VbaError),
+
+    /// Cannot parse formula, stack is too short
+    StackLen,
+    /// Unrecognized data
+    Unrecognized {
+        /// data type
+        typ: &'static str,
+        /// value found
+        val: u8,
+    },
+    /// Workbook is password protected
+    Password,
+    /// Invalid length
+    Len {
+        /// expected length
+        expected: usize,
+        /// found length
+        found: usize,
+        /// length type
+        typ: &'static str,
+    },
+    /// Continue Record is too short
+    ContinueRecordTooShort,
+    /// End of stream
+    EoStream(&'static str),
+
+    /// Invalid Formula
+    InvalidFormula {
+        /// stack size
+        stack_size: usize,
+    },
+    /// Invalid or unknown iftab
+    IfTab(usize),
+    /// Invalid etpg
+    Etpg(u8),
+    /// No vba project
+    NoVba,
+    /// Invalid OfficeArt Record
+    #[cfg(feature = "picture")]
+    Art(&'static str),
+    /// Formula error
+    Formula(crate::formula::FormulaError),
+    /// Worksheet not found
+    WorksheetNotFound(String),
+    /// Table not found
+    TableNotFound(String),
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
+}
+
+from_err!(std::io::Error, XlsError, Io);
+from_err!(crate::cfb::CfbError, XlsError, Cfb);
+from_err!(crate::vba::VbaError, XlsError, Vba);
+from_err!(crate::formula::FormulaError, XlsError, Formula);
+from_err!(crate::crypto::CryptoError, XlsError, Crypto);
+
+impl std::fmt::Display for XlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XlsError::Io(e) => write!(f, "I/O error: {e}"),
+            XlsError::Cfb(e) => write!(f, "Cfb error: {e}"),
+            XlsError::Vba(e) => write!(f, "Vba error: {e}"),
+            XlsError::StackLen => write!(f, "Invalid stack length"),
+            XlsError::Unrecognized { typ, val } => write!(f, "Unrecognized {typ}: 0x{val:0X}"),
+            XlsError::Password => write!(f, "Workbook is password protected"),
+            XlsError::Len {
+                expected,
+                found,
+                typ,
+            } => write!(
+                f,
+                "Invalid {typ} length, expected {expected} maximum, found {found}",
+            ),
+            XlsError::ContinueRecordTooShort => write!(
+                f,
+                "Continued record too short while reading extended string"
+            ),
+            XlsError::EoStream(s) => write!(f, "End of stream '{s}'"),
+            XlsError::InvalidFormula { stack_size } => {
+                write!(f, "Invalid formula (stack size: {stack_size})")
+            }
+            XlsError::IfTab(iftab) => write!(f, "Invalid iftab {iftab:X}"),
+            XlsError::Etpg(etpg) => write!(f, "Invalid etpg {etpg:X}"),
+            XlsError::NoVba => write!(f, "No VBA project"),
+            #[cfg(feature = "picture")]
+            XlsError::Art(s) => write!(f, "Invalid art record '{s}'"),
+            XlsError::Formula(e) => write!(f, "Formula error: {e}"),
+            XlsError::WorksheetNotFound(name) => write!(f, "Worksheet '{name}' not found"),
+            XlsError::TableNotFound(name) => write!(f, "Table '{name}' not found"),
+            XlsError::Crypto(e) => write!(f, "Decryption error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for XlsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            XlsError::Io(e) => Some(e),
+            XlsError::Cfb(e) => Some(e),
+            XlsError::Vba(e) => Some(e),
+            XlsError::Formula(e) => Some(e),
+            XlsError::Crypto(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Options to perform specialized parsing.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct XlsOptions {
+    /// Force a spreadsheet to be interpreted using a particular code page.
+    ///
+    /// XLS files can contain [code page] identifiers. If this identifier is missing or incorrect,
+    /// strings in the parsed spreadsheet may be decoded incorrectly. Setting this field causes
+    /// `calamine::Xls` to interpret strings using the specified code page, which may allow such
+    /// spreadsheets to be decoded properly.
+    ///
+    /// [code page]: https://docs.microsoft.com/en-us/windows/win32/intl/code-page-identifiers
+    pub force_codepage: Option<u16>,
+    /// Only index worksheets when opening the workbook.
+    ///
+    /// By default the workbook stream is read and all worksheets are decoded upfront, the
+    /// stream is kept in memory for comments, styles and the other annotations.
+    /// When set, only the workbook globals are read at open, the worksheets are only
+    /// scanned for their tables: each worksheet substream is read from the file, and
+    /// decoded, on demand, every time it is requested.
+    pub lazy: bool,
+    /// Password of an encrypted workbook.
+    ///
+    /// When not set, workbooks encrypted with Excel's default password, `VelvetSweatshop`,
+    /// are decrypted. Encrypted workbooks fail with `XlsError::Password` if the password
+    /// is missing or wrong.
+    pub password: Option<String>,
+}
+
+/// Data parsed from a single worksheet substream
+struct SheetData {
+    range: Range<DataType>,
+    formula: Range<String>,
+    merge_cells: Vec<Dimensions>,
+}
+
+/// Workbook stream of a xls file
+///
+/// Out of lazy mode the whole stream is read, and decrypted, once when opening the workbook.
+/// In lazy mode only the substream being read is kept, in a buffer reused for the next one.
+struct WorkbookStream {
+    location: Box<StreamLocation>,
+    len: usize,
+    decryptor: Option<Box<XlsDecryptor>>,
+    /// Decrypted bytes of the stream, from `start`
+    data: Vec<u8>,
+    start: usize,
+}
+
+impl WorkbookStream {
+    /// Reads `range` of the stream from the file, unless it has already been read
+    fn load<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        range: std::ops::Range<usize>,
+    ) -> Result<(), XlsError> {
+        if self.start <= range.start && range.end <= self.start + self.data.len() {
+            return Ok(());
+        }
+        self.location.read(reader, range.clone(), &mut self.data)?;
+        self.start = range.start;
+        if let Some(decryptor) = &mut self.decryptor {
+            decrypt_records(decryptor, &mut self.data, self.start);
+        }
+        Ok(())
+    }
+
+    /// Gets `range` of the stream, empty if it has not been read
+    fn get(&self, range: std::ops::Range<usize>) -> &[u8] {
+        match (
+            range.start.checked_sub(self.start),
+            range.end.checked_sub(self.start),
+        ) {
+            (Some(start), Some(end)) => self
+                .data
+                .get(start..min(end, self.data.len()))
+                .unwrap_or_default(),
+            _ => &[],
+        }
+    }
+
+    /// Finds the end of the globals substream, at its EOF record
+    ///
+    /// Only record headers are read, they are never encrypted.
+    fn globals_end<R: Read + Seek>(&self, reader: &mut R) -> Result<usize, XlsError> {
+        const CHUNK_LEN: usize = 64 * 1024;
+        let mut chunk = Vec::new();
+        let mut chunk_start = 0;
+        let mut pos = 0;
+        while pos < self.len {
+            if pos + 4 > chunk_start + chunk.len() {
+                self.location
+                    .read(reader, pos..min(pos + CHUNK_LEN, self.len), &mut chunk)?;
+                chunk_start = pos;
+                if chunk.len() < 4 {
+                    break;
+                }
+            }
+            let header = &chunk[pos - chunk_start..];
+            let typ = read_u16(header);
+            pos += 4 + read_u16(&header[2..]) as usize;
+            if typ == 0x000A {
+                break; // EOF
+            }
+        }
+        Ok(min(pos, self.len))
+    }
+}
+
+/// A struct representing an old xls format file (CFB)
+pub struct Xls<RS> {
+    /// Decoded worksheets, empty in lazy mode
+    sheets: BTreeMap<String, SheetData>,
+    /// Workbook stream, worksheet substreams are located using `sheet_offsets`
+    stream: WorkbookStream,
+    /// BoundSheet8 offsets of each worksheet substream
+    sheet_offsets: BTreeMap<String, usize>,
+    /// Sheet names, in workbook order, as referenced by formulas
+    sheet_names: Vec<String>,
+    strings: Vec<String>,
+    xtis: Vec<Xti>,
+    /// Tables of all the worksheets, needed to decode formulas
+    tables: Vec<TableMetadata>,
+    encoding: XlsEncoding,
+    biff: Biff,
+    vba: Option<VbaProject>,
+    metadata: Metadata,
+    reader: RS,
+    options: XlsOptions,
+    formats: Vec<CellFormat>,
+    is_1904: bool,
+    #[cfg(feature = "picture")]
+    pictures: Option<Vec<(String, Vec<u8>)>>,
+}
+
+impl<RS: Read + Seek> Xls<RS> {
+    /// Creates a new instance using `Options` to inform parsing.
+    ///
+    /// ```
+    /// use calamine::{Xls,XlsOptions};
+    /// # use std::io::Cursor;
+    /// # const BYTES: &'static [u8] = b"";
+    ///
+    /// # fn run() -> Result<Xls<Cursor<&'static [u8]>>, calamine::XlsError> {
+    /// # let reader = std::io::Cursor::new(BYTES);
+    /// let mut options = XlsOptions::default();
+    /// // ...set options...
+    /// let workbook = Xls::new_with_options(reader, options)?;
+    /// # Ok(workbook) }
+    /// # fn main() { assert!(run().is_err()); }
+    /// ```
+    pub fn new_with_options(mut reader: RS, options: XlsOptions) -> Result<Self, XlsError> {
+        let offset_end = reader.seek(SeekFrom::End(0))? as usize;
+        reader.seek(SeekFrom::Start(0))?;
+        let (location, vba, properties) = match Cfb::new(&mut reader, offset_end) {
+            Ok(mut cfb) => {
+                debug!("cfb loaded");
+
+                // Reads vba once for all (better than reading all worksheets once for all)
+                let vba = if cfb.has_directory("_VBA_PROJECT_CUR") {
+                    Some(VbaProject::from_cfb(&mut reader, &mut cfb)?)
+                } else {
+                    None
+                };
+
+                debug!("vba ok");
+
+                // properties are informative, do not fail on unsupported property sets
+                let properties =
+                    read_document_properties(&mut reader, &mut cfb).unwrap_or_else(|e| {
+                        debug!("cannot read document properties: {}", e);
+                        DocumentProperties::default()
+                    });
+
+                // locates workbook and worksheets stream, or early exit
+                let location = cfb
+                    .get_stream_location("Workbook", &mut reader)
+                    .or_else(|_| cfb.get_stream_location("Book", &mut reader))?;
+                (location, vba, properties)
+            }
+            Err(e) => {
+                // BIFF2 to BIFF4 worksheets are not stored in a compound file
+                reader.seek(SeekFrom::Start(0))?;
+                let mut bof = [0; 2];
+                match reader.read_exact(&mut bof).map(|_| read_u16(&bof)) {
+                    Ok(0x0009 | 0x0209 | 0x0409 | 0x0809) => (),
+                    _ => return Err(e.into()),
+                }
+                (
+                    (StreamLocation::File, offset_end),
+                    None,
+                    DocumentProperties::default(),
+                )
+            }
+        };
+
+        let mut xls = Xls {
+            sheets: BTreeMap::new(),
+            stream: WorkbookStream {
+                location: Box::new(location.0),
+                len: location.1,
+                decryptor: None,
+                data: Vec::new(),
+                start: 0,
+            },
+            sheet_offsets: BTreeMap::new(),
+            sheet_names: Vec::new(),
+            strings: Vec::new(),
+            xtis: Vec::new(),
+            tables: Vec::new(),
+            encoding: XlsEncoding::from_codepage(1200)?,
+            biff: Biff::Biff8,
+            vba,
+            reader,
+            metadata: Metadata::default(),
+            options,
+            is_1904: false,
+            formats: Vec::new(),
+            #[cfg(feature = "picture")]
+            pictures: None,
+        };
+
+        xls.metadata.properties = properties;
+        xls.parse_workbook()?;
+
+        debug!("xls parsed");
+
+        Ok(xls)
+    }
+
+    /// Get a cells reader for a given worksheet
+    ///
+    /// Records are decoded on demand, this is the preferred way to read
+    /// large worksheets from a workbook opened in lazy mode.
+    pub fn worksheet_cells_reader<'a>(
+        &'a mut self,
+        name: &str,
+    ) -> Result<XlsCellReader<'a>, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.stream.load(&mut self.reader, range)?;
+        self.cells_reader(name)
+    }
+
+    /// Cells reader of a worksheet of the workbook stream
+    fn cells_reader(&self, name: &str) -> Result<XlsCellReader<'_>, XlsError> {
+        let records = RecordIter {
+            stream: self.stream.get(self.sheet_range(name)?),
+        };
+        XlsCellReader::new(
+            records,
+            &self.strings,
+            &self.formats,
+            self.is_1904,
+            &self.encoding,
+            self.biff,
+            &self.sheet_names,
+            &self.metadata.names,
+            &self.xtis,
+            &self.tables,
+        )
+    }
+
+    /// Position of the substream of a worksheet in the workbook stream
+    fn sheet_range(&self, name: &str) -> Result<std::ops::Range<usize>, XlsError> {
+        let start = *self
+            .sheet_offsets
+            .get(name)
+            .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))?;
+        // the substream ends where the next one starts
+        let end = self
+            .sheet_offsets
+            .values()
+            .copied()
+            .filter(|&pos| pos > start)
+            .min()
+            .map_or(self.stream.len, |end| min(end, self.stream.len));
+        Ok(min(start, end)..end)
+    }
+
+    /// Position of the globals substream in the workbook stream
+    ///
+    /// BIFF2 to BIFF4 worksheets have no globals substream, the whole stream is returned.
+    fn globals_range(&self) -> std::ops::Range<usize> {
+        let end = self
+            .sheet_offsets
+            .values()
+            .copied()
+            .filter(|&pos| pos > 0)
+            .min()
+            .unwrap_or(self.stream.len);
+        0..min(end, self.stream.len)
+    }
+
+    /// Runs `f` with `range` of the workbook stream, read from the file first in lazy mode
+    fn with_stream<T, F>(&mut self, range: std::ops::Range<usize>, f: F) -> Result<T, XlsError>
+    where
+        F: FnOnce(&Self, &[u8]) -> Result<T, XlsError>,
+    {
+        self.stream.load(&mut self.reader, range.clone())?;
+        f(self, self.stream.get(range))
+    }
+
+    /// Reads the shared strings of the workbook globals as text runs
+    fn rich_strings(&self, stream: &[u8]) -> Result<Vec<Vec<TextRun>>, XlsError> {
+        let mut fonts = Vec::new();
+        for record in (RecordIter { stream }) {
+            let mut r = record?;
+            match r.typ {
+                0x0031 => fonts.push(parse_font(&mut r, &self.encoding, self.biff)?),
+                0x00FC => return parse_rich_sst(&mut r, &self.encoding, &fonts),
+                0x000A => break, // EOF
+                _ => (),
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Sheets referenced by the ExternSheet records, by their XTI index
+    fn xti_sheets(&self) -> Vec<Option<SheetRef>> {
+        self.xtis
+            .iter()
+            .map(|xti| {
+                let first = self.sheet_names.get(xti.itab_first as usize)?;
+                let last = self
+                    .sheet_names
+                    .get(xti.itab_last as usize)
+                    .filter(|last| *last != first);
+                Some(SheetRef {
+                    first: first.clone(),
+                    last: last.cloned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Reads the Font records of the workbook globals
+    fn fonts(&self, stream: &[u8]) -> Result<Vec<Font>, XlsError> {
+        let mut fonts = Vec::new();
+        for record in (RecordIter { stream }) {
+            let mut r = record?;
+            match r.typ {
+                0x0031 => fonts.push(parse_font(&mut r, &self.encoding, self.biff)?),
+                0x000A => break, // EOF
+                _ => (),
+            }
+        }
+        Ok(fonts)
+    }
+}
+
+impl<RS: Read + Seek> Reader<RS> for Xls<RS> {
+    type Error = XlsError;
+
+    fn new(reader: RS) -> Result<Self, XlsError> {
+        Self::new_with_options(reader, XlsOptions::default())
+    }
+
+    fn vba_project(&mut self) -> Option<Result<Cow<'_, VbaProject>, XlsError>> {
+        self.vba.as_ref().map(|vba| Ok(Cow::Borrowed(vba)))
+    }
+
+    /// Parses Workbook stream, no need for the relationships variable
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    fn worksheet_range(&mut self, name: &str) -> Result<Range<DataType>, XlsError> {
+        if !self.options.lazy {
+            return self
+                .sheets
+                .get(name)
+                .map(|r| r.range.clone())
+                .ok_or_else(|| XlsError::WorksheetNotFound(name.into()));
+        }
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::with_capacity(cells_reader.dimensions().len().min(1_000_000) as _);
+        while let Some(cell) = cells_reader.next_cell()? {
+            cells.push(cell);
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |xls, stream| {
+            let records = RecordIter { stream };
+            let mut validations = Vec::new();
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    0x000A => break, // EOF
+                    0x01BE => validations.push(parse_data_validation(
+                        r.data,
+                        &xls.sheet_names,
+                        &xls.metadata.names,
+                        &xls.xtis,
+                        &xls.tables,
+                        &xls.encoding,
+                    )?),
+                    _ => (),
+                }
+            }
+            Ok(validations)
+        })
+    }
+
+    fn worksheet_conditional_formats(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<ConditionalFormat>, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |xls, stream| {
+            let records = RecordIter { stream };
+            let mut formats = Vec::new();
+            let mut ranges = Vec::new();
+            let mut depth = 0;
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    0x0809 => depth += 1, // BOF
+                    0x000A => {
+                        // EOF
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    // CondFmt, followed by its CF rules
+                    0x01B0 if r.data.len() >= 14 => {
+                        let count = read_u16(&r.data[12..]) as usize;
+                        ranges = r.data[14..]
+                            .chunks_exact(8)
+                            .take(count)
+                            .map(parse_ref8)
+                            .collect();
+                    }
+                    0x01B1 => {
+                        let mut format = parse_conditional_format(
+                            r.data,
+                            &xls.sheet_names,
+                            &xls.metadata.names,
+                            &xls.xtis,
+                            &xls.tables,
+                            &xls.encoding,
+                        )?;
+                        format.ranges = ranges.clone();
+                        format.priority = formats.len() as u32 + 1;
+                        formats.push(format);
+                    }
+                    _ => (),
+                }
+            }
+            Ok(formats)
+        })
+    }
+
+    /// Charts embedded in BIFF8 substreams are not read
+    fn worksheet_charts(&mut self, name: &str) -> Result<Vec<Chart>, XlsError> {
+        if !self.sheet_offsets.contains_key(name) {
+            return Err(XlsError::WorksheetNotFound(name.into()));
+        }
+        Ok(Vec::new())
+    }
+
+    fn worksheets(&mut self) -> Vec<(String, Range<DataType>)> {
+        if !self.options.lazy {
+            return self
+                .sheets
+                .iter()
+                .map(|(name, sheet)| (name.to_owned(), sheet.range.clone()))
+                .collect();
+        }
+        let names = self.sheet_offsets.keys().cloned().collect::<Vec<_>>();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let ws = self.worksheet_range(&name).ok()?;
+                Some((name, ws))
+            })
+            .collect()
+    }
+
+    fn worksheet_formula(&mut self, name: &str) -> Result<Range<String>, XlsError> {
+        if !self.options.lazy {
+            return self
+                .sheets
+                .get(name)
+                .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))
+                .map(|r| r.formula.clone());
+        }
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cells_reader.next_formula()? {
+            cells.push(cell);
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_ast(&mut self, name: &str) -> Result<Range<Expr>, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |xls, stream| {
+            // sheets are referenced through ExternSheet records in BIFF8, by index before
+            let sheets = match xls.biff {
+                Biff::Biff8 => xls.xti_sheets(),
+                _ => xls
+                    .sheet_names
+                    .iter()
+                    .map(|name| Some(SheetRef::new(name.clone())))
+                    .collect(),
+            };
+            let ctx = RgceContext {
+                kind: xls.biff.rgce_kind(),
+                sheets: &sheets,
+                names: &xls.metadata.names,
+            };
+            let records = RecordIter { stream };
+            let mut cells = Vec::new();
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    // Formula, CellParsedFormula [MS-XLS 2.5.198.3]
+                    0x0006 | 0x0206 | 0x0406 => {
+                        let fmla = parse_formula_record(&r, xls.biff)?;
+                        let pos = (fmla.row, fmla.col);
+                        let expr = or_unrecognized(ctx.parse(fmla.rgce, fmla.extra), pos);
+                        if expr != Expr::Missing {
+                            cells.push(Cell::new(pos, expr));
+                        }
+                    }
+                    0x000A => break, // EOF
+                    _ => (),
+                }
+            }
+            Ok(Range::from_sparse(cells))
+        })
+    }
+
+    fn merged_regions(&mut self, name: &str) -> Result<Vec<Dimensions>, XlsError> {
+        if !self.options.lazy {
+            return self
+                .sheets
+                .get(name)
+                .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))
+                .map(|r| r.merge_cells.clone());
+        }
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |_, stream| {
+            let mut merge_cells = Vec::new();
+            for record in (RecordIter { stream }) {
+                let r = record?;
+                match r.typ {
+                    0x00E5 => merge_cells.extend(parse_merge_cells(r.data)?), // 229: MergeCells
+                    0x000A => break,                                          // 10: EOF
+                    _ => (),
+                }
+            }
+            Ok(merge_cells)
+        })
+    }
+
+    fn styles(&mut self) -> Result<Styles, XlsError> {
+        self.with_stream(self.globals_range(), |xls, stream| {
+            let mut formats = BTreeMap::new();
+            let mut fonts = Vec::new();
+            let mut styles = Vec::new();
+            for record in (RecordIter { stream }) {
+                let mut r = record?;
+                match r.typ {
+                    // Font
+                    0x0031 => fonts.push(parse_font(&mut r, &xls.encoding, xls.biff)?),
+                    // Format
+                    0x041E => {
+                        let (idx, format) = parse_format(&mut r, &xls.encoding, xls.biff)?;
+                        formats.insert(idx.unwrap_or(formats.len() as u16), format);
+                    }
+                    // XF
+                    0x00E0 => styles.push(parse_xf_style(r.data, &formats, &fonts)?),
+                    0x000A => break, // EOF
+                    _ => (),
+                }
+            }
+            Ok(Styles::new(styles))
+        })
+    }
+
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |_, stream| {
+            let records = RecordIter { stream };
+            let mut cells = Vec::new();
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    // Blank, Number, Label, BoolErr, RK, LabelSst, Formula
+                    0x0201 | 0x0203 | 0x0204 | 0x0205 | 0x027E | 0x00FD | 0x0006
+                        if r.data.len() >= 6 =>
+                    {
+                        cells.push(Cell::new(
+                            (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32),
+                            StyleId(read_u16(&r.data[4..]) as u32),
+                        ));
+                    }
+                    // MulRk, MulBlank
+                    0x00BD | 0x00BE if r.data.len() >= 6 => {
+                        let row = read_u16(r.data) as u32;
+                        let col_first = read_u16(&r.data[2..]) as u32;
+                        let step = if r.typ == 0x00BD { 6 } else { 2 };
+                        let ixfes = &r.data[4..r.data.len() - 2];
+                        cells.extend(
+                            ixfes
+                                .chunks_exact(step)
+                                .zip(col_first..)
+                                .map(|(c, col)| Cell::new((row, col), StyleId(read_u16(c) as u32))),
+                        );
+                    }
+                    0x000A => break, // EOF
+                    _ => (),
+                }
+            }
+            Ok(Range::from_sparse(cells))
+        })
+    }
+
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, XlsError> {
+        let fonts = self.with_stream(self.globals_range(), |xls, stream| xls.fonts(stream))?;
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |xls, stream| {
+            let records = RecordIter { stream };
+            let mut obj_id = None;
+            let mut texts = HashMap::new();
+            let mut comments = Vec::new();
+            // embedded charts have their own BOF/EOF substreams
+            let mut depth = 0;
+            for record in records {
+                let mut r = record?;
+                match r.typ {
+                    0x0809 => depth += 1, // BOF
+                    0x000A => {
+                        // EOF
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    // Obj, starting with a FtCmo subrecord
+                    0x005D if r.data.len() >= 8 => obj_id = Some(read_u16(&r.data[6..])),
+                    // TxO
+                    0x01B6 => {
+                        if let Some(id) = obj_id.take() {
+                            texts.insert(id, parse_txo(&mut r, &xls.encoding, &fonts)?);
+                        }
+                    }
+                    // Note
+                    0x001C if r.data.len() >= 8 => match xls.biff {
+                        Biff::Biff8 => {
+                            let position = (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32);
+                            let id = read_u16(&r.data[6..]);
+                            let author = parse_string(&r.data[8..], &xls.encoding, xls.biff)?;
+                            comments.push((position, author, id));
+                        }
+                        _ => {
+                            // the text is in the record, longer texts continue in Note records
+                            // with a row of 0xFFFF
+                            let row = read_u16(r.data);
+                            let cch = (read_u16(&r.data[4..]) as usize).min(r.data.len() - 6);
+                            let mut text = String::new();
+                            let _ = xls.encoding.decode_to(&r.data[6..], cch, &mut text, None);
+                            if row != 0xFFFF {
+                                let position = (row as u32, read_u16(&r.data[2..]) as u32);
+                                comments.push((position, String::new(), comments.len() as u16));
+                            }
+                            if let Some(&(_, _, id)) = comments.last() {
+                                let runs =
+                                    texts.entry(id).or_insert_with(|| vec![TextRun::default()]);
+                                runs[0].text.push_str(&text);
+                            }
+                        }
+                    },
+                    _ => (),
+                }
+            }
+            Ok(comments
+                .into_iter()
+                .map(|(position, author, id)| {
+                    Comment::new(position, author, texts.remove(&id).unwrap_or_default())
+                })
+                .collect())
+        })
+    }
+
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<Vec<TextRun>>, XlsError> {
+        let strings =
+            self.with_stream(self.globals_range(), |xls, stream| xls.rich_strings(stream))?;
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |xls, stream| {
+            let records = RecordIter { stream };
+            let mut cells = Vec::new();
+            let mut depth = 0;
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    0x0809 => depth += 1, // BOF
+                    0x000A => {
+                        // EOF
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    // LabelSst
+                    0x00FD if r.data.len() >= 10 => {
+                        let position = (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32);
+                        match strings.get(read_u32(&r.data[6..]) as usize) {
+                            Some(runs) if !runs.is_empty() => {
+                                cells.push(Cell::new(position, runs.clone()))
+                            }
+                            _ => (),
+                        }
+                    }
+                    // Label
+                    0x0204 => {
+                        if let Some(Cell {
+                            pos,
+                            val: DataType::String(text),
+                        }) = parse_label(r.data, &xls.encoding, xls.biff)?
+                        {
+                            if !text.is_empty() {
+                                cells.push(Cell::new(pos, vec![TextRun { text, font: None }]));
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Ok(Range::from_sparse(cells))
+        })
+    }
+
+    fn worksheet_layout(&mut self, name: &str) -> Result<SheetLayout, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |_, stream| {
+            let records = RecordIter { stream };
+            let mut layout = SheetLayout::default();
+            let mut depth = 0;
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    0x0809 => depth += 1, // BOF
+                    0x000A => {
+                        // EOF
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    // DefaultRowHeight
+                    0x0225 if r.data.len() >= 4 => {
+                        layout.default_row_height = Some(read_u16(&r.data[2..]) as f64 / 20.)
+                    }
+                    // DefColWidth
+                    0x0055 if r.data.len() >= 2 => {
+                        layout.default_column_width = Some(read_u16(r.data) as f64)
+                    }
+                    // ColInfo
+                    0x007D if r.data.len() >= 10 => {
+                        let flags = read_u16(&r.data[8..]);
+                        layout.push_columns(LineLayout {
+                            first: read_u16(r.data) as u32,
+                            last: read_u16(&r.data[2..]) as u32,
+                            hidden: flags & 0x1 != 0,
+                            outline_level: ((flags >> 8) & 0x7) as u8,
+                            size: Some(read_u16(&r.data[4..]) as f64 / 256.),
+                        });
+                    }
+                    // Row
+                    0x0208 if r.data.len() >= 16 => {
+                        let row = read_u16(r.data) as u32;
+                        let flags = read_u16(&r.data[12..]);
+                        let outline_level = (flags & 0x7) as u8;
+                        let hidden = flags & 0x20 != 0;
+                        let custom_height = flags & 0x40 != 0;
+                        if hidden || outline_level > 0 || custom_height {
+                            layout.push_rows(LineLayout {
+                                first: row,
+                                last: row,
+                                hidden,
+                                outline_level,
+                                size: Some((read_u16(&r.data[6..]) & 0x7FFF) as f64 / 20.),
+                            });
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Ok(layout)
+        })
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, XlsError> {
+        let range = self.sheet_range(name)?;
+        self.with_stream(range, |xls, stream| {
+            let records = RecordIter { stream };
+            let mut links: Vec<Hyperlink> = Vec::new();
+            let mut depth = 0;
+            for record in records {
+                let r = record?;
+                match r.typ {
+                    0x0809 => depth += 1, // BOF
+                    0x000A => {
+                        // EOF
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    0x01B8 => links.push(parse_hyperlink(r.data, &xls.encoding)?),
+                    // HLinkTooltip, with a FrtRefHeaderNoGrbit
+                    0x0800 if r.data.len() >= 10 => {
+                        let range = parse_ref8(&r.data[2..]);
+                        if let Some(link) = links.iter_mut().rev().find(|l| l.range == range) {
+                            link.tooltip = Some(utf16_to_string(&r.data[10..]));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Ok(links)
+        })
+    }
+
+    #[cfg(feature = "picture")]
+    fn pictures(&self) -> Option<Vec<(String, Vec<u8>)>> {
+        self.pictures.to_owned()
+    }
+}
+
+impl<RS: Read + Seek> TableReader<RS> for Xls<RS> {
+    /// Tables are read with the workbook
+    fn load_tables(&mut self) -> Result<(), XlsError> {
+        Ok(())
+    }
+
+    fn table_names(&self) -> Vec<&String> {
+        self.tables.iter().map(|t| &t.name).collect()
+    }
+
+    fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        self.tables
+            .iter()
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    fn table_by_name(&mut self, table_name: &str) -> Result<Table<DataType>, XlsError> {
+        let table = self
+            .tables
+            .iter()
+            .find(|t| t.name == table_name)
+            .cloned()
+            .ok_or_else(|| XlsError::TableNotFound(table_name.into()))?;
+        let range = self.worksheet_range(&table.sheet_name)?;
+        Ok(table.table(&range))
+    }
+
+    fn worksheet_formula_resolved(&mut self, name: &str) -> Result<Range<String>, XlsError> {
+        let formulas = self.worksheet_formula(name)?;
+        Ok(resolve_table_references(formulas, name, &self.tables))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Xti {
+    _isup_book: u16,
+    itab_first: i16,
+    itab_last: i16,
+}
+
+impl<RS: Read + Seek> Xls<RS> {
+    fn parse_workbook(&mut self) -> Result<(), XlsError> {
+        let globals = if self.options.lazy {
+            0..self.stream.globals_end(&mut self.reader)?
+        } else {
+            0..self.stream.len
+        };
+        self.stream.load(&mut self.reader, globals)?;
+        self.stream.decryptor = self.decryptor(&self.stream.data)?;
+        if let Some(decryptor) = &mut self.stream.decryptor {
+            decrypt_records(decryptor, &mut self.stream.data, 0);
+        }
+
+        let mut sheet_names = Vec::new();
+        let mut strings = Vec::new();
+        let mut defined_names = Vec::new();
+        let mut legacy_names = Vec::new();
+        let mut bof_dt = None;
+        let mut xtis = Vec::new();
+        let mut formats = BTreeMap::new();
+        let mut xfs = Vec::new();
+        let mut biff = Biff::Biff8; // Binary Interchange File Format (BIFF) version
+        let codepage = self.options.force_codepage.unwrap_or(1200);
+        let mut encoding = XlsEncoding::from_codepage(codepage)?;
+        #[cfg(feature = "picture")]
+        let mut draw_group: Vec<u8> = Vec::new();
+        {
+            let records = RecordIter {
+                stream: &self.stream.data,
+            };
+            for record in records {
+                let mut r = record?;
+                match r.typ {
+                    // CodePage
+                    0x0042 if self.options.force_codepage.is_none() => {
+                        encoding = XlsEncoding::from_codepage(match read_u16(r.data) {
+                            // BIFF2 to BIFF4 Apple Roman and ANSI
+                            0x8000 => 10000,
+                            0x8001 => 1252,
+                            codepage => codepage,
+                        })?
+                    }
+                    0x013D => {
+                        let sheet_len = r.data.len() / 2;
+                        sheet_names.reserve(sheet_len);
+                        self.metadata.sheets.reserve(sheet_len);
+                    }
+                    // Date1904
+                    0x0022 if read_u16(r.data) == 1 => self.is_1904 = true,
+                    // FORMATTING, indexed by their order before BIFF5
+                    0x001E | 0x041E => {
+                        let (idx, format) = parse_format(&mut r, &encoding, biff)?;
+                        let idx = idx.unwrap_or(formats.len() as u16);
+                        formats.insert(idx, detect_custom_number_format(&format));
+                    }
+                    // XFS
+                    0x0043 | 0x0243 | 0x0443 | 0x00E0 => {
+                        xfs.push(parse_xf(&r)?);
+                    }
+                    // RRTabId
+                    0x0085 => {
+                        let (pos, sheet) = parse_sheet_metadata(&mut r, &encoding, biff)?;
+                        self.metadata.sheets.push(sheet.clone());
+                        sheet_names.push((pos, sheet.name)); // BoundSheet8
+                    }
+                    // BOF
+                    0x0009 | 0x0209 | 0x0409 | 0x0809 => {
+                        let bof = parse_bof(&mut r)?;
+                        biff = bof.biff;
+                        bof_dt.get_or_insert(bof.dt);
+                        // ANSI strings, unless there is a CodePage record
+                        if !matches!(biff, Biff::Biff8) && self.options.force_codepage.is_none() {
+                            encoding = XlsEncoding::from_codepage(1252)?;
+                        }
+                    }
+                    // Name, before BIFF8
+                    0x0018 | 0x0218 if !matches!(biff, Biff::Biff8) => {
+                        legacy_names.push(parse_legacy_name(r.data, &encoding, biff)?);
+                    }
+                    0x0018 => {
+                        // Lbl for defined_names
+                        let cch = r.data[3] as usize;
+                        let cce = read_u16(&r.data[4..]) as usize;
+                        let mut name = String::new();
+                        read_unicode_string_no_cch(&encoding, &r.data[14..], &cch, &mut name);
+                        let rgce = &r.data[r.data.len() - cce..];
+                        let formula = parse_defined_names(rgce)?;
+                        defined_names.push((name, formula));
+                    }
+                    0x0017 if matches!(biff, Biff::Biff8) => {
+                        // ExternSheet
+                        let cxti = read_u16(r.data) as usize;
+                        xtis.extend(r.data[2..].chunks(6).take(cxti).map(|xti| Xti {
+                            _isup_book: read_u16(&xti[..2]),
+                            itab_first: read_i16(&xti[2..4]),
+                            itab_last: read_i16(&xti[4..]),
+                        }));
+                    }
+                    0x00FC => strings = parse_sst(&mut r, &encoding)?, // SST
+                    #[cfg(feature = "picture")]
+                    0x00EB => {
+                        // MsoDrawingGroup
+                        draw_group.extend(r.data);
+                        if let Some(cont) = r.cont {
+                            draw_group.extend(cont.iter().flat_map(|v| *v));
+                        }
+                    }
+                    0x000A => break, // EOF,
+                    _ => (),
+                }
+            }
+        }
+
+        self.formats = xfs
+            .into_iter()
+            .map(|fmt| match formats.get(&fmt) {
+                Some(s) => *s,
+                _ => builtin_format_by_code(fmt),
+            })
+            .collect();
+
+        debug!("formats: {:?}", self.formats);
+
+        let defined_names = defined_names
+            .into_iter()
+            .map(|(name, (i, mut f))| {
+                if let Some(i) = i {
+                    let sh = xtis
+                        .get(i)
+                        .and_then(|xti| sheet_names.get(xti.itab_first as usize))
+                        .map_or("#REF", |sh| &sh.1);
+                    f = format!("{sh}!{f}");
+                }
+                (name, f)
+            })
+            .collect::<Vec<_>>();
+        let defined_names = if legacy_names.is_empty() {
+            defined_names
+        } else {
+            // names may reference each other, by index
+            let names = legacy_names
+                .iter()
+                .map(|(name, _)| (name.clone(), String::new()))
+                .collect::<Vec<_>>();
+            let sheets = sheet_names
+                .iter()
+                .map(|(_, name)| Some(SheetRef::new(name.clone())))
+                .collect::<Vec<_>>();
+            let ctx = RgceContext {
+                kind: biff.rgce_kind(),
+                sheets: &sheets,
+                names: &names,
+            };
+            legacy_names
+                .into_iter()
+                .map(|(name, rgce)| {
+                    let formula = ctx
+                        .parse(&rgce, &[])
+                        .map_or_else(|e| format!("Unrecognised formula: {e}"), |e| e.to_string());
+                    (name, formula)
+                })
+                .collect()
+        };
+
+        debug!("defined_names: {:?}", defined_names);
+
+        // BIFF2 to BIFF4 files hold a single worksheet, without BoundSheet8
+        if sheet_names.is_empty() && bof_dt == Some(0x0010) {
+            let sheet = Sheet {
+                name: "Sheet1".to_string(),
+                visible: SheetVisible::Visible,
+                typ: SheetType::WorkSheet,
+            };
+            sheet_names.push((0, sheet.name.clone()));
+            self.metadata.sheets.push(sheet);
+        }
+
+        self.sheet_names = sheet_names.iter().map(|(_, n)| n.clone()).collect();
+        self.sheet_offsets = sheet_names
+            .into_iter()
+            .map(|(pos, name)| (name, pos))
+            .collect();
+        self.strings = strings;
+        self.xtis = xtis;
+        self.encoding = encoding;
+        self.biff = biff;
+        self.metadata.names = defined_names;
+        self.tables = self.read_tables()?;
+
+        if !self.options.lazy {
+            let mut sheets = BTreeMap::new();
+            for name in self.sheet_offsets.keys() {
+                let mut cells_reader = self.cells_reader(name)?;
+                let (range, formula) = cells_reader.read_all()?;
+                sheets.insert(
+                    name.clone(),
+                    SheetData {
+                        range,
+                        formula,
+                        merge_cells: cells_reader.merge_cells().to_vec(),
+                    },
+                );
+            }
+            self.sheets = sheets;
+        }
+
+        #[cfg(feature = "picture")]
+        if !draw_group.is_empty() {
+            let pics = parse_pictures(&draw_group)?;
+            if !pics.is_empty() {
+                self.pictures = Some(pics);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decryptor of the workbook stream, if its first BOF record is followed
+    /// by a FilePass record [MS-XLS 2.2.10]
+    fn decryptor(&self, stream: &[u8]) -> Result<Option<Box<XlsDecryptor>>, XlsError> {
+        let mut pos = 0;
+        let mut next_record = || {
+            let typ = stream.get(pos..pos + 2).map(read_u16)?;
+            let len = stream.get(pos + 2..pos + 4).map(read_u16)? as usize;
+            let data = pos + 4..min(pos + 4 + len, stream.len());
+            pos = data.end;
+            Some((typ, data))
+        };
+        let file_pass = match (next_record(), next_record()) {
+            // 2.4.117 FilePass
+            (Some(_), Some((0x002F, data))) => data,
+            _ => return Ok(None),
+        };
+        let password = self
+            .options
+            .password
+            .as_deref()
+            .unwrap_or("VelvetSweatshop");
+        match XlsDecryptor::new(&stream[file_pass], password) {
+            Err(CryptoError::InvalidPassword) => Err(XlsError::Password),
+            decryptor => Ok(Some(Box::new(decryptor?))),
+        }
+    }
+
+    /// Reads the tables of all the worksheets
+    fn read_tables(&mut self) -> Result<Vec<TableMetadata>, XlsError> {
+        let mut tables = Vec::new();
+        let names = self.sheet_offsets.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            let range = self.sheet_range(&name)?;
+            self.with_stream(range, |xls, stream| {
+                let mut depth = 0;
+                for record in (RecordIter { stream }) {
+                    let r = record?;
+                    match r.typ {
+                        0x0809 => depth += 1, // BOF
+                        0x000A => {
+                            // EOF
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        // Feature11, Feature12
+                        0x0872 | 0x0878 => {
+                            tables.extend(parse_table_feature(r.data, &name, &xls.encoding)?)
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(tables)
+    }
+}
+
+/// Decrypts in place the records of a part of the workbook stream, starting at `start`
+/// in the stream on a record boundary
+fn decrypt_records(decryptor: &mut XlsDecryptor, data: &mut [u8], start: usize) {
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let typ = read_u16(&data[pos..]);
+        let len = read_u16(&data[pos + 2..]) as usize;
+        let record = pos + 4..min(pos + 4 + len, data.len());
+        let skip = match typ {
+            // BOF, FilePass, UsrExcl, FileLock, InterfaceHdr, RRDInfo and RRDHead
+            0x0009 | 0x0209 | 0x0409 | 0x0809 | 0x002F | 0x0194 | 0x0195 | 0x00E1 | 0x0196
+            | 0x0138 => len,
+            // BoundSheet8 lbPlyPos
+            0x0085 => 4,
+            _ => 0,
+        };
+        pos = record.end;
+        if skip < record.len() {
+            decryptor.decrypt(&mut data[record.clone()], start + record.start, skip);
+        }
+    }
+}
+
+/// https://learn.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/4d6a3d1e-d7c5-405f-bbae-d01e9cb79366
+struct Bof {
+    /// Binary Interchange File Format
+    biff: Biff,
+    /// Type of the substream, e.g. 0x0010 for a worksheet
+    dt: u16,
+}
+
+/// https://www.loc.gov/preservation/digital/formats/fdd/fdd000510.shtml#notes
+#[derive(Clone, Copy)]
+pub(crate) enum Biff {
+    Biff2,
+    Biff3,
+    Biff4,
+    Biff5,
+    Biff8,
+    // Used by MS-XLSB Workbook(2.1.7.61) or Worksheet(2.1.7.61) which are not supported yet.
+    // Biff12,
+}
+
+impl Biff {
+    /// Formula flavour of the version
+    fn rgce_kind(self) -> RgceKind {
+        match self {
+            Biff::Biff2 => RgceKind::Biff2,
+            Biff::Biff3 => RgceKind::Biff3,
+            Biff::Biff4 => RgceKind::Biff4,
+            Biff::Biff5 => RgceKind::Biff5,
+            Biff::Biff8 => RgceKind::Biff8,
+        }
+    }
+}
+
+/// BOF [MS-XLS] 2.4.21
+fn parse_bof(r: &mut Record<'_>) -> Result<Bof, XlsError> {
+    let mut dt = 0;
+    let biff_version = read_u16(&r.data[..2]);
+
+    if r.data.len() >= 4 {
+        dt = read_u16(&r.data[2..]);
+    };
+
+    let biff = match (r.typ, biff_version) {
+        // BIFF2 to BIFF4 BOF records have their own type
+        (0x0009, _) => Biff::Biff2,
+        (0x0209, _) => Biff::Biff3,
+        (0x0409, _) => Biff::Biff4,
+        (_, 0x0200 | 0x0002 | 0x0007) => Biff::Biff2,
+        (_, 0x0300) => Biff::Biff3,
+        (_, 0x0400) => Biff::Biff4,
+        (_, 0x0500) => Biff::Biff5,
+        (_, 0x0600) => Biff::Biff8,
+        (_, 0) => {
+            if dt == 0x1000 {
+                Biff::Biff5
+            } else {
+                Biff::Biff8
+            }
+        }
+        _ => Biff::Biff8,
+    };
+
+    Ok(Bof { biff, dt })
+}
+
+/// BoundSheet8 [MS-XLS 2.4.28]
+fn parse_sheet_metadata(
+    r: &mut Record<'_>,
+    encoding: &XlsEncoding,
+    biff: Biff,
+) -> Result<(usize, Sheet), XlsError> {
+    let pos = read_u32(r.data) as usize;
+    let visible = match r.data[4] & 0b0011_1111 {
+        0x00 => SheetVisible::Visible,
+        0x01 => SheetVisible::Hidden,
+        0x02 => SheetVisible::VeryHidden,
+        e => {
+            return Err(XlsError::Unrecognized {
+                typ: "BoundSheet8:hsState",
+                val: e,
+            });
+        }
+    };
+    let typ = match r.data[5] {
+        0x00 => SheetType::WorkSheet,
+        0x01 => SheetType::MacroSheet,
+        0x02 => SheetType::ChartSheet,
+        0x06 => SheetType::Vba,
+        e => {
+            return Err(XlsError::Unrecognized {
+                typ: "BoundSheet8:dt",
+                val: e,
+            });
+        }
+    };
+    r.data = &r.data[6..];
+    let name = parse_short_string(r, encoding, biff)?;
+    let sheet_name = name
+        .as_bytes()
+        .iter()
+        .cloned()
+        .filter(|b| *b != 0)
+        .collect::<Vec<_>>();
+    let name = String::from_utf8(sheet_name).unwrap();
+    Ok((pos, Sheet { name, visible, typ }))
+}
+
+fn parse_number(
+    r: &[u8],
+    formats: &[CellFormat],
+    is_1904: bool,
+) -> Result<Cell<DataType>, XlsError> {
+    if r.len() < 14 {
+        return Err(XlsError::Len {
+            typ: "number",
+            expected: 14,
+            found: r.len(),
+        });
+    }
+    let row = read_u16(r) as u32;
+    let col = read_u16(&r[2..]) as u32;
+    let v = read_f64(&r[6..]);
+    let format = formats.get(read_u16(&r[4..]) as usize);
+
+    Ok(Cell::new((row, col), format_excel_f64(v, format, is_1904)))
+}
+
+fn parse_bool_err(r: &[u8]) -> Result<Cell<DataType>, XlsError> {
+    if r.len() < 8 {
+        return Err(XlsError::Len {
+            typ: "BoolErr",
+            expected: 8,
+            found: r.len(),
+        });
+    }
+    let row = read_u16(r);
+    let col = read_u16(&r[2..]);
+    let pos = (row as u32, col as u32);
+    match r[7] {
+        0x00 => Ok(Cell::new(pos, DataType::Bool(r[6] != 0))),
+        0x01 => Ok(Cell::new(pos, parse_err(r[6])?)),
+        e => Err(XlsError::Unrecognized {
+            typ: "fError",
+            val: e,
+        }),
+    }
+}
+
+fn parse_err(e: u8) -> Result<DataType, XlsError> {
+    match e {
+        0x00 => Ok(DataType::Error(CellErrorType::Null)),
+        0x07 => Ok(DataType::Error(CellErrorType::Div0)),
+        0x0F => Ok(DataType::Error(CellErrorType::Value)),
+        0x17 => Ok(DataType::Error(CellErrorType::Ref)),
+        0x1D => Ok(DataType::Error(CellErrorType::Name)),
+        0x24 => Ok(DataType::Error(CellErrorType::Num)),
+        0x2A => Ok(DataType::Error(CellErrorType::NA)),
+        0x2B => Ok(DataType::Error(CellErrorType::GettingData)),
+        e => Err(XlsError::Unrecognized {
+            typ: "error",
+            val: e,
+        }),
+    }
+}
+
+fn parse_rk(r: &[u8], formats: &[CellFormat], is_1904: bool) -> Result<Cell<DataType>, XlsError> {
+    if r.len() < 10 {
+        return Err(XlsError::Len {
+            typ: "rk",
+            expected: 10,
+            found: r.len(),
+        });
+    }
+    let row = read_u16(r);
+    let col = read_u16(&r[2..]);
+
+    Ok(Cell::new(
+        (row as u32, col as u32),
+        rk_num(&r[4..10], formats, is_1904),
+    ))
+}
+
+fn parse_mul_rk(
+    r: &[u8],
+    cells: &mut Vec<Cell<DataType>>,
+    formats: &[CellFormat],
+    is_1904: bool,
+) -> Result<(), XlsError> {
+    if r.len() < 6 {
+        return Err(XlsError::Len {
+            typ: "rk",
+            expected: 6,
+            found: r.len(),
+        });
+    }
+
+    let row = read_u16(r);
+    let col_first = read_u16(&r[2..]);
+    let col_last = read_u16(&r[r.len() - 2..]);
+
+    if r.len() != 6 + 6 * (col_last - col_first + 1) as usize {
+        return Err(XlsError::Len {
+            typ: "rk",
+            expected: 6 + 6 * (col_last - col_first + 1) as usize,
+            found: r.len(),
+        });
+    }
+
+    for (col, rk) in (col_first as u32..).zip(r[4..r.len() - 2].chunks(6)) {
+        cells.push(Cell::new((row as u32, col), rk_num(rk, formats, is_1904)));
+    }
+    Ok(())
+}
+
+fn rk_num(rk: &[u8], formats: &[CellFormat], is_1904: bool) -> DataType {
+    let d100 = (rk[2] & 1) != 0;
+    let is_int = (rk[2] & 2) != 0;
+    let format = formats.get(read_u16(rk) as usize);
+
+    let mut v = [0u8; 8];
+    v[4..].copy_from_slice(&rk[2..]);
+    v[4] &= 0xFC;
+    if is_int {
+        let v = (read_i32(&v[4..8]) >> 2) as i64;
+        if d100 && v % 100 != 0 {
+            format_excel_f64(v as f64 / 100.0, format, is_1904)
+        } else {
+            format_excel_i64(if d100 { v / 100 } else { v }, format, is_1904)
+        }
+    } else {
+        let v = read_f64(&v);
+        format_excel_f64(if d100 { v / 100.0 } else { v }, format, is_1904)
+    }
+}
+
+/// ShortXLUnicodeString [MS-XLS 2.5.240]
+fn parse_short_string(
+    r: &mut Record<'_>,
+    encoding: &XlsEncoding,
+    biff: Biff,
+) -> Result<String, XlsError> {
+    if r.data.len() < 2 {
+        return Err(XlsError::Len {
+            typ: "short string",
+            expected: 2,
+            found: r.data.len(),
+        });
+    }
+
+    let cch = r.data[0] as usize;
+    r.data = &r.data[1..];
+    let mut high_byte = None;
+
+    if matches!(biff, Biff::Biff8) {
+        high_byte = Some(r.data[0] & 0x1 != 0);
+        r.data = &r.data[1..];
+    }
+
+    let mut s = String::with_capacity(cch);
+    let _ = encoding.decode_to(r.data, cch, &mut s, high_byte);
+    Ok(s)
+}
+
+/// XLUnicodeString [MS-XLS 2.5.294]
+///
+/// Before BIFF8 strings are 8-bit only, with a 1 byte length in BIFF2
+fn parse_string(r: &[u8], encoding: &XlsEncoding, biff: Biff) -> Result<String, XlsError> {
+    let expected = match biff {
+        Biff::Biff2 => 1,
+        Biff::Biff3 | Biff::Biff4 | Biff::Biff5 => 2,
+        Biff::Biff8 => 4,
+    };
+    if r.len() < expected {
+        return Err(XlsError::Len {
+            typ: "string",
+            expected,
+            found: r.len(),
+        });
+    }
+
+    let (cch, high_byte, start) = match biff {
+        Biff::Biff2 => (r[0] as usize, None, 1),
+        Biff::Biff3 | Biff::Biff4 | Biff::Biff5 => (read_u16(r) as usize, None, 2),
+        Biff::Biff8 => (read_u16(r) as usize, Some(r[2] & 0x1 != 0), 3),
+    };
+
+    let mut s = String::with_capacity(cch);
+    let _ = encoding.decode_to(&r[start..], cch, &mut s, high_byte);
+    Ok(s)
+}
+
+fn parse_label(
+    r: &[u8],
+    encoding: &XlsEncoding,
+    biff: Biff,
+) -> Result<Option<Cell<DataType>>, XlsError> {
+    if r.len() < 6 {
+        return Err(XlsError::Len {
+            typ: "label",
+            expected: 6,
+            found: r.len(),
+        });
+    }
+    let row = read_u16(r);
+    let col = read_u16(&r[2..]);
+    let _ixfe = read_u16(&r[4..]);
+    Ok(Some(Cell::new(
+        (row as u32, col as u32),
+        DataType::String(parse_string(&r[6..], encoding, biff)?),
+    )))
+}
+
+fn parse_label_sst(r: &[u8], strings: &[String]) -> Result<Option<Cell<DataType>>, XlsError> {
+    if r.len() < 10 {
+        return Err(XlsError::Len {
+            typ: "label sst",
+            expected: 10,
+            found: r.len(),
+        });
+    }
+    let row = read_u16(r);
+    let col = read_u16(&r[2..]);
+    let i = read_u32(&r[6..]) as usize;
+    if let Some(s) = strings.get(i) {
+        if !s.is_empty() {
+            return Ok(Some(Cell::new(
+                (row as u32, col as u32),
+                DataType::String(s.clone()),
+            )));
+        }
+    }
+    Ok(None)
+}
+
+fn parse_dimensions(r: &[u8]) -> Result<Dimensions, XlsError> {
+    let (rf, rl, cf, cl) = match r.len() {
+        // BIFF2 to BIFF4 records may not be padded
+        8 | 10 => (
+            read_u16(&r[0..2]) as u32,
+            read_u16(&r[2..4]) as u32,
+            read_u16(&r[4..6]) as u32,
+            read_u16(&r[6..8]) as u32,
+        ),
+        14 => (
+            read_u32(&r[0..4]),
+            read_u32(&r[4..8]),
+            read_u16(&r[8..10]) as u32,
+            read_u16(&r[10..12]) as u32,
+        ),
+        _ => {
+            return Err(XlsError::Len {
+                typ: "dimensions",
+                expected: 14,
+                found: r.len(),
+            });
+        }
+    };
+    if 1 <= rl && 1 <= cl {
+        Ok(Dimensions {
+            start: (rf, cf),
+            end: (rl - 1, cl - 1),
+        })
+    } else {
+        Ok(Dimensions {
+            start: (rf, cf),
+            end: (rf, cf),
+        })
+    }
+}
+
+/// MergeCells [MS-XLS 2.4.168]
+fn parse_merge_cells(r: &[u8]) -> Result<Vec<Dimensions>, XlsError> {
+    if r.len() < 2 {
+        return Err(XlsError::Len {
+            typ: "merge cells",
+            expected: 2,
+            found: r.len(),
+        });
+    }
+    let cmcs = read_u16(r) as usize;
+    if r.len() < 2 + cmcs * 8 {
+        return Err(XlsError::Len {
+            typ: "merge cells",
+            expected: 2 + cmcs * 8,
+            found: r.len(),
+        });
+    }
+    // Ref8: rwFirst, rwLast, colFirst, colLast
+    Ok(r[2..2 + cmcs * 8]
+        .chunks(8)
+        .map(|c| Dimensions {
+            start: (read_u16(c) as u32, read_u16(&c[4..]) as u32),
+            end: (read_u16(&c[2..]) as u32, read_u16(&c[6..]) as u32),
+        })
+        .collect())
+}
+
+fn parse_sst(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<Vec<String>, XlsError> {
+    if r.data.len() < 8 {
+        return Err(XlsError::Len {
+            typ: "sst",
+            expected: 8,
+            found: r.data.len(),
+        });
+    }
+    let len: usize = read_i32(&r.data[4..8]).try_into().unwrap();
+    let mut sst = Vec::with_capacity(len);
+    r.data = &r.data[8..];
+
+    for _ in 0..len {
+        sst.push(read_rich_extended_string(r, encoding, None)?);
+    }
+    Ok(sst)
+}
+
+/// Decode SST, keeping the formatting runs of the strings
+fn parse_rich_sst(
+    r: &mut Record<'_>,
+    encoding: &XlsEncoding,
+    fonts: &[Font],
+) -> Result<Vec<Vec<TextRun>>, XlsError> {
+    if r.data.len() < 8 {
+        return Err(XlsError::Len {
+            typ: "sst",
+            expected: 8,
+            found: r.data.len(),
+        });
+    }
+    let len: usize = read_i32(&r.data[4..8]).try_into().unwrap();
+    let mut sst = Vec::with_capacity(len);
+    r.data = &r.data[8..];
+
+    let mut runs = Vec::new();
+    for _ in 0..len {
+        runs.clear();
+        let s = read_rich_extended_string(r, encoding, Some(&mut runs))?;
+        sst.push(text_runs(&s, &runs, fonts));
+    }
+    Ok(sst)
+}
+
+/// Splits `text` in runs, from their first character and font index
+fn text_runs(text: &str, runs: &[(usize, usize)], fonts: &[Font]) -> Vec<TextRun> {
+    let text = text.chars().collect::<Vec<_>>();
+    let mut bounds = vec![(0, None)];
+    for &(start, ifnt) in runs {
+        // the font with index 4 is omitted
+        let ifnt = if ifnt >= 4 { ifnt - 1 } else { ifnt };
+        bounds.push((start, fonts.get(ifnt)));
+    }
+    let mut text_runs = Vec::with_capacity(bounds.len());
+    for (i, &(start, font)) in bounds.iter().enumerate() {
+        let end = bounds
+            .get(i + 1)
+            .map_or(text.len(), |b| b.0)
+            .min(text.len());
+        if start < end {
+            text_runs.push(TextRun {
+                text: text[start..end].iter().collect(),
+                font: font.cloned(),
+            });
+        }
+    }
+    text_runs
+}
+
+/// Decode XF (extract only ifmt - Format identifier)
+///
+/// See: https://learn.microsoft.com/ru-ru/openspecs/office_file_formats/ms-xls/993d15c4-ec04-43e9-ba36-594dfb336c6d
+fn parse_xf(r: &Record<'_>) -> Result<u16, XlsError> {
+    if r.data.len() < 4 {
+        return Err(XlsError::Len {
+            typ: "xf",
+            expected: 4,
+            found: r.data.len(),
+        });
+    }
+
+    match r.typ {
+        // BIFF2: format in the low 6 bits of the number format and flags byte
+        0x0043 => Ok((r.data[2] & 0x3F) as u16),
+        // BIFF3 and BIFF4: 1 byte format index
+        0x0243 | 0x0443 => Ok(r.data[1] as u16),
+        _ => Ok(read_u16(&r.data[2..])),
+    }
+}
+
+/// Decode Format
+///
+/// See: https://learn.microsoft.com/ru-ru/openspecs/office_file_formats/ms-xls/300280fd-e4fe-4675-a924-4d383af48d3b
+///
+/// Formats have no explicit index before BIFF5, they are numbered in order instead
+fn parse_format(
+    r: &mut Record<'_>,
+    encoding: &XlsEncoding,
+    biff: Biff,
+) -> Result<(Option<u16>, String), XlsError> {
+    let (idx, start) = match biff {
+        Biff::Biff2 | Biff::Biff3 => (None, 0),
+        Biff::Biff4 => (None, 2),
+        Biff::Biff5 | Biff::Biff8 => (r.data.get(..2).map(read_u16), 2),
+    };
+    if !matches!(biff, Biff::Biff8) {
+        let cch = r.data.get(start).copied().unwrap_or_default() as usize;
+        let mut s = String::with_capacity(cch);
+        encoding.decode_to(
+            r.data.get(start + 1..).unwrap_or_default(),
+            cch,
+            &mut s,
+            None,
+        );
+        return Ok((idx, s));
+    }
+    if r.data.len() < 5 {
+        return Err(XlsError::Len {
+            typ: "format",
+            expected: 5,
+            found: r.data.len(),
+        });
+    }
+
+    let cch = read_u16(&r.data[2..]) as usize;
+    let high_byte = r.data[4] & 0x1 != 0;
+    r.data = &r.data[5..];
+    let mut s = String::with_capacity(cch);
+    encoding.decode_to(r.data, cch, &mut s, Some(high_byte));
+
+    Ok((idx, s))
+}
+
+/// Decode a Name record written before BIFF8, into its name and formula tokens
+fn parse_legacy_name(
+    r: &[u8],
+    encoding: &XlsEncoding,
+    biff: Biff,
+) -> Result<(String, Vec<u8>), XlsError> {
+    let expected = match biff {
+        Biff::Biff2 => 4,
+        Biff::Biff5 => 14,
+        _ => 6,
+    };
+    if r.len() < expected {
+        return Err(XlsError::Len {
+            typ: "name",
+            expected,
+            found: r.len(),
+        });
+    }
+    let (cch, cce) = match biff {
+        Biff::Biff2 => (r[2] as usize, r[3] as usize),
+        _ => (r[3] as usize, read_u16(&r[4..]) as usize),
+    };
+    let rgce = &r[min(expected + cch, r.len())..];
+    if rgce.len() < cce {
+        return Err(XlsError::Len {
+            typ: "name formula",
+            expected: cce,
+            found: rgce.len(),
+        });
+    }
+    let mut name = String::with_capacity(cch);
+    encoding.decode_to(&r[expected..], cch, &mut name, None);
+    Ok((name, rgce[..cce].to_vec()))
+}
+
+/// Decode Font
+///
+/// See: https://learn.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/b9ec509a-235d-424e-871d-f8e721106501
+fn parse_font(r: &mut Record<'_>, encoding: &XlsEncoding, biff: Biff) -> Result<Font, XlsError> {
+    if r.data.len() < 15 {
+        return Err(XlsError::Len {
+            typ: "font",
+            expected: 15,
+            found: r.data.len(),
+        });
+    }
+    let grbit = read_u16(&r.data[2..]);
+    let mut font = Font {
+        name: None,
+        size: Some(read_u16(r.data) as f64 / 20.),
+        bold: read_u16(&r.data[6..]) >= 700,
+        italic: grbit & 0x2 != 0,
+        underline: r.data[10] != 0,
+        strike: grbit & 0x8 != 0,
+        color: icv_color(read_u16(&r.data[4..])),
+    };
+    r.data = &r.data[14..];
+    font.name = Some(parse_short_string(r, encoding, biff)?);
+    Ok(font)
+}
+
+/// Color of an Icv palette index, indexes above 63 are system colors
+fn icv_color(icv: u16) -> Option<Color> {
+    match icv {
+        0..=63 => Some(Color::Indexed(icv as u32)),
+        _ => Some(Color::Auto),
+    }
+}
+
+/// Decode a full XF into a `Style`
+///
+/// See: https://learn.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/993d15c4-ec04-43e9-ba36-594dfb336c6d
+fn parse_xf_style(
+    r: &[u8],
+    formats: &BTreeMap<u16, String>,
+    fonts: &[Font],
+) -> Result<Style, XlsError> {
+    if r.len() < 4 {
+        return Err(XlsError::Len {
+            typ: "xf",
+            expected: 4,
+            found: r.len(),
+        });
+    }
+    // the font with index 4 is omitted
+    let ifnt = match read_u16(r) as usize {
+        i @ 0..=3 => i,
+        i => i - 1,
+    };
+    let ifmt = read_u16(&r[2..]);
+    let number_format = formats
+        .get(&ifmt)
+        .cloned()
+        .or_else(|| builtin_format_code(ifmt).map(String::from));
+    let font = fonts.get(ifnt).cloned().unwrap_or_default();
+    if r.len() < 20 {
+        // pre BIFF8 layouts, only the format and the font are decoded
+        return Ok(Style {
+            number_format,
+            font,
+            ..Style::default()
+        });
+    }
+    let borders = read_u32(&r[10..]);
+    let colors = read_u32(&r[14..]);
+    let fills = read_u16(&r[18..]);
+    let side = |dg: u32, icv: u32| match border_style(dg as u8 & 0xF) {
+        Some(style) => BorderSide {
+            style: Some(style.into()),
+            color: icv_color(icv as u16 & 0x7F),
+        },
+        None => BorderSide::default(),
+    };
+    Ok(Style {
+        number_format,
+        font,
+        fill: Fill {
+            pattern: fill_pattern((colors >> 26) as u8).map(String::from),
+            foreground: icv_color(fills & 0x7F),
+            background: icv_color(fills >> 7 & 0x7F),
+        },
+        border: Border {
+            left: side(borders, borders >> 16),
+            right: side(borders >> 4, borders >> 23),
+            top: side(borders >> 8, colors),
+            bottom: side(borders >> 12, colors >> 7),
+        },
+        alignment: Alignment {
+            horizontal: horizontal_alignment(r[6] & 0x7).map(String::from),
+            vertical: vertical_alignment(r[6] >> 4 & 0x7).map(String::from),
+            wrap_text: r[6] & 0x8 != 0,
+            indent: (r[8] & 0xF) as u32,
+            rotation: rotation(r[7]),
+        },
+    })
+}
+
+/// Decode TxO [MS-XLS 2.4.329], with its text and formatting runs in Continue records
+fn parse_txo(
+    r: &mut Record<'_>,
+    encoding: &XlsEncoding,
+    fonts: &[Font],
+) -> Result<Vec<TextRun>, XlsError> {
+    if r.data.len() < 14 {
+        return Err(XlsError::Len {
+            typ: "TxO",
+            expected: 14,
+            found: r.data.len(),
+        });
+    }
+    let cch = read_u16(&r.data[10..]) as usize;
+    let cb_runs = read_u16(&r.data[12..]) as usize;
+    if cch == 0 {
+        return Ok(Vec::new());
+    }
+    if !r.continue_record() || r.data.is_empty() {
+        return Err(XlsError::ContinueRecordTooShort);
+    }
+    let high_byte = r.data[0] & 0x1 != 0;
+    r.data = &r.data[1..];
+    let text = read_dbcs(encoding, cch, r, high_byte)?;
+
+    // TxORuns: 8 bytes per run, the last one only holds the text length
+    let mut runs_data = r.data.to_vec();
+    while runs_data.len() < cb_runs && r.continue_record() {
+        runs_data.extend_from_slice(r.data);
+    }
+    let runs = runs_data
+        .chunks_exact(8)
+        .take((cb_runs / 8).saturating_sub(1))
+        .map(|run| (read_u16(run) as usize, read_u16(&run[2..]) as usize))
+        .collect::<Vec<_>>();
+    Ok(text_runs(&text, &runs, fonts))
+}
+
+/// Decode a Ref8 structure (rows and columns, first and last)
+fn parse_ref8(data: &[u8]) -> Dimensions {
+    Dimensions {
+        start: (read_u16(data) as u32, read_u16(&data[4..]) as u32),
+        end: (read_u16(&data[2..]) as u32, read_u16(&data[6..]) as u32),
+    }
+}
+
+/// Decodes a null terminated UTF-16 string
+fn utf16_to_string(data: &[u8]) -> String {
+    let chars = data
+        .chunks_exact(2)
+        .map(read_u16)
+        .take_while(|&c| c != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&chars)
+}
+
+/// Decode HLink [MS-XLS 2.4.140] and its Hyperlink Object [MS-OSHARED 2.3.7.1]
+fn parse_hyperlink(data: &[u8], encoding: &XlsEncoding) -> Result<Hyperlink, XlsError> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], XlsError> {
+        if data.len() < len {
+            return Err(XlsError::Len {
+                typ: "HLink",
+                expected: len,
+                found: data.len(),
+            });
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
+    // HyperlinkString: a character count, including the null terminator, and UTF-16 characters
+    fn hyperlink_string(data: &mut &[u8]) -> Result<String, XlsError> {
+        let len = read_u32(take(data, 4)?) as usize;
+        Ok(utf16_to_string(take(data, len * 2)?))
+    }
+
+    let mut data = data;
+    let range = parse_ref8(take(&mut data, 8)?);
+    // hlinkClsid and streamVersion
+    take(&mut data, 20)?;
+    let flags = read_u32(take(&mut data, 4)?);
+    let mut link = Hyperlink {
+        range,
+        ..Default::default()
+    };
+    if flags & 0x10 != 0 {
+        // displayName
+        hyperlink_string(&mut data)?;
+    }
+    if flags & 0x80 != 0 {
+        // targetFrameName
+        hyperlink_string(&mut data)?;
+    }
+    if flags & 0x1 != 0 {
+        if flags & 0x100 != 0 {
+            link.url = Some(hyperlink_string(&mut data)?);
+        } else {
+            match take(&mut data, 16)? {
+                // URL Moniker
+                [0xE0, 0xC9, 0xEA, 0x79, 0xF9, 0xBA, 0xCE, 0x11, 0x8C, 0x82, 0x00, 0xAA, 0x00, 0x4B, 0xA9, 0x0B] =>
+                {
+                    let len = read_u32(take(&mut data, 4)?) as usize;
+                    link.url = Some(utf16_to_string(take(&mut data, len)?));
+                }
+                // File Moniker, with an ANSI path and an optional unicode path
+                [0x03, 0x03, 0, 0, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46] => {
+                    let anti = read_u16(take(&mut data, 2)?) as usize;
+                    let len = read_u32(take(&mut data, 4)?) as usize;
+                    let ansi = take(&mut data, len)?;
+                    let ansi = &ansi[..ansi.iter().position(|&c| c == 0).unwrap_or(len)];
+                    let mut path = "..\\".repeat(anti);
+                    take(&mut data, 24)?;
+                    let unicode_len = read_u32(take(&mut data, 4)?) as usize;
+                    if unicode_len > 0 {
+                        let len = read_u32(take(&mut data, 4)?) as usize;
+                        take(&mut data, 2)?;
+                        path.push_str(&utf16_to_string(take(&mut data, len)?));
+                    } else {
+                        encoding.decode_to(ansi, ansi.len(), &mut path, None);
+                    }
+                    link.url = Some(path);
+                }
+                // other monikers are not supported, the location cannot be found after them
+                _ => return Ok(link),
+            }
+        }
+    }
+    if flags & 0x8 != 0 {
+        link.location = Some(hyperlink_string(&mut data)?);
+    }
+    Ok(link)
+}
+
+/// Parses a DV record
+fn parse_data_validation(
+    mut data: &[u8],
+    sheets: &[String],
+    names: &[(String, String)],
+    xtis: &[Xti],
+    tables: &[TableMetadata],
+    encoding: &XlsEncoding,
+) -> Result<DataValidation, XlsError> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], XlsError> {
+        if data.len() < len {
+            return Err(XlsError::Len {
+                typ: "DV",
+                expected: len,
+                found: data.len(),
+            });
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
+    // XLUnicodeString, a single null character standing for an empty string
+    fn unicode_string(
+        data: &mut &[u8],
+        encoding: &XlsEncoding,
+    ) -> Result<Option<String>, XlsError> {
+        let cch = read_u16(take(data, 2)?) as usize;
+        let high_byte = take(data, 1)?[0] & 0x1 != 0;
+        let mut s = String::with_capacity(cch);
+        let (_, len) = encoding.decode_to(data, cch, &mut s, Some(high_byte));
+        take(data, len)?;
+        Ok(Some(s).filter(|s| !s.is_empty() && s != "\0"))
+    }
+    // DVParsedFormula, whose size is followed by 2 unused bytes
+    let formula = |data: &mut &[u8]| -> Result<Option<String>, XlsError> {
+        let cce = take(data, 4)?;
+        let rgce = take(data, read_u16(cce) as usize)?;
+        if rgce.is_empty() {
+            return Ok(None);
+        }
+        let mut fmla = cce[..2].to_vec();
+        fmla.extend_from_slice(rgce);
+        // an unsupported formula only drops this formula, not the whole sheet
+        match parse_formula(&fmla, sheets, names, xtis, tables, encoding) {
+            // list values are separated by null characters
+            Ok(f) => Ok(Some(f.replace('\0', ","))),
+            Err(e) => {
+                debug!("Unrecognised data validation formula: {:?}", e);
+                Ok(None)
+            }
+        }
+    };
+    let flags = read_u32(take(&mut data, 4)?);
+    let prompt_title = unicode_string(&mut data, encoding)?;
+    let error_title = unicode_string(&mut data, encoding)?;
+    let prompt_message = unicode_string(&mut data, encoding)?;
+    let error_message = unicode_string(&mut data, encoding)?;
+    let formula1 = formula(&mut data)?;
+    let formula2 = formula(&mut data)?;
+    let count = read_u16(take(&mut data, 2)?) as usize;
+    let mut ranges = Vec::with_capacity(count);
+    for _ in 0..count {
+        ranges.push(parse_ref8(take(&mut data, 8)?));
+    }
+    Ok(DataValidation {
+        ranges,
+        validation_type: ValidationType::from_biff(flags & 0x0F),
+        operator: ValidationOperator::from_biff((flags >> 21) & 0x0F),
+        formula1,
+        formula2,
+        allow_blank: flags & 0x200 != 0,
+        error_title,
+        error_message,
+        prompt_title,
+        prompt_message,
+    })
+}
+
+/// Parses a CF record, without the formatted cells
+fn parse_conditional_format(
+    data: &[u8],
+    sheets: &[String],
+    names: &[(String, String)],
+    xtis: &[Xti],
+    tables: &[TableMetadata],
+    encoding: &XlsEncoding,
+) -> Result<ConditionalFormat, XlsError> {
+    if data.len() < 12 {
+        return Err(XlsError::Len {
+            typ: "CF",
+            expected: 12,
+            found: data.len(),
+        });
+    }
+    let rule_type = match data[0] {
+        1 => ConditionalFormatType::CellIs,
+        2 => ConditionalFormatType::Expression,
+        t => ConditionalFormatType::Other(t.to_string()),
+    };
+    let operator = match rule_type {
+        ConditionalFormatType::CellIs => Some(ValidationOperator::from_biff(
+            (data[1] as u32).saturating_sub(1),
+        )),
+        _ => None,
+    };
+    // DXFN, whose flags tell which optional parts are present
+    let flags = read_u32(&data[6..]);
+    let mut offset = 12;
+    if flags & 0x0200_0000 != 0 {
+        // number format, either user defined and sized or built-in
+        offset += match read_u16(&data[10..]) & 0x1 {
+            0 => 2,
+            _ => data.get(offset..).map_or(0, |d| read_u16(d) as usize),
+        };
+    }
+    for (bit, len) in [(26, 118), (27, 8), (28, 8), (29, 4), (30, 2)] {
+        if flags & (1 << bit) != 0 {
+            offset += len;
+        }
+    }
+    let mut formulas = Vec::new();
+    for cce in [read_u16(&data[2..]), read_u16(&data[4..])] {
+        if cce == 0 {
+            continue;
+        }
+        let end = offset + cce as usize;
+        let rgce = data.get(offset..end).ok_or(XlsError::Len {
+            typ: "CF",
+            expected: end,
+            found: data.len(),
+        })?;
+        let mut fmla = cce.to_le_bytes().to_vec();
+        fmla.extend_from_slice(rgce);
+        formulas.push(parse_formula(&fmla, sheets, names, xtis, tables, encoding)?);
+        offset = end;
+    }
+    Ok(ConditionalFormat {
+        rule_type,
+        operator,
+        formulas,
+        ..Default::default()
+    })
+}
+
+/// Parses the table of a Feature11 or Feature12 record [MS-XLS 2.4.136], if any
+///
+/// Columns are read as long as their optional data can be skipped.
+fn parse_table_feature(
+    data: &[u8],
+    sheet_name: &str,
+    encoding: &XlsEncoding,
+) -> Result<Option<TableMetadata>, XlsError> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        if data.len() < len {
+            return None;
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Some(head)
+    }
+    // XLUnicodeString
+    fn unicode_string(data: &mut &[u8], encoding: &XlsEncoding) -> Option<String> {
+        let cch = read_u16(take(data, 2)?) as usize;
+        let high_byte = take(data, 1)?[0] & 0x1 != 0;
+        let mut s = String::with_capacity(cch);
+        let (_, len) = encoding.decode_to(data, cch, &mut s, Some(high_byte));
+        take(data, len)?;
+        Some(s)
+    }
+    // FrtRefHeaderU, isf, reserved, cref2, cbFeatData, reserved
+    if data.len() < 27 || read_u16(&data[12..]) != 0x0005 {
+        return Ok(None);
+    }
+    let cref2 = read_u16(&data[19..]) as usize;
+    let mut feat = match data.get(27 + 8 * cref2..) {
+        Some(feat) if cref2 > 0 && feat.len() >= 64 => feat,
+        _ => {
+            return Err(XlsError::Len {
+                typ: "Feature11",
+                expected: 27 + 8 * cref2 + 64,
+                found: data.len(),
+            })
+        }
+    };
+    // TableFeatureType
+    let list_type = read_u32(feat);
+    let flags = read_u32(&feat[28..]);
+    let mut table = TableMetadata {
+        id: read_u32(&feat[4..]),
+        name: String::new(),
+        sheet_name: sheet_name.to_string(),
+        columns: Vec::new(),
+        dimensions: parse_ref8(&data[27..]),
+        header_rows: read_u32(&feat[8..]),
+        totals_rows: read_u32(&feat[12..]),
+        // fShowInsertRow
+        insert_row: flags & 0x8 != 0,
+    };
+    feat = &feat[64..];
+    table.name = unicode_string(&mut feat, encoding).ok_or(XlsError::Len {
+        typ: "Feature11",
+        expected: 3,
+        found: feat.len(),
+    })?;
+    // only regular tables have no optional data before their fields
+    if list_type != 0 {
+        return Ok(Some(table));
+    }
+    let count = take(&mut feat, 2).map_or(0, read_u16);
+    for _ in 0..count {
+        // Feat11FieldDataItem
+        let column = (|| {
+            let item = take(&mut feat, 36)?;
+            let field_flags = read_u32(&item[24..]);
+            let name = unicode_string(&mut feat, encoding)?;
+            let caption = unicode_string(&mut feat, encoding)?;
+            // dxfFmtAgg, dxfFmtInsertRow
+            take(&mut feat, read_u32(&item[16..]) as usize)?;
+            take(&mut feat, read_u32(&item[28..]) as usize)?;
+            if field_flags & 0x1 != 0 {
+                // Feat11FdaAutoFilter
+                let len = read_u32(take(&mut feat, 4)?) as usize;
+                take(&mut feat, 2 + len)?;
+            }
+            // fLoadXmapi, fLoadFmla, fLoadTotalFmla: formulas are not skipped
+            if field_flags & 0x4C != 0 {
+                return None;
+            }
+            if field_flags & 0x200 != 0 {
+                // fLoadTotalStr
+                unicode_string(&mut feat, encoding)?;
+            }
+            Some(if caption.is_empty() { name } else { caption })
+        })();
+        match column {
+            Some(column) => table.columns.push(column),
+            None => break,
+        }
+    }
+    Ok(Some(table))
+}
+
+/// Reads the document properties of the summary information property set streams
+fn read_document_properties<R: Read>(
+    reader: &mut R,
+    cfb: &mut Cfb,
+) -> Result<DocumentProperties, XlsError> {
+    let mut properties = DocumentProperties::default();
+    if cfb.has_directory("\u{5}SummaryInformation") {
+        let stream = cfb.get_stream("\u{5}SummaryInformation", reader)?;
+        let section = parse_property_sets(&stream)?.into_iter().next();
+        for (id, value) in section.unwrap_or_default().values {
+            let value = match value {
+                DataType::String(s) | DataType::DateTimeIso(s) if !s.is_empty() => Some(s),
+                _ => continue,
+            };
+            match id {
+                2 => properties.title = value,
+                3 => properties.subject = value,
+                4 => properties.author = value,
+                5 => properties.keywords = value,
+                6 => properties.description = value,
+                8 => properties.last_modified_by = value,
+                12 => properties.created = value,
+                13 => properties.modified = value,
+                18 => properties.application = value,
+                _ => (),
+            }
+        }
+    }
+    if cfb.has_directory("\u{5}DocumentSummaryInformation") {
+        let stream = cfb.get_stream("\u{5}DocumentSummaryInformation", reader)?;
+        let mut sections = parse_property_sets(&stream)?.into_iter();
+        for (id, value) in sections.next().unwrap_or_default().values {
+            let value = match value {
+                DataType::String(s) if !s.is_empty() => Some(s),
+                _ => continue,
+            };
+            match id {
+                2 => properties.category = value,
+                14 => properties.manager = value,
+                15 => properties.company = value,
+                _ => (),
+            }
+        }
+        // user defined properties, named by the dictionary
+        let section = sections.next().unwrap_or_default();
+        for (id, value) in section.values {
+            if let Some((_, name)) = section.names.iter().find(|(i, _)| *i == id) {
+                properties.custom.push((name.clone(), value));
+            }
+        }
+    }
+    Ok(properties)
+}
+
+/// A section of a property set stream
+#[derive(Default)]
+struct PropertySection {
+    /// Values, by property id
+    values: Vec<(u32, DataType)>,
+    /// Names of the user defined properties, by property id
+    names: Vec<(u32, String)>,
+}
+
+/// Gets `len` bytes of a property set stream
+fn property_bytes(data: &[u8], start: usize, len: usize) -> Result<&[u8], XlsError> {
+    data.get(start..start + len).ok_or(XlsError::Len {
+        typ: "property set",
+        expected: start + len,
+        found: data.len(),
+    })
+}
+
+/// Parses the sections of a property set stream
+fn parse_property_sets(stream: &[u8]) -> Result<Vec<PropertySection>, XlsError> {
+    let count = read_usize(property_bytes(stream, 24, 4)?);
+    let mut sections = Vec::with_capacity(count.min(2));
+    for i in 0..count.min(2) {
+        // format id and offset of each section
+        let start = read_usize(property_bytes(stream, 44 + i * 20, 4)?);
+        let section = stream.get(start..).unwrap_or_default();
+        let len = read_usize(property_bytes(section, 4, 4)?);
+        let mut offsets = Vec::with_capacity(len.min(1024));
+        for k in 0..len {
+            let entry = property_bytes(section, 8 + k * 8, 8)?;
+            offsets.push((read_u32(entry), read_usize(&entry[4..])));
+        }
+        // strings are encoded with the code page of the section
+        let codepage = offsets
+            .iter()
+            .find(|(id, _)| *id == 1)
+            .and_then(|(_, offset)| section.get(offset + 4..offset + 6))
+            .map_or(1252, read_u16);
+        let encoding =
+            XlsEncoding::from_codepage(codepage).or_else(|_| XlsEncoding::from_codepage(1252))?;
+        let mut parsed = PropertySection::default();
+        for (id, offset) in offsets {
+            let data = section.get(offset..).unwrap_or_default();
+            match id {
+                0 => parsed.names = parse_property_names(data, &encoding, codepage == 1200)?,
+                1 => (),
+                _ => {
+                    if let Some(value) = parse_property_value(data, &encoding)? {
+                        parsed.values.push((id, value));
+                    }
+                }
+            }
+        }
+        sections.push(parsed);
+    }
+    Ok(sections)
+}
+
+/// Parses the dictionary of a property set section
+fn parse_property_names(
+    data: &[u8],
+    encoding: &XlsEncoding,
+    is_unicode: bool,
+) -> Result<Vec<(u32, String)>, XlsError> {
+    let count = read_usize(property_bytes(data, 0, 4)?);
+    let mut names = Vec::with_capacity(count.min(1024));
+    let mut offset = 4;
+    for _ in 0..count {
+        let entry = property_bytes(data, offset, 8)?;
+        let (id, len) = (read_u32(entry), read_usize(&entry[4..]));
+        offset += 8;
+        let name = if is_unicode {
+            let name = utf16_to_string(property_bytes(data, offset, 2 * len)?);
+            // unicode names are padded to 4 bytes
+            offset += (2 * len + 3) & !3;
+            name
+        } else {
+            let name = encoding.decode_all(property_bytes(data, offset, len)?);
+            offset += len;
+            name.trim_end_matches('\0').to_string()
+        };
+        names.push((id, name));
+    }
+    Ok(names)
+}
+
+/// Parses a typed property value, ignoring unsupported types
+fn parse_property_value(data: &[u8], encoding: &XlsEncoding) -> Result<Option<DataType>, XlsError> {
+    let typ = read_u16(property_bytes(data, 0, 4)?);
+    let value = |len| property_bytes(data, 4, len);
+    let value = match typ {
+        0x0002 => DataType::Int(read_i16(value(2)?) as i64),
+        0x0003 => DataType::Int(read_i32(value(4)?) as i64),
+        0x0012 => DataType::Int(read_u16(value(2)?) as i64),
+        0x0013 => DataType::Int(read_u32(value(4)?) as i64),
+        0x0004 => DataType::Float(f32::from_le_bytes(value(4)?.try_into().unwrap()) as f64),
+        0x0005 => DataType::Float(read_f64(value(8)?)),
+        // VT_DATE, days since 1899-12-30 as Excel dates
+        0x0007 => DataType::DateTime(read_f64(value(8)?)),
+        0x000B => DataType::Bool(read_u16(value(2)?) != 0),
+        0x001E => {
+            let len = read_usize(value(4)?);
+            let s = encoding.decode_all(property_bytes(data, 8, len)?);
+            DataType::String(s.trim_end_matches('\0').to_string())
+        }
+        0x001F => {
+            let len = read_usize(value(4)?);
+            DataType::String(utf16_to_string(property_bytes(data, 8, 2 * len)?))
+        }
+        0x0040 => match read_u64(value(8)?) {
+            0 => return Ok(None),
+            filetime => DataType::DateTimeIso(filetime_to_iso(filetime)),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+/// Converts a FILETIME, in 100 nanoseconds since 1601-01-01, to an ISO 8601 UTC date time
+fn filetime_to_iso(filetime: u64) -> String {
+    let secs = filetime / 10_000_000;
+    let time = secs % 86_400;
+    // civil date from the days since 1970-01-01, shifted to start on 0000-03-01
+    let z = (secs / 86_400) as i64 - 134_774 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+/// Decode XLUnicodeRichExtendedString.
+///
+/// See: <https://docs.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/173d9f51-e5d3-43da-8de2-be7f22e119b9>
+///
+/// The formatting runs, as (first character, font index), are pushed to `runs` if any.
+fn read_rich_extended_string(
+    r: &mut Record<'_>,
+    encoding: &XlsEncoding,
+    runs: Option<&mut Vec<(usize, usize)>>,
+) -> Result<String, XlsError> {
+    if r.data.is_empty() && !r.continue_record() || r.data.len() < 3 {
+        return Err(XlsError::Len {
+            typ: "rich extended string",
+            expected: 3,
+            found: r.data.len(),
+        });
+    }
+
+    let cch = read_u16(r.data) as usize;
+    let flags = r.data[2];
+
+    r.data = &r.data[3..];
+
+    let high_byte = flags & 0x1 != 0;
+
+    // how many FormatRun in rgRun data block
+    let mut c_run = 0;
+
+    // how many bytes in ExtRst data block
+    let mut cb_ext_rst = 0;
+
+    // if flag fRichSt exists, read cRun and forward.
+    if flags & 0x8 != 0 {
+        c_run = read_u16(r.data) as usize;
+        r.data = &r.data[2..];
+    }
+
+    // if flag fExtSt exists, read cbExtRst and forward.
+    if flags & 0x4 != 0 {
+        cb_ext_rst = read_i32(r.data) as usize;
+        r.data = &r.data[4..];
+    }
+
+    // read rgb data block for the string we want
+    let s = read_dbcs(encoding, cch, r, high_byte)?;
+
+    // read or skip rgRun data block. Note: each FormatRun contain 4 bytes.
+    match runs {
+        Some(runs) => runs.extend(
+            r.read_bytes(c_run * 4)?
+                .chunks_exact(4)
+                .map(|run| (read_u16(run) as usize, read_u16(&run[2..]) as usize)),
+        ),
+        None => r.skip(c_run * 4)?,
+    }
+
+    // skip ExtRst data block.
+    r.skip(cb_ext_rst)?;
+
+    Ok(s)
+}
+
+fn read_dbcs(
+    encoding: &XlsEncoding,
+    mut len: usize,
+    r: &mut Record<'_>,
+    mut high_byte: bool,
+) -> Result<String, XlsError> {
+    let mut s = String::with_capacity(len);
+    while len > 0 {
+        let (l, at) = encoding.decode_to(r.data, len, &mut s, Some(high_byte));
+        r.data = &r.data[at..];
+        len -= l;
+        if len > 0 {
+            if r.continue_record() {
+                high_byte = r.data[0] & 0x1 != 0;
+                r.data = &r.data[1..];
+            } else {
+                return Err(XlsError::EoStream("dbcs"));
+            }
+        }
+    }
+    Ok(s)
+}
+
+/// Reads a string of `len` characters after its flags byte, returns the number of bytes read
+fn read_unicode_string_no_cch(
+    encoding: &XlsEncoding,
+    buf: &[u8],
+    len: &usize,
+    s: &mut String,
+) -> usize {
+    let (_, bytes) = encoding.decode_to(&buf[1..], *len, s, Some(buf[0] & 0x1 != 0));
+    1 + bytes
+}
+
+pub(crate) struct Record<'a> {
+    typ: u16,
+    data: &'a [u8],
+    cont: Option<Vec<&'a [u8]>>,
+}
+
+impl<'a> Record<'a> {
+    fn continue_record(&mut self) -> bool {
+        match self.cont {
+            None => false,
+            Some(ref mut v) => {
+                if v.is_empty() {
+                    false
+                } else {
+                    self.data = v.remove(0);
+                    true
+                }
+            }
+        }
+    }
+
+    fn read_bytes(&mut self, mut len: usize) -> Result<Vec<u8>, XlsError> {
+        let mut bytes = Vec::with_capacity(len);
+        while len > 0 {
+            if self.data.is_empty() && !self.continue_record() {
+                return Err(XlsError::ContinueRecordTooShort);
+            }
+            let l = min(len, self.data.len());
+            let (b, next) = self.data.split_at(l);
+            bytes.extend_from_slice(b);
+            self.data = next;
+            len -= l;
+        }
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, mut len: usize) -> Result<(), XlsError> {
+        while len > 0 {
+            if self.data.is_empty() && !self.continue_record() {
+                return Err(XlsError::ContinueRecordTooShort);
+            }
+            let l = min(len, self.data.len());
+            let (_, next) = self.data.split_at(l);
+            self.data = next;
+            len -= l;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RecordIter<'a> {
+    stream: &'a [u8],
+}
+
+impl<'a> Iterator for RecordIter<'a> {
+    type Item = Result<Record<'a>, XlsError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stream.len() < 4 {
+            return if self.stream.is_empty() {
+                None
+            } else {
+                Some(Err(XlsError::EoStream("record type and length")))
+            };
+        }
+        let t = read_u16(self.stream);
+        let mut len = read_u16(&self.stream[2..]) as usize;
+        if self.stream.len() < len + 4 {
+            return Some(Err(XlsError::EoStream("record length")));
+        }
+        let (data, next) = self.stream.split_at(len + 4);
+        self.stream = next;
+        let d = &data[4..];
+
+        // Append next record data if it is a Continue record
+        let cont = if next.len() > 4 && read_u16(next) == 0x003C {
+            let mut cont = Vec::new();
+            while self.stream.len() > 4 && read_u16(self.stream) == 0x003C {
+                len = read_u16(&self.stream[2..]) as usize;
+                if self.stream.len() < len + 4 {
+                    return Some(Err(XlsError::EoStream("continue record length")));
+                }
+                let sp = self.stream.split_at(len + 4);
+                cont.push(&sp.0[4..]);
+                self.stream = sp.1;
+            }
+            Some(cont)
+        } else {
+            None
+        };
+
+        Some(Ok(Record {
+            typ: t,
+            data: d,
+            cont,
+        }))
+    }
+}
+
+/// Formula parsing
+///
+/// Does not implement ALL possibilities, only Area are parsed
+fn parse_defined_names(rgce: &[u8]) -> Result<(Option<usize>, String), XlsError> {
+    if rgce.is_empty() {
+        // TODO: do something better here ...
+        return Ok((None, "empty rgce".to_string()));
+    }
+    let ptg = rgce[0];
+    let res = match ptg {
+        0x3a | 0x5a | 0x7a => {
+            // PtgRef3d
+            let ixti = read_u16(&rgce[1..3]) as usize;
+            let mut f = String::new();
+            // TODO: check with relative columns
+            f.push('$');
+            push_column(read_u16(&rgce[5..7]) as u32, &mut f);
+            f.push('$');
+            f.push_str(&format!("{}", read_u16(&rgce[3..5]) as u32 + 1));
+            (Some(ixti), f)
+        }
+        0x3b | 0x5b | 0x7b => {
+            // PtgArea3d
+            let ixti = read_u16(&rgce[1..3]) as usize;
+            let mut f = String::new();
+            // TODO: check with relative columns
+            f.push('$');
+            push_column(read_u16(&rgce[7..9]) as u32, &mut f);
+            f.push('$');
+            f.push_str(&format!("{}", read_u16(&rgce[3..5]) as u32 + 1));
+            f.push(':');
+            f.push('$');
+            push_column(read_u16(&rgce[9..11]) as u32, &mut f);
+            f.push('$');
+            f.push_str(&format!("{}", read_u16(&rgce[5..7]) as u32 + 1));
+            (Some(ixti), f)
+        }
+        0x3c | 0x5c | 0x7c | 0x3d | 0x5d | 0x7d => {
+            // PtgAreaErr3d or PtfRefErr3d
+            let ixti = read_u16(&rgce[1..3]) as usize;
+            (Some(ixti), "#REF!".to_string())
+        }
+        _ => (None, format!("Unsupported ptg: {:x}", ptg)),
+    };
+    Ok(res)
+}
+
+/// Formula parsing
+///
+/// CellParsedFormula [MS-XLS 2.5.198.3]
+fn parse_formula(
+    mut rgce: &[u8],
+    sheets: &[String],
+    names: &[(String, String)],
+    xtis: &[Xti],
+    tables: &[TableMetadata],
+    encoding: &XlsEncoding,
+) -> Result<String, XlsError> {
+    let mut stack = Vec::new();
+    let mut formula = String::with_capacity(rgce.len());
+    let cce = read_u16(rgce) as usize;
+    rgce = &rgce[2..2 + cce];
+    while !rgce.is_empty() {
+        let ptg = rgce[0];
+        rgce = &rgce[1..];
+        match ptg {
+            0x3a | 0x5a | 0x7a => {
+                // PtgRef3d
+                let ixti = read_u16(&rgce[0..2]);
+                let rowu = read_u16(&rgce[2..]);
+                let colu = read_u16(&rgce[4..]);
+                let sh = xtis
+                    .get(ixti as usize)
+                    .and_then(|xti| sheets.get(xti.itab_first as usize))
+                    .map_or("#REF", |sh| sh);
+                stack.push(formula.len());
+                formula.push_str(sh);
+                formula.push('!');
+                let col = colu << 2; // first 14 bits only
+                if colu & 2 != 0 {
+                    formula.push('$');
+                }
+                push_column(col as u32, &mut formula);
+                if colu & 1 != 0 {
+                    formula.push('$');
+                }
+                write!(&mut formula, "{}", rowu + 1).unwrap();
+                rgce = &rgce[6..];
+            }
+            0x3b | 0x5b | 0x7b => {
+                // PtgArea3d
+                let ixti = read_u16(&rgce[0..2]);
+                stack.push(formula.len());
+                formula.push_str(sheets.get(ixti as usize).map_or("#REF", |s| &**s));
+                formula.push('!');
+                // TODO: check with relative columns
+                formula.push('$');
+                push_column(read_u16(&rgce[6..8]) as u32, &mut formula);
+                write!(&mut formula, "${}:$", read_u16(&rgce[2..4]) as u32 + 1).unwrap();
+                push_column(read_u16(&rgce[8..10]) as u32, &mut formula);
+                write!(&mut formula, "${}", read_u16(&rgce[4..6]) as u32 + 1).unwrap();
+                rgce = &rgce[10..];
+            }
+            0x3c | 0x5c | 0x7c => {
+                // PtfRefErr3d
+                let ixti = read_u16(&rgce[0..2]);
+                stack.push(formula.len());
+                formula.push_str(sheets.get(ixti as usize).map_or("#REF", |s| &**s));
+                formula.push('!');
+                formula.push_str("#REF!");
+                rgce = &rgce[6..];
+            }
+            0x3d | 0x5d | 0x7d => {
+                // PtgAreaErr3d
+                let ixti = read_u16(&rgce[0..2]);
+                stack.push(formula.len());
+                formula.push_str(sheets.get(ixti as usize).map_or("#REF", |s| &**s));
+                formula.push('!');
+                formula.push_str("#REF!");
+                rgce = &rgce[10..];
+            }
+            0x01 => {
+                // PtgExp: array/shared formula, ignore
+                debug!("ignoring PtgExp array/shared formula");
+                stack.push(formula.len());
+                rgce = &rgce[4..];
+            }
+            0x03..=0x11 => {
+                // binary operation
+                let e2 = stack.pop().ok_or(XlsError::StackLen)?;
+                // imaginary 'e1' will actually already be the start of the binary op
+                let op = match ptg {
+                    0x03 => "+",
+                    0x04 => "-",
+                    0x05 => "*",
+                    0x06 => "/",
+                    0x07 => "^",
+                    0x08 => "&",
+                    0x09 => "<",
+                    0x0A => "<=",
+                    0x0B => "=",
+                    0x0C => ">=",
+                    0x0D => ">",
+                    0x0E => "<>",
+                    0x0F => " ",
+                    0x10 => ",",
+                    0x11 => ":",
+                    _ => unreachable!(),
+                };
+                let e2 = formula.split_off(e2);
+                write!(&mut formula, "{}{}", op, e2).unwrap();
+            }
+            0x12 => {
+                let e = stack.last().ok_or(XlsError::StackLen)?;
+                formula.insert(*e, '+');
+            }
+            0x13 => {
+                let e = stack.last().ok_or(XlsError::StackLen)?;
+                formula.insert(*e, '-');
+            }
+            0x14 => {
+                formula.push('%');
+            }
+            0x15 => {
+                let e = stack.last().ok_or(XlsError::StackLen)?;
+                formula.insert(*e, '(');
+                formula.push(')');
+            }
+            0x16 => {
+                stack.push(formula.len());
+            }
+            0x17 => {
+                stack.push(formula.len());
+                formula.push('\"');
+                let cch = rgce[0] as usize;
+                let len = read_unicode_string_no_cch(encoding, &rgce[1..], &cch, &mut formula);
+                formula.push('\"');
+                rgce = &rgce[1 + len..];
+            }
+            0x18 if rgce[0] == 0x19 => {
+                // PtgList
+                stack.push(formula.len());
+                formula.push_str(&ptg_list(&rgce[1..13], tables));
+                rgce = &rgce[13..];
+            }
+            0x18 => {
+                rgce = &rgce[5..];
+            }
+            0x19 => {
+                let etpg = rgce[0];
+                rgce = &rgce[1..];
+                match etpg {
+                    0x01 | 0x02 | 0x08 | 0x20 | 0x21 => rgce = &rgce[2..],
+                    0x04 => {
+                        // PtgAttrChoose
+                        let n = read_u16(&rgce[..2]) as usize + 1;
+                        rgce = &rgce[2 + 2 * n..]; // ignore
+                    }
+                    0x10 => {
+                        rgce = &rgce[2..];
+                        let e = *stack.last().ok_or(XlsError::StackLen)?;
+                        let e = formula.split_off(e);
+                        write!(&mut formula, "SUM({})", e).unwrap();
+                    }
+                    0x40 | 0x41 => {
+                        // PtfAttrSpace
+                        let e = *stack.last().ok_or(XlsError::StackLen)?;
+                        let space = match rgce[0] {
+                            0x00 | 0x02 | 0x04 | 0x06 => ' ',
+                            0x01 | 0x03 | 0x05 => '\r',
+                            val => {
+                                return Err(XlsError::Unrecognized {
+                                    typ: "PtgAttrSpaceType",
+                                    val,
+                                });
+                            }
+                        };
+                        let cch = rgce[1];
+                        for _ in 0..cch {
+                            formula.insert(e, space);
+                        }
+                        rgce = &rgce[2..];
+                    }
+                    e => return Err(XlsError::Etpg(e)),
+                }
+            }
+            0x1C => {
+                stack.push(formula.len());
+                let err = rgce[0];
+                rgce = &rgce[1..];
+                match err {
+                    0x00 => formula.push_str("#NULL!"),
+                    0x07 => formula.push_str("#DIV/0!"),
+                    0x0F => formula.push_str("#VALUE!"),
+                    0x17 => formula.push_str("#REF!"),
+                    0x1D => formula.push_str("#NAME?"),
+                    0x24 => formula.push_str("#NUM!"),
+                    0x2A => formula.push_str("#N/A"),
+                    0x2B => formula.push_str("#GETTING_DATA"),
+                    e => {
+                        return Err(XlsError::Unrecognized {
+                            typ: "BErr",
+                            val: e,
+                        });
+                    }
+                }
+            }
+            0x1D => {
+                stack.push(formula.len());
+                formula.push_str(if rgce[0] == 0 { "FALSE" } else { "TRUE" });
+                rgce = &rgce[1..];
+            }
+            0x1E => {
+                stack.push(formula.len());
+                write!(&mut formula, "{}", read_u16(rgce)).unwrap();
+                rgce = &rgce[2..];
+            }
+            0x1F => {
+                stack.push(formula.len());
+                write!(&mut formula, "{}", read_f64(rgce)).unwrap();
+                rgce = &rgce[8..];
+            }
+            0x20 | 0x40 | 0x60 => {
+                // PtgArray: ignore
+                stack.push(formula.len());
+                formula.push_str("{PtgArray}");
+                rgce = &rgce[7..];
+            }
+            0x21 | 0x22 | 0x41 | 0x42 | 0x61 | 0x62 => {
+                let (iftab, argc) = match ptg {
+                    0x22 | 0x42 | 0x62 => {
+                        let iftab = read_u16(&rgce[1..]) as usize;
+                        let argc = rgce[0] as usize;
+                        rgce = &rgce[3..];
+                        (iftab, argc)
+                    }
+                    _ => {
+                        let iftab = read_u16(rgce) as usize;
+                        if iftab > crate::utils::FTAB_LEN {
+                            return Err(XlsError::IfTab(iftab));
+                        }
+                        rgce = &rgce[2..];
+                        let argc = crate::utils::FTAB_ARGC[iftab] as usize;
+                        (iftab, argc)
+                    }
+                };
+                if stack.len() < argc {
+                    return Err(XlsError::StackLen);
+                }
+                if argc > 0 {
+                    let args_start = stack.len() - argc;
+                    let mut args = stack.split_off(args_start);
+                    let start = args[0];
+                    for s in &mut args {
+                        *s -= start;
+                    }
+                    let fargs = formula.split_off(start);
+                    stack.push(formula.len());
+                    args.push(fargs.len());
+                    formula.push_str(
+                        crate::utils::FTAB
+                            .get(iftab)
+                            .ok_or(XlsError::IfTab(iftab))?,
+                    );
+                    formula.push('(');
+                    for w in args.windows(2) {
+                        formula.push_str(&fargs[w[0]..w[1]]);
+                        formula.push(',');
+                    }
+                    formula.pop();
+                    formula.push(')');
+                } else {
+                    stack.push(formula.len());
+                    formula.push_str(crate::utils::FTAB[iftab]);
+                    formula.push_str("()");
+                }
+            }
+            0x23 | 0x43 | 0x63 => {
+                let iname = read_u32(rgce) as usize - 1; // one-based
+                stack.push(formula.len());
+                formula.push_str(names.get(iname).map_or("#REF!", |n| &*n.0));
+                rgce = &rgce[4..];
+            }
+            0x24 | 0x44 | 0x64 => {
+                stack.push(formula.len());
+                let row = read_u16(rgce) + 1;
+                let col = read_u16(&[rgce[2], rgce[3] & 0x3F]);
+                if rgce[3] & 0x80 != 0x80 {
+                    formula.push('$');
+                }
+                push_column(col as u32, &mut formula);
+                if rgce[3] & 0x40 != 0x40 {
+                    formula.push('$');
+                }
+                formula.push_str(&format!("{}", row));
+                rgce = &rgce[4..];
+            }
+            0x25 | 0x45 | 0x65 => {
+                stack.push(formula.len());
+                formula.push('$');
+                push_column(read_u16(&rgce[4..6]) as u32, &mut formula);
+                write!(&mut formula, "${}:$", read_u16(&rgce[0..2]) as u32 + 1).unwrap();
+                push_column(read_u16(&rgce[6..8]) as u32, &mut formula);
+                write!(&mut formula, "${}", read_u16(&rgce[2..4]) as u32 + 1).unwrap();
+                rgce = &rgce[8..];
+            }
+            0x2A | 0x4A | 0x6A => {
+                stack.push(formula.len());
+                formula.push_str("#REF!");
+                rgce = &rgce[4..];
+            }
+            0x2B | 0x4B | 0x6B => {
+                stack.push(formula.len());
+                formula.push_str("#REF!");
+                rgce = &rgce[8..];
+            }
+            0x39 | 0x59 => {
+                // PfgNameX
+                stack.push(formula.len());
+                formula.push_str("[PtgNameX]");
+                rgce = &rgce[6..];
+            }
+            _ => {
+                return Err(XlsError::Unrecognized {
+                    typ: "ptg",
+                    val: ptg,
+                });
+            }
+        }
+    }
+    if stack.len() == 1 {
+        Ok(formula)
+    } else {
+        Err(XlsError::InvalidFormula {
+            stack_size: stack.len(),
+        })
+    }
+}
+
+/// FormulaValue [MS-XLS 2.5.133]
+fn parse_formula_value(r: &[u8]) -> Result<Option<DataType>, XlsError> {
+    match *r {
+        // String, value should be in next record
+        [0x00, .., 0xFF, 0xFF] => Ok(None),
+        [0x01, _, b, .., 0xFF, 0xFF] => Ok(Some(DataType::Bool(b != 0))),
+        [0x02, _, e, .., 0xFF, 0xFF] => parse_err(e).map(Some),
+        // ignore, return blank string value
+        [0x03, _, .., 0xFF, 0xFF] => Ok(Some(DataType::String("".to_string()))),
+        [e, .., 0xFF, 0xFF] => Err(XlsError::Unrecognized {
+            typ: "error",
+            val: e,
+        }),
+        _ => Ok(Some(DataType::Float(read_f64(r)))),
+    }
+}
+
+/// Formula record [MS-XLS 2.4.127], or its BIFF2 to BIFF4 variants
+struct FormulaRecord<'a> {
+    row: u32,
+    col: u32,
+    /// FormulaValue
+    value: &'a [u8],
+    /// Formula tokens
+    rgce: &'a [u8],
+    /// Additional data of the tokens, e.g. array constants
+    extra: &'a [u8],
+}
+
+fn parse_formula_record<'a>(r: &Record<'a>, biff: Biff) -> Result<FormulaRecord<'a>, XlsError> {
+    // offsets of the value, of the token count and of the tokens
+    let (value, cce, rgce) = match (r.typ, biff) {
+        // BIFF2: 3 bytes of cell attributes and a 1 byte token count
+        (0x0006, Biff::Biff2) => (7, 16, 17),
+        (0x0206 | 0x0406, _) => (6, 16, 18),
+        _ => (6, 20, 22),
+    };
+    if r.data.len() < rgce {
+        return Err(XlsError::Len {
+            typ: "Formula",
+            expected: rgce,
+            found: r.data.len(),
+        });
+    }
+    let cce = match biff {
+        Biff::Biff2 => r.data[cce] as usize,
+        _ => read_u16(&r.data[cce..]) as usize,
+    };
+    let (rgce, extra) = r.data[rgce..].split_at(min(cce, r.data.len() - rgce));
+    Ok(FormulaRecord {
+        row: read_u16(r.data) as u32,
+        col: read_u16(&r.data[2..]) as u32,
+        value: &r.data[value..value + 8],
+        rgce,
+        extra,
+    })
+}
+
+/// BIFF2 Integer, Number, Label and BoolErr records
+///
+/// Their XF index is part of the cell attributes, or comes in a preceding IXFE record
+fn parse_biff2_cell(
+    r: &Record<'_>,
+    ixfe: Option<u16>,
+    formats: &[CellFormat],
+    is_1904: bool,
+    encoding: &XlsEncoding,
+) -> Result<Cell<DataType>, XlsError> {
+    let expected = match r.typ {
+        0x0003 => 15,
+        0x0004 => 8,
+        _ => 9,
+    };
+    if r.data.len() < expected {
+        return Err(XlsError::Len {
+            typ: "BIFF2 cell",
+            expected,
+            found: r.data.len(),
+        });
+    }
+    let pos = (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32);
+    let format = match r.data[4] & 0x3F {
+        0x3F => ixfe,
+        xf => Some(xf as u16),
+    }
+    .and_then(|xf| formats.get(xf as usize));
+    let value = match r.typ {
+        0x0002 => format_excel_i64(read_u16(&r.data[7..]) as i64, format, is_1904),
+        0x0003 => format_excel_f64(read_f64(&r.data[7..]), format, is_1904),
+        0x0004 => DataType::String(parse_string(&r.data[7..], encoding, Biff::Biff2)?),
+        _ => match r.data[8] {
+            0x00 => DataType::Bool(r.data[7] != 0),
+            _ => parse_err(r.data[7])?,
+        },
+    };
+    Ok(Cell::new(pos, value))
+}
+
+/// OfficeArtRecord [MS-ODRAW 1.3.1]
+#[cfg(feature = "picture")]
+struct ArtRecord<'a> {
+    instance: u16,
+    typ: u16,
+    data: &'a [u8],
+}
+
+#[cfg(feature = "picture")]
+struct ArtRecordIter<'a> {
+    stream: &'a [u8],
+}
+
+#[cfg(feature = "picture")]
+impl<'a> Iterator for ArtRecordIter<'a> {
+    type Item = Result<ArtRecord<'a>, XlsError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stream.len() < 8 {
+            return if self.stream.is_empty() {
+                None
+            } else {
+                Some(Err(XlsError::EoStream("art record header")))
+            };
+        }
+        let ver_ins = read_u16(self.stream);
+        let instance = ver_ins >> 4;
+        let typ = read_u16(&self.stream[2..]);
+        if typ < 0xF000 {
+            return Some(Err(XlsError::Art("type range 0xF000 - 0xFFFF")));
+        }
+        let len = read_usize(&self.stream[4..]);
+        if self.stream.len() < len + 8 {
+            return Some(Err(XlsError::EoStream("art record length")));
+        }
+        let (d, next) = self.stream.split_at(len + 8);
+        self.stream = next;
+        let data = &d[8..];
+
+        Some(Ok(ArtRecord {
+            instance,
+            typ,
+            data,
+        }))
+    }
+}
+
+/// Parsing pictures
+#[cfg(feature = "picture")]
+fn parse_pictures(stream: &[u8]) -> Result<Vec<(String, Vec<u8>)>, XlsError> {
+    let mut pics = Vec::new();
+    let records = ArtRecordIter { stream };
+    for record in records {
+        let r = record?;
+        match r.typ {
+            // OfficeArtDggContainer [MS-ODRAW 2.2.12]
+            // OfficeArtBStoreContainer [MS-ODRAW 2.2.20]
+            0xF000 | 0xF001 => pics.extend(parse_pictures(r.data)?),
+            // OfficeArtFBSE [MS-ODRAW 2.2.32]
+            0xF007 => {
+                let skip = 36 + r.data[33] as usize;
+                pics.extend(parse_pictures(&r.data[skip..])?);
+            }
+            // OfficeArtBlip [MS-ODRAW 2.2.23]
+            0xF01A | 0xF01B | 0xF01C | 0xF01D | 0xF01E | 0xF01F | 0xF029 | 0xF02A => {
+                let ext_skip = match r.typ {
+                    // OfficeArtBlipEMF [MS-ODRAW 2.2.24]
+                    0xF01A => {
+                        let skip = match r.instance {
+                            0x3D4 => 50usize,
+                            0x3D5 => 66,
+                            _ => unreachable!(),
+                        };
+                        Ok(("emf", skip))
+                    }
+                    // OfficeArtBlipWMF [MS-ODRAW 2.2.25]
+                    0xF01B => {
+                        let skip = match r.instance {
+                            0x216 => 50usize,
+                            0x217 => 66,
+                            _ => unreachable!(),
+                        };
+                        Ok(("wmf", skip))
+                    }
+                    // OfficeArtBlipPICT [MS-ODRAW 2.2.26]
+                    0xF01C => {
+                        let skip = match r.instance {
+                            0x542 => 50usize,
+                            0x543 => 66,
+                            _ => unreachable!(),
+                        };
+                        Ok(("pict", skip))
+                    }
+                    // OfficeArtBlipJPEG [MS-ODRAW 2.2.27]
+                    0xF01D | 0xF02A => {
+                        let skip = match r.instance {
+                            0x46A | 0x6E2 => 17usize,
+                            0x46B | 0x6E3 => 33,
+                            _ => unreachable!(),
+                        };
+                        Ok(("jpg", skip))
+                    }
+                    // OfficeArtBlipPNG [MS-ODRAW 2.2.28]
+                    0xF01E => {
+                        let skip = match r.instance {
+                            0x6E0 => 17usize,
+                            0x6E1 => 33,
+                            _ => unreachable!(),
+                        };
+                        Ok(("png", skip))
+                    }
+                    // OfficeArtBlipDIB [MS-ODRAW 2.2.29]
+                    0xF01F => {
+                        let skip = match r.instance {
+                            0x7A8 => 17usize,
+                            0x7A9 => 33,
+                            _ => unreachable!(),
+                        };
+                        Ok(("dib", skip))
+                    }
+                    // OfficeArtBlipTIFF [MS-ODRAW 2.2.30]
+                    0xF029 => {
+                        let skip = match r.instance {
+                            0x6E4 => 17usize,
+                            0x6E5 => 33,
+                            _ => unreachable!(),
+                        };
+                        Ok(("tiff", skip))
+                    }
+                    _ => Err(XlsError::Art("picture type not support")),
+                };
+                let ext_skip = ext_skip?;
+                pics.push((ext_skip.0.to_string(), Vec::from(&r.data[ext_skip.1..])));
+            }
+            _ => {}
+        }
+    }
+    Ok(pics)
+}
@@ -1,8 +1,9 @@
 use crate::{
     datatype::DataTypeRef,
     formats::{format_excel_f64_ref, CellFormat},
+    formula::{or_unrecognized, Expr, RgceContext, RgceKind, SheetRef},
     utils::{read_f64, read_i32, read_u32, read_usize},
-    Cell, CellErrorType, Dimensions, XlsbError,
+    Cell, CellErrorType, Dimensions, TableMetadata, XlsbError,
 };
 
 use super::{cell_format, parse_formula, wide_str, RecordIter};
@@ -14,6 +15,9 @@ pub struct XlsbCellsReader<'a> {
     strings: &'a [String],
     extern_sheets: &'a [String],
     metadata_names: &'a [(String, String)],
+    tables: &'a [TableMetadata],
+    /// Sheets of `extern_sheets`, as referenced in formula syntax trees
+    sheet_refs: Vec<Option<SheetRef>>,
     typ: u16,
     row: u32,
     is_1904: bool,
@@ -28,6 +32,7 @@ impl<'a> XlsbCellsReader<'a> {
         strings: &'a [String],
         extern_sheets: &'a [String],
         metadata_names: &'a [(String, String)],
+        tables: &'a [TableMetadata],
         is_1904: bool,
     ) -> Result<Self, XlsbError> {
         let mut buf = Vec::with_capacity(1024);
@@ -61,6 +66,11 @@ impl<'a> XlsbCellsReader<'a> {
             strings,
             extern_sheets,
             metadata_names,
+            tables,
+            sheet_refs: extern_sheets
+                .iter()
+                .map(|s| (!s.starts_with('#')).then(|| SheetRef::new(s.as_str())))
+                .collect(),
             dimensions,
             typ: 0,
             row: 0,
@@ -92,7 +102,7 @@ impl<'a> XlsbCellsReader<'a> {
                             let v = (v as f64) / 100.0;
                             format_excel_f64_ref(
                                 v,
-                                cell_format(&self.formats, &self.buf),
+                                cell_format(self.formats, &self.buf),
                                 self.is_1904,
                             )
                         } else {
@@ -103,7 +113,7 @@ impl<'a> XlsbCellsReader<'a> {
                         v[4..].copy_from_slice(&self.buf[8..12]);
                         let v = read_f64(&v);
                         let v = if d100 { v / 100.0 } else { v };
-                        format_excel_f64_ref(v, cell_format(&self.formats, &self.buf), self.is_1904)
+                        format_excel_f64_ref(v, cell_format(self.formats, &self.buf), self.is_1904)
                     }
                 }
                 0x0003 => {
@@ -124,7 +134,7 @@ impl<'a> XlsbCellsReader<'a> {
                 0x0004 | 0x000A => DataTypeRef::Bool(self.buf[8] != 0), // BrtCellBool or BrtFmlaBool
                 0x0005 | 0x0009 => {
                     let v = read_f64(&self.buf[8..16]);
-                    format_excel_f64_ref(v, cell_format(&self.formats, &self.buf), self.is_1904)
+                    format_excel_f64_ref(v, cell_format(self.formats, &self.buf), self.is_1904)
                 } // BrtCellReal or BrtFmlaNum
                 0x0006 | 0x0008 => {
                     DataTypeRef::String(wide_str(&self.buf[8..], &mut 0)?.into_owned())
@@ -152,52 +162,76 @@ impl<'a> XlsbCellsReader<'a> {
     }
 
     pub fn next_formula(&mut self) -> Result<Option<Cell<String>>, XlsbError> {
-        let value = loop {
+        let col = match self.next_formula_record()? {
+            Some(col) => col,
+            None => return Ok(None),
+        };
+        let (rgce, _) = formula_rgce(self.typ, &self.buf);
+        let value = parse_formula(rgce, self.extern_sheets, self.metadata_names, self.tables)?;
+        Ok(Some(Cell::new((self.row, col), value)))
+    }
+
+    /// Reads the next cell formula as a syntax tree
+    pub fn next_formula_ast(&mut self) -> Result<Option<Cell<Expr>>, XlsbError> {
+        let col = match self.next_formula_record()? {
+            Some(col) => col,
+            None => return Ok(None),
+        };
+        let ctx = RgceContext {
+            kind: RgceKind::Biff12,
+            sheets: &self.sheet_refs,
+            names: self.metadata_names,
+        };
+        let (rgce, extra) = formula_rgce(self.typ, &self.buf);
+        let value = or_unrecognized(ctx.parse(rgce, extra), (self.row, col));
+        Ok(Some(Cell::new((self.row, col), value)))
+    }
+
+    /// Moves to the next formula cell record, returns its column
+    fn next_formula_record(&mut self) -> Result<Option<u32>, XlsbError> {
+        loop {
             self.typ = self.iter.read_type()?;
             let _ = self.iter.fill_buffer(&mut self.buf)?;
 
-            let value = match self.typ {
+            match self.typ {
                 // 0x0001 => continue, // DataType::Empty, // BrtCellBlank
-                0x0008 => {
-                    // BrtFmlaString
-                    let cch = read_u32(&self.buf[8..]) as usize;
-                    let formula = &self.buf[14 + cch * 2..];
-                    let cce = read_u32(formula) as usize;
-                    let rgce = &formula[4..4 + cce];
-                    parse_formula(rgce, &self.extern_sheets, &self.metadata_names)?
-                }
-                0x0009 => {
-                    // BrtFmlaNum
-                    let formula = &self.buf[18..];
-                    let cce = read_u32(formula) as usize;
-                    let rgce = &formula[4..4 + cce];
-                    parse_formula(rgce, &self.extern_sheets, &self.metadata_names)?
-                }
-                0x000A | 0x000B => {
-                    // BrtFmlaBool | BrtFmlaError
-                    let formula = &self.buf[11..];
-                    let cce = read_u32(formula) as usize;
-                    let rgce = &formula[4..4 + cce];
-                    parse_formula(rgce, &self.extern_sheets, &self.metadata_names)?
-                }
+                // BrtFmlaString | BrtFmlaNum | BrtFmlaBool | BrtFmlaError
+                0x0008..=0x000B => return Ok(Some(read_u32(&self.buf))),
                 0x0000 => {
                     // BrtRowHdr
                     self.row = read_u32(&self.buf);
                     if self.row > 0x0010_0000 {
                         return Ok(None); // invalid row
                     }
-                    continue;
                 }
                 0x0092 => return Ok(None), // BrtEndSheetData
-                _ => continue, // anything else, ignore and try next, without changing idx
-            };
-            break value;
-        };
-        let col = read_u32(&self.buf);
-        Ok(Some(Cell::new((self.row, col), value)))
+                _ => (), // anything else, ignore and try next, without changing idx
+            }
+        }
     }
 }
 
+/// Formula tokens (rgce) and additional data (rgcb) of a formula cell record
+///
+/// CellParsedFormula [MS-XLSB 2.5.97.1]
+fn formula_rgce(typ: u16, buf: &[u8]) -> (&[u8], &[u8]) {
+    let formula = match typ {
+        // BrtFmlaString
+        0x0008 => {
+            let cch = read_u32(&buf[8..]) as usize;
+            &buf[14 + cch * 2..]
+        }
+        // BrtFmlaNum
+        0x0009 => &buf[18..],
+        // BrtFmlaBool | BrtFmlaError
+        _ => &buf[11..],
+    };
+    let cce = read_u32(formula) as usize;
+    let rgce = &formula[4..4 + cce];
+    let extra = formula.get(8 + cce..).unwrap_or_default();
+    (rgce, extra)
+}
+
 fn parse_dimensions(buf: &[u8]) -> Dimensions {
     Dimensions {
         start: (read_u32(&buf[0..4]), read_u32(&buf[8..12])),
@@ -4,7 +4,7 @@ pub use cells_reader::XlsbCellsReader;
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::io::{BufReader, Read, Seek};
+use std::io::{BufReader, Cursor, Read, Seek};
 use std::string::String;
 
 use log::debug;
@@ -17,11 +17,27 @@ use quick_xml::Reader as XmlReader;
 use zip::read::{ZipArchive, ZipFile};
 use zip::result::ZipError;
 
+use crate::crypto::{is_encrypted, read_package};
 use crate::datatype::DataTypeRef;
-use crate::formats::{builtin_format_by_code, detect_custom_number_format, CellFormat};
-use crate::utils::{push_column, read_f64, read_i32, read_u16, read_u32, read_usize};
+use crate::formats::{
+    builtin_format_by_code, builtin_format_code, detect_custom_number_format, CellFormat,
+};
+use crate::formula::{ptg_list, resolve_table_references, Expr};
+use crate::style::{
+    border_style, fill_pattern, horizontal_alignment, rotation, vertical_alignment, Alignment,
+    Border, BorderSide, Color, Fill, Font, Style, TextRun,
+};
+use crate::utils::{
+    push_column, read_charts, read_document_properties, read_f64, read_i32, read_relationships,
+    read_u16, read_u32, read_usize,
+};
 use crate::vba::VbaProject;
-use crate::{Cell, DataType, Metadata, Range, Reader, Sheet, SheetType, SheetVisible};
+use crate::{
+    Cell, Chart, Comment, ConditionalFormat, ConditionalFormatType, DataType, DataValidation,
+    Dimensions, Hyperlink, LineLayout, Metadata, Range, Reader, Sheet, SheetLayout, SheetType,
+    SheetVisible, StyleId, Styles, Table, TableMetadata, TableReader, Tables, ValidationOperator,
+    ValidationType,
+};
 
 /// A Xlsb specific error
 #[derive(Debug)]
@@ -68,6 +84,13 @@ pub enum XlsbError {
         /// buffer length
         buf_len: usize,
     },
+    /// Record too short for its type
+    RecordLen {
+        /// record type
+        typ: u16,
+        /// record length
+        len: usize,
+    },
     /// Unrecognized data
     Unrecognized {
         /// data type
@@ -75,13 +98,23 @@ pub enum XlsbError {
         /// value found
         val: String,
     },
+    /// Formula error
+    Formula(crate::formula::FormulaError),
     /// Worksheet not found
     WorksheetNotFound(String),
+    /// Table not found
+    TableNotFound(String),
+    /// Workbook is password protected
+    Password,
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
 }
 
 from_err!(std::io::Error, XlsbError, Io);
 from_err!(zip::result::ZipError, XlsbError, Zip);
 from_err!(quick_xml::Error, XlsbError, Xml);
+from_err!(crate::formula::FormulaError, XlsbError, Formula);
+from_err!(crate::crypto::CryptoError, XlsbError, Crypto);
 
 impl std::fmt::Display for XlsbError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -106,10 +139,17 @@ impl std::fmt::Display for XlsbError {
                 f,
                 "Wide str length exceeds buffer length ({ws_len} > {buf_len})",
             ),
+            XlsbError::RecordLen { typ, len } => {
+                write!(f, "Record {typ:X} too short: {len} bytes")
+            }
             XlsbError::Unrecognized { typ, val } => {
                 write!(f, "Unrecognized {typ}: {val}")
             }
+            XlsbError::Formula(e) => write!(f, "Formula error: {e}"),
             XlsbError::WorksheetNotFound(name) => write!(f, "Worksheet '{name}' not found"),
+            XlsbError::TableNotFound(name) => write!(f, "Table '{name}' not found"),
+            XlsbError::Password => write!(f, "Workbook is password protected"),
+            XlsbError::Crypto(e) => write!(f, "Decryption error: {e}"),
         }
     }
 }
@@ -121,6 +161,8 @@ impl std::error::Error for XlsbError {
             XlsbError::Zip(e) => Some(e),
             XlsbError::Xml(e) => Some(e),
             XlsbError::Vba(e) => Some(e),
+            XlsbError::Formula(e) => Some(e),
+            XlsbError::Crypto(e) => Some(e),
             _ => None,
         }
     }
@@ -136,6 +178,8 @@ pub struct Xlsb<RS> {
     formats: Vec<CellFormat>,
     is_1904: bool,
     metadata: Metadata,
+    /// Tables: Name, Sheet, Columns, Data dimensions
+    tables: Tables,
     #[cfg(feature = "picture")]
     pictures: Option<Vec<(String, Vec<u8>)>>,
 }
@@ -272,6 +316,32 @@ impl<RS: Read + Seek> Xlsb<RS> {
         Ok(())
     }
 
+    /// Reads the shared strings as text runs, only needed for rich text
+    fn read_rich_shared_strings(&mut self, fonts: &[Font]) -> Result<Vec<Vec<TextRun>>, XlsbError> {
+        let mut iter = match RecordIter::from_zip(&mut self.zip, "xl/sharedStrings.bin") {
+            Ok(iter) => iter,
+            Err(_) => return Ok(Vec::new()), // it is fine if path does not exists
+        };
+        let mut buf = Vec::with_capacity(1024);
+
+        let _ = iter.next_skip_blocks(0x009F, &[], &mut buf)?; // BrtBeginSst
+        let len = read_usize(&buf[4..8]);
+
+        // BrtSSTItems
+        let mut strings = Vec::with_capacity(len);
+        for _ in 0..len {
+            let _ = iter.next_skip_blocks(
+                0x0013,
+                &[
+                    (0x0023, Some(0x0024)), // future
+                ],
+                &mut buf,
+            )?; // BrtSSTItem
+            strings.push(rich_str(&buf, fonts)?);
+        }
+        Ok(strings)
+    }
+
     /// MS-XLSB 2.1.7.61
     fn read_workbook(
         &mut self,
@@ -369,7 +439,8 @@ impl<RS: Read + Seek> Xlsb<RS> {
                     let name = wide_str(&buf[9..len], &mut str_len)?.into_owned();
                     let rgce_len = read_u32(&buf[9 + str_len..]) as usize;
                     let rgce = &buf[13 + str_len..13 + str_len + rgce_len];
-                    let formula = parse_formula(rgce, &self.extern_sheets, &defined_names)?;
+                    // tables are not known yet
+                    let formula = parse_formula(rgce, &self.extern_sheets, &defined_names, &[])?;
                     defined_names.push((name, formula));
                 }
                 0x009D | 0x0225 | 0x018D | 0x0180 | 0x009A | 0x0252 | 0x0229 | 0x009B | 0x0084 => {
@@ -398,10 +469,94 @@ impl<RS: Read + Seek> Xlsb<RS> {
             &self.strings,
             &self.extern_sheets,
             &self.metadata.names,
+            self.tables.as_deref().unwrap_or_default(),
             self.is_1904,
         )
     }
 
+    /// Reads the font table of the styles part
+    fn fonts(&mut self) -> Result<Vec<Font>, XlsbError> {
+        let mut iter = match RecordIter::from_zip(&mut self.zip, "xl/styles.bin") {
+            Ok(iter) => iter,
+            Err(XlsbError::FileNotFound(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut buf = Vec::with_capacity(1024);
+        let mut fonts = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtFont
+                0x002B => fonts.push(parse_font(&buf)?),
+                // BrtEndFonts, BrtEndStyleSheet
+                0x0264 | 0x0117 => return Ok(fonts),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.59 Table
+    fn read_table_metadata(&mut self) -> Result<(), XlsbError> {
+        let mut tables = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        for (sheet_name, path) in self.sheets.clone() {
+            let parts = read_relationships::<_, XlsbError>(&mut self.zip, &path)?;
+            for part in parts
+                .into_iter()
+                .filter(|r| !r.external && r.typ.ends_with("/table"))
+            {
+                let mut iter = RecordIter::from_zip(&mut self.zip, &part.target)?;
+                let mut table = None;
+                let mut columns = Vec::new();
+                loop {
+                    let typ = iter.read_type()?;
+                    let _ = iter.fill_buffer(&mut buf)?;
+                    match typ {
+                        // BrtBeginList
+                        0x015F => {
+                            let mut consumed = 0;
+                            let name = nullable_wide_str(&buf[64..], &mut consumed)?;
+                            let display_name =
+                                nullable_wide_str(&buf[64 + consumed..], &mut consumed)?;
+                            table = Some(TableMetadata {
+                                id: read_u32(&buf[20..24]),
+                                name: display_name.or(name).unwrap_or_default().into_owned(),
+                                sheet_name: sheet_name.clone(),
+                                columns: Vec::new(),
+                                dimensions: Dimensions {
+                                    start: (read_u32(&buf[0..4]), read_u32(&buf[8..12])),
+                                    end: (read_u32(&buf[4..8]), read_u32(&buf[12..16])),
+                                },
+                                header_rows: read_u32(&buf[24..28]),
+                                totals_rows: read_u32(&buf[28..32]),
+                                // fForceInsertToBeVisible
+                                insert_row: read_u32(&buf[32..36]) & 0x4 != 0,
+                            });
+                        }
+                        // BrtBeginListCol
+                        0x0161 => {
+                            let mut consumed = 0;
+                            let name = nullable_wide_str(&buf[24..], &mut consumed)?;
+                            let caption = nullable_wide_str(&buf[24 + consumed..], &mut consumed)?;
+                            let column = caption.filter(|c| !c.is_empty()).or(name);
+                            columns.push(column.unwrap_or_default().into_owned());
+                        }
+                        // BrtEndList
+                        0x0160 => break,
+                        _ => (),
+                    }
+                }
+                if let Some(mut table) = table {
+                    table.columns = columns;
+                    tables.push(table);
+                }
+            }
+        }
+        self.tables = Some(tables);
+        Ok(())
+    }
+
     #[cfg(feature = "picture")]
     fn read_pictures(&mut self) -> Result<(), XlsbError> {
         let mut pics = Vec::new();
@@ -431,10 +586,24 @@ impl<RS: Read + Seek> Xlsb<RS> {
     }
 }
 
+impl Xlsb<Cursor<Vec<u8>>> {
+    /// Opens a workbook encrypted with a password, with the Agile or Standard encryption
+    ///
+    /// The decrypted package is kept in memory. Workbooks which are not encrypted are
+    /// opened as well, ignoring the password.
+    pub fn new_with_password<R: Read + Seek>(reader: R, password: &str) -> Result<Self, XlsbError> {
+        let package = read_package(reader, password)?;
+        Self::new(Cursor::new(package))
+    }
+}
+
 impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
     type Error = XlsbError;
 
-    fn new(reader: RS) -> Result<Self, XlsbError> {
+    fn new(mut reader: RS) -> Result<Self, XlsbError> {
+        if is_encrypted(&mut reader)? {
+            return Err(XlsbError::Password);
+        }
         let mut xlsb = Xlsb {
             zip: ZipArchive::new(reader)?,
             sheets: Vec::new(),
@@ -443,6 +612,7 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
             formats: Vec::new(),
             is_1904: false,
             metadata: Metadata::default(),
+            tables: None,
             #[cfg(feature = "picture")]
             pictures: None,
         };
@@ -450,6 +620,7 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
         xlsb.read_styles()?;
         let relationships = xlsb.read_relationships()?;
         xlsb.read_workbook(&relationships)?;
+        xlsb.metadata.properties = read_document_properties::<_, XlsbError>(&mut xlsb.zip);
         #[cfg(feature = "picture")]
         xlsb.read_pictures()?;
 
@@ -483,6 +654,8 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
 
     /// MS-XLSB 2.1.7.62
     fn worksheet_formula(&mut self, name: &str) -> Result<Range<String>, XlsbError> {
+        // table references are named after the tables
+        self.load_tables()?;
         let mut cells_reader = self.worksheet_cells_reader(name)?;
         let mut cells = Vec::with_capacity(cells_reader.dimensions().len().min(1_000_000) as _);
         while let Some(cell) = cells_reader.next_formula()? {
@@ -494,6 +667,520 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
     }
 
     /// MS-XLSB 2.1.7.62
+    fn worksheet_formula_ast(&mut self, name: &str) -> Result<Range<Expr>, XlsbError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::with_capacity(cells_reader.dimensions().len().min(1_000_000) as _);
+        while let Some(cell) = cells_reader.next_formula_ast()? {
+            if cell.val != Expr::Missing {
+                cells.push(cell);
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    /// MS-XLSB 2.1.7.62
+    fn merged_regions(&mut self, name: &str) -> Result<Vec<Dimensions>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut regions = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtMergeCell
+                0x00B0 if len < 16 => return Err(XlsbError::RecordLen { typ, len }),
+                0x00B0 => regions.push(Dimensions {
+                    start: (read_u32(&buf[0..4]), read_u32(&buf[8..12])),
+                    end: (read_u32(&buf[4..8]), read_u32(&buf[12..16])),
+                }),
+                // BrtEndMergeCells, BrtEndSheet
+                0x00B2 | 0x0082 => return Ok(regions),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.50 Styles
+    fn styles(&mut self) -> Result<Styles, XlsbError> {
+        let mut iter = match RecordIter::from_zip(&mut self.zip, "xl/styles.bin") {
+            Ok(iter) => iter,
+            Err(XlsbError::FileNotFound(_)) => return Ok(Styles::default()),
+            Err(e) => return Err(e),
+        };
+        let mut buf = Vec::with_capacity(1024);
+        let mut number_formats = BTreeMap::new();
+        let mut fonts = Vec::new();
+        let mut fills = Vec::new();
+        let mut borders = Vec::new();
+        let mut styles = Vec::new();
+        let mut in_cell_xfs = false;
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtFmt
+                0x002C => {
+                    let fmt_str = wide_str(&buf[2..], &mut 0)?;
+                    number_formats.insert(read_u16(&buf), fmt_str.into_owned());
+                }
+                // BrtFont
+                0x002B => fonts.push(parse_font(&buf)?),
+                // BrtFill
+                0x002D => fills.push(Fill {
+                    pattern: fill_pattern(read_u32(&buf) as u8).map(String::from),
+                    foreground: read_color(&buf[4..12]),
+                    background: read_color(&buf[12..20]),
+                }),
+                // BrtBorder
+                0x002E => borders.push(Border {
+                    top: read_border_side(&buf[1..11]),
+                    bottom: read_border_side(&buf[11..21]),
+                    left: read_border_side(&buf[21..31]),
+                    right: read_border_side(&buf[31..41]),
+                }),
+                // BrtBeginCellXFs
+                0x0269 => in_cell_xfs = true,
+                // BrtXF
+                0x002F if in_cell_xfs => {
+                    let fmt_code = read_u16(&buf[2..4]);
+                    let flags = read_u16(&buf[12..14]);
+                    styles.push(Style {
+                        number_format: number_formats
+                            .get(&fmt_code)
+                            .cloned()
+                            .or_else(|| builtin_format_code(fmt_code).map(String::from)),
+                        font: fonts
+                            .get(read_u16(&buf[4..6]) as usize)
+                            .cloned()
+                            .unwrap_or_default(),
+                        fill: fills
+                            .get(read_u16(&buf[6..8]) as usize)
+                            .cloned()
+                            .unwrap_or_default(),
+                        border: borders
+                            .get(read_u16(&buf[8..10]) as usize)
+                            .cloned()
+                            .unwrap_or_default(),
+                        alignment: Alignment {
+                            horizontal: horizontal_alignment((flags & 0x7) as u8).map(String::from),
+                            vertical: vertical_alignment((flags >> 3 & 0x7) as u8)
+                                .map(String::from),
+                            wrap_text: flags & 0x40 != 0,
+                            indent: buf[11] as u32,
+                            rotation: rotation(buf[10]),
+                        },
+                    });
+                }
+                // BrtEndCellXFs, BrtEndStyleSheet
+                0x026A | 0x0117 => return Ok(Styles::new(styles)),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.4.671 BrtHLink
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let rels = read_relationships::<_, XlsbError>(&mut self.zip, &path)?;
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut links = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                0x01EE => {
+                    let range = Dimensions {
+                        start: (read_u32(&buf[0..4]), read_u32(&buf[8..12])),
+                        end: (read_u32(&buf[4..8]), read_u32(&buf[12..16])),
+                    };
+                    // relationship id (nullable), location, tooltip and display strings
+                    let mut consumed = 0;
+                    let mut offset = 16;
+                    let id = if read_u32(&buf[offset..]) == 0xFFFF_FFFF {
+                        offset += 4;
+                        None
+                    } else {
+                        let id = wide_str(&buf[offset..], &mut consumed)?.into_owned();
+                        offset += consumed;
+                        Some(id)
+                    };
+                    let location = wide_str(&buf[offset..], &mut consumed)?.into_owned();
+                    offset += consumed;
+                    let tooltip = wide_str(&buf[offset..], &mut consumed)?.into_owned();
+                    links.push(Hyperlink {
+                        range,
+                        url: id.and_then(|id| {
+                            rels.iter().find(|r| r.id == id).map(|r| r.target.clone())
+                        }),
+                        location: Some(location).filter(|l| !l.is_empty()),
+                        tooltip: Some(tooltip).filter(|t| !t.is_empty()),
+                    });
+                }
+                // BrtEndSheet
+                0x0082 => return Ok(links),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.8 Comments
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let parts = read_relationships::<_, XlsbError>(&mut self.zip, &path)?;
+        let part = match parts
+            .into_iter()
+            .find(|r| !r.external && r.typ.ends_with("/comments"))
+        {
+            Some(r) => r.target,
+            None => return Ok(Vec::new()),
+        };
+        let fonts = self.fonts()?;
+        let mut iter = RecordIter::from_zip(&mut self.zip, &part)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut authors = Vec::new();
+        let mut comments = Vec::new();
+        let mut current = None;
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtCommentAuthor
+                0x0278 => authors.push(wide_str(&buf, &mut 0)?.into_owned()),
+                // BrtBeginComment: author index and UncheckedRfX
+                0x027B => {
+                    current = Some((read_u32(&buf), (read_u32(&buf[4..]), read_u32(&buf[12..]))))
+                }
+                // BrtCommentText
+                0x027D => {
+                    if let Some((author, position)) = current.take() {
+                        let author = authors.get(author as usize).cloned().unwrap_or_default();
+                        comments.push(Comment::new(position, author, rich_str(&buf, &fonts)?));
+                    }
+                }
+                // BrtEndComments
+                0x0275 => return Ok(comments),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.62
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut cells = Vec::new();
+        let mut row = 0;
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtRowHdr
+                0x0000 => row = read_u32(&buf),
+                // Cell records and BrtCellRString, the style is stored in the 24 bits following the column
+                0x0001..=0x000B | 0x003E if len < 7 => {
+                    return Err(XlsbError::RecordLen { typ, len })
+                }
+                0x0001..=0x000B | 0x003E => {
+                    let col = read_u32(&buf);
+                    let id = u32::from_le_bytes([buf[4], buf[5], buf[6], 0]);
+                    // the default style is implied
+                    if id != 0 {
+                        cells.push(Cell::new((row, col), StyleId(id)));
+                    }
+                }
+                // BrtEndSheetData
+                0x0092 => return Ok(Range::from_sparse(cells)),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.62
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<Vec<TextRun>>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let fonts = self.fonts()?;
+        let strings = self.read_rich_shared_strings(&fonts)?;
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut cells = Vec::new();
+        let mut row = 0;
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            let runs = match typ {
+                // BrtRowHdr
+                0x0000 => {
+                    row = read_u32(&buf);
+                    continue;
+                }
+                // BrtCellSt
+                0x0006 => vec![TextRun {
+                    text: wide_str(&buf[8..], &mut 0)?.into_owned(),
+                    font: None,
+                }],
+                // BrtCellIsst
+                0x0007 => strings
+                    .get(read_usize(&buf[8..12]))
+                    .cloned()
+                    .unwrap_or_default(),
+                // BrtCellRString
+                0x003E => rich_str(&buf[8..], &fonts)?,
+                // BrtEndSheetData
+                0x0092 => return Ok(Range::from_sparse(cells)),
+                _ => continue,
+            };
+            if runs.iter().any(|r| !r.text.is_empty()) {
+                cells.push(Cell::new((row, read_u32(&buf)), runs));
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.62
+    fn worksheet_layout(&mut self, name: &str) -> Result<SheetLayout, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut layout = SheetLayout::default();
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtWsFmtInfo
+                0x01E5 => {
+                    let width = read_u32(&buf);
+                    layout.default_column_width = Some(if width == 0xFFFF_FFFF {
+                        read_u16(&buf[4..]) as f64
+                    } else {
+                        width as f64 / 256.
+                    });
+                    layout.default_row_height = Some(read_u16(&buf[6..]) as f64 / 20.);
+                }
+                // BrtColInfo
+                0x003C => {
+                    let flags = read_u16(&buf[16..]);
+                    layout.push_columns(LineLayout {
+                        first: read_u32(&buf),
+                        last: read_u32(&buf[4..]),
+                        hidden: flags & 0x1 != 0,
+                        outline_level: ((flags >> 8) & 0x7) as u8,
+                        size: Some(read_u32(&buf[8..]) as f64 / 256.),
+                    });
+                }
+                // BrtRowHdr
+                0x0000 => {
+                    let row = read_u32(&buf);
+                    let outline_level = buf[11] & 0x7;
+                    let hidden = buf[11] & 0x10 != 0;
+                    let custom_height = buf[11] & 0x20 != 0;
+                    if hidden || outline_level > 0 || custom_height {
+                        layout.push_rows(LineLayout {
+                            first: row,
+                            last: row,
+                            hidden,
+                            outline_level,
+                            size: Some(read_u16(&buf[8..]) as f64 / 20.),
+                        });
+                    }
+                }
+                // BrtEndSheetData
+                0x0092 => return Ok(layout),
+                _ => (),
+            }
+        }
+    }
+
+    /// MS-XLSB 2.1.7.62
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, XlsbError> {
+        self.load_tables()?;
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut validations = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtDVal
+                0x0040 => {
+                    let flags = read_u32(&buf[0..4]);
+                    let validation_type = ValidationType::from_biff(flags & 0x0F);
+                    let mut validation = DataValidation {
+                        validation_type,
+                        operator: ValidationOperator::from_biff((flags >> 20) & 0x0F),
+                        allow_blank: flags & 0x100 != 0,
+                        ..Default::default()
+                    };
+                    let count = read_usize(&buf[4..8]);
+                    let mut offset = 8;
+                    for _ in 0..count {
+                        let rfx = &buf[offset..offset + 16];
+                        validation.ranges.push(Dimensions {
+                            start: (read_u32(&rfx[0..4]), read_u32(&rfx[8..12])),
+                            end: (read_u32(&rfx[4..8]), read_u32(&rfx[12..16])),
+                        });
+                        offset += 16;
+                    }
+                    let mut strings = [None, None, None, None];
+                    for s in strings.iter_mut() {
+                        let mut consumed = 0;
+                        let value = wide_str(&buf[offset..], &mut consumed)?;
+                        offset += consumed;
+                        *s = Some(value.into_owned()).filter(|v| !v.is_empty());
+                    }
+                    let [error_title, error_message, prompt_title, prompt_message] = strings;
+                    validation.error_title = error_title;
+                    validation.error_message = error_message;
+                    validation.prompt_title = prompt_title;
+                    validation.prompt_message = prompt_message;
+                    let mut formulas = [None, None];
+                    for f in formulas.iter_mut() {
+                        // rgce followed by its extra data
+                        let cce = read_usize(&buf[offset..offset + 4]);
+                        let rgce = &buf[offset + 4..offset + 4 + cce];
+                        offset += 4 + cce;
+                        offset += 4 + read_usize(&buf[offset..offset + 4]);
+                        if !rgce.is_empty() {
+                            let formula = parse_formula(
+                                rgce,
+                                &self.extern_sheets,
+                                &self.metadata.names,
+                                self.tables.as_deref().unwrap_or_default(),
+                            )?;
+                            // list values are separated by null characters
+                            *f = Some(formula.replace('\0', ","));
+                        }
+                    }
+                    let [formula1, formula2] = formulas;
+                    validation.formula1 = formula1;
+                    validation.formula2 = formula2;
+                    validations.push(validation);
+                }
+                // BrtEndSheet
+                0x0082 => return Ok(validations),
+                _ => (),
+            }
+        }
+    }
+
+    fn worksheet_conditional_formats(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<ConditionalFormat>, XlsbError> {
+        self.load_tables()?;
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut formats: Vec<ConditionalFormat> = Vec::new();
+        let mut ranges = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let _ = iter.fill_buffer(&mut buf)?;
+            match typ {
+                // BrtBeginConditionalFormatting
+                0x01CD => {
+                    ranges.clear();
+                    let count = read_usize(&buf[8..12]);
+                    for rfx in buf[12..].chunks_exact(16).take(count) {
+                        ranges.push(Dimensions {
+                            start: (read_u32(&rfx[0..4]), read_u32(&rfx[8..12])),
+                            end: (read_u32(&rfx[4..8]), read_u32(&rfx[12..16])),
+                        });
+                    }
+                }
+                // BrtBeginCFRule
+                0x01CF => {
+                    let rule_type = match read_u32(&buf[0..4]) {
+                        1 => ConditionalFormatType::CellIs,
+                        2 => ConditionalFormatType::Expression,
+                        3 => ConditionalFormatType::ColorScale,
+                        4 => ConditionalFormatType::DataBar,
+                        5 => ConditionalFormatType::Other("top10".to_string()),
+                        6 => ConditionalFormatType::IconSet,
+                        t => ConditionalFormatType::Other(t.to_string()),
+                    };
+                    let dxf_id = read_u32(&buf[8..12]);
+                    let operator = match rule_type {
+                        ConditionalFormatType::CellIs => Some(ValidationOperator::from_biff(
+                            read_u32(&buf[16..20]).saturating_sub(1),
+                        )),
+                        _ => None,
+                    };
+                    let mut format = ConditionalFormat {
+                        ranges: ranges.clone(),
+                        rule_type,
+                        priority: read_u32(&buf[12..16]),
+                        operator,
+                        dxf_id: Some(dxf_id).filter(|&id| id != 0xFFFF_FFFF),
+                        ..Default::default()
+                    };
+                    // formulas sizes, followed by a nullable parameter string and the formulas
+                    let sizes = [30, 34, 38].map(|i| read_usize(&buf[i..i + 4]));
+                    let mut offset = 42;
+                    if read_u32(&buf[offset..]) == 0xFFFF_FFFF {
+                        offset += 4;
+                    } else {
+                        let mut consumed = 0;
+                        let _ = wide_str(&buf[offset..], &mut consumed)?;
+                        offset += consumed;
+                    }
+                    for size in sizes.into_iter().filter(|&size| size > 0) {
+                        let cce = read_usize(&buf[offset..offset + 4]);
+                        let rgce = &buf[offset + 4..offset + 4 + cce];
+                        format.formulas.push(parse_formula(
+                            rgce,
+                            &self.extern_sheets,
+                            &self.metadata.names,
+                            self.tables.as_deref().unwrap_or_default(),
+                        )?);
+                        offset += size;
+                    }
+                    formats.push(format);
+                }
+                // BrtEndSheet
+                0x0082 => break,
+                _ => (),
+            }
+        }
+        formats.sort_by_key(|f| f.priority);
+        Ok(formats)
+    }
+
+    /// Charts are DrawingML parts, as in xlsx files
+    fn worksheet_charts(&mut self, name: &str) -> Result<Vec<Chart>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        read_charts::<_, XlsbError>(&mut self.zip, &path)
+    }
+
     fn worksheets(&mut self) -> Vec<(String, Range<DataType>)> {
         let sheets = self
             .sheets
@@ -515,6 +1202,54 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
     }
 }
 
+impl<RS: Read + Seek> TableReader<RS> for Xlsb<RS> {
+    fn load_tables(&mut self) -> Result<(), XlsbError> {
+        if self.tables.is_none() {
+            self.read_table_metadata()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn table_names(&self) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    fn table_by_name(&mut self, table_name: &str) -> Result<Table<DataType>, XlsbError> {
+        let table = self
+            .tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .find(|t| t.name == table_name)
+            .cloned()
+            .ok_or_else(|| XlsbError::TableNotFound(table_name.into()))?;
+        let range = self.worksheet_range(&table.sheet_name)?;
+        Ok(table.table(&range))
+    }
+
+    fn worksheet_formula_resolved(&mut self, name: &str) -> Result<Range<String>, XlsbError> {
+        let formulas = self.worksheet_formula(name)?;
+        let tables = self.tables.as_deref().unwrap_or_default();
+        Ok(resolve_table_references(formulas, name, tables))
+    }
+}
+
 pub(crate) struct RecordIter<'a> {
     b: [u8; 1],
     r: BufReader<ZipFile<'a>>,
@@ -592,6 +1327,98 @@ impl<'a> RecordIter<'a> {
     }
 }
 
+/// MS-XLSB 2.4.659 BrtFont
+fn parse_font(buf: &[u8]) -> Result<Font, XlsbError> {
+    let grbit = read_u16(&buf[2..4]);
+    Ok(Font {
+        name: Some(wide_str(&buf[21..], &mut 0)?.into_owned()),
+        size: Some(read_u16(buf) as f64 / 20.),
+        bold: read_u16(&buf[4..6]) >= 700,
+        italic: grbit & 0x2 != 0,
+        underline: buf[8] != 0,
+        strike: grbit & 0x8 != 0,
+        color: read_color(&buf[12..20]),
+    })
+}
+
+/// MS-XLSB 2.5.121 RichStr, as text runs
+///
+/// Run fonts are indexes in `fonts`.
+fn rich_str(buf: &[u8], fonts: &[Font]) -> Result<Vec<TextRun>, XlsbError> {
+    let mut str_len = 0;
+    let text = wide_str(&buf[1..], &mut str_len)?;
+    let rest = &buf[1 + str_len..];
+    if buf[0] & 0x1 == 0 || rest.len() < 4 {
+        return Ok(vec![TextRun {
+            text: text.into_owned(),
+            font: None,
+        }]);
+    }
+    // StrRun: first character index (in UTF-16 code units) and font index
+    let utf16 = text.encode_utf16().collect::<Vec<_>>();
+    let count = read_u32(rest) as usize;
+    let mut runs = Vec::with_capacity(count + 1);
+    let mut bounds = vec![(0, None)];
+    bounds.extend(
+        rest[4..]
+            .chunks_exact(4)
+            .take(count)
+            .map(|r| (read_u16(r) as usize, fonts.get(read_u16(&r[2..]) as usize))),
+    );
+    for (i, &(start, font)) in bounds.iter().enumerate() {
+        let end = bounds
+            .get(i + 1)
+            .map_or(utf16.len(), |b| b.0)
+            .min(utf16.len());
+        if start < end {
+            runs.push(TextRun {
+                text: String::from_utf16_lossy(&utf16[start..end]),
+                font: font.cloned(),
+            });
+        }
+    }
+    Ok(runs)
+}
+
+/// MS-XLSB 2.5.25 BrtColor
+fn read_color(buf: &[u8]) -> Option<Color> {
+    match buf[0] >> 1 {
+        0 => Some(Color::Auto),
+        1 => Some(Color::Indexed(buf[1] as u32)),
+        2 => Some(Color::Argb(u32::from_be_bytes([
+            buf[7], buf[4], buf[5], buf[6],
+        ]))),
+        3 => Some(Color::Theme {
+            index: buf[1] as u32,
+            tint: i16::from_le_bytes([buf[2], buf[3]]) as f64 / 32767.,
+        }),
+        _ => None,
+    }
+}
+
+/// MS-XLSB 2.5.14 Blxf
+fn read_border_side(buf: &[u8]) -> BorderSide {
+    match border_style(buf[0]) {
+        Some(style) => BorderSide {
+            style: Some(style.into()),
+            color: read_color(&buf[2..10]),
+        },
+        None => BorderSide::default(),
+    }
+}
+
+/// XLNullableWideString, a null string having a 0xFFFFFFFF length
+fn nullable_wide_str<'a>(
+    buf: &'a [u8],
+    str_len: &mut usize,
+) -> Result<Option<Cow<'a, str>>, XlsbError> {
+    if read_u32(buf) == 0xFFFF_FFFF {
+        *str_len = 4;
+        return Ok(None);
+    }
+    wide_str(buf, str_len).map(Some)
+}
+
 fn wide_str<'a>(buf: &'a [u8], str_len: &mut usize) -> Result<Cow<'a, str>, XlsbError> {
     let len = read_u32(buf) as usize;
     if buf.len() < 4 + len * 2 {
@@ -615,6 +1442,7 @@ fn parse_formula(
     mut rgce: &[u8],
     sheets: &[String],
     names: &[(String, String)],
+    tables: &[TableMetadata],
 ) -> Result<String, XlsbError> {
     if rgce.is_empty() {
         return Ok(String::new());
@@ -696,8 +1524,8 @@ fn parse_formula(
                     0x09 => "<",
                     0x0A => "<=",
                     0x0B => "=",
-                    0x0C => ">",
-                    0x0D => ">=",
+                    0x0C => ">=",
+                    0x0D => ">",
                     0x0E => "<>",
                     0x0F => " ",
                     0x10 => ",",
@@ -739,7 +1567,11 @@ fn parse_formula(
                 let eptg = rgce[0];
                 rgce = &rgce[1..];
                 match eptg {
-                    0x19 => rgce = &rgce[12..],
+                    0x19 => {
+                        // PtgList
+                        formula.push_str(&ptg_list(&rgce[..12], tables));
+                        rgce = &rgce[12..];
+                    }
                     0x1D => rgce = &rgce[4..],
                     e => return Err(XlsbError::Etpg(e)),
                 }
@@ -891,7 +1723,7 @@ fn parse_formula(
             0x29 | 0x49 | 0x69 => {
                 let cce = read_u16(rgce) as usize;
                 rgce = &rgce[2..];
-                let f = parse_formula(&rgce[..cce], sheets, names)?;
+                let f = parse_formula(&rgce[..cce], sheets, names, tables)?;
                 stack.push(formula.len());
                 formula.push_str(&f);
                 rgce = &rgce[cce..];
@@ -1,14 +1,18 @@
+use std::collections::HashMap;
+
 use quick_xml::{
     events::{attributes::Attribute, BytesStart, Event},
     name::QName,
 };
 
 use super::{
-    get_attribute, get_dimension, get_row, get_row_column, read_string, Dimensions, XlReader,
+    get_attribute, get_dimension, get_row, get_row_column, read_rich_text, read_string, Dimensions,
+    XlReader,
 };
 use crate::{
     datatype::DataTypeRef,
     formats::{format_excel_f64_ref, CellFormat},
+    style::TextRun,
     Cell, XlsxError,
 };
 
@@ -23,6 +27,16 @@ pub struct XlsxCellReader<'a> {
     col_index: u32,
     buf: Vec<u8>,
     cell_buf: Vec<u8>,
+    formulas: Formulas,
+}
+
+/// Shared and array formulas met so far, only anchor cells hold their text
+#[derive(Default)]
+struct Formulas {
+    /// Anchor position and formula, by shared index
+    shared: HashMap<u32, ((u32, u32), String)>,
+    /// Array formulas and the area they span
+    arrays: Vec<(Dimensions, String)>,
 }
 
 impl<'a> XlsxCellReader<'a> {
@@ -68,6 +82,7 @@ impl<'a> XlsxCellReader<'a> {
             col_index: 0,
             buf: Vec::with_capacity(1024),
             cell_buf: Vec::with_capacity(1024),
+            formulas: Formulas::default(),
         })
     }
 
@@ -165,7 +180,9 @@ impl<'a> XlsxCellReader<'a> {
                         self.cell_buf.clear();
                         match self.xml.read_event_into(&mut self.cell_buf) {
                             Ok(Event::Start(ref e)) => {
-                                if let Some(f) = read_formula(&mut self.xml, e)? {
+                                if let Some(f) =
+                                    read_formula(&mut self.xml, e, pos, &mut self.formulas)?
+                                {
                                     value = Some(f);
                                 }
                             }
@@ -175,6 +192,16 @@ impl<'a> XlsxCellReader<'a> {
                             _ => (),
                         }
                     }
+                    if value.is_none() {
+                        // other cells of an array formula hold no formula
+                        self.formulas.arrays.retain(|(d, _)| d.end.0 >= pos.0);
+                        value = self
+                            .formulas
+                            .arrays
+                            .iter()
+                            .find(|(d, _)| d.contains(pos.0, pos.1))
+                            .map(|(_, f)| f.clone());
+                    }
                     self.col_index += 1;
                     return Ok(Some(Cell::new(pos, value.unwrap_or_default())));
                 }
@@ -187,6 +214,189 @@ impl<'a> XlsxCellReader<'a> {
             }
         }
     }
+
+    /// Reads the text runs of the next cell, `strings` being the rich shared strings
+    ///
+    /// Cells without a shared or inline string have no runs.
+    pub(crate) fn next_rich_text(
+        &mut self,
+        strings: &[Vec<TextRun>],
+    ) -> Result<Option<Cell<Vec<TextRun>>>, XlsxError> {
+        loop {
+            self.buf.clear();
+            match self.xml.read_event_into(&mut self.buf) {
+                Ok(Event::Start(ref row_element))
+                    if row_element.local_name().as_ref() == b"row" =>
+                {
+                    let attribute = get_attribute(row_element.attributes(), QName(b"r"))?;
+                    if let Some(range) = attribute {
+                        let row = get_row(range)?;
+                        self.row_index = row;
+                    }
+                }
+                Ok(Event::End(ref row_element)) if row_element.local_name().as_ref() == b"row" => {
+                    self.row_index += 1;
+                    self.col_index = 0;
+                }
+                Ok(Event::Start(ref c_element)) if c_element.local_name().as_ref() == b"c" => {
+                    let attribute = get_attribute(c_element.attributes(), QName(b"r"))?;
+                    let pos = if let Some(range) = attribute {
+                        let (row, col) = get_row_column(range)?;
+                        self.col_index = col;
+                        (row, col)
+                    } else {
+                        (self.row_index, self.col_index)
+                    };
+                    let shared = get_attribute(c_element.attributes(), QName(b"t"))? == Some(b"s");
+                    let mut runs = Vec::new();
+                    loop {
+                        self.cell_buf.clear();
+                        match self.xml.read_event_into(&mut self.cell_buf) {
+                            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"is" => {
+                                runs = read_rich_text(&mut self.xml, "is")?;
+                            }
+                            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"v" => {
+                                let mut v = String::new();
+                                let mut v_buf = Vec::new();
+                                loop {
+                                    v_buf.clear();
+                                    match self.xml.read_event_into(&mut v_buf)? {
+                                        Event::Text(t) => v.push_str(&t.unescape()?),
+                                        Event::End(end) if end.name() == e.name() => break,
+                                        Event::Eof => return Err(XlsxError::XmlEof("v")),
+                                        _ => (),
+                                    }
+                                }
+                                if shared {
+                                    let idx: usize = v.trim().parse()?;
+                                    runs = strings.get(idx).cloned().unwrap_or_default();
+                                }
+                            }
+                            Ok(Event::Start(ref e)) => {
+                                self.xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                            }
+                            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"c" => break,
+                            Ok(Event::Eof) => return Err(XlsxError::XmlEof("c")),
+                            Err(e) => return Err(XlsxError::Xml(e)),
+                            _ => (),
+                        }
+                    }
+                    self.col_index += 1;
+                    return Ok(Some(Cell::new(pos, runs)));
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    return Ok(None);
+                }
+                Ok(Event::Eof) => return Err(XlsxError::XmlEof("sheetData")),
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// An xlsx row iterator, streaming one row at a time out of a `XlsxCellReader`
+///
+/// Each row is yielded with its (0 based) index and its cells, indexed by their
+/// absolute column: gaps between cells are filled with `DataTypeRef::Empty` and
+/// rows without any cell are not yielded at all.
+///
+/// Unlike `worksheet_range`, the worksheet is never loaded as a whole, at most one row
+/// is kept in memory. Rows are therefore yielded in file order, which is not necessarily
+/// sorted. A row whose cells are split by other rows cannot be yielded as a whole: it is
+/// an `XlsxError::InterleavedRow` error.
+pub struct XlsxRowReader<'a> {
+    cells: XlsxCellReader<'a>,
+    /// First cell of the next row, already read from the underlying reader
+    pending: Option<Cell<DataTypeRef<'a>>>,
+    /// Indexes of the yielded rows, as sorted and disjoint inclusive intervals
+    yielded: Vec<(u32, u32)>,
+    done: bool,
+}
+
+impl<'a> XlsxRowReader<'a> {
+    pub(crate) fn new(cells: XlsxCellReader<'a>) -> Self {
+        XlsxRowReader {
+            cells,
+            pending: None,
+            yielded: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Records `row` as yielded, returns false if it already was
+    fn insert_yielded(&mut self, row: u32) -> bool {
+        // first interval starting after `row`
+        let i = self.yielded.partition_point(|&(start, _)| start <= row);
+        if i > 0 {
+            let prev = &mut self.yielded[i - 1];
+            if prev.1 >= row {
+                return false;
+            }
+            if prev.1 + 1 == row {
+                prev.1 = row;
+                if self.yielded.get(i).map_or(false, |next| next.0 - 1 == row) {
+                    self.yielded[i - 1].1 = self.yielded.remove(i).1;
+                }
+                return true;
+            }
+        }
+        match self.yielded.get_mut(i) {
+            Some(next) if next.0 - 1 == row => next.0 = row,
+            _ => self.yielded.insert(i, (row, row)),
+        }
+        true
+    }
+
+    /// Reads the next row, as its row index and its cells
+    pub fn next_row(&mut self) -> Result<Option<(u32, Vec<DataTypeRef<'a>>)>, XlsxError> {
+        let first = match self.pending.take() {
+            Some(cell) => cell,
+            None if self.done => return Ok(None),
+            None => match self.cells.next_cell()? {
+                Some(cell) => cell,
+                None => {
+                    self.done = true;
+                    return Ok(None);
+                }
+            },
+        };
+        let row = first.pos.0;
+        if !self.insert_yielded(row) {
+            self.done = true;
+            return Err(XlsxError::InterleavedRow(row));
+        }
+        let mut values = Vec::new();
+        let mut cell = first;
+        loop {
+            let col = cell.pos.1 as usize;
+            if col >= values.len() {
+                values.resize(col + 1, DataTypeRef::Empty);
+            }
+            // cells may not be sorted within a row
+            values[col] = cell.val;
+            match self.cells.next_cell()? {
+                Some(c) if c.pos.0 == row => cell = c,
+                Some(c) => {
+                    self.pending = Some(c);
+                    break;
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        Ok(Some((row, values)))
+    }
+}
+
+impl<'a> Iterator for XlsxRowReader<'a> {
+    type Item = Result<(u32, Vec<DataTypeRef<'a>>), XlsxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_row().transpose()
+    }
 }
 
 fn read_value<'s>(
@@ -304,9 +514,16 @@ fn read_v<'s>(
     }
 }
 
-fn read_formula<'s>(
+/// Reads the formula of the cell at `pos`
+///
+/// Cells sharing a formula (`t="shared"`) get the formula of the anchor cell, with its
+/// relative references moved accordingly. Array formulas (`t="array"`) are remembered
+/// so the other cells of their area can return the same formula.
+fn read_formula(
     xml: &mut XlReader<'_>,
     e: &BytesStart<'_>,
+    pos: (u32, u32),
+    formulas: &mut Formulas,
 ) -> Result<Option<String>, XlsxError> {
     match e.local_name().as_ref() {
         b"is" | b"v" => {
@@ -325,8 +542,200 @@ fn read_formula<'s>(
                 }
                 f_buf.clear();
             }
+            match get_attribute(e.attributes(), QName(b"t"))? {
+                Some(b"shared") => {
+                    let si = match get_attribute(e.attributes(), QName(b"si"))? {
+                        Some(si) => xml.decoder().decode(si)?.parse::<u32>()?,
+                        None => return Ok(Some(f)),
+                    };
+                    if !f.is_empty() {
+                        formulas.shared.insert(si, (pos, f.clone()));
+                    } else if let Some((anchor, shared)) = formulas.shared.get(&si) {
+                        let offset = (
+                            pos.0 as i64 - anchor.0 as i64,
+                            pos.1 as i64 - anchor.1 as i64,
+                        );
+                        f = offset_formula(shared, offset);
+                    }
+                }
+                Some(b"array") => {
+                    if let Some(r) = get_attribute(e.attributes(), QName(b"ref"))? {
+                        formulas.arrays.push((get_dimension(r)?, f.clone()));
+                    }
+                }
+                _ => (),
+            }
             Ok(Some(f))
         }
         _ => Err(XlsxError::UnexpectedNode("v, f, or is")),
     }
 }
+
+/// Moves the relative references of a formula by `offset` (rows, columns)
+///
+/// References moved out of the worksheet become `#REF!`.
+pub(crate) fn offset_formula(formula: &str, offset: (i64, i64)) -> String {
+    let b = formula.as_bytes();
+    let is_word = |c: u8| c.is_ascii_alphanumeric() || matches!(c, b'$' | b'_' | b'.' | b'\\');
+    let word_end = |mut i: usize| {
+        while i < b.len() && is_word(b[i]) {
+            i += 1;
+        }
+        i
+    };
+    let mut out = String::with_capacity(formula.len());
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            // string literals and quoted sheet names, quotes are escaped by doubling them
+            q @ (b'"' | b'\'') => {
+                let start = i;
+                i += 1;
+                while i < b.len() {
+                    if b[i] == q {
+                        i += 1;
+                        if b.get(i) != Some(&q) {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                out.push_str(&formula[start..i.min(b.len())]);
+            }
+            // structured references and external workbooks
+            b'[' => {
+                let start = i;
+                let mut depth = 0;
+                while i < b.len() {
+                    match b[i] {
+                        b'[' => depth += 1,
+                        b']' => depth -= 1,
+                        _ => (),
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                out.push_str(&formula[start..i]);
+            }
+            c if is_word(c) => {
+                let start = i;
+                i = word_end(i);
+                let word = &formula[start..i];
+                match b.get(i) {
+                    // function or sheet name
+                    Some(b'(' | b'!') => out.push_str(word),
+                    Some(b':') if i + 1 < b.len() && is_word(b[i + 1]) => {
+                        let end = word_end(i + 1);
+                        let other = &formula[i + 1..end];
+                        if let (Some(a), Some(z)) = (parse_col(word), parse_col(other)) {
+                            // whole columns
+                            push_col(a, offset.1, &mut out);
+                            out.push(':');
+                            push_col(z, offset.1, &mut out);
+                            i = end;
+                        } else if let (Some(a), Some(z)) = (parse_row(word), parse_row(other)) {
+                            // whole rows
+                            push_row(a, offset.0, &mut out);
+                            out.push(':');
+                            push_row(z, offset.0, &mut out);
+                            i = end;
+                        } else {
+                            push_ref(word, offset, &mut out);
+                        }
+                    }
+                    _ => push_ref(word, offset, &mut out),
+                }
+            }
+            _ => {
+                let c = formula[i..].chars().next().unwrap_or_default();
+                out.push(c);
+                i += c.len_utf8().max(1);
+            }
+        }
+    }
+    out
+}
+
+/// A column reference, absolute or not, e.g. `$AB`, as (is absolute, 0 based column)
+fn parse_col(s: &str) -> Option<(bool, u32)> {
+    let (abs, letters) = match s.strip_prefix('$') {
+        Some(l) => (true, l),
+        None => (false, s),
+    };
+    if letters.is_empty() || letters.len() > 3 || !letters.bytes().all(|c| c.is_ascii_alphabetic())
+    {
+        return None;
+    }
+    let col = letters.bytes().fold(0, |col, c| {
+        col * 26 + (c.to_ascii_uppercase() - b'A') as u32 + 1
+    });
+    (col <= 16_384).then_some((abs, col - 1))
+}
+
+/// A row reference, absolute or not, e.g. `$12`, as (is absolute, 0 based row)
+fn parse_row(s: &str) -> Option<(bool, u32)> {
+    let (abs, digits) = match s.strip_prefix('$') {
+        Some(d) => (true, d),
+        None => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let row = digits.parse::<u32>().ok()?;
+    (1..=1_048_576).contains(&row).then_some((abs, row - 1))
+}
+
+fn push_col((abs, col): (bool, u32), offset: i64, out: &mut String) {
+    let col = if abs { col as i64 } else { col as i64 + offset };
+    if !(0..16_384).contains(&col) {
+        out.push_str("#REF!");
+        return;
+    }
+    if abs {
+        out.push('$');
+    }
+    let mut letters = Vec::new();
+    let mut col = col + 1;
+    while col > 0 {
+        letters.push(b'A' + ((col - 1) % 26) as u8);
+        col = (col - 1) / 26;
+    }
+    out.extend(letters.iter().rev().map(|&c| c as char));
+}
+
+fn push_row((abs, row): (bool, u32), offset: i64, out: &mut String) {
+    let row = if abs { row as i64 } else { row as i64 + offset };
+    if !(0..1_048_576).contains(&row) {
+        out.push_str("#REF!");
+        return;
+    }
+    if abs {
+        out.push('$');
+    }
+    out.push_str(&(row + 1).to_string());
+}
+
+/// Moves `word` if it is a cell reference, e.g. `A1` or `$B$2`
+fn push_ref(word: &str, (rows, cols): (i64, i64), out: &mut String) {
+    let split = word
+        .bytes()
+        .enumerate()
+        .skip(1)
+        .find(|&(_, c)| c.is_ascii_digit() || c == b'$')
+        .map(|(i, _)| i);
+    let parsed = split.and_then(|i| Some((parse_col(&word[..i])?, parse_row(&word[i..])?)));
+    match parsed {
+        Some((col, row)) => {
+            let start = out.len();
+            push_col(col, cols, out);
+            push_row(row, rows, out);
+            if out[start..].contains("#REF!") {
+                out.truncate(start);
+                out.push_str("#REF!");
+            }
+        }
+        None => out.push_str(word),
+    }
+}
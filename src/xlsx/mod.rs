@@ -1,27 +1,37 @@
 mod cells_reader;
+mod writer;
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::BufReader;
-use std::io::{Read, Seek};
+use std::io::{Cursor, Read, Seek};
 use std::str::FromStr;
 
 use log::warn;
 use quick_xml::events::attributes::{Attribute, Attributes};
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::name::QName;
 use quick_xml::Reader as XmlReader;
 use zip::read::{ZipArchive, ZipFile};
 use zip::result::ZipError;
 
+use crate::crypto::{is_encrypted, read_package};
 use crate::datatype::DataTypeRef;
-use crate::formats::{builtin_format_by_id, detect_custom_number_format, CellFormat};
+use crate::formats::{
+    builtin_format_by_id, builtin_format_code, detect_custom_number_format, CellFormat,
+};
+use crate::formula::{self, resolve_table_references, Expr};
+use crate::style::{rotation, Alignment, Border, BorderSide, Color, Fill, Font, Style, TextRun};
+use crate::utils::{read_charts, read_document_properties, read_relationships};
 use crate::vba::VbaProject;
 use crate::{
-    Cell, CellErrorType, DataType, Dimensions, Metadata, Range, Reader, Sheet, SheetType,
-    SheetVisible, Table,
+    Cell, CellErrorType, Chart, Comment, ConditionalFormat, ConditionalFormatType, DataType,
+    DataValidation, Dimensions, Hyperlink, LineLayout, Metadata, Range, Reader, Sheet, SheetLayout,
+    SheetType, SheetVisible, StyleId, Styles, Table, TableMetadata, TableReader, Tables,
+    ValidationOperator, ValidationType,
 };
-pub use cells_reader::XlsxCellReader;
+pub use cells_reader::{XlsxCellReader, XlsxRowReader};
+pub use writer::XlsxWriter;
 
 pub(crate) type XlReader<'a> = XmlReader<BufReader<ZipFile<'a>>>;
 
@@ -83,10 +93,18 @@ pub enum XlsxError {
     CellError(String),
     /// Workbook is password protected
     Password,
+    /// Formula error
+    Formula(crate::formula::FormulaError),
     /// Worksheet not found
     WorksheetNotFound(String),
     /// Table not found
     TableNotFound(String),
+    /// Invalid worksheet name
+    WorksheetName(String),
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
+    /// Row split by other rows, its first cells being already streamed
+    InterleavedRow(u32),
 }
 
 from_err!(std::io::Error, XlsxError, Io);
@@ -96,6 +114,8 @@ from_err!(quick_xml::Error, XlsxError, Xml);
 from_err!(std::string::ParseError, XlsxError, Parse);
 from_err!(std::num::ParseFloatError, XlsxError, ParseFloat);
 from_err!(std::num::ParseIntError, XlsxError, ParseInt);
+from_err!(crate::formula::FormulaError, XlsxError, Formula);
+from_err!(crate::crypto::CryptoError, XlsxError, Crypto);
 
 impl std::fmt::Display for XlsxError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -133,9 +153,13 @@ impl std::fmt::Display for XlsxError {
             XlsxError::Unexpected(e) => write!(f, "{e}"),
             XlsxError::Unrecognized { typ, val } => write!(f, "Unrecognized {typ}: {val}"),
             XlsxError::CellError(e) => write!(f, "Unsupported cell error value '{e}'"),
+            XlsxError::Formula(e) => write!(f, "Formula error: {e}"),
             XlsxError::WorksheetNotFound(n) => write!(f, "Worksheet '{n}' not found"),
             XlsxError::Password => write!(f, "Workbook is password protected"),
             XlsxError::TableNotFound(n) => write!(f, "Table '{n}' not found"),
+            XlsxError::WorksheetName(n) => write!(f, "Invalid worksheet name '{n}'"),
+            XlsxError::Crypto(e) => write!(f, "Decryption error: {e}"),
+            XlsxError::InterleavedRow(r) => write!(f, "Row {r} is split by other rows"),
         }
     }
 }
@@ -150,6 +174,8 @@ impl std::error::Error for XlsxError {
             XlsxError::Parse(e) => Some(e),
             XlsxError::ParseInt(e) => Some(e),
             XlsxError::ParseFloat(e) => Some(e),
+            XlsxError::Formula(e) => Some(e),
+            XlsxError::Crypto(e) => Some(e),
             _ => None,
         }
     }
@@ -166,13 +192,12 @@ impl FromStr for CellErrorType {
             "#NUM!" => Ok(CellErrorType::Num),
             "#REF!" => Ok(CellErrorType::Ref),
             "#VALUE!" => Ok(CellErrorType::Value),
+            "#GETTING_DATA" => Ok(CellErrorType::GettingData),
             _ => Err(XlsxError::CellError(s.into())),
         }
     }
 }
 
-type Tables = Option<Vec<(String, String, Vec<String>, Dimensions)>>;
-
 /// A struct representing xml zipped excel file
 /// Xlsx, Xlsm, Xlam
 pub struct Xlsx<RS> {
@@ -218,6 +243,29 @@ impl<RS: Read + Seek> Xlsx<RS> {
         Ok(())
     }
 
+    /// Reads the shared strings as text runs, only needed for rich text
+    fn read_rich_shared_strings(&mut self) -> Result<Vec<Vec<TextRun>>, XlsxError> {
+        let mut xml = match xml_reader(&mut self.zip, "xl/sharedStrings.xml") {
+            None => return Ok(Vec::new()),
+            Some(x) => x?,
+        };
+        let mut strings = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"si" => {
+                    strings.push(read_rich_text(&mut xml, "si")?);
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sst" => break,
+                Ok(Event::Eof) => return Err(XlsxError::XmlEof("sst")),
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(strings)
+    }
+
     fn read_styles(&mut self) -> Result<(), XlsxError> {
         let mut xml = match xml_reader(&mut self.zip, "xl/styles.xml") {
             None => return Ok(()),
@@ -536,6 +584,10 @@ impl<RS: Read + Seek> Xlsx<RS> {
                         Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"table" => {
                             for a in e.attributes() {
                                 match a.map_err(XlsxError::XmlAttr)? {
+                                    Attribute {
+                                        key: QName(b"id"),
+                                        value: v,
+                                    } => table_meta.id = xml.decoder().decode(&v)?.parse()?,
                                     Attribute {
                                         key: QName(b"displayName"),
                                         value: v,
@@ -589,22 +641,16 @@ impl<RS: Read + Seek> Xlsx<RS> {
                         _ => (),
                     }
                 }
-                let mut dims = get_dimension(table_meta.ref_cells.as_bytes())?;
-                if table_meta.header_row_count != 0 {
-                    dims.start.0 += table_meta.header_row_count;
-                }
-                if table_meta.totals_row_count != 0 {
-                    dims.end.0 -= table_meta.header_row_count;
-                }
-                if table_meta.insert_row {
-                    dims.end.0 -= 1;
-                }
-                new_tables.push((
-                    table_meta.display_name,
-                    sheet_name.clone(),
-                    column_names,
-                    dims,
-                ));
+                new_tables.push(TableMetadata {
+                    id: table_meta.id,
+                    name: table_meta.display_name,
+                    sheet_name: sheet_name.clone(),
+                    columns: column_names,
+                    dimensions: get_dimension(table_meta.ref_cells.as_bytes())?,
+                    header_rows: table_meta.header_row_count,
+                    totals_rows: table_meta.totals_row_count,
+                    insert_row: table_meta.insert_row,
+                });
             }
             if let Some(tables) = &mut self.tables {
                 tables.append(&mut new_tables);
@@ -659,7 +705,7 @@ impl<RS: Read + Seek> Xlsx<RS> {
             .as_ref()
             .expect("Tables must be loaded before they are referenced")
             .iter()
-            .map(|(name, ..)| name)
+            .map(|t| &t.name)
             .collect()
     }
     /// Get the names of all the tables in a sheet
@@ -668,8 +714,8 @@ impl<RS: Read + Seek> Xlsx<RS> {
             .as_ref()
             .expect("Tables must be loaded before they are referenced")
             .iter()
-            .filter(|(_, sheet, ..)| sheet == sheet_name)
-            .map(|(name, ..)| name)
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
             .collect()
     }
 
@@ -681,25 +727,41 @@ impl<RS: Read + Seek> Xlsx<RS> {
             .as_ref()
             .expect("Tables must be loaded before they are referenced")
             .iter()
-            .find(|(table, ..)| table == table_name)
+            .find(|t| t.name == table_name)
+            .cloned()
             .ok_or_else(|| XlsxError::TableNotFound(table_name.into()))?;
-        let name = match_table_meta.0.to_owned();
-        let sheet_name = match_table_meta.1.clone();
-        let columns = match_table_meta.2.clone();
-        let start_dim = match_table_meta.3.start;
-        let end_dim = match_table_meta.3.end;
-        let range = self.worksheet_range(&sheet_name)?;
-        let tbl_rng = range.range(start_dim, end_dim);
-        Ok(Table {
-            name,
-            sheet_name,
-            columns,
-            data: tbl_rng,
-        })
+        let range = self.worksheet_range(&match_table_meta.sheet_name)?;
+        Ok(match_table_meta.table(&range))
+    }
+}
+
+impl<RS: Read + Seek> TableReader<RS> for Xlsx<RS> {
+    fn load_tables(&mut self) -> Result<(), XlsxError> {
+        Xlsx::load_tables(self)
+    }
+
+    fn table_names(&self) -> Vec<&String> {
+        Xlsx::table_names(self)
+    }
+
+    fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        Xlsx::table_names_in_sheet(self, sheet_name)
+    }
+
+    fn table_by_name(&mut self, table_name: &str) -> Result<Table<DataType>, XlsxError> {
+        Xlsx::table_by_name(self, table_name)
+    }
+
+    fn worksheet_formula_resolved(&mut self, name: &str) -> Result<Range<String>, XlsxError> {
+        self.load_tables()?;
+        let formulas = self.worksheet_formula(name)?;
+        let tables = self.tables.as_deref().unwrap_or_default();
+        Ok(resolve_table_references(formulas, name, tables))
     }
 }
 
 struct InnerTableMetadata {
+    id: u32,
     display_name: String,
     ref_cells: String,
     header_row_count: u32,
@@ -710,6 +772,7 @@ struct InnerTableMetadata {
 impl InnerTableMetadata {
     fn new() -> Self {
         Self {
+            id: 0,
             display_name: String::new(),
             ref_cells: String::new(),
             header_row_count: 1,
@@ -728,7 +791,7 @@ impl<RS: Read + Seek> Xlsx<RS> {
         let (_, path) = self
             .sheets
             .iter()
-            .find(|&&(ref n, _)| n == name)
+            .find(|(n, _)| n == name)
             .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
         let xml = xml_reader(&mut self.zip, path)
             .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
@@ -738,6 +801,27 @@ impl<RS: Read + Seek> Xlsx<RS> {
         XlsxCellReader::new(xml, strings, formats, is_1904)
     }
 
+    /// Get a streaming iterator over the rows of the given worksheet
+    ///
+    /// Contrary to `worksheet_range`, only one row is in memory at any time, which
+    /// makes it suitable for very large worksheets. Rows are yielded in file order, see
+    /// `XlsxRowReader` for rows which are not sorted.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{open_workbook, Xlsx};
+    ///
+    /// # let path = format!("{}/tests/issue3.xlsm", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+    /// for row in workbook.worksheet_rows("Sheet1").unwrap() {
+    ///     let (index, cells) = row.unwrap();
+    ///     println!("row {} has {} cells", index, cells.len());
+    /// }
+    /// ```
+    pub fn worksheet_rows<'a>(&'a mut self, name: &str) -> Result<XlsxRowReader<'a>, XlsxError> {
+        self.worksheet_cells_reader(name).map(XlsxRowReader::new)
+    }
+
     /// Get worksheet range where shared string values are only borrowed
     pub fn worksheet_range_ref<'a>(
         &'a mut self,
@@ -764,11 +848,34 @@ impl<RS: Read + Seek> Xlsx<RS> {
     }
 }
 
+impl Xlsx<Cursor<Vec<u8>>> {
+    /// Opens a workbook encrypted with a password, with the Agile or Standard encryption
+    ///
+    /// The decrypted package is kept in memory. Workbooks which are not encrypted are
+    /// opened as well, ignoring the password.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Xlsx, Reader};
+    ///
+    /// # let path = format!("{}/tests/encrypted_agile.xlsx", env!("CARGO_MANIFEST_DIR"));
+    /// let file = std::fs::File::open(path).unwrap();
+    /// let mut workbook = Xlsx::new_with_password(file, "calamine").unwrap();
+    /// let range = workbook.worksheet_range("Sheet1").unwrap();
+    /// ```
+    pub fn new_with_password<R: Read + Seek>(reader: R, password: &str) -> Result<Self, XlsxError> {
+        let package = read_package(reader, password)?;
+        Self::new(Cursor::new(package))
+    }
+}
+
 impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
     type Error = XlsxError;
 
     fn new(mut reader: RS) -> Result<Self, XlsxError> {
-        check_for_password_protected(&mut reader)?;
+        if is_encrypted(&mut reader)? {
+            return Err(XlsxError::Password);
+        }
 
         let mut xlsx = Xlsx {
             zip: ZipArchive::new(reader)?,
@@ -785,6 +892,7 @@ impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
         xlsx.read_styles()?;
         let relationships = xlsx.read_relationships()?;
         xlsx.read_workbook(&relationships)?;
+        xlsx.metadata.properties = read_document_properties::<_, XlsxError>(&mut xlsx.zip);
         #[cfg(feature = "picture")]
         xlsx.read_pictures()?;
 
@@ -830,6 +938,449 @@ impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
         Ok(Range::from_sparse(cells))
     }
 
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<Vec<TextRun>>, XlsxError> {
+        let strings = self.read_rich_shared_strings()?;
+        let mut cell_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cell_reader.next_rich_text(&strings)? {
+            if !cell.val.is_empty() {
+                cells.push(cell);
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_ast(&mut self, name: &str) -> Result<Range<Expr>, XlsxError> {
+        let mut cell_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cell_reader.next_formula()? {
+            if !cell.val.is_empty() {
+                let expr = formula::or_unrecognized(formula::parse(&cell.val), cell.pos);
+                cells.push(Cell::new(cell.pos, expr));
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn merged_regions(&mut self, name: &str) -> Result<Vec<Dimensions>, XlsxError> {
+        let (_, path) = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let mut xml = xml_reader(&mut self.zip, path)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
+        let mut regions = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    // merged cells are stored after the cell data, skip it altogether
+                    let end = e.to_end().into_owned();
+                    xml.read_to_end_into(end.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"mergeCell" => {
+                    if let Some(r) = get_attribute(e.attributes(), QName(b"ref"))? {
+                        regions.push(get_dimension(r)?);
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(regions)
+    }
+
+    fn styles(&mut self) -> Result<Styles, XlsxError> {
+        let mut xml = match xml_reader(&mut self.zip, "xl/styles.xml") {
+            None => return Ok(Styles::default()),
+            Some(x) => x?,
+        };
+        let mut number_formats = BTreeMap::new();
+        let mut fonts = Vec::new();
+        let mut fills = Vec::new();
+        let mut borders = Vec::new();
+        let mut styles = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                    b"numFmt" => {
+                        let id = get_attribute(e.attributes(), QName(b"numFmtId"))?;
+                        let code = get_attribute(e.attributes(), QName(b"formatCode"))?;
+                        if let (Some(id), Some(code)) = (id, code) {
+                            let id: u32 = xml.decoder().decode(id)?.parse()?;
+                            let code = quick_xml::escape::unescape(&xml.decoder().decode(code)?)
+                                .map_err(|e| XlsxError::Xml(e.into()))?
+                                .into_owned();
+                            number_formats.insert(id, code);
+                        }
+                    }
+                    b"font" => fonts.push(read_font(&mut xml, e.name())?),
+                    b"fill" => fills.push(read_fill(&mut xml)?),
+                    b"border" => borders.push(read_border(&mut xml)?),
+                    b"cellXfs" => loop {
+                        buf.clear();
+                        match xml.read_event_into(&mut buf) {
+                            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"xf" => {
+                                let id = |name: &[u8]| -> Result<Option<usize>, XlsxError> {
+                                    match get_attribute(e.attributes(), QName(name))? {
+                                        Some(v) => Ok(Some(xml.decoder().decode(v)?.parse()?)),
+                                        None => Ok(None),
+                                    }
+                                };
+                                let num_fmt_id = id(b"numFmtId")?.unwrap_or(0);
+                                let font_id = id(b"fontId")?.unwrap_or(0);
+                                let fill_id = id(b"fillId")?.unwrap_or(0);
+                                let border_id = id(b"borderId")?.unwrap_or(0);
+                                let number_format =
+                                    number_formats.get(&(num_fmt_id as u32)).cloned().or_else(
+                                        || builtin_format_code(num_fmt_id as u16).map(String::from),
+                                    );
+                                let alignment = read_alignment(&mut xml)?;
+                                styles.push(Style {
+                                    number_format,
+                                    font: fonts.get(font_id).cloned().unwrap_or_default(),
+                                    fill: fills.get(fill_id).cloned().unwrap_or_default(),
+                                    border: borders.get(border_id).cloned().unwrap_or_default(),
+                                    alignment,
+                                });
+                            }
+                            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"cellXfs" => {
+                                // cellXfs comes after all fonts, fills and borders
+                                return Ok(Styles::new(styles));
+                            }
+                            Ok(Event::Eof) => return Err(XlsxError::XmlEof("cellXfs")),
+                            Err(e) => return Err(XlsxError::Xml(e)),
+                            _ => (),
+                        }
+                    },
+                    _ => (),
+                },
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"styleSheet" => break,
+                Ok(Event::Eof) => return Err(XlsxError::XmlEof("styleSheet")),
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(Styles::new(styles))
+    }
+
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, XlsxError> {
+        let (_, path) = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let mut xml = xml_reader(&mut self.zip, path)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
+        let mut cells = Vec::new();
+        let (mut row, mut col) = (0, 0);
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"row" => {
+                    if let Some(r) = get_attribute(e.attributes(), QName(b"r"))? {
+                        row = get_row(r)?;
+                    }
+                    col = 0;
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"row" => row += 1,
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"c" => {
+                    if let Some(r) = get_attribute(e.attributes(), QName(b"r"))? {
+                        (row, col) = get_row_column(r)?;
+                    }
+                    let id = match get_attribute(e.attributes(), QName(b"s"))? {
+                        Some(s) => xml.decoder().decode(s)?.parse()?,
+                        None => 0,
+                    };
+                    // the default style is implied, e.g. on blank cells spanning the whole sheet
+                    if id != 0 {
+                        cells.push(Cell::new((row, col), StyleId(id)));
+                    }
+                    col += 1;
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sheetData" => break,
+                Ok(Event::Eof) => return Err(XlsxError::XmlEof("sheetData")),
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, XlsxError> {
+        let path = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, path)| path.clone())
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let mut comments = Vec::new();
+        let mut threads = Vec::new();
+        for rel in read_relationships::<_, XlsxError>(&mut self.zip, &path)? {
+            if rel.external {
+                continue;
+            }
+            if let Some(mut xml) = xml_reader(&mut self.zip, &rel.target).transpose()? {
+                if rel.typ.ends_with("/comments") {
+                    comments = read_comments(&mut xml)?;
+                } else if rel.typ.ends_with("/threadedComment") {
+                    threads = read_threaded_comments(&mut xml)?;
+                }
+            }
+        }
+        if threads.is_empty() {
+            return Ok(comments);
+        }
+
+        // threaded comments are also saved as legacy comments, for older versions of Excel
+        let mut persons = HashMap::new();
+        for rel in read_relationships::<_, XlsxError>(&mut self.zip, "xl/workbook.xml")? {
+            if !rel.external && rel.typ.ends_with("/person") {
+                if let Some(mut xml) = xml_reader(&mut self.zip, &rel.target).transpose()? {
+                    persons = read_persons(&mut xml)?;
+                }
+            }
+        }
+        comments.retain(|c| !threads.iter().any(|t| t.comment.position == c.position));
+        let mut roots: Vec<ThreadedComment> = Vec::new();
+        for mut thread in threads {
+            thread.comment.author = persons.get(&thread.person).cloned().unwrap_or_default();
+            match roots
+                .iter_mut()
+                .find(|r| Some(&r.id) == thread.parent.as_ref())
+            {
+                Some(root) => root.comment.replies.push(thread.comment),
+                None => roots.push(thread),
+            }
+        }
+        comments.extend(roots.into_iter().map(|t| t.comment));
+        comments.sort_by_key(|c| c.position);
+        Ok(comments)
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, XlsxError> {
+        let path = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, path)| path.clone())
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let rels = read_relationships::<_, XlsxError>(&mut self.zip, &path)?;
+        let mut xml = xml_reader(&mut self.zip, &path)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
+        let mut links = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    // hyperlinks are stored after the cell data, skip it altogether
+                    let end = e.to_end().into_owned();
+                    xml.read_to_end_into(end.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"hyperlink" => {
+                    let mut link = Hyperlink::default();
+                    for a in e.attributes() {
+                        let a = a.map_err(XlsxError::XmlAttr)?;
+                        match a.key.local_name().as_ref() {
+                            b"ref" => link.range = get_dimension(&a.value)?,
+                            // the relationship id, in the relationships namespace
+                            b"id" => {
+                                let id = a.decode_and_unescape_value(&xml)?;
+                                link.url =
+                                    rels.iter().find(|r| r.id == id).map(|r| r.target.clone());
+                            }
+                            b"location" => {
+                                link.location =
+                                    Some(a.decode_and_unescape_value(&xml)?.into_owned())
+                            }
+                            b"tooltip" => {
+                                link.tooltip = Some(a.decode_and_unescape_value(&xml)?.into_owned())
+                            }
+                            _ => (),
+                        }
+                    }
+                    links.push(link);
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(links)
+    }
+
+    fn worksheet_layout(&mut self, name: &str) -> Result<SheetLayout, XlsxError> {
+        let (_, path) = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let mut xml = xml_reader(&mut self.zip, path)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
+        let mut layout = SheetLayout::default();
+        let mut buf = Vec::with_capacity(1024);
+        let mut row = 0;
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetFormatPr" => {
+                    for a in e.attributes() {
+                        let a = a.map_err(XlsxError::XmlAttr)?;
+                        match a.key {
+                            QName(b"defaultRowHeight") => {
+                                layout.default_row_height =
+                                    Some(xml.decoder().decode(&a.value)?.parse()?)
+                            }
+                            QName(b"defaultColWidth") => {
+                                layout.default_column_width =
+                                    Some(xml.decoder().decode(&a.value)?.parse()?)
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"col" => {
+                    let mut columns = LineLayout::default();
+                    for a in e.attributes() {
+                        let a = a.map_err(XlsxError::XmlAttr)?;
+                        let v = xml.decoder().decode(&a.value)?;
+                        match a.key {
+                            QName(b"min") => columns.first = v.parse::<u32>()?.saturating_sub(1),
+                            QName(b"max") => columns.last = v.parse::<u32>()?.saturating_sub(1),
+                            QName(b"width") => columns.size = Some(v.parse()?),
+                            QName(b"hidden") => columns.hidden = v == "1" || v == "true",
+                            QName(b"outlineLevel") => columns.outline_level = v.parse()?,
+                            _ => (),
+                        }
+                    }
+                    layout.push_columns(columns);
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"row" => {
+                    let mut rows = LineLayout::default();
+                    let mut custom_height = false;
+                    for a in e.attributes() {
+                        let a = a.map_err(XlsxError::XmlAttr)?;
+                        let v = xml.decoder().decode(&a.value)?;
+                        match a.key {
+                            QName(b"r") => row = get_row(&a.value)?,
+                            QName(b"ht") => rows.size = Some(v.parse()?),
+                            QName(b"customHeight") => custom_height = v == "1" || v == "true",
+                            QName(b"hidden") => rows.hidden = v == "1" || v == "true",
+                            QName(b"outlineLevel") => rows.outline_level = v.parse()?,
+                            _ => (),
+                        }
+                    }
+                    rows.first = row;
+                    rows.last = row;
+                    if rows.hidden || rows.outline_level > 0 || custom_height {
+                        layout.push_rows(rows);
+                    }
+                    row += 1;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"c" => {
+                    xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sheetData" => break,
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(layout)
+    }
+
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, XlsxError> {
+        let (_, path) = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let mut xml = xml_reader(&mut self.zip, path)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
+        let mut validations = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    // validations are stored after the cell data, skip it altogether
+                    let end = e.to_end().into_owned();
+                    xml.read_to_end_into(end.name(), &mut Vec::new())?;
+                }
+                // both the `dataValidation` elements and their `x14` extensions,
+                // used for lists referencing other sheets
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"dataValidation" => {
+                    validations.push(read_data_validation(&mut xml, e)?);
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(validations)
+    }
+
+    fn worksheet_conditional_formats(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<ConditionalFormat>, XlsxError> {
+        let (_, path) = self
+            .sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let mut xml = xml_reader(&mut self.zip, path)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))??;
+        let mut formats = Vec::new();
+        let mut ids = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    // rules are stored after the cell data, skip it altogether
+                    let end = e.to_end().into_owned();
+                    xml.read_to_end_into(end.name(), &mut Vec::new())?;
+                }
+                // both the `conditionalFormatting` elements and their `x14` extensions
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"conditionalFormatting" => {
+                    for (id, format) in read_conditional_formatting(&mut xml, e)? {
+                        match id {
+                            Some(id) if ids.contains(&id) => (),
+                            Some(id) => {
+                                ids.push(id);
+                                formats.push(format);
+                            }
+                            None => formats.push(format),
+                        }
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        formats.sort_by_key(|f| f.priority);
+        Ok(formats)
+    }
+
+    fn worksheet_charts(&mut self, name: &str) -> Result<Vec<Chart>, XlsxError> {
+        let (_, path) = self
+            .sheets
+            .iter()
+            .find(|&(n, _)| n == name)
+            .ok_or_else(|| XlsxError::WorksheetNotFound(name.into()))?;
+        let path = path.clone();
+        read_charts::<_, XlsxError>(&mut self.zip, &path)
+    }
+
     fn worksheets(&mut self) -> Vec<(String, Range<DataType>)> {
         let names = self
             .sheets
@@ -983,6 +1534,40 @@ fn get_row_and_optional_column(range: &[u8]) -> Result<(u32, Option<u32>), XlsxE
     Ok((row, col.checked_sub(1)))
 }
 
+/// Decodes the `_xHHHH_` escape sequence starting `s`, if any (ECMA-376 Part 1, 22.9.2.19)
+pub(crate) fn xstring_escape(s: &str) -> Option<char> {
+    let hex = s.strip_prefix("_x")?.get(..5)?.strip_suffix('_')?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+/// Unescapes the `_xHHHH_` sequences of a string, `_x005F_` being a literal `_`
+fn unescape_xstring(s: String) -> String {
+    if !s.contains("_x") {
+        return s;
+    }
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while let Some(i) = rest.find("_x") {
+        unescaped.push_str(&rest[..i]);
+        rest = &rest[i..];
+        match xstring_escape(rest) {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[7..];
+            }
+            None => {
+                unescaped.push_str("_x");
+                rest = &rest[2..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
 /// attempts to read either a simple or richtext string
 pub(crate) fn read_string(
     xml: &mut XlReader<'_>,
@@ -995,11 +1580,9 @@ pub(crate) fn read_string(
     loop {
         buf.clear();
         match xml.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"r" => {
-                if rich_buffer.is_none() {
-                    // use a buffer since richtext has multiples <r> and <t> for the same cell
-                    rich_buffer = Some(String::new());
-                }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"r" && rich_buffer.is_none() => {
+                // use a buffer since richtext has multiples <r> and <t> for the same cell
+                rich_buffer = Some(String::new());
             }
             Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"rPh" => {
                 is_phonetic_text = true;
@@ -1021,6 +1604,7 @@ pub(crate) fn read_string(
                         _ => (),
                     }
                 }
+                let value = unescape_xstring(value);
                 if let Some(ref mut s) = rich_buffer {
                     s.push_str(&value);
                 } else {
@@ -1036,23 +1620,552 @@ pub(crate) fn read_string(
     }
 }
 
-fn check_for_password_protected<RS: Read + Seek>(reader: &mut RS) -> Result<(), XlsxError> {
-    let offset_end = reader.seek(std::io::SeekFrom::End(0))? as usize;
-    reader.seek(std::io::SeekFrom::Start(0))?;
+/// Reads a rich text element (CT_Rst), keeping the formatting of each run
+pub(crate) fn read_rich_text(
+    xml: &mut XlReader<'_>,
+    closing: &'static str,
+) -> Result<Vec<TextRun>, XlsxError> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut runs = Vec::new();
+    let mut font = None;
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"r" => font = None,
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"rPr" => {
+                font = Some(read_font(xml, e.name())?);
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"rPh" => {
+                // phonetic text is not part of the string
+                xml.read_to_end_into(e.name(), &mut Vec::new())?;
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"t" => {
+                let mut text = String::new();
+                let mut t_buf = Vec::new();
+                loop {
+                    t_buf.clear();
+                    match xml.read_event_into(&mut t_buf)? {
+                        Event::Text(t) => text.push_str(&t.unescape()?),
+                        Event::End(end) if end.name() == e.name() => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("t")),
+                        _ => (),
+                    }
+                }
+                runs.push(TextRun {
+                    text: unescape_xstring(text),
+                    font: font.take(),
+                });
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == closing.as_bytes() => {
+                return Ok(runs)
+            }
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof(closing)),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads a comments part (CT_Comments)
+fn read_comments(xml: &mut XlReader<'_>) -> Result<Vec<Comment>, XlsxError> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut authors = Vec::new();
+    let mut comments = Vec::new();
+    let mut current = None;
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"author" => {
+                let mut author = String::new();
+                let mut a_buf = Vec::new();
+                loop {
+                    a_buf.clear();
+                    match xml.read_event_into(&mut a_buf)? {
+                        Event::Text(t) => author.push_str(&t.unescape()?),
+                        Event::End(end) if end.name() == e.name() => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("author")),
+                        _ => (),
+                    }
+                }
+                authors.push(author);
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"comment" => {
+                let position = match get_attribute(e.attributes(), QName(b"ref"))? {
+                    Some(r) => get_row_column(r)?,
+                    None => continue,
+                };
+                let author = match get_attribute(e.attributes(), QName(b"authorId"))? {
+                    Some(id) => xml.decoder().decode(id)?.parse()?,
+                    None => 0,
+                };
+                current = Some((position, author));
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"text" => {
+                let runs = read_rich_text(xml, "text")?;
+                if let Some((position, author)) = current.take() {
+                    let author = authors.get::<usize>(author).cloned().unwrap_or_default();
+                    comments.push(Comment::new(position, author, runs));
+                }
+            }
+            Ok(Event::Eof) => return Ok(comments),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
 
-    if let Ok(cfb) = crate::cfb::Cfb::new(reader, offset_end) {
-        if cfb.has_directory("EncryptedPackage") {
-            return Err(XlsxError::Password);
+/// A threaded comment, before being attached to its thread
+struct ThreadedComment {
+    id: String,
+    parent: Option<String>,
+    person: String,
+    comment: Comment,
+}
+
+/// Reads a threaded comments part (CT_ThreadedComments, MS-XLSX 2.6.205)
+fn read_threaded_comments(xml: &mut XlReader<'_>) -> Result<Vec<ThreadedComment>, XlsxError> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut comments = Vec::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"threadedComment" => {
+                let mut comment = ThreadedComment {
+                    id: String::new(),
+                    parent: None,
+                    person: String::new(),
+                    comment: Comment::default(),
+                };
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    match a.key {
+                        QName(b"ref") => comment.comment.position = get_row_column(&a.value)?,
+                        QName(b"id") => comment.id = xml.decoder().decode(&a.value)?.into_owned(),
+                        QName(b"parentId") => {
+                            comment.parent = Some(xml.decoder().decode(&a.value)?.into_owned())
+                        }
+                        QName(b"personId") => {
+                            comment.person = xml.decoder().decode(&a.value)?.into_owned()
+                        }
+                        _ => (),
+                    }
+                }
+                comments.push(comment);
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"text" => {
+                let mut text = String::new();
+                let mut t_buf = Vec::new();
+                loop {
+                    t_buf.clear();
+                    match xml.read_event_into(&mut t_buf)? {
+                        Event::Text(t) => text.push_str(&t.unescape()?),
+                        Event::End(end) if end.name() == e.name() => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("text")),
+                        _ => (),
+                    }
+                }
+                if let Some(c) = comments.last_mut() {
+                    c.comment.runs = vec![TextRun {
+                        text: text.clone(),
+                        font: None,
+                    }];
+                    c.comment.text = text;
+                }
+            }
+            Ok(Event::Eof) => return Ok(comments),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads the display name of each person id, from a persons part (MS-XLSX 2.6.203)
+fn read_persons(xml: &mut XlReader<'_>) -> Result<HashMap<String, String>, XlsxError> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut persons = HashMap::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"person" => {
+                let id = get_attribute(e.attributes(), QName(b"id"))?;
+                let name = get_attribute(e.attributes(), QName(b"displayName"))?;
+                if let (Some(id), Some(name)) = (id, name) {
+                    let name = quick_xml::escape::unescape(&xml.decoder().decode(name)?)
+                        .map_err(|e| XlsxError::Xml(e.into()))?
+                        .into_owned();
+                    persons.insert(xml.decoder().decode(id)?.into_owned(), name);
+                }
+            }
+            Ok(Event::Eof) => return Ok(persons),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
         }
+    }
+}
+
+/// Reads a `dataValidation` element
+fn read_data_validation(
+    xml: &mut XlReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<DataValidation, XlsxError> {
+    let mut validation = DataValidation::default();
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let v = a.decode_and_unescape_value(xml)?;
+        match a.key.local_name().as_ref() {
+            b"type" => {
+                validation.validation_type = match &*v {
+                    "whole" => ValidationType::Whole,
+                    "decimal" => ValidationType::Decimal,
+                    "list" => ValidationType::List,
+                    "date" => ValidationType::Date,
+                    "time" => ValidationType::Time,
+                    "textLength" => ValidationType::TextLength,
+                    "custom" => ValidationType::Custom,
+                    _ => ValidationType::Any,
+                }
+            }
+            b"operator" => validation.operator = get_operator(&v),
+            b"allowBlank" => validation.allow_blank = v == "1" || v == "true",
+            b"sqref" => validation.ranges = get_sqref(&v)?,
+            b"errorTitle" => validation.error_title = Some(v.into_owned()),
+            b"error" => validation.error_message = Some(v.into_owned()),
+            b"promptTitle" => validation.prompt_title = Some(v.into_owned()),
+            b"prompt" => validation.prompt_message = Some(v.into_owned()),
+            _ => (),
+        }
+    }
+    let mut buf = Vec::new();
+    let mut value = String::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e)
+                if matches!(
+                    e.local_name().as_ref(),
+                    b"formula1" | b"formula2" | b"sqref"
+                ) =>
+            {
+                value.clear();
+            }
+            Event::Text(t) => value.push_str(&t.unescape()?),
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"formula1" => validation.formula1 = Some(value.trim().to_string()),
+                b"formula2" => validation.formula2 = Some(value.trim().to_string()),
+                // `x14` extensions store the validated cells in an element
+                b"sqref" => validation.ranges = get_sqref(value.trim())?,
+                b"dataValidation" => return Ok(validation),
+                _ => (),
+            },
+            Event::Eof => return Err(XlsxError::XmlEof("dataValidation")),
+            _ => (),
+        }
+    }
+}
+
+/// Reads a `conditionalFormatting` element, and its rules with their extension id
+///
+/// Rules extended in `x14` elements are written twice, sharing the same id.
+fn read_conditional_formatting(
+    xml: &mut XlReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<Vec<(Option<String>, ConditionalFormat)>, XlsxError> {
+    let mut ranges = match e.try_get_attribute(b"sqref")? {
+        Some(a) => get_sqref(&a.decode_and_unescape_value(xml)?)?,
+        None => Vec::new(),
     };
+    let mut formats: Vec<(Option<String>, ConditionalFormat)> = Vec::new();
+    let mut buf = Vec::new();
+    let mut value = String::new();
+    let mut in_cfvo = false;
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.local_name().as_ref() == b"cfRule" => {
+                let mut format = ConditionalFormat::default();
+                let mut operator = None;
+                let mut id = None;
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    let v = a.decode_and_unescape_value(xml)?;
+                    match a.key.local_name().as_ref() {
+                        b"type" => {
+                            format.rule_type = match &*v {
+                                "cellIs" => ConditionalFormatType::CellIs,
+                                "expression" => ConditionalFormatType::Expression,
+                                "colorScale" => ConditionalFormatType::ColorScale,
+                                "dataBar" => ConditionalFormatType::DataBar,
+                                "iconSet" => ConditionalFormatType::IconSet,
+                                _ => ConditionalFormatType::Other(v.into_owned()),
+                            }
+                        }
+                        b"priority" => format.priority = v.parse()?,
+                        b"operator" => operator = Some(get_operator(&v)),
+                        b"dxfId" => format.dxf_id = Some(v.parse()?),
+                        b"id" => id = Some(v.into_owned()),
+                        _ => (),
+                    }
+                }
+                if format.rule_type == ConditionalFormatType::CellIs {
+                    format.operator = operator;
+                }
+                formats.push((id, format));
+            }
+            Event::Start(ref e) if e.local_name().as_ref() == b"cfvo" => in_cfvo = true,
+            Event::Start(ref e)
+                if matches!(
+                    e.local_name().as_ref(),
+                    b"formula" | b"f" | b"sqref" | b"id"
+                ) =>
+            {
+                value.clear();
+            }
+            Event::Text(t) => value.push_str(&t.unescape()?),
+            Event::End(ref e) => match e.local_name().as_ref() {
+                // `x14` extensions store the formulas in `xm:f` elements
+                // thresholds of color scales, data bars and icon sets are not formulas
+                b"formula" | b"f" if !in_cfvo => {
+                    if let Some((_, format)) = formats.last_mut() {
+                        format.formulas.push(value.trim().to_string());
+                    }
+                }
+                b"cfvo" => in_cfvo = false,
+                // the id of the extension of a rule
+                b"id" => {
+                    if let Some((id, _)) = formats.last_mut() {
+                        *id = Some(value.trim().to_string());
+                    }
+                }
+                // and the formatted cells after the rules
+                b"sqref" => ranges = get_sqref(value.trim())?,
+                b"conditionalFormatting" => break,
+                _ => (),
+            },
+            Event::Eof => return Err(XlsxError::XmlEof("conditionalFormatting")),
+            _ => (),
+        }
+    }
+    for (_, format) in &mut formats {
+        format.ranges = ranges.clone();
+    }
+    Ok(formats)
+}
+
+/// Converts a comparison operator
+fn get_operator(operator: &str) -> ValidationOperator {
+    match operator {
+        "notBetween" => ValidationOperator::NotBetween,
+        "equal" => ValidationOperator::Equal,
+        "notEqual" => ValidationOperator::NotEqual,
+        "greaterThan" => ValidationOperator::GreaterThan,
+        "lessThan" => ValidationOperator::LessThan,
+        "greaterThanOrEqual" => ValidationOperator::GreaterThanOrEqual,
+        "lessThanOrEqual" => ValidationOperator::LessThanOrEqual,
+        _ => ValidationOperator::Between,
+    }
+}
+
+/// Parses a list of space separated references, e.g. `A1 B2:C3`
+fn get_sqref(sqref: &str) -> Result<Vec<Dimensions>, XlsxError> {
+    sqref
+        .split_whitespace()
+        .map(|r| get_dimension(r.as_bytes()))
+        .collect()
+}
+
+/// Reads a `color`-like element (CT_Color)
+fn read_color(e: &BytesStart<'_>) -> Result<Option<Color>, XlsxError> {
+    let mut color = None;
+    let mut tint = 0.;
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let v = std::str::from_utf8(&a.value).unwrap_or_default();
+        match a.key {
+            QName(b"rgb") => color = Color::from_hex(v),
+            QName(b"indexed") => color = Some(Color::Indexed(v.parse()?)),
+            QName(b"theme") => {
+                color = Some(Color::Theme {
+                    index: v.parse()?,
+                    tint: 0.,
+                })
+            }
+            QName(b"auto") if v == "1" || v == "true" => color = Some(Color::Auto),
+            QName(b"tint") => tint = v.parse()?,
+            _ => (),
+        }
+    }
+    if let Some(Color::Theme {
+        tint: ref mut t, ..
+    }) = color
+    {
+        *t = tint;
+    }
+    Ok(color)
+}
+
+/// Reads the `val` attribute of a boolean property (CT_BooleanProperty), true by default
+fn bool_property(e: &BytesStart<'_>) -> Result<bool, XlsxError> {
+    Ok(!matches!(
+        get_attribute(e.attributes(), QName(b"val"))?,
+        Some(b"0" | b"false")
+    ))
+}
+
+/// Reads a `font` element, or a rich text run properties element
+pub(crate) fn read_font(xml: &mut XlReader<'_>, QName(closing): QName) -> Result<Font, XlsxError> {
+    let mut font = Font::default();
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let val = || -> Result<Option<String>, XlsxError> {
+                    get_attribute(e.attributes(), QName(b"val"))?
+                        .map(|v| Ok(xml.decoder().decode(v)?.into_owned()))
+                        .transpose()
+                };
+                match e.local_name().as_ref() {
+                    b"b" => font.bold = bool_property(e)?,
+                    b"i" => font.italic = bool_property(e)?,
+                    b"strike" => font.strike = bool_property(e)?,
+                    b"u" => font.underline = val()?.map_or(true, |v| v != "none"),
+                    b"sz" => font.size = val()?.map(|v| v.parse()).transpose()?,
+                    b"name" | b"rFont" => font.name = val()?,
+                    b"color" => font.color = read_color(e)?,
+                    _ => (),
+                }
+            }
+            Ok(Event::End(ref e)) if e.name().as_ref() == closing => return Ok(font),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("font")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
 
-    Ok(())
+/// Reads a `fill` element
+fn read_fill(xml: &mut XlReader<'_>) -> Result<Fill, XlsxError> {
+    let mut fill = Fill::default();
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"patternFill" => {
+                    fill.pattern = get_attribute(e.attributes(), QName(b"patternType"))?
+                        .map(|v| xml.decoder().decode(v).map(|v| v.into_owned()))
+                        .transpose()?
+                        .filter(|v| v != "none");
+                }
+                b"fgColor" => fill.foreground = read_color(e)?,
+                b"bgColor" => fill.background = read_color(e)?,
+                _ => (),
+            },
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"fill" => return Ok(fill),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("fill")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads a `border` element
+fn read_border(xml: &mut XlReader<'_>) -> Result<Border, XlsxError> {
+    let mut border = Border::default();
+    let mut side: Option<&mut BorderSide> = None;
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let name = e.local_name();
+                let s = match name.as_ref() {
+                    b"left" | b"start" => Some(&mut border.left),
+                    b"right" | b"end" => Some(&mut border.right),
+                    b"top" => Some(&mut border.top),
+                    b"bottom" => Some(&mut border.bottom),
+                    b"color" => {
+                        if let Some(s) = side.as_mut() {
+                            s.color = read_color(e)?;
+                        }
+                        continue;
+                    }
+                    _ => None,
+                };
+                side = s;
+                if let Some(s) = side.as_mut() {
+                    s.style = get_attribute(e.attributes(), QName(b"style"))?
+                        .map(|v| xml.decoder().decode(v).map(|v| v.into_owned()))
+                        .transpose()?
+                        .filter(|v| v != "none");
+                }
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"border" => return Ok(border),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("border")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads the optional `alignment` child of a `xf` element
+fn read_alignment(xml: &mut XlReader<'_>) -> Result<Alignment, XlsxError> {
+    let mut alignment = Alignment::default();
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"alignment" => {
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    let v = xml.decoder().decode(&a.value)?;
+                    match a.key {
+                        QName(b"horizontal") if v != "general" => {
+                            alignment.horizontal = Some(v.into_owned())
+                        }
+                        QName(b"vertical") => alignment.vertical = Some(v.into_owned()),
+                        QName(b"wrapText") => alignment.wrap_text = v == "1" || v == "true",
+                        QName(b"indent") => alignment.indent = v.parse()?,
+                        QName(b"textRotation") => {
+                            alignment.rotation = rotation(v.parse::<u32>()?.min(255) as u8)
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"xf" => return Ok(alignment),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("xf")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_offset_formula() {
+        use super::cells_reader::offset_formula;
+        assert_eq!(offset_formula("A1+$B$2+C$3+$D4", (1, 1)), "B2+$B$2+D$3+$D5");
+        assert_eq!(offset_formula("SUM(A:A,1:1)", (2, 1)), "SUM(B:B,3:3)");
+        assert_eq!(
+            offset_formula("LOG10(A1)&\"A1\"", (1, 0)),
+            "LOG10(A2)&\"A1\""
+        );
+        assert_eq!(
+            offset_formula("'Sheet 1'!A1+Sheet2!Z9", (0, 1)),
+            "'Sheet 1'!B1+Sheet2!AA9"
+        );
+        assert_eq!(
+            offset_formula("Table1[[#This Row],[A1]]*2", (1, 1)),
+            "Table1[[#This Row],[A1]]*2"
+        );
+        assert_eq!(offset_formula("A2-1.5E+3", (-1, 0)), "A1-1.5E+3");
+        assert_eq!(offset_formula("A1", (-1, 0)), "#REF!");
+    }
+
     #[test]
     fn test_dimensions() {
         assert_eq!(get_row_column(b"A1").unwrap(), (0, 0));
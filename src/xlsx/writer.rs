@@ -0,0 +1,346 @@
+//! A minimal xlsx writer
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+
+use quick_xml::escape::escape;
+use zip::write::{FileOptions, ZipWriter};
+use zip::CompressionMethod;
+
+use crate::formula::CellRef;
+use crate::{CellErrorType, DataType, Range};
+
+use super::{xstring_escape, XlsxError};
+
+/// Cell formats (`cellXfs`) of the styles part, indexed by `Format as usize`
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    General = 0,
+    Date = 1,
+    DateTime = 2,
+    Duration = 3,
+}
+
+const STYLES: &str = r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="1"><font><sz val="11"/><name val="Calibri"/><family val="2"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="4"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="14" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/><xf numFmtId="22" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/><xf numFmtId="46" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>"#;
+
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+const NS_MAIN: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+const NS_REL: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const NS_PKG_REL: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+const CT_PREFIX: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml";
+
+/// Writes `Range<DataType>` worksheets into a new xlsx file
+///
+/// Values are written as follows:
+/// - `Int` and `Float` as numbers, non finite floats as `#NUM!` errors
+/// - `String` as shared strings
+/// - `DateTime` and `Duration` as numbers, with a date or an elapsed time number format
+/// - `DateTimeIso` as ISO 8601 dates (`t="d"`), `DurationIso` as strings
+/// - `Bool` and `Error` as booleans and errors
+///
+/// Reading the file back with [`Xlsx`](crate::Xlsx) returns the same values, except for
+/// `Int` which are read as `Float`.
+///
+/// # Examples
+/// ```
+/// use calamine::{DataType, Range, XlsxWriter};
+/// use std::io::Cursor;
+///
+/// let mut range = Range::new((0, 0), (1, 1));
+/// range.set_value((0, 0), DataType::String("total".to_string()));
+/// range.set_value((0, 1), DataType::Float(42.));
+///
+/// let mut writer = XlsxWriter::new();
+/// writer.add_worksheet("Sheet1", range).unwrap();
+/// writer.add_defined_name("Total", "Sheet1!$B$1");
+/// let xlsx = writer.write(Cursor::new(Vec::new())).unwrap().into_inner();
+/// assert!(!xlsx.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct XlsxWriter {
+    sheets: Vec<(String, Range<DataType>)>,
+    defined_names: Vec<(String, String)>,
+}
+
+impl XlsxWriter {
+    /// Creates a new writer, without any worksheet
+    pub fn new() -> XlsxWriter {
+        XlsxWriter::default()
+    }
+
+    /// Adds a worksheet
+    ///
+    /// Worksheet names must be unique (case insensitive), have at most 31 characters and
+    /// cannot contain any of `[]:*?/\`.
+    pub fn add_worksheet(
+        &mut self,
+        name: &str,
+        range: Range<DataType>,
+    ) -> Result<&mut Self, XlsxError> {
+        let invalid = name.is_empty()
+            || name.chars().count() > 31
+            || name.starts_with('\'')
+            || name.ends_with('\'')
+            || name.contains(|c| "[]:*?/\\".contains(c))
+            || self
+                .sheets
+                .iter()
+                .any(|(n, _)| n.to_lowercase() == name.to_lowercase());
+        if invalid {
+            return Err(XlsxError::WorksheetName(name.to_string()));
+        }
+        self.sheets.push((name.to_string(), range));
+        Ok(self)
+    }
+
+    /// Adds a workbook defined name, e.g. `("Data", "Sheet1!$A$1:$B$10")`
+    pub fn add_defined_name(&mut self, name: &str, formula: &str) -> &mut Self {
+        let formula = formula.strip_prefix('=').unwrap_or(formula);
+        self.defined_names
+            .push((name.to_string(), formula.to_string()));
+        self
+    }
+
+    /// Writes the workbook into a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), XlsxError> {
+        let file = BufWriter::new(File::create(path)?);
+        self.write(file)?.flush()?;
+        Ok(())
+    }
+
+    /// Writes the workbook into a writer, and returns it
+    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<W, XlsxError> {
+        if self.sheets.is_empty() {
+            return Err(XlsxError::Unexpected(
+                "a workbook must have at least one worksheet",
+            ));
+        }
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut part = |name: &str, content: &str| -> Result<(), XlsxError> {
+            zip.start_file(name, options)?;
+            zip.write_all(XML_HEADER.as_bytes())?;
+            zip.write_all(content.as_bytes())?;
+            Ok(())
+        };
+
+        part("[Content_Types].xml", &self.content_types())?;
+        part(
+            "_rels/.rels",
+            &format!(
+                "<Relationships xmlns=\"{NS_PKG_REL}\"><Relationship Id=\"rId1\" Type=\"{NS_REL}/officeDocument\" Target=\"xl/workbook.xml\"/></Relationships>"
+            ),
+        )?;
+        part("xl/workbook.xml", &self.workbook())?;
+        part("xl/_rels/workbook.xml.rels", &self.workbook_rels())?;
+        part("xl/styles.xml", STYLES)?;
+
+        let mut strings = SharedStrings::default();
+        for (i, (_, range)) in self.sheets.iter().enumerate() {
+            let sheet = worksheet(range, &mut strings);
+            part(&format!("xl/worksheets/sheet{}.xml", i + 1), &sheet)?;
+        }
+        part("xl/sharedStrings.xml", &strings.xml())?;
+
+        Ok(zip.finish()?)
+    }
+
+    fn content_types(&self) -> String {
+        let mut xml = format!(
+            "<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"{CT_PREFIX}.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/styles.xml\" ContentType=\"{CT_PREFIX}.styles+xml\"/>\
+             <Override PartName=\"/xl/sharedStrings.xml\" ContentType=\"{CT_PREFIX}.sharedStrings+xml\"/>"
+        );
+        for i in 1..=self.sheets.len() {
+            xml.push_str(&format!(
+                "<Override PartName=\"/xl/worksheets/sheet{i}.xml\" ContentType=\"{CT_PREFIX}.worksheet+xml\"/>"
+            ));
+        }
+        xml.push_str("</Types>");
+        xml
+    }
+
+    fn workbook(&self) -> String {
+        let mut xml = format!("<workbook xmlns=\"{NS_MAIN}\" xmlns:r=\"{NS_REL}\"><sheets>");
+        for (i, (name, _)) in self.sheets.iter().enumerate() {
+            xml.push_str(&format!(
+                "<sheet name=\"{}\" sheetId=\"{}\" r:id=\"rId{}\"/>",
+                escape(name),
+                i + 1,
+                i + 1
+            ));
+        }
+        xml.push_str("</sheets>");
+        if !self.defined_names.is_empty() {
+            xml.push_str("<definedNames>");
+            for (name, formula) in &self.defined_names {
+                xml.push_str(&format!(
+                    "<definedName name=\"{}\">{}</definedName>",
+                    escape(name),
+                    escape(formula)
+                ));
+            }
+            xml.push_str("</definedNames>");
+        }
+        xml.push_str("</workbook>");
+        xml
+    }
+
+    fn workbook_rels(&self) -> String {
+        let mut xml = format!("<Relationships xmlns=\"{NS_PKG_REL}\">");
+        let n = self.sheets.len();
+        for i in 1..=n {
+            xml.push_str(&format!(
+                "<Relationship Id=\"rId{i}\" Type=\"{NS_REL}/worksheet\" Target=\"worksheets/sheet{i}.xml\"/>"
+            ));
+        }
+        xml.push_str(&format!(
+            "<Relationship Id=\"rId{}\" Type=\"{NS_REL}/styles\" Target=\"styles.xml\"/>\
+             <Relationship Id=\"rId{}\" Type=\"{NS_REL}/sharedStrings\" Target=\"sharedStrings.xml\"/>\
+             </Relationships>",
+            n + 1,
+            n + 2
+        ));
+        xml
+    }
+}
+
+/// Shared strings table, without duplicates
+#[derive(Default)]
+struct SharedStrings {
+    strings: Vec<String>,
+    index: HashMap<String, usize>,
+    count: usize,
+}
+
+impl SharedStrings {
+    fn get_or_insert(&mut self, s: &str) -> usize {
+        self.count += 1;
+        if let Some(i) = self.index.get(s) {
+            return *i;
+        }
+        let i = self.strings.len();
+        self.strings.push(s.to_string());
+        self.index.insert(s.to_string(), i);
+        i
+    }
+
+    fn xml(&self) -> String {
+        let mut xml = format!(
+            "<sst xmlns=\"{NS_MAIN}\" count=\"{}\" uniqueCount=\"{}\">",
+            self.count,
+            self.strings.len()
+        );
+        for s in &self.strings {
+            xml.push_str("<si>");
+            push_text(&mut xml, s);
+            xml.push_str("</si>");
+        }
+        xml.push_str("</sst>");
+        xml
+    }
+}
+
+/// Pushes a `<t>` element, preserving leading and trailing spaces
+fn push_text(xml: &mut String, s: &str) {
+    if s.starts_with(char::is_whitespace) || s.ends_with(char::is_whitespace) {
+        xml.push_str("<t xml:space=\"preserve\">");
+    } else {
+        xml.push_str("<t>");
+    }
+    // characters not allowed in xml are escaped as `_xHHHH_` (ECMA-376 Part 1, 22.9.2.19),
+    // the `_` of a literal `_xHHHH_` as `_x005F_`
+    let s = escape(s);
+    for (i, c) in s.char_indices() {
+        match c {
+            '\t' | '\n' | '\r' => xml.push(c),
+            c if (c as u32) < 0x20 => xml.push_str(&format!("_x{:04X}_", c as u32)),
+            '_' if xstring_escape(&s[i..]).is_some() => xml.push_str("_x005F_"),
+            c => xml.push(c),
+        }
+    }
+    xml.push_str("</t>");
+}
+
+/// Formats a number the way Excel writes it
+fn number(v: f64) -> String {
+    let abs = v.abs();
+    if abs == 0. || (1e-6..1e15).contains(&abs) {
+        v.to_string()
+    } else {
+        format!("{v:E}")
+    }
+}
+
+fn error(e: &CellErrorType) -> &'static str {
+    match e {
+        CellErrorType::Div0 => "#DIV/0!",
+        CellErrorType::NA => "#N/A",
+        CellErrorType::Name => "#NAME?",
+        CellErrorType::Null => "#NULL!",
+        CellErrorType::Num => "#NUM!",
+        CellErrorType::Ref => "#REF!",
+        CellErrorType::Value => "#VALUE!",
+        CellErrorType::GettingData => "#GETTING_DATA",
+    }
+}
+
+fn worksheet(range: &Range<DataType>, strings: &mut SharedStrings) -> String {
+    let mut xml = format!("<worksheet xmlns=\"{NS_MAIN}\" xmlns:r=\"{NS_REL}\">");
+    if let (Some(start), Some(end)) = (range.start(), range.end()) {
+        xml.push_str(&format!(
+            "<dimension ref=\"{}:{}\"/>",
+            CellRef::new(start.0, start.1),
+            CellRef::new(end.0, end.1)
+        ));
+    }
+    xml.push_str("<sheetData>");
+    let (row0, col0) = range.start().unwrap_or_default();
+    let mut current_row = None;
+    for (r, c, v) in range.used_cells() {
+        let (row, col) = (row0 + r as u32, col0 + c as u32);
+        if current_row != Some(row) {
+            if current_row.is_some() {
+                xml.push_str("</row>");
+            }
+            xml.push_str(&format!("<row r=\"{}\">", row + 1));
+            current_row = Some(row);
+        }
+        let pos = CellRef::new(row, col);
+        let (typ, format, value) = match v {
+            DataType::Int(i) => ("", Format::General, i.to_string()),
+            DataType::Float(f) if !f.is_finite() => {
+                ("e", Format::General, error(&CellErrorType::Num).to_string())
+            }
+            DataType::Float(f) => ("", Format::General, number(*f)),
+            DataType::DateTime(f) if f.fract() == 0. => ("", Format::Date, number(*f)),
+            DataType::DateTime(f) => ("", Format::DateTime, number(*f)),
+            DataType::Duration(f) => ("", Format::Duration, number(*f)),
+            DataType::String(s) | DataType::DurationIso(s) => {
+                ("s", Format::General, strings.get_or_insert(s).to_string())
+            }
+            DataType::DateTimeIso(s) => ("d", Format::DateTime, escape(s).into_owned()),
+            DataType::Bool(b) => ("b", Format::General, (*b as u8).to_string()),
+            DataType::Error(e) => ("e", Format::General, error(e).to_string()),
+            DataType::Empty => continue,
+        };
+        xml.push_str(&format!("<c r=\"{pos}\""));
+        if !typ.is_empty() {
+            xml.push_str(&format!(" t=\"{typ}\""));
+        }
+        if !matches!(format, Format::General) {
+            xml.push_str(&format!(" s=\"{}\"", format as usize));
+        }
+        xml.push_str(&format!("><v>{value}</v></c>"));
+    }
+    if current_row.is_some() {
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
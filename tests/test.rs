@@ -1,10 +1,17 @@
+use calamine::formula::{BinaryOp, CellRef, SheetRef};
+use calamine::style::Color;
 use calamine::DataType::{
     Bool, DateTime, DateTimeIso, Duration, DurationIso, Empty, Error, Float, String,
 };
 use calamine::{
-    open_workbook, open_workbook_auto, Ods, Reader, Sheet, SheetType, SheetVisible, Xls, Xlsb, Xlsx,
+    open_workbook, open_workbook_auto, ChartReference, ChartSeries, ChartType, ConditionalFormat,
+    ConditionalFormatType, CryptoError, DataValidation, DeError, Dimensions, DocumentProperties,
+    Hyperlink, LineLayout, Ods, RangeDeserializerBuilder, Reader, Sheet, SheetType, SheetVisible,
+    TableReader, TextRun, ValidationOperator, ValidationType, Xls, XlsOptions, Xlsb, XlsbError,
+    Xlsx, XlsxError, XlsxWriter,
 };
-use calamine::{CellErrorType::*, DataType};
+use calamine::{CellErrorType::*, DataType, DataTypeRef, Expr, Range, StyleId};
+use std::fs::File;
 use std::io::Cursor;
 use std::sync::Once;
 
@@ -648,7 +655,7 @@ fn issue_127() {
     setup();
 
     let root = env!("CARGO_MANIFEST_DIR");
-    let ordered_names: Vec<std::string::String> = vec![
+    let ordered_names: Vec<std::string::String> = [
         "Sheet1", "Sheet2", "Sheet3", "Sheet4", "Sheet5", "Sheet6", "Sheet7", "Sheet8",
     ]
     .iter()
@@ -740,6 +747,116 @@ fn table() {
     xls.worksheet_range_at(0).unwrap().unwrap();
 }
 
+#[test]
+fn table_reader() {
+    setup();
+
+    let path = format!("{}/tests/tables.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsb: Xlsb<_> = open_workbook(&path).unwrap();
+    xlsb.load_tables().unwrap();
+    assert_eq!(xlsb.table_names(), vec!["Dates"]);
+    assert_eq!(xlsb.table_names_in_sheet("Sheet1"), vec!["Dates"]);
+    let table = xlsb.table_by_name("Dates").unwrap();
+    assert_eq!(table.sheet_name(), "Sheet1");
+    assert_eq!(table.columns(), ["date", "value"]);
+    assert_eq!(table.data().start(), Some((1, 0)));
+    assert_eq!(table.data().end(), Some((2, 1)));
+    assert_eq!(table.data().get((0, 1)), Some(&Float(16.)));
+    assert!(matches!(
+        xlsb.table_by_name("Missing"),
+        Err(XlsbError::TableNotFound(_))
+    ));
+
+    // ods named database ranges, through `Sheets`
+    let path = format!("{}/tests/tables.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods = open_workbook_auto(&path).unwrap();
+    ods.load_tables().unwrap();
+    assert_eq!(ods.table_names(), vec!["Prices", "Raw"]);
+    assert_eq!(ods.table_names_in_sheet("My sheet"), vec!["Raw"]);
+    let table = ods.table_by_name("Prices").unwrap();
+    assert_eq!(table.columns(), ["item", "price"]);
+    assert_eq!(table.data().start(), Some((2, 1)));
+    assert_eq!(table.data().get((0, 0)), Some(&String("apple".to_string())));
+    assert_eq!(table.data().get((1, 1)), Some(&Float(2.)));
+    let table = ods.table_by_name("Raw").unwrap();
+    assert_eq!(table.sheet_name(), "My sheet");
+    assert!(table.columns().is_empty());
+    assert_eq!(table.data().get((1, 0)), Some(&Float(20.)));
+}
+
+#[test]
+fn structured_references() {
+    setup();
+
+    let path = format!(
+        "{}/tests/structured_references.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let formulas = xlsx.worksheet_formula("Sheet1").unwrap();
+    assert_eq!(formulas.get_value((1, 2)).unwrap(), "[@Qty]*[@Price]");
+    let formulas = xlsx.worksheet_formula_resolved("Sheet1").unwrap();
+    assert_eq!(formulas.get_value((0, 4)).unwrap(), "SUM($C$2:$C$4)");
+    assert_eq!(formulas.get_value((1, 2)).unwrap(), "$A$2*$B$2");
+    assert_eq!(formulas.get_value((3, 2)).unwrap(), "$A$4*$B$4");
+    assert_eq!(
+        formulas.get_value((1, 4)).unwrap(),
+        "\"Sales[Amount]\"&$A$1"
+    );
+    let formulas = xlsx.worksheet_formula_resolved("Sheet2").unwrap();
+    assert_eq!(formulas.get_value((0, 0)).unwrap(), "SUM(Sheet1!$A$2:$B$4)");
+    assert_eq!(formulas.get_value((1, 0)).unwrap(), "COUNT(Missing[Col])");
+
+    let ast = xlsx.worksheet_formula_ast("Sheet1").unwrap();
+    assert_eq!(
+        ast.get_value((1, 2)).unwrap(),
+        &Expr::Binary(
+            BinaryOp::Mul,
+            Box::new(Expr::Table {
+                table: None,
+                spec: "@Qty".to_string(),
+            }),
+            Box::new(Expr::Table {
+                table: None,
+                spec: "@Price".to_string(),
+            }),
+        )
+    );
+    assert_eq!(
+        ast.get_value((0, 4)).unwrap().to_string(),
+        "SUM(Sales[Amount])"
+    );
+    let ast = xlsx.worksheet_formula_ast("Sheet2").unwrap();
+    assert_eq!(
+        ast.get_value((1, 0)).unwrap().to_string(),
+        "COUNT(Missing[Col])"
+    );
+
+    // PtgList tokens
+    for file in ["structured_references.xlsb", "structured_references.xls"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        workbook.load_tables().unwrap();
+        assert_eq!(workbook.table_names(), vec!["Dates"], "{file}");
+        let table = workbook.table_by_name("Dates").unwrap();
+        assert_eq!(table.columns(), ["date", "value"], "{file}");
+        let formulas = workbook.worksheet_formula("Sheet1").unwrap();
+        assert_eq!(
+            formulas.get_value((0, 3)).unwrap(),
+            "SUM(Dates[value])",
+            "{file}"
+        );
+        assert_eq!(
+            formulas.get_value((1, 2)).unwrap(),
+            "Dates[[#This Row],[value]]*2",
+            "{file}"
+        );
+        let formulas = workbook.worksheet_formula_resolved("Sheet1").unwrap();
+        assert_eq!(formulas.get_value((0, 3)).unwrap(), "SUM($B$2:$B$3)");
+        assert_eq!(formulas.get_value((1, 2)).unwrap(), "$B$2*2");
+    }
+}
+
 #[test]
 fn date_xls() {
     setup();
@@ -753,7 +870,7 @@ fn date_xls() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let duration = chrono::Duration::seconds(255 * 60 * 60 + 10 * 60 + 10);
@@ -777,7 +894,7 @@ fn date_xls_1904() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let duration = chrono::Duration::seconds(255 * 60 * 60 + 10 * 60 + 10);
@@ -801,7 +918,7 @@ fn date_xlsx() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let duration = chrono::Duration::seconds(255 * 60 * 60 + 10 * 60 + 10);
@@ -825,7 +942,7 @@ fn date_xlsx_1904() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let duration = chrono::Duration::seconds(255 * 60 * 60 + 10 * 60 + 10);
@@ -859,7 +976,7 @@ fn date_xlsx_iso() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
         assert_eq!(range.get_value((0, 0)).unwrap().as_time(), None);
         assert_eq!(range.get_value((0, 0)).unwrap().as_datetime(), None);
@@ -906,7 +1023,7 @@ fn date_ods() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let time = chrono::NaiveTime::from_hms_opt(10, 10, 10).unwrap();
@@ -943,7 +1060,7 @@ fn date_xlsb() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let duration = chrono::Duration::seconds(255 * 60 * 60 + 10 * 60 + 10);
@@ -967,7 +1084,7 @@ fn date_xlsb_1904() {
 
     #[cfg(feature = "dates")]
     {
-        let date = chrono::NaiveDate::from_ymd_opt(2021, 01, 01).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
         assert_eq!(range.get_value((0, 0)).unwrap().as_date(), Some(date));
 
         let duration = chrono::Duration::seconds(255 * 60 * 60 + 10 * 60 + 10);
@@ -1499,6 +1616,75 @@ fn issue_102() {
     );
 }
 
+#[test]
+fn encrypted_workbooks() {
+    setup();
+
+    let path = format!("{}/tests/date.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let expected = xlsx.worksheet_range("Sheet1").unwrap();
+
+    // agile encryption, with data integrity
+    let path = format!("{}/tests/encrypted_agile.xlsx", env!("CARGO_MANIFEST_DIR"));
+    assert!(matches!(
+        open_workbook::<Xlsx<_>, _>(&path),
+        Err(XlsxError::Password)
+    ));
+    let mut xlsx = Xlsx::new_with_password(File::open(&path).unwrap(), "calamine").unwrap();
+    let range = xlsx.worksheet_range("Sheet1").unwrap();
+    assert!(range.used_cells().eq(expected.used_cells()));
+    assert!(matches!(
+        Xlsx::new_with_password(File::open(&path).unwrap(), "wrong"),
+        Err(XlsxError::Crypto(CryptoError::InvalidPassword))
+    ));
+    let mut tampered = std::fs::read(&path).unwrap();
+    tampered[512 + 4096 + 100] ^= 0xFF;
+    assert!(matches!(
+        Xlsx::new_with_password(Cursor::new(tampered), "calamine"),
+        Err(XlsxError::Crypto(CryptoError::Integrity))
+    ));
+    // the data integrity cannot be skipped
+    let mut unverified = std::fs::read(&path).unwrap();
+    let start = unverified
+        .windows(14)
+        .position(|w| w == b"<dataIntegrity")
+        .unwrap();
+    let end = start
+        + unverified[start..]
+            .windows(2)
+            .position(|w| w == b"/>")
+            .unwrap()
+        + 2;
+    unverified[start..end].fill(b' ');
+    assert!(matches!(
+        Xlsx::new_with_password(Cursor::new(unverified), "calamine"),
+        Err(XlsxError::Crypto(CryptoError::Invalid(_)))
+    ));
+
+    // standard encryption
+    let path = format!(
+        "{}/tests/encrypted_standard.xlsb",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    assert!(matches!(
+        open_workbook::<Xlsb<_>, _>(&path),
+        Err(XlsbError::Password)
+    ));
+    let mut xlsb = Xlsb::new_with_password(File::open(&path).unwrap(), "calamine").unwrap();
+    let range = xlsb.worksheet_range("Sheet1").unwrap();
+    assert_eq!(range.get_value((1, 1)), Some(&Float(16.)));
+    assert!(matches!(
+        Xlsb::new_with_password(File::open(&path).unwrap(), "wrong"),
+        Err(XlsbError::Crypto(CryptoError::InvalidPassword))
+    ));
+
+    // workbooks which are not encrypted
+    let path = format!("{}/tests/date.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx = Xlsx::new_with_password(File::open(&path).unwrap(), "calamine").unwrap();
+    let range = xlsx.worksheet_range("Sheet1").unwrap();
+    assert!(range.used_cells().eq(expected.used_cells()));
+}
+
 #[test]
 fn issue_374() {
     let path = format!("{}/tests/biff5_write.xls", env!("CARGO_MANIFEST_DIR"));
@@ -1519,13 +1705,146 @@ fn issue_374() {
 fn issue_385() {
     let path = format!("{}/tests/issue_385.xls", env!("CARGO_MANIFEST_DIR"));
 
-    assert!(
-        matches!(
-            open_workbook::<Xls<_>, std::string::String>(path),
-            Err(calamine::XlsError::Password)
-        ),
-        "Is expeced to return XlsError::Password error"
+    // encrypted with the default password
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    assert_eq!(xls.sheet_names(), ["Sheet1"]);
+    assert!(xls.worksheet_range("Sheet1").unwrap().is_empty());
+
+    let mut options = XlsOptions::default();
+    options.password = Some("calamine".to_string());
+    assert!(matches!(
+        Xls::new_with_options(File::open(&path).unwrap(), options),
+        Err(calamine::XlsError::Password)
+    ));
+
+    // RC4 CryptoAPI encryption with the default password, over several blocks
+    let path = format!("{}/tests/encrypted_default.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        xls.sheet_names(),
+        ["datatypes", "Sheet1", "issue2", "issue5", "issue6"]
+    );
+    let range = xls.worksheet_range("issue2").unwrap();
+    range_eq!(
+        range,
+        [
+            [Float(1.), String("a".to_string())],
+            [Float(2.), String("b".to_string())],
+            [Float(3.), String("c".to_string())]
+        ]
+    );
+
+    let mut options = XlsOptions::default();
+    options.password = Some("calamine".to_string());
+    assert!(matches!(
+        Xls::new_with_options(File::open(&path).unwrap(), options),
+        Err(calamine::XlsError::Password)
+    ));
+}
+
+#[test]
+fn encrypted_xls() {
+    setup();
+
+    for encryption in ["xor", "rc4", "cryptoapi"] {
+        let path = format!(
+            "{}/tests/encrypted_{encryption}.xls",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        assert!(
+            matches!(
+                open_workbook::<Xls<_>, _>(&path),
+                Err(calamine::XlsError::Password)
+            ),
+            "{encryption}"
+        );
+
+        let mut options = XlsOptions::default();
+        options.password = Some("wrong".to_string());
+        assert!(
+            matches!(
+                Xls::new_with_options(File::open(&path).unwrap(), options),
+                Err(calamine::XlsError::Password)
+            ),
+            "{encryption}"
+        );
+
+        let mut options = XlsOptions::default();
+        options.password = Some("calamine".to_string());
+        let mut xls = Xls::new_with_options(File::open(&path).unwrap(), options).unwrap();
+        assert_eq!(xls.sheet_names(), ["Sheet1"], "{encryption}");
+        let range = xls.worksheet_range("Sheet1").unwrap();
+        range_eq!(
+            range,
+            [
+                [DateTime(44197.), DataType::Int(15)],
+                [DateTime(44198.), DataType::Int(16)],
+                [Duration(10.632060185185185), DataType::Int(17)]
+            ]
+        );
+    }
+}
+
+#[test]
+fn legacy_xls() {
+    setup();
+
+    for biff in ["biff2", "biff3", "biff4", "biff5"] {
+        let path = format!("{}/tests/{biff}.xls", env!("CARGO_MANIFEST_DIR"));
+        let mut xls: Xls<_> = open_workbook(&path).unwrap();
+
+        let range = xls.worksheet_range("Sheet1").unwrap();
+        let integer = match biff {
+            // BIFF3 has no integer cells
+            "biff3" => Float(42.),
+            _ => DataType::Int(42),
+        };
+        let mut expected = vec![
+            [String("calamine".to_string()), Bool(true)],
+            [String("integer".to_string()), integer],
+            [String("float".to_string()), Float(1.5)],
+            [String("date".to_string()), DateTime(44197.)],
+            [String("error".to_string()), Error(Div0)],
+            [Float(43.5), Empty],
+            [String("calamine!".to_string()), Empty],
+            [Float(2.), Empty],
+        ];
+        let mut formulas = vec!["SUM(B2:B3)", "A1&\"!\"", "ROUND(B3,0)"];
+        if biff == "biff5" {
+            expected.extend([[Float(7.), Empty], [Float(43.5), Empty]]);
+            formulas.extend(["Sheet2!A1", "SUM(Total)"]);
+        }
+        range_eq!(range, expected);
+
+        let range = xls.worksheet_formula("Sheet1").unwrap();
+        assert_eq!(range.start(), Some((5, 0)), "{biff}");
+        assert!(range.rows().map(|r| r[0].as_str()).eq(formulas), "{biff}");
+
+        let range = xls.worksheet_formula_ast("Sheet1").unwrap();
+        assert_eq!(
+            range.get_value((6, 0)),
+            Some(&Expr::Binary(
+                BinaryOp::Concat,
+                Box::new(Expr::Cell {
+                    sheet: None,
+                    cell: CellRef::new(0, 0),
+                }),
+                Box::new(Expr::String("!".to_string())),
+            )),
+            "{biff}"
+        );
+    }
+
+    // BIFF5 workbook in a `Book` stream
+    let path = format!("{}/tests/biff5.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    assert_eq!(xls.sheet_names(), ["Sheet1", "Sheet2"]);
+    assert_eq!(
+        xls.defined_names(),
+        [("Total".to_string(), "Sheet1!$B$2:$B$3".to_string())]
     );
+    let range = xls.worksheet_range("Sheet2").unwrap();
+    range_eq!(range, [[Float(7.)]]);
 }
 
 #[test]
@@ -1565,3 +1884,1484 @@ fn issue_384_multiple_formula() {
     ];
     assert_eq!(formula, expected)
 }
+
+#[test]
+fn merged_regions_xlsx() {
+    setup();
+
+    let path = format!("{}/tests/issue_261.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let regions = xlsx.merged_regions("Some Sheet").unwrap();
+    assert_eq!(regions.len(), 25);
+    assert_eq!(
+        regions[..3],
+        [
+            Dimensions::new((0, 0), (4, 4)),
+            Dimensions::new((0, 5), (4, 7)),
+            Dimensions::new((52, 0), (52, 7)),
+        ]
+    );
+    assert!(regions[0].contains(2, 3));
+    assert!(!regions[0].contains(2, 5));
+    assert!(xlsx.merged_regions("Not a sheet").is_err());
+}
+
+#[test]
+fn merged_regions_xlsb() {
+    setup();
+
+    let path = format!("{}/tests/merged_cells.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsb: Xlsb<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        xlsb.merged_regions("Sheet1").unwrap(),
+        [
+            Dimensions::new((0, 0), (1, 2)),
+            Dimensions::new((3, 1), (5, 1)),
+        ]
+    );
+
+    // merged cells and cell records cut short
+    let path = format!(
+        "{}/tests/truncated_records.xlsb",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut xlsb: Xlsb<_> = open_workbook(&path).unwrap();
+    assert!(matches!(
+        xlsb.merged_regions("Sheet1"),
+        Err(XlsbError::RecordLen {
+            typ: 0x00B0,
+            len: 8
+        })
+    ));
+    assert!(matches!(
+        xlsb.worksheet_styles("Sheet1"),
+        Err(XlsbError::RecordLen { len: 4, .. })
+    ));
+}
+
+#[test]
+fn merged_regions_xls() {
+    setup();
+
+    let path = format!("{}/tests/issue_271.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let regions = xls.merged_regions("sheet1").unwrap();
+    assert_eq!(regions.len(), 79);
+    assert_eq!(
+        regions[..2],
+        [
+            Dimensions::new((17, 19), (17, 21)),
+            Dimensions::new((17, 24), (17, 25)),
+        ]
+    );
+    assert_eq!(xls.merged_regions("sheet2").unwrap().len(), 47);
+}
+
+#[test]
+fn merged_regions_ods() {
+    setup();
+
+    let path = format!("{}/tests/merged_cells.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        ods.merged_regions("Sheet1").unwrap(),
+        [Dimensions::new((1, 0), (2, 0))]
+    );
+
+    // Sheets dispatches to the underlying reader
+    let mut sheets = open_workbook_auto(&path).unwrap();
+    assert_eq!(
+        sheets.merged_regions("Sheet1").unwrap(),
+        [Dimensions::new((1, 0), (2, 0))]
+    );
+}
+
+#[test]
+fn reader_default_methods() {
+    // a reader only implementing the required methods
+    struct Csv(Range<DataType>, calamine::Metadata);
+
+    impl Reader<Cursor<Vec<u8>>> for Csv {
+        type Error = calamine::Error;
+
+        fn new(_: Cursor<Vec<u8>>) -> Result<Self, Self::Error> {
+            Ok(Csv(Range::default(), Default::default()))
+        }
+
+        fn vba_project(
+            &mut self,
+        ) -> Option<Result<std::borrow::Cow<'_, calamine::vba::VbaProject>, Self::Error>> {
+            None
+        }
+
+        fn metadata(&self) -> &calamine::Metadata {
+            &self.1
+        }
+
+        fn worksheet_range(&mut self, _: &str) -> Result<Range<DataType>, Self::Error> {
+            Ok(self.0.clone())
+        }
+
+        fn worksheets(&mut self) -> Vec<(std::string::String, Range<DataType>)> {
+            Vec::new()
+        }
+
+        fn worksheet_formula(
+            &mut self,
+            _: &str,
+        ) -> Result<Range<std::string::String>, Self::Error> {
+            Ok(Range::default())
+        }
+
+        #[cfg(feature = "picture")]
+        fn pictures(&self) -> Option<Vec<(std::string::String, Vec<u8>)>> {
+            None
+        }
+    }
+
+    let mut csv = Csv::new(Cursor::new(Vec::new())).unwrap();
+    assert!(csv.merged_regions("Sheet1").unwrap().is_empty());
+    assert!(csv.worksheet_formula_ast("Sheet1").unwrap().is_empty());
+    assert!(csv.worksheet_styles("Sheet1").unwrap().is_empty());
+    assert!(csv.worksheet_comments("Sheet1").unwrap().is_empty());
+    assert!(csv.worksheet_hyperlinks("Sheet1").unwrap().is_empty());
+    assert!(csv.worksheet_rich_text("Sheet1").unwrap().is_empty());
+    assert_eq!(csv.worksheet_layout("Sheet1").unwrap(), Default::default());
+    assert!(csv.worksheet_data_validations("Sheet1").unwrap().is_empty());
+    assert!(csv
+        .worksheet_conditional_formats("Sheet1")
+        .unwrap()
+        .is_empty());
+    assert!(csv.worksheet_charts("Sheet1").unwrap().is_empty());
+    assert!(csv.worksheet_display_range("Sheet1").unwrap().is_empty());
+}
+
+#[test]
+fn xlsx_worksheet_rows() {
+    setup();
+
+    let path = format!("{}/tests/rows_reader.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let rows = xlsx
+        .worksheet_rows("Sheet1")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        [
+            (
+                1,
+                vec![
+                    DataTypeRef::Float(1.),
+                    DataTypeRef::Empty,
+                    DataTypeRef::Float(3.)
+                ]
+            ),
+            (
+                4,
+                vec![
+                    DataTypeRef::Empty,
+                    DataTypeRef::String("five".to_string()),
+                    DataTypeRef::Float(6.)
+                ]
+            ),
+            (3, vec![DataTypeRef::Bool(true)]),
+        ]
+    );
+}
+
+#[test]
+fn xlsx_worksheet_rows_interleaved() {
+    setup();
+
+    let path = format!("{}/tests/rows_interleaved.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let mut rows = xlsx.worksheet_rows("Sheet1").unwrap();
+    assert_eq!(
+        rows.next().unwrap().unwrap(),
+        (1, vec![DataTypeRef::Float(1.)])
+    );
+    assert_eq!(
+        rows.next().unwrap().unwrap(),
+        (2, vec![DataTypeRef::Float(2.)])
+    );
+    // the cells of row 1 are split by row 2
+    assert!(matches!(
+        rows.next(),
+        Some(Err(XlsxError::InterleavedRow(1)))
+    ));
+    assert!(rows.next().is_none());
+}
+
+#[test]
+fn xlsx_worksheet_rows_match_range() {
+    setup();
+
+    let path = format!("{}/tests/issue_261.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = xlsx.worksheet_range("Some Sheet").unwrap();
+    let mut count = 0;
+    for row in xlsx.worksheet_rows("Some Sheet").unwrap() {
+        let (r, cells) = row.unwrap();
+        for (c, v) in cells.into_iter().enumerate() {
+            if v != DataTypeRef::Empty {
+                assert_eq!(
+                    range.get_value((r, c as u32)),
+                    Some(&DataType::from(v)),
+                    "Mismatch at position ({}, {})",
+                    r,
+                    c
+                );
+                count += 1;
+            }
+        }
+    }
+    assert_eq!(count, range.used_cells().count());
+}
+
+#[test]
+fn xls_lazy() {
+    setup();
+
+    for (file, password) in [
+        ("issues.xls", None),
+        ("issue_271.xls", None),
+        ("xls_formula.xls", None),
+        ("date.xls", None),
+        ("comments.xls", None),
+        ("rich_text.xls", None),
+        ("biff4.xls", None),
+        ("encrypted_rc4.xls", Some("calamine")),
+        ("encrypted_xor.xls", Some("calamine")),
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut options = XlsOptions::default();
+        options.password = password.map(str::to_string);
+        let mut eager = Xls::new_with_options(File::open(&path).unwrap(), options.clone()).unwrap();
+        options.lazy = true;
+        let mut lazy = Xls::new_with_options(File::open(&path).unwrap(), options).unwrap();
+        assert_eq!(eager.styles().unwrap(), lazy.styles().unwrap(), "{file}");
+        assert_eq!(eager.sheet_names(), lazy.sheet_names());
+        for name in eager.sheet_names() {
+            let (e, l) = (
+                eager.worksheet_range(&name).unwrap(),
+                lazy.worksheet_range(&name).unwrap(),
+            );
+            assert_eq!(e.start(), l.start(), "{file}: {name}");
+            assert_eq!(
+                e.used_cells().collect::<Vec<_>>(),
+                l.used_cells().collect::<Vec<_>>(),
+                "{file}: {name}"
+            );
+            let (e, l) = (
+                eager.worksheet_formula(&name).unwrap(),
+                lazy.worksheet_formula(&name).unwrap(),
+            );
+            assert_eq!(e.start(), l.start(), "{file}: {name}");
+            assert_eq!(
+                e.used_cells().collect::<Vec<_>>(),
+                l.used_cells().collect::<Vec<_>>(),
+                "{file}: {name}"
+            );
+            assert_eq!(
+                eager.merged_regions(&name).unwrap(),
+                lazy.merged_regions(&name).unwrap(),
+                "{file}: {name}"
+            );
+            assert_eq!(
+                eager.worksheet_comments(&name).unwrap(),
+                lazy.worksheet_comments(&name).unwrap(),
+                "{file}: {name}"
+            );
+            let (e, l) = (
+                eager.worksheet_rich_text(&name).unwrap(),
+                lazy.worksheet_rich_text(&name).unwrap(),
+            );
+            assert!(e.used_cells().eq(l.used_cells()), "{file}: {name}");
+            let (e, l) = (
+                eager.worksheet_styles(&name).unwrap(),
+                lazy.worksheet_styles(&name).unwrap(),
+            );
+            assert!(e.used_cells().eq(l.used_cells()), "{file}: {name}");
+        }
+        assert_eq!(eager.worksheets().len(), lazy.worksheets().len());
+    }
+}
+
+#[test]
+fn xls_cells_reader() {
+    setup();
+
+    let path = format!("{}/tests/issues.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let mut cells_reader = xls.worksheet_cells_reader("issue2").unwrap();
+    let mut cells = Vec::new();
+    while let Some(cell) = cells_reader.next_cell().unwrap() {
+        cells.push((cell.get_position(), cell.get_value().clone()));
+    }
+    assert_eq!(
+        cells,
+        [
+            ((0, 0), Float(1.)),
+            ((0, 1), String("a".to_string())),
+            ((1, 0), Float(2.)),
+            ((1, 1), String("b".to_string())),
+            ((2, 0), Float(3.)),
+            ((2, 1), String("c".to_string())),
+        ]
+    );
+    assert!(xls.worksheet_cells_reader("not a sheet").is_err());
+}
+
+#[test]
+fn de_error_position_and_header() {
+    setup();
+
+    let path = format!("{}/tests/temperature.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = excel.worksheet_range("Sheet1").unwrap();
+    let mut iter = RangeDeserializerBuilder::new()
+        .from_range::<_, (f64, f64)>(&range)
+        .unwrap();
+    match iter.next().unwrap() {
+        Err(DeError::Cell {
+            pos,
+            header,
+            value,
+            error,
+        }) => {
+            assert_eq!(pos, (1, 0));
+            assert_eq!(header.as_deref(), Some("label"));
+            assert_eq!(value, String("celsius".to_string()));
+            assert!(matches!(*error, DeError::Custom(_)), "{error:?}");
+        }
+        r => panic!("unexpected result {r:?}"),
+    }
+}
+
+#[test]
+fn ods_cells_reader() {
+    setup();
+
+    let path = format!(
+        "{}/tests/number_rows_repeated.ods",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let mut cells_reader = ods.worksheet_cells_reader("Sheet1").unwrap();
+    let mut cells = Vec::new();
+    while let Some(cell) = cells_reader.next_cell().unwrap() {
+        cells.push(cell.get_position());
+    }
+    // repeated rows are expanded
+    assert_eq!(
+        cells,
+        [
+            (0, 0),
+            (0, 1),
+            (1, 0),
+            (1, 1),
+            (2, 0),
+            (2, 1),
+            (5, 0),
+            (5, 1),
+            (7, 0),
+            (7, 1)
+        ]
+    );
+    drop(cells_reader);
+    assert!(ods.worksheet_cells_reader("not a sheet").is_err());
+}
+
+#[test]
+fn ods_worksheets() {
+    setup();
+
+    let path = format!("{}/tests/issues.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let worksheets = ods.worksheets();
+    let mut names = ods.sheet_names();
+    names.sort();
+    assert_eq!(
+        worksheets
+            .iter()
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<_>>(),
+        names
+    );
+    for (name, range) in worksheets {
+        let expected = ods.worksheet_range(&name).unwrap();
+        assert_eq!(range.start(), expected.start(), "{name}");
+        assert_eq!(
+            range.rows().collect::<Vec<_>>(),
+            expected.rows().collect::<Vec<_>>(),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn styles_xlsx() {
+    setup();
+
+    let path = format!("{}/tests/styles.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let styles = xlsx.styles().unwrap();
+    assert_eq!(styles.len(), 3);
+
+    let style = &styles[StyleId(1)];
+    assert_eq!(style.number_format.as_deref(), Some("0.000"));
+    assert_eq!(style.font.name.as_deref(), Some("Arial"));
+    assert_eq!(style.font.size, Some(14.));
+    assert!(style.font.bold && style.font.italic && style.font.underline);
+    assert_eq!(style.font.color, Some(Color::Argb(0xFFFF0000)));
+    assert!(style.fill.is_visible());
+    assert_eq!(style.fill.foreground, Some(Color::Argb(0xFFFFFF00)));
+    assert_eq!(style.border.left.style.as_deref(), Some("thin"));
+    assert_eq!(style.border.top.style, None);
+    assert_eq!(style.border.bottom.style.as_deref(), Some("double"));
+    assert_eq!(
+        style.border.bottom.color,
+        Some(Color::Theme {
+            index: 4,
+            tint: -0.5
+        })
+    );
+    assert_eq!(style.alignment.horizontal.as_deref(), Some("center"));
+    assert_eq!(style.alignment.vertical.as_deref(), Some("top"));
+    assert!(style.alignment.wrap_text);
+    assert_eq!(style.alignment.rotation, 45);
+
+    // builtin number format
+    assert_eq!(styles[StyleId(2)].number_format.as_deref(), Some("0.00%"));
+    assert_eq!(styles[StyleId(2)].alignment.indent, 2);
+    assert!(!styles[StyleId(0)].fill.is_visible());
+
+    let ids = xlsx.worksheet_styles("Sheet1").unwrap();
+    assert_eq!(ids.get_value((0, 0)), Some(&StyleId(1)));
+    assert_eq!(ids.get_value((0, 1)), Some(&StyleId(0)));
+    assert_eq!(ids.get_value((1, 2)), Some(&StyleId(2)));
+}
+
+#[test]
+fn styles_xlsb() {
+    setup();
+
+    let path = format!("{}/tests/date.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsb: Xlsb<_> = open_workbook(&path).unwrap();
+    let styles = xlsb.styles().unwrap();
+    let ids = xlsb.worksheet_styles("Sheet1").unwrap();
+    let style = &styles[*ids.get_value((0, 0)).unwrap()];
+    assert_eq!(style.number_format.as_deref(), Some("yyyy\\-mm\\-dd"));
+    assert_eq!(style.font.name.as_deref(), Some("Arial"));
+    assert_eq!(style.font.size, Some(10.));
+    let style = &styles[*ids.get_value((2, 0)).unwrap()];
+    assert_eq!(style.number_format.as_deref(), Some("[hh]:mm:ss"));
+}
+
+#[test]
+fn styles_xls() {
+    setup();
+
+    let path = format!("{}/tests/date.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let styles = xls.styles().unwrap();
+    let ids = xls.worksheet_styles("Sheet1").unwrap();
+    let style = &styles[*ids.get_value((0, 0)).unwrap()];
+    assert_eq!(style.number_format.as_deref(), Some("yyyy\\-mm\\-dd"));
+    assert_eq!(style.font.name.as_deref(), Some("Arial"));
+    assert_eq!(style.font.size, Some(10.));
+    let style = &styles[*ids.get_value((0, 1)).unwrap()];
+    assert_eq!(style.number_format.as_deref(), Some("General"));
+    let style = &styles[*ids.get_value((2, 0)).unwrap()];
+    assert_eq!(style.number_format.as_deref(), Some("[hh]:mm:ss"));
+}
+
+#[test]
+fn styles_ods() {
+    setup();
+
+    let path = format!("{}/tests/styles.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let styles = ods.styles().unwrap();
+    assert_eq!(styles.len(), 3);
+
+    let style = &styles[StyleId(1)];
+    assert_eq!(style.font.name.as_deref(), Some("Arial"));
+    assert_eq!(style.font.size, Some(14.));
+    assert!(style.font.bold && style.font.italic && style.font.underline);
+    assert_eq!(style.fill.foreground, Some(Color::Argb(0xFFFFFF00)));
+    assert_eq!(style.border.top.style.as_deref(), Some("thin"));
+    assert_eq!(style.alignment.horizontal.as_deref(), Some("center"));
+    assert_eq!(style.alignment.vertical.as_deref(), Some("center"));
+    assert_eq!(style.alignment.rotation, -45);
+    assert!(style.alignment.wrap_text);
+
+    let style = &styles[StyleId(2)];
+    assert!(!style.fill.is_visible());
+    assert_eq!(style.border.bottom.style.as_deref(), Some("double"));
+    assert_eq!(style.border.left.style, None);
+    assert_eq!(style.alignment.horizontal.as_deref(), Some("right"));
+
+    let ids = ods.worksheet_styles("Sheet1").unwrap();
+    assert_eq!(ids.get_value((0, 0)), Some(&StyleId(1)));
+    // repeated rows are expanded
+    assert_eq!(ids.get_value((1, 2)), Some(&StyleId(2)));
+    assert_eq!(ids.get_value((2, 2)), Some(&StyleId(2)));
+    assert_eq!(ids.get_value((2, 0)), Some(&StyleId(0)));
+    // neither the default style of the column (0, 3) nor trailing formatted rows are
+    // repeated in each cell
+    assert_eq!(ids.end(), Some((2, 2)));
+}
+
+#[test]
+fn issue_174_styles() {
+    setup();
+
+    // blank cells up to XFD1048576, all with the default style
+    let path = format!("{}/tests/issue_174.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let ids = xlsx.worksheet_styles("Sheet1").unwrap();
+    assert_eq!(ids.get_size(), (1, 11));
+    assert_eq!(ids.get_value((0, 1)), Some(&StyleId(3)));
+    let range = xlsx.worksheet_display_range("Sheet1").unwrap();
+    assert_eq!(range.get_size(), (2, 11));
+    assert_eq!(range.get_value((1, 0)).map(|s| &**s), Some("123"));
+}
+
+#[test]
+fn worksheet_display_range() {
+    setup();
+
+    let path = format!("{}/tests/styles.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = xlsx.worksheet_display_range("Sheet1").unwrap();
+    assert_eq!(range.get_value((0, 0)).map(|s| &**s), Some("1.500"));
+    assert_eq!(range.get_value((0, 1)).map(|s| &**s), Some("2"));
+    assert_eq!(range.get_value((1, 2)).map(|s| &**s), Some("25.00%"));
+
+    let path = format!("{}/tests/date.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let range = xls.worksheet_display_range("Sheet1").unwrap();
+    let dates = range.rows().map(|r| r[0].clone()).collect::<Vec<_>>();
+    assert_eq!(dates[0], "2021-01-01");
+}
+
+#[test]
+fn xlsx_shared_formula() {
+    setup();
+
+    let path = format!("{}/tests/shared_formula.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let formula = xlsx.worksheet_formula("Sheet1").unwrap();
+    let get = |pos| formula.get_value(pos).map(|f| &**f);
+    assert_eq!(get((0, 1)), Some("A1*2+$A$1+SUM(A$1:A1)"));
+    assert_eq!(get((0, 2)), Some("B1*2+$A$1+SUM(B$1:B1)"));
+    assert_eq!(get((1, 1)), Some("A2*2+$A$1+SUM(A$1:A2)"));
+    assert_eq!(get((2, 2)), Some("B3*2+$A$1+SUM(B$1:B3)"));
+    // array formulas are the same for all the cells of the area
+    assert_eq!(get((0, 3)), Some("A1:A3*10"));
+    assert_eq!(get((2, 3)), Some("A1:A3*10"));
+    assert_eq!(get((3, 3)).unwrap_or_default(), "");
+}
+
+#[test]
+fn formula_comparison_operators() {
+    setup();
+
+    // PtgGt (0x0D) is `>`, PtgGe (0x0C) is `>=`
+    for file in ["issues.xls", "issues.xlsb"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let formula = workbook.worksheet_formula("datatypes").unwrap();
+        let formulas = formula.used_cells().map(|(_, _, f)| f).collect::<Vec<_>>();
+        assert_eq!(formulas, ["CONCATENATE(\"a\",\"b\")", "A1>A2"], "{}", file);
+    }
+}
+
+#[test]
+fn formula_ast() {
+    setup();
+
+    let expected = Expr::Binary(
+        BinaryOp::Add,
+        Box::new(Expr::Cell {
+            sheet: None,
+            cell: CellRef::new(0, 1),
+        }),
+        Box::new(Expr::Name("OneRange".to_string())),
+    );
+    for file in ["issues.xls", "issues.xlsb", "issues.xlsx", "issues.ods"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let formula = workbook.worksheet_formula_ast("Sheet1").unwrap();
+        assert_eq!(formula.get_value((1, 0)), Some(&expected), "{}", file);
+
+        let formula = workbook.worksheet_formula_ast("datatypes").unwrap();
+        let rendered = formula
+            .cells()
+            .map(|(_, _, f)| f.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(rendered, ["CONCATENATE(\"a\",\"b\")", "A1>A2"], "{}", file);
+    }
+
+    let path = format!("{}/tests/xls_formula.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let formula = xls.worksheet_formula_ast("Sheet1").unwrap();
+    assert_eq!(
+        formula.get_value((2, 0)),
+        Some(&Expr::Binary(
+            BinaryOp::Mul,
+            Box::new(Expr::Number(2.)),
+            Box::new(Expr::Cell {
+                sheet: Some(SheetRef::new("Sheet2")),
+                cell: CellRef::new(0, 0),
+            })
+        ))
+    );
+
+    // formulas which cannot be parsed do not fail the worksheet
+    let path = format!(
+        "{}/tests/formula_unparsable.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let formula = xlsx.worksheet_formula_ast("Sheet1").unwrap();
+    let rendered = formula
+        .cells()
+        .map(|(_, _, f)| f.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(rendered, ["1+1", "#NAME?", "A1*2"]);
+}
+
+#[cfg(feature = "eval")]
+#[test]
+fn evaluate_uncached_formulas() {
+    setup();
+
+    let path = format!(
+        "{}/tests/uncached_formulas.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+
+    // formulas without cached value are read as empty
+    let range = excel.worksheet_range("Calc").unwrap();
+    assert_eq!(range.get_value((0, 0)), None);
+
+    let range = excel.worksheet_range_evaluated("Calc").unwrap();
+    range_eq!(
+        range,
+        [
+            [Float(15.)],
+            [Float(5.)],
+            [Float(45.)],
+            [String("high".to_string())],
+            [String("cherry".to_string())],
+            [DateTime(45351.)],
+            [Error(Div0)],
+            [String("APPLE!".to_string())],
+            [Float(42.)],
+        ]
+    );
+    assert!(excel.worksheet_range_evaluated("Missing").is_err());
+
+    // formulas which cannot be parsed evaluate to #NAME?
+    let path = format!(
+        "{}/tests/formula_unparsable.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = excel.worksheet_range_evaluated("Sheet1").unwrap();
+    range_eq!(range, [[Float(2.)], [Error(Name)], [Float(4.)]]);
+}
+
+#[test]
+fn xlsx_writer_roundtrip() {
+    setup();
+
+    let mut values = Range::new((1, 1), (3, 3));
+    values.set_value((1, 1), String("name".to_string()));
+    values.set_value((1, 2), String(" padded ".to_string()));
+    values.set_value((1, 3), String("name".to_string()));
+    values.set_value((2, 1), Float(1.5));
+    values.set_value((2, 2), DataType::Int(-3));
+    values.set_value((2, 3), Bool(true));
+    values.set_value((3, 1), DateTime(45351.));
+    values.set_value((3, 2), DateTime(45351.25));
+    values.set_value((3, 3), Duration(1.5));
+    let mut errors = Range::new((0, 0), (0, 2));
+    errors.set_value((0, 0), Error(Div0));
+    errors.set_value((0, 1), Error(NA));
+    errors.set_value((0, 2), Float(f64::INFINITY));
+
+    let mut writer = XlsxWriter::new();
+    writer
+        .add_worksheet("Values & dates", values)
+        .unwrap()
+        .add_worksheet("Errors", errors)
+        .unwrap()
+        .add_defined_name("Names", "='Values & dates'!$B$2:$D$2");
+    assert!(writer.add_worksheet("errors", Range::empty()).is_err());
+    assert!(writer.add_worksheet("a/b", Range::empty()).is_err());
+    let buf = writer.write(Cursor::new(Vec::new())).unwrap();
+
+    let mut excel = Xlsx::new(Cursor::new(buf.into_inner())).unwrap();
+    assert_eq!(excel.sheet_names(), ["Values & dates", "Errors"]);
+    assert_eq!(
+        excel.defined_names(),
+        [(
+            "Names".to_string(),
+            "'Values & dates'!$B$2:$D$2".to_string()
+        )]
+    );
+
+    let range = excel.worksheet_range("Values & dates").unwrap();
+    assert_eq!(range.start(), Some((1, 1)));
+    range_eq!(
+        range,
+        [
+            [
+                String("name".to_string()),
+                String(" padded ".to_string()),
+                String("name".to_string())
+            ],
+            [Float(1.5), Float(-3.), Bool(true)],
+            [DateTime(45351.), DateTime(45351.25), Duration(1.5)],
+        ]
+    );
+    let range = excel.worksheet_range("Errors").unwrap();
+    range_eq!(range, [[Error(Div0), Error(NA), Error(Num)]]);
+}
+
+#[test]
+fn xlsx_writer_escaped_strings() {
+    setup();
+
+    let strings = [
+        "bell\u{7} and nul\u{0}",
+        "tab\tline\nfeed",
+        "_x0041_",
+        "_x005F_x0041_",
+        "_x12_ and _xZZZZ_",
+    ];
+    let mut range = Range::new((0, 0), (strings.len() as u32 - 1, 0));
+    for (row, s) in strings.iter().enumerate() {
+        range.set_value((row as u32, 0), DataType::String(s.to_string()));
+    }
+    let mut writer = XlsxWriter::new();
+    writer.add_worksheet("Sheet1", range).unwrap();
+    let buf = writer.write(Cursor::new(Vec::new())).unwrap();
+
+    let mut excel = Xlsx::new(Cursor::new(buf.into_inner())).unwrap();
+    let range = excel.worksheet_range("Sheet1").unwrap();
+    let read = range
+        .rows()
+        .map(|r| r[0].get_string().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(read, strings);
+}
+
+#[test]
+fn worksheet_comments() {
+    setup();
+
+    let path = format!("{}/tests/comments.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let comments = excel.worksheet_comments("Sheet1").unwrap();
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0].position, (1, 1));
+    assert_eq!(comments[0].author, "John Doe");
+    assert_eq!(comments[0].text, "John Doe:\nA & B");
+    assert_eq!(comments[0].runs.len(), 2);
+    let font = comments[0].runs[0].font.as_ref().unwrap();
+    assert!(font.bold);
+    assert_eq!(font.name.as_deref(), Some("Tahoma"));
+    assert!(!comments[0].runs[1].font.as_ref().unwrap().bold);
+    // the threaded comment replaces its legacy fallback
+    assert_eq!(comments[1].position, (3, 2));
+    assert_eq!(comments[1].author, "Alice");
+    assert_eq!(comments[1].text, "Is this right?");
+    assert_eq!(comments[1].replies.len(), 1);
+    assert_eq!(comments[1].replies[0].author, "Bob & Co");
+    assert_eq!(comments[1].replies[0].text, "Yes <3");
+    assert!(excel.worksheet_comments("Sheet2").unwrap().is_empty());
+    assert!(excel.worksheet_comments("Missing").is_err());
+
+    let path = format!("{}/tests/comments.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsb<_> = open_workbook(&path).unwrap();
+    let comments = excel.worksheet_comments("Sheet1").unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].position, (2, 1));
+    assert_eq!(comments[0].author, "Jane");
+    assert_eq!(comments[0].text, "Hello world");
+    let runs: Vec<_> = comments[0].runs.iter().map(|r| r.text.as_str()).collect();
+    assert_eq!(runs, ["Hello ", "world"]);
+    let font = comments[0].runs[0].font.as_ref().unwrap();
+    assert_eq!(font.name.as_deref(), Some("Arial"));
+
+    let path = format!("{}/tests/comments.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xls<_> = open_workbook(&path).unwrap();
+    let comments = excel.worksheet_comments("Sheet1").unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].position, (1, 2));
+    assert_eq!(comments[0].author, "Jane");
+    assert_eq!(comments[0].text, "Bold plain");
+    let runs: Vec<_> = comments[0].runs.iter().map(|r| r.text.as_str()).collect();
+    assert_eq!(runs, ["Bold ", "plain"]);
+    assert!(comments[0].runs[0].font.as_ref().unwrap().bold);
+    assert!(!comments[0].runs[1].font.as_ref().unwrap().bold);
+
+    let path = format!("{}/tests/comments.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let range = ods.worksheet_range("Sheet1").unwrap();
+    assert_eq!(range.get_value((3, 2)), Some(&Float(3.)));
+    let comments = ods.worksheet_comments("Sheet1").unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].position, (3, 2));
+    assert_eq!(comments[0].author, "Jane");
+    assert_eq!(comments[0].text, "Bold and  plain\nsecond line");
+    assert_eq!(comments[0].runs.len(), 2);
+    assert!(comments[0].runs[0].font.as_ref().unwrap().bold);
+    assert_eq!(comments[0].runs[1].font, None);
+}
+
+#[test]
+fn worksheet_hyperlinks() {
+    setup();
+
+    let path = format!("{}/tests/hyperlinks.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let links = excel.worksheet_hyperlinks("Sheet1").unwrap();
+    assert_eq!(
+        links,
+        [
+            Hyperlink {
+                range: Dimensions::new((0, 0), (0, 0)),
+                url: Some("https://example.com/?a=1&b=2".to_string()),
+                location: None,
+                tooltip: Some("Open & see".to_string()),
+            },
+            Hyperlink {
+                range: Dimensions::new((0, 1), (1, 2)),
+                url: None,
+                location: Some("Sheet2!A1".to_string()),
+                tooltip: None,
+            },
+            Hyperlink {
+                range: Dimensions::new((3, 3), (3, 3)),
+                url: Some("file.xlsx".to_string()),
+                location: Some("part".to_string()),
+                tooltip: None,
+            },
+        ]
+    );
+    assert!(excel.worksheet_hyperlinks("Sheet2").unwrap().is_empty());
+
+    let path = format!("{}/tests/hyperlinks.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsb<_> = open_workbook(&path).unwrap();
+    let links = excel.worksheet_hyperlinks("Sheet1").unwrap();
+    assert_eq!(
+        links,
+        [
+            Hyperlink {
+                range: Dimensions::new((0, 0), (0, 0)),
+                url: Some("https://example.com".to_string()),
+                location: None,
+                tooltip: Some("Open".to_string()),
+            },
+            Hyperlink {
+                range: Dimensions::new((1, 1), (2, 2)),
+                url: None,
+                location: Some("Sheet1!A1".to_string()),
+                tooltip: None,
+            },
+        ]
+    );
+
+    let path = format!("{}/tests/hyperlinks.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xls<_> = open_workbook(&path).unwrap();
+    let links = excel.worksheet_hyperlinks("Sheet1").unwrap();
+    assert_eq!(
+        links,
+        [
+            Hyperlink {
+                range: Dimensions::new((0, 0), (0, 0)),
+                url: Some("https://example.com/".to_string()),
+                location: None,
+                tooltip: Some("Open site".to_string()),
+            },
+            Hyperlink {
+                range: Dimensions::new((1, 1), (2, 2)),
+                url: None,
+                location: Some("Sheet1!A1".to_string()),
+                tooltip: None,
+            },
+            Hyperlink {
+                range: Dimensions::new((3, 0), (3, 0)),
+                url: Some("..\\file.xls".to_string()),
+                location: Some("Sheet2!B2".to_string()),
+                tooltip: None,
+            },
+        ]
+    );
+
+    let path = format!("{}/tests/hyperlinks.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let range = ods.worksheet_range("Sheet1").unwrap();
+    range_eq!(
+        range,
+        [[
+            String("site".to_string()),
+            String("go jump".to_string()),
+            String("go jump".to_string())
+        ]]
+    );
+    let links = ods.worksheet_hyperlinks("Sheet1").unwrap();
+    assert_eq!(
+        links,
+        [
+            Hyperlink {
+                range: Dimensions::new((0, 0), (0, 0)),
+                url: Some("https://example.com/".to_string()),
+                location: None,
+                tooltip: Some("Open site".to_string()),
+            },
+            Hyperlink {
+                range: Dimensions::new((0, 1), (0, 2)),
+                url: None,
+                location: Some("Sheet1.A1".to_string()),
+                tooltip: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn worksheet_rich_text() {
+    setup();
+
+    fn texts(range: &Range<Vec<TextRun>>, pos: (u32, u32)) -> Vec<&str> {
+        range
+            .get_value(pos)
+            .map(|runs| runs.iter().map(|r| r.text.as_str()).collect())
+            .unwrap_or_default()
+    }
+    fn is_bold(range: &Range<Vec<TextRun>>, pos: (u32, u32)) -> Vec<bool> {
+        range
+            .get_value(pos)
+            .map(|runs| {
+                runs.iter()
+                    .map(|r| r.font.as_ref().map_or(false, |f| f.bold))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    let path = format!("{}/tests/rich_text.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = excel.worksheet_rich_text("Sheet1").unwrap();
+    assert_eq!(texts(&range, (0, 0)), ["plain"]);
+    assert_eq!(range.get_value((0, 0)).unwrap()[0].font, None);
+    assert_eq!(texts(&range, (0, 1)), ["Bold", " plain"]);
+    assert_eq!(is_bold(&range, (0, 1)), [true, false]);
+    let font = range.get_value((0, 1)).unwrap()[0].font.clone().unwrap();
+    assert!(font.italic && font.underline);
+    assert_eq!(font.size, Some(12.));
+    assert_eq!(font.color, Some(Color::Argb(0xFFFF0000)));
+    assert_eq!(font.name.as_deref(), Some("Arial"));
+    assert!(texts(&range, (0, 2)).is_empty());
+    assert_eq!(texts(&range, (1, 0)), ["italic"]);
+    assert!(
+        range.get_value((1, 0)).unwrap()[0]
+            .font
+            .as_ref()
+            .unwrap()
+            .italic
+    );
+    // formula results have no runs
+    assert!(texts(&range, (1, 1)).is_empty());
+
+    let path = format!("{}/tests/rich_text.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsb<_> = open_workbook(&path).unwrap();
+    let range = excel.worksheet_rich_text("Sheet1").unwrap();
+    assert_eq!(range.start(), Some((4, 0)));
+    assert_eq!(texts(&range, (4, 0)), ["plain"]);
+    assert_eq!(texts(&range, (4, 1)), ["Bold", " plain"]);
+    assert_eq!(is_bold(&range, (4, 1)), [true, false]);
+    assert_eq!(texts(&range, (4, 2)), ["italic"]);
+    assert_eq!(is_bold(&range, (4, 2)), [true]);
+    assert_eq!(texts(&range, (4, 3)), ["inline"]);
+    assert_eq!(
+        excel.worksheet_range("Sheet1").unwrap().get_value((4, 1)),
+        Some(&String("Bold plain".to_string()))
+    );
+
+    let path = format!("{}/tests/rich_text.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xls<_> = open_workbook(&path).unwrap();
+    let range = excel.worksheet_rich_text("Sheet1").unwrap();
+    assert_eq!(range.start(), Some((4, 0)));
+    assert_eq!(texts(&range, (4, 0)), ["plain"]);
+    assert_eq!(texts(&range, (4, 1)), ["Bold", " plain"]);
+    assert_eq!(is_bold(&range, (4, 1)), [true, false]);
+    assert_eq!(
+        excel.worksheet_range("Sheet1").unwrap().get_value((4, 1)),
+        Some(&String("Bold plain".to_string()))
+    );
+
+    let path = format!("{}/tests/rich_text.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let range = ods.worksheet_rich_text("Sheet1").unwrap();
+    assert_eq!(texts(&range, (0, 0)), ["plain"]);
+    assert_eq!(texts(&range, (0, 1)), ["Bold", " plain\nline"]);
+    assert_eq!(is_bold(&range, (0, 1)), [true, false]);
+    assert!(
+        range.get_value((0, 1)).unwrap()[0]
+            .font
+            .as_ref()
+            .unwrap()
+            .italic
+    );
+    assert!(texts(&range, (0, 2)).is_empty());
+    assert_eq!(texts(&range, (0, 3)), ["twice"]);
+    assert_eq!(texts(&range, (0, 4)), ["twice"]);
+}
+
+#[test]
+fn worksheet_rich_text_namespaced() {
+    setup();
+
+    let path = format!(
+        "{}/tests/richtext-namespaced.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = excel.worksheet_rich_text("Sheet1").unwrap();
+    let texts = range
+        .used_cells()
+        .map(|(row, col, runs)| (row, col, runs[0].text.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        texts,
+        [
+            (0, 0, "inline string\r\nLine 2\r\nLine 3"),
+            (0, 7, "shared string\r\nLine 2\r\nLine 3")
+        ]
+    );
+    for sheet in ["Sheet2", "Sheet3"] {
+        assert!(excel.worksheet_rich_text(sheet).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn worksheet_layout() {
+    setup();
+
+    fn line(
+        first: u32,
+        last: u32,
+        hidden: bool,
+        outline_level: u8,
+        size: Option<f64>,
+    ) -> LineLayout {
+        LineLayout {
+            first,
+            last,
+            hidden,
+            outline_level,
+            size,
+        }
+    }
+
+    let path = format!("{}/tests/layout.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let layout = excel.worksheet_layout("Sheet1").unwrap();
+    assert_eq!(layout.default_row_height, Some(15.));
+    assert_eq!(layout.default_column_width, Some(9.5));
+    assert_eq!(
+        layout.rows,
+        [
+            line(1, 1, true, 0, None),
+            line(2, 2, false, 0, Some(30.)),
+            line(4, 5, false, 1, None),
+        ]
+    );
+    assert_eq!(
+        layout.columns,
+        [
+            line(0, 0, false, 0, Some(20.5)),
+            line(1, 2, true, 0, Some(9.140625)),
+            line(3, 4, false, 1, Some(9.140625)),
+        ]
+    );
+    assert!(layout.is_row_hidden(1) && !layout.is_row_hidden(0));
+    assert!(layout.is_column_hidden(2) && !layout.is_column_hidden(3));
+    assert_eq!(layout.row(5).map(|l| l.outline_level), Some(1));
+    assert_eq!(layout.row(6), None);
+
+    let path = format!("{}/tests/layout.xlsb", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsb<_> = open_workbook(&path).unwrap();
+    let layout = excel.worksheet_layout("Sheet1").unwrap();
+    assert_eq!(layout.default_row_height, Some(15.));
+    assert_eq!(layout.default_column_width, Some(8.));
+    assert_eq!(layout.rows[0].first, 1);
+    assert!(layout.rows[0].hidden);
+    assert_eq!(layout.rows[1], line(2, 2, false, 1, Some(30.)));
+    assert_eq!(
+        layout.columns,
+        [
+            line(0, 0, false, 0, Some(20.)),
+            line(1, 2, true, 0, Some(9.)),
+            line(3, 4, false, 1, Some(9.)),
+        ]
+    );
+
+    let path = format!("{}/tests/layout.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xls<_> = open_workbook(&path).unwrap();
+    let layout = excel.worksheet_layout("Sheet1").unwrap();
+    assert_eq!(layout.default_row_height, Some(15.));
+    assert_eq!(layout.default_column_width, Some(8.));
+    assert_eq!(
+        layout.rows,
+        [
+            line(1, 1, true, 0, Some(15.)),
+            line(2, 2, false, 0, Some(30.)),
+            line(4, 5, false, 1, Some(15.)),
+        ]
+    );
+    assert_eq!(
+        layout.columns,
+        [
+            line(0, 0, false, 0, Some(20.)),
+            line(1, 2, true, 0, Some(9.)),
+            line(3, 4, false, 1, Some(9.)),
+        ]
+    );
+
+    let path = format!("{}/tests/layout.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let layout = ods.worksheet_layout("Sheet1").unwrap();
+    assert_eq!(
+        layout.rows,
+        [
+            line(1, 1, true, 0, None),
+            line(2, 2, false, 0, Some(30.)),
+            line(4, 5, false, 1, None),
+        ]
+    );
+    assert_eq!(
+        layout.columns,
+        [
+            line(0, 0, false, 0, Some(13.)),
+            line(1, 2, true, 0, None),
+            line(3, 4, false, 1, None),
+        ]
+    );
+}
+
+#[test]
+fn worksheet_data_validations() {
+    setup();
+
+    let list = DataValidation {
+        ranges: vec![Dimensions::new((0, 0), (2, 0))],
+        validation_type: ValidationType::List,
+        formula1: Some("\"Yes,No\"".to_string()),
+        allow_blank: true,
+        error_title: Some("Invalid".to_string()),
+        error_message: Some("Pick Yes or No".to_string()),
+        prompt_title: Some("Answer".to_string()),
+        prompt_message: Some("Yes or No".to_string()),
+        ..Default::default()
+    };
+    let whole = DataValidation {
+        ranges: vec![
+            Dimensions::new((0, 1), (2, 1)),
+            Dimensions::new((0, 3), (0, 3)),
+        ],
+        validation_type: ValidationType::Whole,
+        operator: ValidationOperator::Between,
+        formula1: Some("1".to_string()),
+        formula2: Some("10".to_string()),
+        ..Default::default()
+    };
+    let decimal = DataValidation {
+        ranges: vec![Dimensions::new((0, 2), (0, 2))],
+        validation_type: ValidationType::Decimal,
+        operator: ValidationOperator::GreaterThan,
+        formula1: Some("0.5".to_string()),
+        ..Default::default()
+    };
+
+    for ext in ["xlsx", "xlsb", "xls", "ods"] {
+        let path = format!(
+            "{}/tests/data_validations.{}",
+            env!("CARGO_MANIFEST_DIR"),
+            ext
+        );
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let validations = workbook.worksheet_data_validations("Sheet1").unwrap();
+        assert_eq!(
+            validations[..3],
+            [list.clone(), whole.clone(), decimal.clone()],
+            "{}",
+            ext
+        );
+        assert_eq!(
+            validations[0].list_values(),
+            Some(vec!["Yes".to_string(), "No".to_string()])
+        );
+        assert!(validations[1].contains((2, 1)) && !validations[1].contains((1, 3)));
+    }
+
+    // lists referencing other sheets are stored in x14 extensions
+    let path = format!("{}/tests/data_validations.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let validations = excel.worksheet_data_validations("Sheet1").unwrap();
+    assert_eq!(validations.len(), 4);
+    assert_eq!(validations[3].validation_type, ValidationType::List);
+    assert_eq!(validations[3].formula1.as_deref(), Some("Sheet2!$A$1:$A$2"));
+    assert_eq!(validations[3].ranges, [Dimensions::new((0, 4), (1, 4))]);
+    assert_eq!(validations[3].list_values(), None);
+}
+
+#[test]
+fn issue_271_data_validations() {
+    setup();
+
+    let path = format!("{}/tests/issue_271.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    for sheet in ["sheet1", "sheet2"] {
+        let validations = xls.worksheet_data_validations(sheet).unwrap();
+        assert_eq!(validations.len(), 1, "{sheet}");
+        assert_eq!(validations[0].validation_type, ValidationType::List);
+        assert_eq!(validations[0].ranges, [Dimensions::new((2, 6), (2, 6))]);
+        // UTF-16 string token
+        assert_eq!(validations[0].formula1.as_deref(), Some("\"男,女\""));
+    }
+}
+
+#[test]
+fn worksheet_conditional_formats() {
+    setup();
+
+    fn rule(
+        range: Dimensions,
+        rule_type: ConditionalFormatType,
+        priority: u32,
+        operator: Option<ValidationOperator>,
+        formulas: &[&str],
+    ) -> ConditionalFormat {
+        ConditionalFormat {
+            ranges: vec![range],
+            rule_type,
+            priority,
+            operator,
+            formulas: formulas.iter().map(|f| f.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    let rules = [
+        rule(
+            Dimensions::new((0, 0), (2, 0)),
+            ConditionalFormatType::CellIs,
+            1,
+            Some(ValidationOperator::GreaterThan),
+            &["5"],
+        ),
+        rule(
+            Dimensions::new((0, 1), (2, 1)),
+            ConditionalFormatType::Expression,
+            2,
+            None,
+            &["$A$1>10"],
+        ),
+        rule(
+            Dimensions::new((0, 2), (0, 2)),
+            ConditionalFormatType::CellIs,
+            3,
+            Some(ValidationOperator::Between),
+            &["1", "10"],
+        ),
+        rule(
+            Dimensions::new((0, 3), (2, 3)),
+            ConditionalFormatType::ColorScale,
+            4,
+            None,
+            &[],
+        ),
+    ];
+
+    for (ext, count) in [("xlsx", 5), ("xlsb", 4), ("xls", 3), ("ods", 4)] {
+        let path = format!(
+            "{}/tests/conditional_formats.{}",
+            env!("CARGO_MANIFEST_DIR"),
+            ext
+        );
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let formats = workbook.worksheet_conditional_formats("Sheet1").unwrap();
+        assert_eq!(formats.len(), count, "{}", ext);
+        for (format, expected) in formats.iter().zip(&rules) {
+            let format = ConditionalFormat {
+                dxf_id: None,
+                style_name: None,
+                ..format.clone()
+            };
+            assert_eq!(&format, expected, "{}", ext);
+        }
+        let dxf_ids: Vec<_> = formats.iter().map(|f| f.dxf_id).collect();
+        match ext {
+            "xlsx" | "xlsb" => assert_eq!(dxf_ids[..4], [Some(0), Some(1), Some(0), None]),
+            _ => assert!(dxf_ids.iter().all(Option::is_none)),
+        }
+    }
+
+    // data bars extended in x14 are read once
+    let path = format!(
+        "{}/tests/conditional_formats.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut excel: Xlsx<_> = open_workbook(&path).unwrap();
+    let formats = excel.worksheet_conditional_formats("Sheet1").unwrap();
+    assert_eq!(formats[4].rule_type, ConditionalFormatType::DataBar);
+    assert!(formats[4].formulas.is_empty());
+
+    let path = format!(
+        "{}/tests/conditional_formats.ods",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let formats = ods.worksheet_conditional_formats("Sheet1").unwrap();
+    assert_eq!(formats[1].style_name.as_deref(), Some("Bad"));
+}
+
+#[test]
+fn worksheet_charts() {
+    setup();
+
+    let reference = |formula: &str, sheet: &str, start, end| {
+        Some(ChartReference {
+            formula: formula.to_string(),
+            sheet: Some(sheet.to_string()),
+            range: Some(Dimensions { start, end }),
+        })
+    };
+    for file in ["charts.xlsx", "charts.xlsb"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let charts = workbook.worksheet_charts("Sheet1").unwrap();
+        assert_eq!(charts.len(), 2, "{file}");
+        assert_eq!(charts[0].name.as_deref(), Some("Chart 1"));
+        assert_eq!(
+            charts[0].anchor,
+            Some(Dimensions {
+                start: (1, 4),
+                end: (15, 10)
+            })
+        );
+        assert_eq!(charts[0].chart_type, ChartType::Column);
+        assert_eq!(charts[0].title.as_deref(), Some("Sales & costs\n2023"));
+        assert_eq!(
+            charts[0].series,
+            [
+                ChartSeries {
+                    chart_type: ChartType::Column,
+                    name: Some("Sales".to_string()),
+                    name_reference: reference("Sheet1!$B$1", "Sheet1", (0, 1), (0, 1)),
+                    categories: reference("Sheet1!$A$2:$A$4", "Sheet1", (1, 0), (3, 0)),
+                    values: reference("Sheet1!$B$2:$B$4", "Sheet1", (1, 1), (3, 1)),
+                },
+                ChartSeries {
+                    chart_type: ChartType::Line,
+                    name: Some("Costs".to_string()),
+                    name_reference: reference("Sheet1!$C$1", "Sheet1", (0, 2), (0, 2)),
+                    categories: reference("Sheet1!$A$2:$A$4", "Sheet1", (1, 0), (3, 0)),
+                    values: reference("'My data'!$C$2:$C$4", "My data", (1, 2), (3, 2)),
+                },
+            ]
+        );
+        // automatic title of a single series chart
+        assert_eq!(charts[1].chart_type, ChartType::Scatter);
+        assert_eq!(charts[1].title.as_deref(), Some("Costs"));
+        assert_eq!(
+            charts[1].series[0].categories,
+            reference("Sheet1!$B$2:$B$4", "Sheet1", (1, 1), (3, 1))
+        );
+    }
+
+    let path = format!("{}/tests/charts.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    assert_eq!(xlsx.sheets_metadata()[1].typ, SheetType::ChartSheet);
+    let charts = xlsx.worksheet_charts("Chart1").unwrap();
+    assert_eq!(charts.len(), 1);
+    assert_eq!(charts[0].anchor, None);
+    assert_eq!(charts[0].chart_type, ChartType::Pie);
+    assert_eq!(charts[0].title.as_deref(), Some("Share"));
+    let values = charts[0].series[0].values.as_ref().unwrap();
+    assert_eq!(values.formula, "(Sheet1!$B$2,Sheet1!$B$4)");
+    assert_eq!(values.range, None);
+
+    let path = format!("{}/tests/date.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    assert!(xls.worksheet_charts("Sheet1").unwrap().is_empty());
+}
+
+#[test]
+fn document_properties() {
+    setup();
+
+    let some = |s: &str| Some(s.to_string());
+    let expected = DocumentProperties {
+        title: some("Quarterly report"),
+        subject: some("Finance"),
+        author: some("Alice"),
+        keywords: some("report, q1"),
+        description: some("Figures of the first quarter"),
+        category: some("Reports"),
+        last_modified_by: some("Bob"),
+        created: some("2023-01-31T08:30:00Z"),
+        modified: some("2023-02-01T17:45:10Z"),
+        application: some("Microsoft Excel"),
+        company: some("ACME"),
+        manager: some("Carol"),
+        custom: vec![
+            ("Reviewed".to_string(), Bool(true)),
+            ("Version".to_string(), DataType::Int(3)),
+            ("Owner".to_string(), String("Finance team".to_string())),
+        ],
+    };
+    for ext in ["xlsx", "xlsb", "xls"] {
+        let path = format!("{}/tests/properties.{}", env!("CARGO_MANIFEST_DIR"), ext);
+        let workbook = open_workbook_auto(&path).unwrap();
+        assert_eq!(workbook.metadata().properties(), &expected, "{}", ext);
+    }
+
+    // parts are found through the package relationships, a malformed part does not fail
+    let path = format!("{}/tests/properties_rels.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let properties = xlsx.metadata().properties();
+    assert_eq!(properties.title, some("Quarterly report"));
+    assert_eq!(properties.modified, some("2023-02-01T17:45:10Z"));
+    assert_eq!(properties.company, None);
+    assert!(properties.custom.is_empty());
+
+    let path = format!("{}/tests/properties.ods", env!("CARGO_MANIFEST_DIR"));
+    let ods: Ods<_> = open_workbook(&path).unwrap();
+    let expected = DocumentProperties {
+        category: None,
+        created: some("2023-01-31T08:30:00"),
+        modified: some("2023-02-01T17:45:10"),
+        application: some("LibreOffice/7.5"),
+        company: None,
+        manager: None,
+        custom: vec![
+            ("Reviewed".to_string(), Bool(true)),
+            ("Version".to_string(), Float(3.)),
+            ("Owner".to_string(), String("Finance team".to_string())),
+        ],
+        ..expected
+    };
+    assert_eq!(ods.metadata().properties(), &expected);
+}